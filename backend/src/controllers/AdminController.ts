@@ -15,6 +15,7 @@ import {
     getAssociatedTokenAddress,
     createAssociatedTokenAccountIdempotentInstruction
 } from '@solana/spl-token';
+import { sendWithBlockhashRefresh } from '../services/TransactionLanding';
 
 /**
  * Get platform status and configuration
@@ -403,6 +404,12 @@ export const sweepProtocolFees = async (req: Request, res: Response) => {
             PAYMENT_ROUTER_PROGRAM_ID
         );
 
+        // Market config carries the fee challenge window; only fees past it are claimable
+        const [marketConfigPDA] = PublicKey.findProgramAddressSync(
+            [Buffer.from("market_config")],
+            PAYMENT_ROUTER_PROGRAM_ID
+        );
+
         // Admin Wallet ATA (Where USDC goes)
         // Use PROTOCOL_FEE_DESTINATION if set, otherwise fallback to admin signer
         const destinationWallet = process.env.PROTOCOL_FEE_DESTINATION
@@ -450,24 +457,24 @@ export const sweepProtocolFees = async (req: Request, res: Response) => {
             { pubkey: feeVaultStatePDA, isSigner: false, isWritable: true },
             { pubkey: feeVaultPDA, isSigner: false, isWritable: true },
             { pubkey: adminATAResolved, isSigner: false, isWritable: true }, // admin_wallet
+            { pubkey: marketConfigPDA, isSigner: false, isWritable: false },
             { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
         ];
 
+        // amount (u64, atomic units): 0 sweeps the full claimable protocol balance
+        const amountBuffer = Buffer.alloc(8);
+        amountBuffer.writeBigUInt64LE(BigInt(req.body?.amount ?? 0));
+
         const ix = new TransactionInstruction({
             programId: PAYMENT_ROUTER_PROGRAM_ID,
             keys,
-            data: discriminator
+            data: Buffer.concat([discriminator, amountBuffer])
         });
 
         tx.add(ix);
-        tx.feePayer = feePayerKeypair.publicKey;
-        const { blockhash } = await connection.getLatestBlockhash();
-        tx.recentBlockhash = blockhash;
-
-        tx.sign(feePayerKeypair, adminKeypair); // Both sign if different, or admin signs as authority
 
-        const sig = await connection.sendRawTransaction(tx.serialize());
-        await connection.confirmTransaction(sig, 'confirmed');
+        // Both sign if different, or admin signs as authority; resent on a fresh blockhash if it expires
+        const sig = await sendWithBlockhashRefresh(connection, tx.instructions, feePayerKeypair, [adminKeypair]);
 
         console.log(`[Admin] Protocol fees swept: ${sig}`);
 
@@ -119,7 +119,8 @@ export class BuilderController {
                     { pubkey: feeVaultPDA, isSigner: false, isWritable: true },
                     { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
                 ],
-                data: discriminator
+                // swap: Option<SwapLeg> = None (payout in USDC)
+                data: Buffer.concat([discriminator, Buffer.from([0])])
             });
 
             const { blockhash } = await connection.getLatestBlockhash();
@@ -22,7 +22,7 @@ import { configService } from '../services/ConfigService';
 // Configuration
 const RPC_URL = process.env.SOLANA_RPC_URL || 'https://api.devnet.solana.com';
 const TREASURY = new PublicKey(process.env.ATTENTIUM_VAULT_ADDRESS || '2kDpvEhgoLkUbqFJqxMpUXMtr2gVYbfqNF8kGrfoZMAV');
-const PAYMENT_ROUTER_PROGRAM_ID = new PublicKey(process.env.PAYMENT_ROUTER_PROGRAM_ID || 'Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS');
+const PAYMENT_ROUTER_PROGRAM_ID = new PublicKey(process.env.PAYMENT_ROUTER_PROGRAM_ID || 'EZPqKzvizknKZmkYC69NgiBeCs1uDVfET1MQpC7tQvin');
 const IS_DEVNET = RPC_URL.includes('devnet');
 
 // USDC Mint - Uses Devnet faucet mint when on Devnet
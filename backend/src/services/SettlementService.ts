@@ -8,11 +8,13 @@ import {
 } from '@solana/web3.js';
 import {
     TOKEN_PROGRAM_ID,
+    ASSOCIATED_TOKEN_PROGRAM_ID,
     getAssociatedTokenAddress,
     createAssociatedTokenAccountIdempotentInstruction
 } from '@solana/spl-token';
 import crypto from 'crypto';
 import { redisClient } from '../utils/redis';
+import { estimatePriorityFee, priorityFeeInstruction, nextDurableNonce } from './TransactionLanding';
 
 // Configuration
 const RPC_URL = process.env.SOLANA_RPC_URL || 'https://api.devnet.solana.com';
@@ -30,6 +32,41 @@ const GAS_STATION_THRESHOLD = 5.00; // $5.00 USDC
 
 const connection = new Connection(RPC_URL, 'confirmed');
 
+// Chaos mode: randomly drops, duplicates and delays claim broadcasts so the program's
+// nonce replay protection and the pending/restore bookkeeping can be exercised end to end.
+// Only honored against a local validator.
+const IS_LOCAL_VALIDATOR = /localhost|127\.0\.0\.1/.test(RPC_URL);
+const CHAOS_MODE = process.env.SETTLEMENT_CHAOS_MODE === 'true' && IS_LOCAL_VALIDATOR;
+if (process.env.SETTLEMENT_CHAOS_MODE === 'true' && !IS_LOCAL_VALIDATOR) {
+    console.warn('[SettlementService] SETTLEMENT_CHAOS_MODE ignored: RPC is not a local validator');
+}
+const CHAOS_DROP_RATE = 0.2;
+const CHAOS_DUPLICATE_RATE = 0.2;
+const CHAOS_MAX_DELAY_MS = 2000;
+
+// Sends a signed transaction, injecting failures in chaos mode. A delayed send lets a
+// later claim overtake it, which is how reordering shows up between independent claims.
+const broadcastTransaction = async (raw: Buffer): Promise<string> => {
+    const send = () => connection.sendRawTransaction(raw, {
+        skipPreflight: false,
+        preflightCommitment: 'confirmed'
+    });
+    if (!CHAOS_MODE) return send();
+
+    await new Promise(resolve => setTimeout(resolve, Math.random() * CHAOS_MAX_DELAY_MS));
+    if (Math.random() < CHAOS_DROP_RATE) {
+        console.warn('[Settlement][chaos] Dropping submission');
+        throw new Error('chaos: submission dropped');
+    }
+    const txHash = await send();
+    if (Math.random() < CHAOS_DUPLICATE_RATE) {
+        console.warn(`[Settlement][chaos] Duplicating submission ${txHash}`);
+        // The replay must never land twice; its outcome is only logged
+        send().catch(err => console.warn(`[Settlement][chaos] Duplicate rejected: ${err.message}`));
+    }
+    return txHash;
+};
+
 // Load Router Admin Keypair (Authority)
 const getRouterAdminKeypair = (): Keypair => {
     const secret = process.env.ROUTER_ADMIN_KEYPAIR;
@@ -149,6 +186,18 @@ export class SettlementService {
             );
 
 
+            // Per-human holdback PDA (quality-review retention)
+            const [holdbackPDA] = PublicKey.findProgramAddressSync(
+                [Buffer.from("holdback"), userKey.toBuffer()],
+                PAYMENT_ROUTER_PROGRAM_ID
+            );
+
+            // Human's payout preferences (read on-chain; may not exist yet)
+            const [payoutPrefsPDA] = PublicKey.findProgramAddressSync(
+                [Buffer.from("payout_prefs"), userKey.toBuffer()],
+                PAYMENT_ROUTER_PROGRAM_ID
+            );
+
             for (const [bidId, data] of Object.entries(campaignMap)) {
                 if (data.totalAmount < 0.000001) continue;
 
@@ -162,9 +211,14 @@ export class SettlementService {
                     [Buffer.from("market_config")],
                     PAYMENT_ROUTER_PROGRAM_ID
                 );
+                const [relayerStatsPDA] = PublicKey.findProgramAddressSync(
+                    [Buffer.from("relayer_stats"), routerAdmin.publicKey.toBuffer()],
+                    PAYMENT_ROUTER_PROGRAM_ID
+                );
 
                 // Handle Builder Code logic
                 let builderBalancePDA = PAYMENT_ROUTER_PROGRAM_ID; // Placeholder if none
+                let builderStatsPDA = PAYMENT_ROUTER_PROGRAM_ID; // Placeholder if none
                 let builderCodeBytes = Buffer.alloc(32);
                 let hasBuilder = false;
 
@@ -186,6 +240,15 @@ export class SettlementService {
                         if (builderAccountInfo) {
                             builderBalancePDA = pda;
                             hasBuilder = true;
+
+                            // Monthly stats are opt-in; only pass them once initialized
+                            const [statsPda] = PublicKey.findProgramAddressSync(
+                                [Buffer.from("builder_stats"), builderCodeBytes],
+                                PAYMENT_ROUTER_PROGRAM_ID
+                            );
+                            if (await connection.getAccountInfo(statsPda)) {
+                                builderStatsPDA = statsPda;
+                            }
                         } else {
                             console.warn(`[Settlement] Builder account for code "${data.builderCode}" not initialized on-chain. Proceeding without builder split.`);
                             hasBuilder = false;
@@ -200,8 +263,13 @@ export class SettlementService {
                 // 8 bytes nonce (u64)
                 // 1 byte option tag (0 or 1)
                 // 32 bytes builder_code (only if option=1)
+                // 1 byte quality_score option tag (always None for now)
+                // SettlementExtras, all None:
+                // 1 byte builder_code_str option tag (codes are passed raw)
+                // 1 byte response_hash option tag (no receipt is created)
+                // 1 byte quality_multiplier_bps option tag (settles at the agreed price)
 
-                const dataSize = 8 + 8 + 8 + 8 + 1 + (hasBuilder ? 32 : 0);
+                const dataSize = 8 + 8 + 8 + 8 + 1 + (hasBuilder ? 32 : 0) + 1 + 1 + 1 + 1;
                 const dataBuffer = Buffer.alloc(dataSize);
 
                 let offset = 0;
@@ -225,12 +293,20 @@ export class SettlementService {
                 } else {
                     dataBuffer.writeUInt8(0, offset); offset += 1; // None
                 }
+                dataBuffer.writeUInt8(0, offset); offset += 1; // quality_score: None
+                dataBuffer.writeUInt8(0, offset); offset += 1; // builder_code_str: None
+                dataBuffer.writeUInt8(0, offset); offset += 1; // response_hash: None
+                dataBuffer.writeUInt8(0, offset); offset += 1; // quality_multiplier_bps: None
 
                 const keys = [
-                    { pubkey: routerAdmin.publicKey, isSigner: true, isWritable: false },
+                    // Router pays for the payout ATA if the program has to create it
+                    { pubkey: routerAdmin.publicKey, isSigner: true, isWritable: true },
                     { pubkey: escrowPDA, isSigner: false, isWritable: true },
                     { pubkey: vaultATA, isSigner: false, isWritable: true },
+                    { pubkey: userKey, isSigner: false, isWritable: false },
+                    { pubkey: payoutPrefsPDA, isSigner: false, isWritable: false },
                     { pubkey: userATA, isSigner: false, isWritable: true },
+                    { pubkey: USDC_MINT, isSigner: false, isWritable: false },
 
                     // Fee Vault Accounts
                     { pubkey: feeVaultStatePDA, isSigner: false, isWritable: true },
@@ -239,8 +315,39 @@ export class SettlementService {
                     // Builder Balance (Optional) - Pass program ID for None (Anchor convention for Option<Account>)
                     { pubkey: hasBuilder ? builderBalancePDA : PAYMENT_ROUTER_PROGRAM_ID, isSigner: false, isWritable: hasBuilder },
 
-                    { pubkey: marketConfigPDA, isSigner: false, isWritable: false },
-                    { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false }
+                    // Builder Stats (Optional) - monthly leaderboard buckets
+                    { pubkey: builderStatsPDA, isSigner: false, isWritable: !builderStatsPDA.equals(PAYMENT_ROUTER_PROGRAM_ID) },
+
+                    // Session (Optional) - claims aggregate many sessions, so none is passed
+                    { pubkey: PAYMENT_ROUTER_PROGRAM_ID, isSigner: false, isWritable: false },
+
+                    // Holdback (created on first use; required when holdback is enabled)
+                    { pubkey: holdbackPDA, isSigner: false, isWritable: true },
+
+                    // Attestation Key + Instructions Sysvar (Optional) - not attested yet
+                    { pubkey: PAYMENT_ROUTER_PROGRAM_ID, isSigner: false, isWritable: false },
+                    { pubkey: PAYMENT_ROUTER_PROGRAM_ID, isSigner: false, isWritable: false },
+
+                    // Receipt + Human/Agent Quality Stats (Optional) - claims carry no score
+                    { pubkey: PAYMENT_ROUTER_PROGRAM_ID, isSigner: false, isWritable: false },
+                    { pubkey: PAYMENT_ROUTER_PROGRAM_ID, isSigner: false, isWritable: false },
+                    { pubkey: PAYMENT_ROUTER_PROGRAM_ID, isSigner: false, isWritable: false },
+
+                    // Credit Line + Collateral (Optional) - claims are fully escrow-funded
+                    { pubkey: PAYMENT_ROUTER_PROGRAM_ID, isSigner: false, isWritable: false },
+                    { pubkey: PAYMENT_ROUTER_PROGRAM_ID, isSigner: false, isWritable: false },
+
+                    // Mint Fee Vault (Optional) - claims settle in USDC through the primary fee vault
+                    { pubkey: PAYMENT_ROUTER_PROGRAM_ID, isSigner: false, isWritable: false },
+
+                    // Writable: settlements feed the velocity circuit breaker
+                    { pubkey: marketConfigPDA, isSigner: false, isWritable: true },
+                    { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+                    { pubkey: ASSOCIATED_TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+                    { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+
+                    // Relayer Stats - records the router's gas spend for reimbursement
+                    { pubkey: relayerStatsPDA, isSigner: false, isWritable: true }
                 ];
 
                 instructions.push(new TransactionInstruction({
@@ -255,9 +362,21 @@ export class SettlementService {
             }
 
             // 4. Build Transaction
-            const { blockhash } = await connection.getLatestBlockhash();
+            // Priority fee tracks recent fees on the fee vault, which every claim writes
+            const priorityFee = await estimatePriorityFee(connection, [feeVaultPDA, userATA]);
+            if (priorityFee > 0) instructions.unshift(priorityFeeInstruction(priorityFee));
+
             const tx = new Transaction();
-            tx.recentBlockhash = blockhash;
+            // The user may sign near the end of the intent's TTL, long after a blockhash
+            // expires; a durable nonce keeps the router's signature valid until then
+            const nonceInfo = await nextDurableNonce(connection, routerAdmin.publicKey);
+            if (nonceInfo) {
+                tx.nonceInfo = nonceInfo;
+                tx.recentBlockhash = nonceInfo.nonce;
+            } else {
+                const { blockhash } = await connection.getLatestBlockhash();
+                tx.recentBlockhash = blockhash;
+            }
             tx.feePayer = feePayerKey;
             tx.add(...instructions);
 
@@ -366,10 +485,7 @@ export class SettlementService {
             }
 
             console.log(`[Settlement] Transaction verified, broadcasting for ${claimId}...`);
-            const txHash = await connection.sendRawTransaction(signedTx.serialize(), {
-                skipPreflight: false,
-                preflightCommitment: 'confirmed'
-            });
+            const txHash = await broadcastTransaction(signedTx.serialize());
 
             console.log(`[Settlement] Transaction sent: ${txHash}`);
 
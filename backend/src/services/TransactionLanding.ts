@@ -0,0 +1,114 @@
+import {
+    Connection,
+    PublicKey,
+    Transaction,
+    TransactionInstruction,
+    Keypair,
+    SystemProgram,
+    ComputeBudgetProgram,
+    NonceInformation,
+    TransactionExpiredBlockheightExceededError
+} from '@solana/web3.js';
+import { redisClient } from '../utils/redis';
+
+// Priority fees: percentile of recent fees paid for the accounts a transaction writes
+const PRIORITY_FEE_PERCENTILE = Number(process.env.PRIORITY_FEE_PERCENTILE || 75);
+const MIN_PRIORITY_FEE = Number(process.env.MIN_PRIORITY_FEE_MICROLAMPORTS || 0);
+const MAX_PRIORITY_FEE = Number(process.env.MAX_PRIORITY_FEE_MICROLAMPORTS || 1_000_000);
+
+// Durable nonce accounts (authority = router admin) for transactions that wait on a
+// user signature longer than a blockhash lives. Size the pool for concurrently open
+// intents: landing one intent advances its nonce and invalidates others sharing it.
+const NONCE_ACCOUNTS = (process.env.ROUTER_NONCE_ACCOUNTS || '')
+    .split(',')
+    .map(s => s.trim())
+    .filter(Boolean)
+    .map(s => new PublicKey(s));
+
+const MAX_SEND_ATTEMPTS = 3;
+
+/**
+ * Adaptive priority fee (micro-lamports per CU) from the configured percentile of
+ * recent prioritization fees on `writableAccounts`, clamped to the configured range.
+ */
+export const estimatePriorityFee = async (
+    connection: Connection,
+    writableAccounts: PublicKey[]
+): Promise<number> => {
+    try {
+        const recent = await connection.getRecentPrioritizationFees({
+            lockedWritableAccounts: writableAccounts
+        });
+        const fees = recent.map(f => f.prioritizationFee).sort((a, b) => a - b);
+        if (fees.length === 0) return MIN_PRIORITY_FEE;
+        const rank = Math.min(fees.length - 1, Math.floor(fees.length * PRIORITY_FEE_PERCENTILE / 100));
+        return Math.min(MAX_PRIORITY_FEE, Math.max(MIN_PRIORITY_FEE, fees[rank]));
+    } catch (e) {
+        console.warn('[Landing] Priority fee estimate failed, using minimum:', e);
+        return MIN_PRIORITY_FEE;
+    }
+};
+
+export const priorityFeeInstruction = (microLamports: number): TransactionInstruction =>
+    ComputeBudgetProgram.setComputeUnitPrice({ microLamports });
+
+/**
+ * Durable nonce for a long-lived transaction, round-robin over the configured pool.
+ * Returns null when no nonce accounts are configured; use a recent blockhash instead.
+ */
+export const nextDurableNonce = async (
+    connection: Connection,
+    authority: PublicKey
+): Promise<NonceInformation | null> => {
+    if (NONCE_ACCOUNTS.length === 0) return null;
+
+    const slot = await redisClient.client.incr('router:nonce:cursor');
+    const noncePubkey = NONCE_ACCOUNTS[slot % NONCE_ACCOUNTS.length];
+    const nonceAccount = await connection.getNonce(noncePubkey, 'confirmed');
+    if (!nonceAccount) throw new Error(`Nonce account ${noncePubkey.toBase58()} not found`);
+    if (!nonceAccount.authorizedPubkey.equals(authority)) {
+        throw new Error(`Nonce account ${noncePubkey.toBase58()} is not controlled by the router`);
+    }
+
+    return {
+        nonce: nonceAccount.nonce,
+        nonceInstruction: SystemProgram.nonceAdvance({ noncePubkey, authorizedPubkey: authority })
+    };
+};
+
+/**
+ * Sends a transaction signed only by server keys. If its blockhash expires before it
+ * confirms (e.g. dropped under congestion), it is rebuilt on a fresh blockhash,
+ * re-signed and resent. Safe to retry: the program's settlement nonces reject replays.
+ */
+export const sendWithBlockhashRefresh = async (
+    connection: Connection,
+    instructions: TransactionInstruction[],
+    feePayer: Keypair,
+    signers: Keypair[] = []
+): Promise<string> => {
+    const writable = instructions.flatMap(ix => ix.keys.filter(k => k.isWritable).map(k => k.pubkey));
+    const fee = await estimatePriorityFee(connection, writable);
+
+    for (let attempt = 1; ; attempt++) {
+        const { blockhash, lastValidBlockHeight } = await connection.getLatestBlockhash('confirmed');
+        const tx = new Transaction({ feePayer: feePayer.publicKey, blockhash, lastValidBlockHeight });
+        if (fee > 0) tx.add(priorityFeeInstruction(fee));
+        tx.add(...instructions);
+        tx.sign(feePayer, ...signers.filter(s => !s.publicKey.equals(feePayer.publicKey)));
+
+        const signature = await connection.sendRawTransaction(tx.serialize());
+        try {
+            const result = await connection.confirmTransaction({ signature, blockhash, lastValidBlockHeight }, 'confirmed');
+            if (result.value.err) {
+                throw new Error(`Transaction failed: ${JSON.stringify(result.value.err)}`);
+            }
+            return signature;
+        } catch (e) {
+            if (!(e instanceof TransactionExpiredBlockheightExceededError) || attempt >= MAX_SEND_ATTEMPTS) {
+                throw e;
+            }
+            console.warn(`[Landing] ${signature} expired, resigning on a fresh blockhash (attempt ${attempt + 1})`);
+        }
+    }
+};
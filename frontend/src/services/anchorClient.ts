@@ -130,6 +130,9 @@ export class AnchorClient {
                     agentTokenAccount: agentTokenAccount,
                     escrowAccount: escrowPDA,
                     vault: vaultPDA,
+                    creditLine: null, // Optional (underwritten escrows)
+                    creditCollateral: null,
+                    mintFeeVault: null, // Optional (secondary-mint escrows)
                     // Auto-resolved: mint, tokenProgram, systemProgram, rent
                 })
                 .rpc();
@@ -151,6 +151,8 @@ export class PaymentBridge {
                 totalPayout: 0,
                 fee: 0,
                 netPayout: 0,
+                remainingBalance: 0,
+                remainingLocked: 0,
                 txSignature: signature,
                 timestamp: tx.blockTime || Date.now() / 1000,
             };
@@ -11,6 +11,8 @@ import {
     Idl,
     BN,
     web3,
+    utils,
+    BorshAccountsCoder,
 } from "@coral-xyz/anchor";
 import {
     PublicKey,
@@ -20,9 +22,13 @@ import {
     TransactionInstruction,
     SystemProgram,
     SYSVAR_RENT_PUBKEY,
+    SYSVAR_INSTRUCTIONS_PUBKEY,
+    Ed25519Program,
+    GetProgramAccountsFilter,
 } from "@solana/web3.js";
 import {
     TOKEN_PROGRAM_ID,
+    ASSOCIATED_TOKEN_PROGRAM_ID,
     getAssociatedTokenAddress,
     createAssociatedTokenAccountInstruction,
 } from "@solana/spl-token";
@@ -32,94 +38,2676 @@ export const PAYMENT_ROUTER_PROGRAM_ID = new PublicKey(
     "Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS"
 );
 
+// Clockwork thread program; threads sign as PDAs of it
+export const CLOCKWORK_THREAD_PROGRAM_ID = new PublicKey(
+    "CLoCKyJ6DXBJqqu2VWx9RLbgnwwR6BMHHuyasVmfMzBh"
+);
+
 // USDC Devnet Mint (example - replace with actual)
 export const USDC_MINT = new PublicKey(
     "4zMMC9srt5Ri5X14GAgXhaHii3GnPAEERYPJgZJDncDU"
 );
 
-// --- PDA Derivations ---
+// withdrawEscrow amount meaning "everything withdrawable" (matches WITHDRAW_ALL in lib.rs)
+export const WITHDRAW_ALL = new BN("18446744073709551615");
+
+// MarketConfig feature flags (matches MarketConfig::FEATURE_* in lib.rs)
+export const FEATURES = {
+    DELAYED_RELEASE: 1 << 0,
+    BUILDER_SPLITS: 1 << 1,
+    ORACLE_PRICING: 1 << 2,
+    PERMISSIONED_AGENTS: 1 << 3,
+    TOKEN2022: 1 << 4,
+    MULTI_MINT: 1 << 5,
+} as const;
+
+// --- Account Layouts ---
+// Byte offsets (after the 8-byte discriminator) of the pubkeys operators filter on.
+// The program commits to these: fields ahead of them never change size or order.
+
+export const ACCOUNT_LAYOUT = {
+    EscrowAccount: { agent: 8, mint: 97 },
+    Session: { escrow: 8, human: 40 },
+    SettlementReceipt: { human: 8, agent: 40, escrow: 72 },
+} as const;
+
+function accountFilters(
+    account: keyof typeof ACCOUNT_LAYOUT,
+    field: string,
+    key: PublicKey
+): GetProgramAccountsFilter[] {
+    const layout = ACCOUNT_LAYOUT[account] as Record<string, number>;
+    return [
+        {
+            memcmp: {
+                offset: 0,
+                bytes: utils.bytes.bs58.encode(BorshAccountsCoder.accountDiscriminator(account)),
+            },
+        },
+        { memcmp: { offset: layout[field], bytes: key.toBase58() } },
+    ];
+}
+
+// --- Signed Messages ---
+
+/** Bytes an agent signs to authorize a relayed deposit (matches deposit_authorization_message). */
+export function depositAuthorizationMessage(
+    agentPubkey: PublicKey,
+    amount: BN,
+    expiresAt: BN,
+    nonce: BN
+): Buffer {
+    const [escrowPDA] = findEscrowPDA(agentPubkey);
+    return Buffer.concat([
+        Buffer.from("deposit_auth"),
+        escrowPDA.toBuffer(),
+        amount.toArrayLike(Buffer, "le", 8),
+        expiresAt.toTwos(64).toArrayLike(Buffer, "le", 8),
+        nonce.toArrayLike(Buffer, "le", 8),
+    ]);
+}
+
+/** Bytes an attestation key signs for a settlement (matches settlement_attestation_message). */
+export function settlementAttestationMessage(
+    agentPubkey: PublicKey,
+    humanWallet: PublicKey,
+    verifiedSeconds: BN,
+    agreedPricePerSecond: BN,
+    nonce: BN,
+    qualityMultiplierBps: number = 10_000 // 1x when no multiplier is applied
+): Buffer {
+    const [escrowPDA] = findEscrowPDA(agentPubkey);
+    const multiplier = Buffer.alloc(2);
+    multiplier.writeUInt16LE(qualityMultiplierBps);
+    return Buffer.concat([
+        escrowPDA.toBuffer(),
+        humanWallet.toBuffer(),
+        verifiedSeconds.toArrayLike(Buffer, "le", 8),
+        agreedPricePerSecond.toArrayLike(Buffer, "le", 8),
+        nonce.toArrayLike(Buffer, "le", 8),
+        multiplier,
+    ]);
+}
+
+/** Bytes a wallet signs to fund an agent's escrow (matches transfer_authorization_message). */
+export function transferAuthorizationMessage(
+    from: PublicKey,
+    agentPubkey: PublicKey,
+    value: BN,
+    validAfter: BN,
+    validBefore: BN,
+    nonce: Buffer // 32 random bytes
+): Buffer {
+    const [escrowPDA] = findEscrowPDA(agentPubkey);
+    return Buffer.concat([
+        Buffer.from("receive_with_auth"),
+        from.toBuffer(),
+        escrowPDA.toBuffer(),
+        value.toArrayLike(Buffer, "le", 8),
+        validAfter.toTwos(64).toArrayLike(Buffer, "le", 8),
+        validBefore.toTwos(64).toArrayLike(Buffer, "le", 8),
+        nonce,
+    ]);
+}
+
+/** Bytes a human signs to have their rating of a settlement submitted for them (matches agent_rating_message). */
+export function agentRatingMessage(receiptPDA: PublicKey, rating: number): Buffer {
+    return Buffer.concat([Buffer.from("agent_rating"), receiptPDA.toBuffer(), Buffer.from([rating])]);
+}
+
+// --- PDA Derivations ---
+
+export function findMarketConfigPDA(): [PublicKey, number] {
+    return PublicKey.findProgramAddressSync(
+        [Buffer.from("market_config")],
+        PAYMENT_ROUTER_PROGRAM_ID
+    );
+}
+
+export function findEscrowPDA(agentPubkey: PublicKey): [PublicKey, number] {
+    return PublicKey.findProgramAddressSync(
+        [Buffer.from("escrow"), agentPubkey.toBuffer()],
+        PAYMENT_ROUTER_PROGRAM_ID
+    );
+}
+
+export function findFeeVaultStatePDA(): [PublicKey, number] {
+    return PublicKey.findProgramAddressSync(
+        [Buffer.from("fee_vault_state")],
+        PAYMENT_ROUTER_PROGRAM_ID
+    );
+}
+
+export function findFeeVaultPDA(feeVaultStatePDA: PublicKey): [PublicKey, number] {
+    return PublicKey.findProgramAddressSync(
+        [Buffer.from("fee_vault"), feeVaultStatePDA.toBuffer()],
+        PAYMENT_ROUTER_PROGRAM_ID
+    );
+}
+
+export function findBuilderBalancePDA(builderCode: string): [PublicKey, number] {
+    const builderCodeBytes = Buffer.alloc(32);
+    const codeBuffer = Buffer.from(builderCode);
+    if (codeBuffer.length <= 32) {
+        codeBuffer.copy(builderCodeBytes);
+    }
+    return PublicKey.findProgramAddressSync(
+        [Buffer.from("builder"), builderCodeBytes],
+        PAYMENT_ROUTER_PROGRAM_ID
+    );
+};
+
+/**
+ * Builder code for a human-readable referral string: its SHA-256, exactly as
+ * close_settlement hashes `builder_code_str`. Register builders under this code.
+ */
+export function builderCodeFromString(referral: string): Buffer {
+    return Buffer.from(utils.sha256.hash(referral), "hex");
+}
+
+export function findBuilderStatsPDA(builderCode: string): [PublicKey, number] {
+    const builderCodeBytes = Buffer.alloc(32);
+    const codeBuffer = Buffer.from(builderCode);
+    if (codeBuffer.length <= 32) {
+        codeBuffer.copy(builderCodeBytes);
+    }
+    return PublicKey.findProgramAddressSync(
+        [Buffer.from("builder_stats"), builderCodeBytes],
+        PAYMENT_ROUTER_PROGRAM_ID
+    );
+}
+
+export function findMatcherBalancePDA(matcherCode: Buffer): [PublicKey, number] {
+    return PublicKey.findProgramAddressSync(
+        [Buffer.from("matcher"), matcherCode],
+        PAYMENT_ROUTER_PROGRAM_ID
+    );
+}
+
+export function findSessionPDA(
+    escrowPDA: PublicKey,
+    sessionId: BN
+): [PublicKey, number] {
+    return PublicKey.findProgramAddressSync(
+        [Buffer.from("session"), escrowPDA.toBuffer(), sessionId.toArrayLike(Buffer, "le", 8)],
+        PAYMENT_ROUTER_PROGRAM_ID
+    );
+}
+
+export function findGroupSessionPDA(escrowPDA: PublicKey, sessionId: BN): [PublicKey, number] {
+    return PublicKey.findProgramAddressSync(
+        [Buffer.from("group_session"), escrowPDA.toBuffer(), sessionId.toArrayLike(Buffer, "le", 8)],
+        PAYMENT_ROUTER_PROGRAM_ID
+    );
+}
+
+export function findTaskPDA(
+    escrowPDA: PublicKey,
+    taskId: BN
+): [PublicKey, number] {
+    return PublicKey.findProgramAddressSync(
+        [Buffer.from("task"), escrowPDA.toBuffer(), taskId.toArrayLike(Buffer, "le", 8)],
+        PAYMENT_ROUTER_PROGRAM_ID
+    );
+}
+
+export function findTaskGatePDA(taskPDA: PublicKey): [PublicKey, number] {
+    return PublicKey.findProgramAddressSync(
+        [Buffer.from("task_gate"), taskPDA.toBuffer()],
+        PAYMENT_ROUTER_PROGRAM_ID
+    );
+}
+
+export function findTaskPolicyPDA(taskPDA: PublicKey): [PublicKey, number] {
+    return PublicKey.findProgramAddressSync(
+        [Buffer.from("task_policy"), taskPDA.toBuffer()],
+        PAYMENT_ROUTER_PROGRAM_ID
+    );
+}
+
+export function findAttestationIssuerPDA(issuerProgram: PublicKey): [PublicKey, number] {
+    return PublicKey.findProgramAddressSync(
+        [Buffer.from("attestation_issuer"), issuerProgram.toBuffer()],
+        PAYMENT_ROUTER_PROGRAM_ID
+    );
+}
+
+export function findBountyPDA(taskPDA: PublicKey): [PublicKey, number] {
+    return PublicKey.findProgramAddressSync(
+        [Buffer.from("bounty"), taskPDA.toBuffer()],
+        PAYMENT_ROUTER_PROGRAM_ID
+    );
+}
+
+export function findTaskMilestonesPDA(taskPDA: PublicKey): [PublicKey, number] {
+    return PublicKey.findProgramAddressSync(
+        [Buffer.from("task_milestones"), taskPDA.toBuffer()],
+        PAYMENT_ROUTER_PROGRAM_ID
+    );
+}
+
+export function findEscrowObserverPDA(escrowPDA: PublicKey, observer: PublicKey): [PublicKey, number] {
+    return PublicKey.findProgramAddressSync(
+        [Buffer.from("escrow_observer"), escrowPDA.toBuffer(), observer.toBuffer()],
+        PAYMENT_ROUTER_PROGRAM_ID
+    );
+}
+
+export function findDataConsentPDA(humanWallet: PublicKey, scopeHash: Buffer): [PublicKey, number] {
+    return PublicKey.findProgramAddressSync(
+        [Buffer.from("data_consent"), humanWallet.toBuffer(), scopeHash],
+        PAYMENT_ROUTER_PROGRAM_ID
+    );
+}
+
+export function findRecoveryPDA(escrowPDA: PublicKey): [PublicKey, number] {
+    return PublicKey.findProgramAddressSync(
+        [Buffer.from("recovery"), escrowPDA.toBuffer()],
+        PAYMENT_ROUTER_PROGRAM_ID
+    );
+}
+
+export function findPayoutPrefsPDA(humanWallet: PublicKey): [PublicKey, number] {
+    return PublicKey.findProgramAddressSync(
+        [Buffer.from("payout_prefs"), humanWallet.toBuffer()],
+        PAYMENT_ROUTER_PROGRAM_ID
+    );
+}
+
+export function findPayoutSplitPDA(humanWallet: PublicKey): [PublicKey, number] {
+    return PublicKey.findProgramAddressSync(
+        [Buffer.from("payout_split"), humanWallet.toBuffer()],
+        PAYMENT_ROUTER_PROGRAM_ID
+    );
+}
+
+export function findApiKeyPDA(agentPubkey: PublicKey, keyHash: Buffer): [PublicKey, number] {
+    return PublicKey.findProgramAddressSync(
+        [Buffer.from("api_key"), agentPubkey.toBuffer(), keyHash],
+        PAYMENT_ROUTER_PROGRAM_ID
+    );
+}
+
+export function findPaymentIntentPDA(escrowPDA: PublicKey, intentId: BN): [PublicKey, number] {
+    return PublicKey.findProgramAddressSync(
+        [Buffer.from("payment_intent"), escrowPDA.toBuffer(), intentId.toArrayLike(Buffer, "le", 8)],
+        PAYMENT_ROUTER_PROGRAM_ID
+    );
+}
+
+export function findEscrowSummaryPDA(escrowPDA: PublicKey, index: number): [PublicKey, number] {
+    const indexBytes = Buffer.alloc(4);
+    indexBytes.writeUInt32LE(index);
+    return PublicKey.findProgramAddressSync(
+        [Buffer.from("escrow_summary"), escrowPDA.toBuffer(), indexBytes],
+        PAYMENT_ROUTER_PROGRAM_ID
+    );
+}
+
+export function findEscrowSnapshotPDA(escrowPDA: PublicKey, challenge: Buffer): [PublicKey, number] {
+    return PublicKey.findProgramAddressSync(
+        [Buffer.from("escrow_snapshot"), escrowPDA.toBuffer(), challenge],
+        PAYMENT_ROUTER_PROGRAM_ID
+    );
+}
+
+function orgIdBytes(orgId: string): Buffer {
+    const bytes = Buffer.alloc(32);
+    const idBuffer = Buffer.from(orgId);
+    if (idBuffer.length <= 32) {
+        idBuffer.copy(bytes);
+    }
+    return bytes;
+}
+
+export function findInvoicePDA(summaryPDA: PublicKey): [PublicKey, number] {
+    return PublicKey.findProgramAddressSync(
+        [Buffer.from("invoice"), summaryPDA.toBuffer()],
+        PAYMENT_ROUTER_PROGRAM_ID
+    );
+}
+
+export function findInvoiceMintPDA(summaryPDA: PublicKey): [PublicKey, number] {
+    return PublicKey.findProgramAddressSync(
+        [Buffer.from("invoice_mint"), summaryPDA.toBuffer()],
+        PAYMENT_ROUTER_PROGRAM_ID
+    );
+}
+
+export function findOrgPDA(orgId: string): [PublicKey, number] {
+    return PublicKey.findProgramAddressSync(
+        [Buffer.from("org"), orgIdBytes(orgId)],
+        PAYMENT_ROUTER_PROGRAM_ID
+    );
+}
+
+export function findOrgMemberPDA(humanWallet: PublicKey): [PublicKey, number] {
+    return PublicKey.findProgramAddressSync(
+        [Buffer.from("org_member"), humanWallet.toBuffer()],
+        PAYMENT_ROUTER_PROGRAM_ID
+    );
+}
+
+export function findAvailabilityPDA(humanWallet: PublicKey): [PublicKey, number] {
+    return PublicKey.findProgramAddressSync(
+        [Buffer.from("availability"), humanWallet.toBuffer()],
+        PAYMENT_ROUTER_PROGRAM_ID
+    );
+}
+
+export function findBlockedAgentsPDA(humanWallet: PublicKey): [PublicKey, number] {
+    return PublicKey.findProgramAddressSync(
+        [Buffer.from("blocked_agents"), humanWallet.toBuffer()],
+        PAYMENT_ROUTER_PROGRAM_ID
+    );
+}
+
+export function findDepositAuthPDA(agentPubkey: PublicKey, nonce: BN): [PublicKey, number] {
+    return PublicKey.findProgramAddressSync(
+        [Buffer.from("deposit_auth"), agentPubkey.toBuffer(), nonce.toArrayLike(Buffer, "le", 8)],
+        PAYMENT_ROUTER_PROGRAM_ID
+    );
+}
+
+export function findTransferAuthPDA(from: PublicKey, nonce: Buffer): [PublicKey, number] {
+    return PublicKey.findProgramAddressSync(
+        [Buffer.from("transfer_auth"), from.toBuffer(), nonce],
+        PAYMENT_ROUTER_PROGRAM_ID
+    );
+}
+
+export function findEscrowExportPDA(agentPubkey: PublicKey, exportId: BN): [PublicKey, number] {
+    return PublicKey.findProgramAddressSync(
+        [Buffer.from("escrow_export"), agentPubkey.toBuffer(), exportId.toArrayLike(Buffer, "le", 8)],
+        PAYMENT_ROUTER_PROGRAM_ID
+    );
+}
+
+export function findEscrowImportPDA(escrowExportPDA: PublicKey): [PublicKey, number] {
+    return PublicKey.findProgramAddressSync(
+        [Buffer.from("escrow_import"), escrowExportPDA.toBuffer()],
+        PAYMENT_ROUTER_PROGRAM_ID
+    );
+}
+
+export function findReceiptPDA(escrowPDA: PublicKey, nonce: BN): [PublicKey, number] {
+    return PublicKey.findProgramAddressSync(
+        [Buffer.from("receipt"), escrowPDA.toBuffer(), nonce.toArrayLike(Buffer, "le", 8)],
+        PAYMENT_ROUTER_PROGRAM_ID
+    );
+}
+
+export function findHumanStatsPDA(humanWallet: PublicKey): [PublicKey, number] {
+    return PublicKey.findProgramAddressSync(
+        [Buffer.from("human_stats"), humanWallet.toBuffer()],
+        PAYMENT_ROUTER_PROGRAM_ID
+    );
+}
+
+export function findAgentStatsPDA(agentPubkey: PublicKey): [PublicKey, number] {
+    return PublicKey.findProgramAddressSync(
+        [Buffer.from("agent_stats"), agentPubkey.toBuffer()],
+        PAYMENT_ROUTER_PROGRAM_ID
+    );
+}
+
+export function findAgentRatingPDA(agentPubkey: PublicKey): [PublicKey, number] {
+    return PublicKey.findProgramAddressSync(
+        [Buffer.from("agent_rating"), agentPubkey.toBuffer()],
+        PAYMENT_ROUTER_PROGRAM_ID
+    );
+}
+
+export function findCreditLinePDA(escrowPDA: PublicKey): [PublicKey, number] {
+    return PublicKey.findProgramAddressSync(
+        [Buffer.from("credit_line"), escrowPDA.toBuffer()],
+        PAYMENT_ROUTER_PROGRAM_ID
+    );
+}
+
+export function findCreditCollateralPDA(creditLinePDA: PublicKey): [PublicKey, number] {
+    return PublicKey.findProgramAddressSync(
+        [Buffer.from("credit_collateral"), creditLinePDA.toBuffer()],
+        PAYMENT_ROUTER_PROGRAM_ID
+    );
+}
+
+export function findLstCollateralPDA(lstMint: PublicKey): [PublicKey, number] {
+    return PublicKey.findProgramAddressSync(
+        [Buffer.from("lst_collateral"), lstMint.toBuffer()],
+        PAYMENT_ROUTER_PROGRAM_ID
+    );
+}
+
+export function findLstPositionPDA(escrowPDA: PublicKey, lstMint: PublicKey): [PublicKey, number] {
+    return PublicKey.findProgramAddressSync(
+        [Buffer.from("lst_position"), escrowPDA.toBuffer(), lstMint.toBuffer()],
+        PAYMENT_ROUTER_PROGRAM_ID
+    );
+}
+
+export function findLstVaultPDA(lstPositionPDA: PublicKey): [PublicKey, number] {
+    return PublicKey.findProgramAddressSync(
+        [Buffer.from("lst_vault"), lstPositionPDA.toBuffer()],
+        PAYMENT_ROUTER_PROGRAM_ID
+    );
+}
+
+export function findLiquidityBufferPDA(): [PublicKey, number] {
+    return PublicKey.findProgramAddressSync(
+        [Buffer.from("liquidity_buffer")],
+        PAYMENT_ROUTER_PROGRAM_ID
+    );
+}
+
+export function findLiquidityBufferVaultPDA(): [PublicKey, number] {
+    return PublicKey.findProgramAddressSync(
+        [Buffer.from("liquidity_buffer_vault")],
+        PAYMENT_ROUTER_PROGRAM_ID
+    );
+}
+
+export function findMintFeeVaultPDA(mint: PublicKey): [PublicKey, number] {
+    return PublicKey.findProgramAddressSync(
+        [Buffer.from("mint_fee_vault"), mint.toBuffer()],
+        PAYMENT_ROUTER_PROGRAM_ID
+    );
+}
+
+export function findRelayerStatsPDA(relayer: PublicKey): [PublicKey, number] {
+    return PublicKey.findProgramAddressSync(
+        [Buffer.from("relayer_stats"), relayer.toBuffer()],
+        PAYMENT_ROUTER_PROGRAM_ID
+    );
+}
+
+export function findForwardPDA(escrowPDA: PublicKey, forwardId: BN): [PublicKey, number] {
+    return PublicKey.findProgramAddressSync(
+        [Buffer.from("forward"), escrowPDA.toBuffer(), forwardId.toArrayLike(Buffer, "le", 8)],
+        PAYMENT_ROUTER_PROGRAM_ID
+    );
+}
+
+export function findCohortPoolPDA(cohortId: Buffer): [PublicKey, number] {
+    return PublicKey.findProgramAddressSync(
+        [Buffer.from("cohort"), cohortId],
+        PAYMENT_ROUTER_PROGRAM_ID
+    );
+}
+
+export function findCohortMemberPDA(cohortPoolPDA: PublicKey, human: PublicKey): [PublicKey, number] {
+    return PublicKey.findProgramAddressSync(
+        [Buffer.from("cohort_member"), cohortPoolPDA.toBuffer(), human.toBuffer()],
+        PAYMENT_ROUTER_PROGRAM_ID
+    );
+}
+
+export function findCohortEpochPDA(cohortPoolPDA: PublicKey, epoch: BN): [PublicKey, number] {
+    return PublicKey.findProgramAddressSync(
+        [Buffer.from("cohort_epoch"), cohortPoolPDA.toBuffer(), epoch.toArrayLike(Buffer, "le", 8)],
+        PAYMENT_ROUTER_PROGRAM_ID
+    );
+}
+
+export function findCohortDepositPDA(cohortPoolPDA: PublicKey, human: PublicKey): [PublicKey, number] {
+    return PublicKey.findProgramAddressSync(
+        [Buffer.from("cohort_deposit"), cohortPoolPDA.toBuffer(), human.toBuffer()],
+        PAYMENT_ROUTER_PROGRAM_ID
+    );
+}
+
+export function findFaucetPDA(): [PublicKey, number] {
+    return PublicKey.findProgramAddressSync(
+        [Buffer.from("faucet")],
+        PAYMENT_ROUTER_PROGRAM_ID
+    );
+}
+
+export function findFaucetClaimPDA(wallet: PublicKey): [PublicKey, number] {
+    return PublicKey.findProgramAddressSync(
+        [Buffer.from("faucet_claim"), wallet.toBuffer()],
+        PAYMENT_ROUTER_PROGRAM_ID
+    );
+}
+
+export function findPriceIndexPDA(category: Buffer): [PublicKey, number] {
+    return PublicKey.findProgramAddressSync(
+        [Buffer.from("price_index"), category],
+        PAYMENT_ROUTER_PROGRAM_ID
+    );
+}
+
+export function findSpotPricePDA(category: Buffer): [PublicKey, number] {
+    return PublicKey.findProgramAddressSync(
+        [Buffer.from("spot_price"), category],
+        PAYMENT_ROUTER_PROGRAM_ID
+    );
+}
+
+export function findSessionStatsPDA(category: Buffer): [PublicKey, number] {
+    return PublicKey.findProgramAddressSync(
+        [Buffer.from("session_stats"), category],
+        PAYMENT_ROUTER_PROGRAM_ID
+    );
+}
+
+export function findCctpDepositPDA(sourceDomain: number, cctpNonce: BN): [PublicKey, number] {
+    const domain = Buffer.alloc(4);
+    domain.writeUInt32LE(sourceDomain);
+    return PublicKey.findProgramAddressSync(
+        [Buffer.from("cctp_deposit"), domain, cctpNonce.toArrayLike(Buffer, "le", 8)],
+        PAYMENT_ROUTER_PROGRAM_ID
+    );
+}
+
+export function findRevenuePoolPDA(): [PublicKey, number] {
+    return PublicKey.findProgramAddressSync(
+        [Buffer.from("revenue_pool")],
+        PAYMENT_ROUTER_PROGRAM_ID
+    );
+}
+
+export function findRevenueShareMintPDA(positionIndex: BN): [PublicKey, number] {
+    return PublicKey.findProgramAddressSync(
+        [Buffer.from("revenue_share_mint"), positionIndex.toArrayLike(Buffer, "le", 8)],
+        PAYMENT_ROUTER_PROGRAM_ID
+    );
+}
+
+export function findRevenueSharePDA(nftMint: PublicKey): [PublicKey, number] {
+    return PublicKey.findProgramAddressSync(
+        [Buffer.from("revenue_share"), nftMint.toBuffer()],
+        PAYMENT_ROUTER_PROGRAM_ID
+    );
+}
+
+export function findFeeEpochPDA(epoch: BN): [PublicKey, number] {
+    return PublicKey.findProgramAddressSync(
+        [Buffer.from("fee_epoch"), epoch.toArrayLike(Buffer, "le", 8)],
+        PAYMENT_ROUTER_PROGRAM_ID
+    );
+}
+
+export function findEscrowPositionMintPDA(escrowPDA: PublicKey): [PublicKey, number] {
+    return PublicKey.findProgramAddressSync(
+        [Buffer.from("escrow_position"), escrowPDA.toBuffer()],
+        PAYMENT_ROUTER_PROGRAM_ID
+    );
+}
+
+export function findSponsorPositionPDA(
+    escrowPDA: PublicKey,
+    sponsor: PublicKey
+): [PublicKey, number] {
+    return PublicKey.findProgramAddressSync(
+        [Buffer.from("sponsor"), escrowPDA.toBuffer(), sponsor.toBuffer()],
+        PAYMENT_ROUTER_PROGRAM_ID
+    );
+}
+
+export function findHoldbackPDA(humanWallet: PublicKey): [PublicKey, number] {
+    return PublicKey.findProgramAddressSync(
+        [Buffer.from("holdback"), humanWallet.toBuffer()],
+        PAYMENT_ROUTER_PROGRAM_ID
+    );
+}
+
+/** Signer of a Clockwork thread; pass it to setAutomationAuthority. */
+export function findThreadPDA(threadAuthority: PublicKey, threadId: string): [PublicKey, number] {
+    return PublicKey.findProgramAddressSync(
+        [Buffer.from("thread"), threadAuthority.toBuffer(), Buffer.from(threadId)],
+        CLOCKWORK_THREAD_PROGRAM_ID
+    );
+}
+
+export function findShardPDA(shardId: number): [PublicKey, number] {
+    return PublicKey.findProgramAddressSync(
+        [Buffer.from("shard"), Buffer.from([shardId])],
+        PAYMENT_ROUTER_PROGRAM_ID
+    );
+}
+
+/** Shard an agent falls in (matches MarketConfig::shard_for); null when unsharded. */
+export function shardForAgent(agentPubkey: PublicKey, shardCount: number): number | null {
+    if (shardCount === 0) {
+        return null;
+    }
+    return Math.floor((agentPubkey.toBuffer()[0] * shardCount) / 256);
+}
+
+export function findSettlementQueuePDA(): [PublicKey, number] {
+    return PublicKey.findProgramAddressSync(
+        [Buffer.from("settlement_queue")],
+        PAYMENT_ROUTER_PROGRAM_ID
+    );
+}
+
+export function findQueuedSettlementPDA(seq: BN): [PublicKey, number] {
+    return PublicKey.findProgramAddressSync(
+        [Buffer.from("queued_settlement"), seq.toArrayLike(Buffer, "le", 8)],
+        PAYMENT_ROUTER_PROGRAM_ID
+    );
+}
+
+export function findAttestationKeyPDA(key: PublicKey): [PublicKey, number] {
+    return PublicKey.findProgramAddressSync(
+        [Buffer.from("attestation_key"), key.toBuffer()],
+        PAYMENT_ROUTER_PROGRAM_ID
+    );
+}
+
+export function findEmissionSchedulePDA(): [PublicKey, number] {
+    return PublicKey.findProgramAddressSync(
+        [Buffer.from("emissions")],
+        PAYMENT_ROUTER_PROGRAM_ID
+    );
+}
+
+export function findAttnMintPDA(): [PublicKey, number] {
+    return PublicKey.findProgramAddressSync(
+        [Buffer.from("attn_mint")],
+        PAYMENT_ROUTER_PROGRAM_ID
+    );
+}
+
+// --- IDL placeholder (would be generated by anchor build) ---
+// In production, import the generated IDL from target/idl/payment_router.json
+// --- IDL placeholder (would be generated by anchor build) ---
+// In production, import the generated IDL from target/idl/payment_router.json
+const IDL_PLACEHOLDER: any = {
+    version: "0.1.0",
+    name: "payment_router",
+    instructions: [],
+    accounts: [],
+};
+
+// --- Main Client Class ---
+
+export class PaymentRouterClient {
+    private program: Program;
+    private provider: AnchorProvider;
+    private connection: Connection;
+
+    constructor(provider: AnchorProvider, idl?: Idl) {
+        this.provider = provider;
+        this.connection = provider.connection;
+        this.program = new Program(
+            idl ?? IDL_PLACEHOLDER,
+            PAYMENT_ROUTER_PROGRAM_ID,
+            provider
+        );
+    }
+
+    // --- Initialize Market Config ---
+
+    async initializeMarketConfig(
+        admin: Keypair,
+        feeBasisPoints: number,
+        paymentMint: PublicKey = USDC_MINT
+    ): Promise<string> {
+        const [configPDA] = findMarketConfigPDA();
+
+        const tx = await this.program.methods
+            .initializeMarketConfig(feeBasisPoints)
+            .accounts({
+                admin: admin.publicKey,
+                config: configPDA,
+                paymentMint,
+                systemProgram: SystemProgram.programId,
+            })
+            .signers([admin])
+            .rpc();
+
+        return tx;
+    }
+
+    // Only succeeds before the fee vault has been initialized
+    async reinitializeMarketConfig(
+        admin: Keypair,
+        feeBasisPoints: number,
+        paymentMint: PublicKey = USDC_MINT
+    ): Promise<string> {
+        const [configPDA] = findMarketConfigPDA();
+        const [feeVaultStatePDA] = findFeeVaultStatePDA();
+
+        const tx = await (this.program.methods as any)
+            .reinitializeMarketConfig(feeBasisPoints)
+            .accounts({
+                admin: admin.publicKey,
+                config: configPDA,
+                feeVaultState: feeVaultStatePDA,
+                paymentMint,
+            })
+            .signers([admin])
+            .rpc();
+
+        return tx;
+    }
+
+    // --- Initialize Fee Vault (NEW) ---
+    async initializeFeeVault(
+        admin: Keypair,
+        mint: PublicKey
+    ): Promise<string> {
+        const [feeVaultStatePDA] = findFeeVaultStatePDA();
+        const [feeVaultPDA] = findFeeVaultPDA(feeVaultStatePDA);
+
+        const tx = await (this.program.methods as any)
+            .initializeFeeVault()
+            .accounts({
+                admin: admin.publicKey,
+                feeVaultState: feeVaultStatePDA,
+                feeVault: feeVaultPDA,
+                mint: mint,
+                marketConfig: findMarketConfigPDA()[0],
+                systemProgram: SystemProgram.programId,
+                tokenProgram: TOKEN_PROGRAM_ID,
+                rent: SYSVAR_RENT_PUBKEY,
+            })
+            .signers([admin])
+            .rpc();
+
+        return tx;
+    }
+
+    // --- Settlement Shards (disjoint agent key ranges per worker) ---
+
+    async setShardSigner(admin: Keypair, shardId: number, signer: PublicKey): Promise<string> {
+        const tx = await (this.program.methods as any)
+            .setShardSigner(shardId, signer)
+            .accounts({
+                admin: admin.publicKey,
+                marketConfig: findMarketConfigPDA()[0],
+                shard: findShardPDA(shardId)[0],
+                systemProgram: SystemProgram.programId,
+            })
+            .signers([admin])
+            .rpc();
+
+        return tx;
+    }
+
+    async assignEscrowShard(agentPubkey: PublicKey): Promise<string> {
+        return (this.program.methods as any)
+            .assignEscrowShard()
+            .accounts({
+                escrowAccount: findEscrowPDA(agentPubkey)[0],
+                marketConfig: findMarketConfigPDA()[0],
+            })
+            .rpc();
+    }
+
+    // --- Automation (thread PDA may run maintenance without the admin key) ---
+
+    async setAutomationAuthority(
+        admin: Keypair,
+        automationAuthority: PublicKey // PublicKey.default revokes
+    ): Promise<string> {
+        const tx = await (this.program.methods as any)
+            .setAutomationAuthority(automationAuthority)
+            .accounts({
+                admin: admin.publicKey,
+                marketConfig: findMarketConfigPDA()[0],
+            })
+            .signers([admin])
+            .rpc();
+
+        return tx;
+    }
+
+    /** Builds expire_session for a thread to schedule; `operator` is the thread PDA. */
+    async expireSessionInstruction(
+        operator: PublicKey,
+        agentPubkey: PublicKey,
+        sessionId: BN
+    ): Promise<TransactionInstruction> {
+        const [escrowPDA] = findEscrowPDA(agentPubkey);
+        const [configPDA] = findMarketConfigPDA();
+        const config = await (this.program.account as any).marketConfig.fetch(configPDA);
+
+        return (this.program.methods as any)
+            .expireSession()
+            .accounts({
+                operator,
+                router: config.authority,
+                session: findSessionPDA(escrowPDA, sessionId)[0],
+                escrowAccount: escrowPDA,
+                marketConfig: configPDA,
+            })
+            .instruction();
+    }
+
+    // --- Router Liveness (stale heartbeat opens agent and human fallbacks) ---
+
+    async setHeartbeatTimeout(admin: Keypair, heartbeatTimeout: BN): Promise<string> {
+        const tx = await (this.program.methods as any)
+            .setHeartbeatTimeout(heartbeatTimeout) // Seconds; 0 turns liveness checks off
+            .accounts({
+                admin: admin.publicKey,
+                marketConfig: findMarketConfigPDA()[0],
+            })
+            .signers([admin])
+            .rpc();
+
+        return tx;
+    }
+
+    async setStandbyAuthority(
+        admin: Keypair,
+        standbyAuthority: PublicKey, // PublicKey.default removes the standby
+        failoverDelay: BN // Seconds stale past the heartbeat timeout before it takes over
+    ): Promise<string> {
+        const tx = await (this.program.methods as any)
+            .setStandbyAuthority(standbyAuthority, failoverDelay)
+            .accounts({
+                admin: admin.publicKey,
+                marketConfig: findMarketConfigPDA()[0],
+            })
+            .signers([admin])
+            .rpc();
+
+        return tx;
+    }
+
+    async routerHeartbeat(router: Keypair): Promise<string> {
+        const tx = await (this.program.methods as any)
+            .routerHeartbeat()
+            .accounts({
+                router: router.publicKey,
+                marketConfig: findMarketConfigPDA()[0],
+            })
+            .signers([router])
+            .rpc();
+
+        return tx;
+    }
+
+    /** Withdraws including open-session reserves; only accepted while the router is stale. */
+    async fastWithdrawEscrow(agent: Keypair, amount: BN, vaultAddress: PublicKey): Promise<string> {
+        const tx = await (this.program.methods as any)
+            .fastWithdrawEscrow(amount)
+            .accounts({
+                agent: agent.publicKey,
+                agentTokenAccount: await getAssociatedTokenAddress(USDC_MINT, agent.publicKey),
+                escrowAccount: findEscrowPDA(agent.publicKey)[0],
+                vault: vaultAddress,
+                marketConfig: findMarketConfigPDA()[0],
+                tokenProgram: TOKEN_PROGRAM_ID,
+            })
+            .signers([agent])
+            .rpc();
+
+        return tx;
+    }
+
+    /**
+     * Withdraws whatever is withdrawable when the transaction lands. With `close`, the
+     * emptied escrow and vault are closed in the same transaction; that fails (and so
+     * does the withdrawal) if sponsored funds or session reserves remain.
+     */
+    async withdrawAll(agent: Keypair, vaultAddress: PublicKey, close = false): Promise<string> {
+        const agentTokenAccount = await getAssociatedTokenAddress(USDC_MINT, agent.publicKey);
+        const escrowAccount = findEscrowPDA(agent.publicKey)[0];
+        const post = close
+            ? [
+                  await (this.program.methods as any)
+                      .closeEscrow()
+                      .accounts({
+                          agent: agent.publicKey,
+                          agentTokenAccount,
+                          escrowAccount,
+                          vault: vaultAddress,
+                          tokenProgram: TOKEN_PROGRAM_ID,
+                      })
+                      .instruction(),
+              ]
+            : [];
+        const tx = await (this.program.methods as any)
+            .withdrawEscrow(WITHDRAW_ALL)
+            .accounts({
+                agent: agent.publicKey,
+                agentTokenAccount,
+                escrowAccount,
+                vault: vaultAddress,
+                tokenProgram: TOKEN_PROGRAM_ID,
+            })
+            .postInstructions(post)
+            .signers([agent])
+            .rpc();
+
+        return tx;
+    }
+
+    // --- Settlement Circuit Breaker (pauses settlement when rolling-hour volume exceeds a ceiling) ---
+
+    async setVelocityCeiling(admin: Keypair, velocityCeiling: BN): Promise<string> {
+        const tx = await (this.program.methods as any)
+            .setVelocityCeiling(velocityCeiling)
+            .accounts({
+                admin: admin.publicKey,
+                marketConfig: findMarketConfigPDA()[0],
+            })
+            .signers([admin])
+            .rpc();
+
+        return tx;
+    }
+
+    async resumeSettlements(admin: Keypair): Promise<string> {
+        const tx = await (this.program.methods as any)
+            .resumeSettlements()
+            .accounts({
+                admin: admin.publicKey,
+                marketConfig: findMarketConfigPDA()[0],
+            })
+            .signers([admin])
+            .rpc();
+
+        return tx;
+    }
+
+    // --- Fee Holiday (settlements in [start, end) pay no protocol fee; start == end cancels) ---
+
+    async setFeeHoliday(authority: Keypair, start: BN, end: BN): Promise<string> {
+        const tx = await (this.program.methods as any)
+            .setFeeHoliday(start, end)
+            .accounts({
+                authority: authority.publicKey,
+                marketConfig: findMarketConfigPDA()[0],
+            })
+            .signers([authority])
+            .rpc();
+
+        return tx;
+    }
+
+    // --- Fee Challenge Window (settlement fees stay pending, unclaimable, until it passes) ---
+
+    async setFeeChallengeWindow(authority: Keypair, seconds: BN): Promise<string> {
+        const tx = await (this.program.methods as any)
+            .setFeeChallengeWindow(seconds)
+            .accounts({
+                authority: authority.publicKey,
+                marketConfig: findMarketConfigPDA()[0],
+                feeVaultState: findFeeVaultStatePDA()[0],
+            })
+            .signers([authority])
+            .rpc();
+
+        return tx;
+    }
+
+    // --- Per-Escrow Fee Override (market authority; null = market fee) ---
+
+    async setFeeBpsOverride(
+        admin: Keypair,
+        agentPubkey: PublicKey,
+        feeBpsOverride: number | null
+    ): Promise<string> {
+        const [escrowPDA] = findEscrowPDA(agentPubkey);
+
+        const tx = await (this.program.methods as any)
+            .setFeeBpsOverride(feeBpsOverride)
+            .accounts({
+                admin: admin.publicKey,
+                marketConfig: findMarketConfigPDA()[0],
+                escrowAccount: escrowPDA,
+            })
+            .signers([admin])
+            .rpc();
+
+        return tx;
+    }
+
+    // --- Quality Multiplier (engagement bounds on checkpoint prices; 10000 = 1x) ---
+
+    async setQualityMultiplierBounds(admin: Keypair, minBps: number, maxBps: number): Promise<string> {
+        const tx = await (this.program.methods as any)
+            .setQualityMultiplierBounds(minBps, maxBps)
+            .accounts({
+                admin: admin.publicKey,
+                marketConfig: findMarketConfigPDA()[0],
+            })
+            .signers([admin])
+            .rpc();
+
+        return tx;
+    }
+
+    // --- KYC Tiers (escrow balance caps per admin-attested tier; 0 = uncapped) ---
+
+    async setDepositCaps(admin: Keypair, depositCaps: BN[]): Promise<string> {
+        const tx = await (this.program.methods as any)
+            .setDepositCaps(depositCaps)
+            .accounts({
+                admin: admin.publicKey,
+                marketConfig: findMarketConfigPDA()[0],
+            })
+            .signers([admin])
+            .rpc();
+
+        return tx;
+    }
+
+    async setKycTier(admin: Keypair, agentPubkey: PublicKey, kycTier: number): Promise<string> {
+        const tx = await (this.program.methods as any)
+            .setKycTier(kycTier)
+            .accounts({
+                admin: admin.publicKey,
+                marketConfig: findMarketConfigPDA()[0],
+                escrowAccount: findEscrowPDA(agentPubkey)[0],
+            })
+            .signers([admin])
+            .rpc();
+
+        return tx;
+    }
+
+    // --- Deposit Escrow ---
+
+    async depositEscrow(
+        agent: Keypair,
+        amount: BN,
+        vaultAddress: PublicKey
+    ): Promise<string> {
+        const [escrowPDA] = findEscrowPDA(agent.publicKey);
+        const agentTokenAccount = await getAssociatedTokenAddress(
+            USDC_MINT,
+            agent.publicKey
+        );
+
+        const tx = await this.program.methods
+            .depositEscrow(amount)
+            .accounts({
+                agent: agent.publicKey,
+                agentTokenAccount: agentTokenAccount,
+                escrowAccount: escrowPDA,
+                vault: vaultAddress,
+                tokenProgram: TOKEN_PROGRAM_ID,
+                systemProgram: SystemProgram.programId,
+                rent: SYSVAR_RENT_PUBKEY,
+                creditLine: null, // Optional (repays underwritten debt first)
+                creditCollateral: null, // Optional
+                mintFeeVault: null, // Optional (secondary-mint escrows)
+            })
+            .signers([agent])
+            .rpc();
+
+        return tx;
+    }
+
+    // --- Durable Nonce (deposits/withdrawals signed offline, submitted hours later) ---
+    //
+    // An online machine prepares the transaction against the agent's nonce account; the
+    // air-gapped treasury signs it; anyone submits it later. The nonce advance is always
+    // instruction 0. Program instructions that check a preceding ed25519 instruction read
+    // it relative to their own index, so add that pair back to back after the advance.
+
+    async depositEscrowInstruction(
+        agentPubkey: PublicKey,
+        amount: BN,
+        vaultAddress: PublicKey
+    ): Promise<TransactionInstruction> {
+        const [feeVaultStatePDA] = findFeeVaultStatePDA();
+
+        return (this.program.methods as any)
+            .depositEscrow(amount)
+            .accounts({
+                agent: agentPubkey,
+                agentTokenAccount: await getAssociatedTokenAddress(USDC_MINT, agentPubkey),
+                escrowAccount: findEscrowPDA(agentPubkey)[0],
+                marketConfig: findMarketConfigPDA()[0],
+                feeVaultState: feeVaultStatePDA,
+                feeVault: findFeeVaultPDA(feeVaultStatePDA)[0],
+                vault: vaultAddress,
+                tokenProgram: TOKEN_PROGRAM_ID,
+                systemProgram: SystemProgram.programId,
+                rent: SYSVAR_RENT_PUBKEY,
+                creditLine: null,
+                creditCollateral: null,
+                mintFeeVault: null,
+            })
+            .instruction();
+    }
+
+    async withdrawEscrowInstruction(
+        agentPubkey: PublicKey,
+        amount: BN,
+        vaultAddress: PublicKey
+    ): Promise<TransactionInstruction> {
+        return (this.program.methods as any)
+            .withdrawEscrow(amount)
+            .accounts({
+                agent: agentPubkey,
+                agentTokenAccount: await getAssociatedTokenAddress(USDC_MINT, agentPubkey),
+                escrowAccount: findEscrowPDA(agentPubkey)[0],
+                vault: vaultAddress,
+                tokenProgram: TOKEN_PROGRAM_ID,
+            })
+            .instruction();
+    }
+
+    /**
+     * Unsigned transaction pinned to the nonce account's current value instead of a
+     * recent blockhash. Serialize with `requireAllSignatures: false` to carry it to the
+     * offline signer; it stays valid until the nonce is advanced.
+     */
+    async prepareDurableNonceTransaction(
+        feePayer: PublicKey,
+        durableNonce: DurableNonce,
+        instructions: TransactionInstruction[]
+    ): Promise<Transaction> {
+        const nonceAccount = await this.connection.getNonce(durableNonce.nonceAccount, "confirmed");
+        if (!nonceAccount) {
+            throw new Error(`Nonce account ${durableNonce.nonceAccount.toBase58()} not found`);
+        }
+        if (!nonceAccount.authorizedPubkey.equals(durableNonce.authority)) {
+            throw new Error("Nonce authority does not match the nonce account");
+        }
+
+        const tx = new Transaction({
+            feePayer,
+            nonceInfo: {
+                nonce: nonceAccount.nonce,
+                nonceInstruction: SystemProgram.nonceAdvance({
+                    noncePubkey: durableNonce.nonceAccount,
+                    authorizedPubkey: durableNonce.authority,
+                }),
+            },
+        });
+        tx.add(...instructions);
+        return tx;
+    }
+
+    /** Submits a fully signed durable-nonce transaction and waits for confirmation. */
+    async submitDurableNonceTransaction(signed: Transaction | Buffer): Promise<string> {
+        const raw = Buffer.isBuffer(signed) ? signed : signed.serialize();
+        const signature = await this.connection.sendRawTransaction(raw);
+        const result = await this.connection.confirmTransaction(signature, "confirmed");
+        if (result.value.err) {
+            throw new Error(`Transaction failed: ${JSON.stringify(result.value.err)}`);
+        }
+        return signature;
+    }
+
+    // --- Fund Tasks Batch (one transfer split across existing tasks) ---
+
+    async fundTasksBatch(
+        agent: Keypair,
+        tasks: { taskId: BN; amount: BN }[],
+        vaultAddress: PublicKey
+    ): Promise<string> {
+        const [escrowPDA] = findEscrowPDA(agent.publicKey);
+        const [feeVaultStatePDA] = findFeeVaultStatePDA();
+        const [feeVaultPDA] = findFeeVaultPDA(feeVaultStatePDA);
+        const agentTokenAccount = await getAssociatedTokenAddress(USDC_MINT, agent.publicKey);
+
+        const tx = await (this.program.methods as any)
+            .fundTasksBatch(tasks.map((t) => t.amount))
+            .accounts({
+                agent: agent.publicKey,
+                agentTokenAccount: agentTokenAccount,
+                escrowAccount: escrowPDA,
+                feeVaultState: feeVaultStatePDA,
+                feeVault: feeVaultPDA,
+                vault: vaultAddress,
+                marketConfig: findMarketConfigPDA()[0],
+                tokenProgram: TOKEN_PROGRAM_ID,
+                mintFeeVault: null, // Optional (secondary-mint escrows)
+            })
+            .remainingAccounts(
+                tasks.map((t) => ({
+                    pubkey: findTaskPDA(escrowPDA, t.taskId)[0],
+                    isSigner: false,
+                    isWritable: true,
+                }))
+            )
+            .signers([agent])
+            .rpc();
+
+        return tx;
+    }
+
+    // --- Task NFT Gates (sessions require a verified collection NFT) ---
+
+    async setTaskGate(agent: Keypair, taskId: BN, collection: PublicKey): Promise<string> {
+        const [escrowPDA] = findEscrowPDA(agent.publicKey);
+        const [taskPDA] = findTaskPDA(escrowPDA, taskId);
+        const [taskGatePDA] = findTaskGatePDA(taskPDA);
+
+        const tx = await (this.program.methods as any)
+            .setTaskGate(collection)
+            .accounts({
+                agent: agent.publicKey,
+                escrowAccount: escrowPDA,
+                task: taskPDA,
+                taskGate: taskGatePDA,
+                systemProgram: SystemProgram.programId,
+            })
+            .signers([agent])
+            .rpc();
+
+        return tx;
+    }
+
+    async clearTaskGate(agent: Keypair, taskId: BN): Promise<string> {
+        const [escrowPDA] = findEscrowPDA(agent.publicKey);
+        const [taskGatePDA] = findTaskGatePDA(findTaskPDA(escrowPDA, taskId)[0]);
+
+        const tx = await (this.program.methods as any)
+            .clearTaskGate()
+            .accounts({
+                agent: agent.publicKey,
+                escrowAccount: escrowPDA,
+                taskGate: taskGatePDA,
+            })
+            .signers([agent])
+            .rpc();
+
+        return tx;
+    }
+
+    // --- Task Attestation Policies (age/region attestations from whitelisted issuers) ---
+
+    async setTaskPolicy(
+        agent: Keypair,
+        taskId: BN,
+        policyHash: number[],
+        attestationType: "ageOver18" | "region" | "ageOver18AndRegion"
+    ): Promise<string> {
+        const [escrowPDA] = findEscrowPDA(agent.publicKey);
+        const [taskPDA] = findTaskPDA(escrowPDA, taskId);
+        const [taskPolicyPDA] = findTaskPolicyPDA(taskPDA);
+
+        const tx = await (this.program.methods as any)
+            .setTaskPolicy(policyHash, { [attestationType]: {} })
+            .accounts({
+                agent: agent.publicKey,
+                escrowAccount: escrowPDA,
+                task: taskPDA,
+                taskPolicy: taskPolicyPDA,
+                systemProgram: SystemProgram.programId,
+            })
+            .signers([agent])
+            .rpc();
+
+        return tx;
+    }
+
+    async clearTaskPolicy(agent: Keypair, taskId: BN): Promise<string> {
+        const [escrowPDA] = findEscrowPDA(agent.publicKey);
+        const [taskPolicyPDA] = findTaskPolicyPDA(findTaskPDA(escrowPDA, taskId)[0]);
+
+        const tx = await (this.program.methods as any)
+            .clearTaskPolicy()
+            .accounts({
+                agent: agent.publicKey,
+                escrowAccount: escrowPDA,
+                taskPolicy: taskPolicyPDA,
+            })
+            .signers([agent])
+            .rpc();
+
+        return tx;
+    }
+
+    async addAttestationIssuer(admin: Keypair, issuerProgram: PublicKey): Promise<string> {
+        const tx = await (this.program.methods as any)
+            .addAttestationIssuer(issuerProgram)
+            .accounts({
+                admin: admin.publicKey,
+                marketConfig: findMarketConfigPDA()[0],
+                attestationIssuer: findAttestationIssuerPDA(issuerProgram)[0],
+                systemProgram: SystemProgram.programId,
+            })
+            .signers([admin])
+            .rpc();
+
+        return tx;
+    }
+
+    async revokeAttestationIssuer(admin: Keypair, issuerProgram: PublicKey): Promise<string> {
+        const tx = await (this.program.methods as any)
+            .revokeAttestationIssuer()
+            .accounts({
+                admin: admin.publicKey,
+                marketConfig: findMarketConfigPDA()[0],
+                attestationIssuer: findAttestationIssuerPDA(issuerProgram)[0],
+            })
+            .signers([admin])
+            .rpc();
+
+        return tx;
+    }
+
+    // --- Bounties (fixed payouts on router-attested completion) ---
+
+    async postBounty(agent: Keypair, taskId: BN, human: PublicKey, amount: BN): Promise<string> {
+        const [escrowPDA] = findEscrowPDA(agent.publicKey);
+        const [taskPDA] = findTaskPDA(escrowPDA, taskId);
+
+        const tx = await (this.program.methods as any)
+            .postBounty(human, amount)
+            .accounts({
+                agent: agent.publicKey,
+                escrowAccount: escrowPDA,
+                task: taskPDA,
+                bounty: findBountyPDA(taskPDA)[0],
+                systemProgram: SystemProgram.programId,
+            })
+            .signers([agent])
+            .rpc();
+
+        return tx;
+    }
+
+    async completeBounty(
+        router: Keypair,
+        agentPubkey: PublicKey,
+        taskId: BN,
+        human: PublicKey,
+        completedBps: number,
+        completionHash: number[],
+        vaultAddress: PublicKey
+    ): Promise<string> {
+        const [escrowPDA] = findEscrowPDA(agentPubkey);
+        const [bountyPDA] = findBountyPDA(findTaskPDA(escrowPDA, taskId)[0]);
+        const [feeVaultStatePDA] = findFeeVaultStatePDA();
+        const [feeVaultPDA] = findFeeVaultPDA(feeVaultStatePDA);
+
+        const { org, orgTreasury } = await this.orgAccounts(human);
+
+        const tx = await (this.program.methods as any)
+            .completeBounty(completedBps, completionHash)
+            .accounts({
+                router: router.publicKey,
+                escrowAccount: escrowPDA,
+                bounty: bountyPDA,
+                vault: vaultAddress,
+                human,
+                payoutPrefs: findPayoutPrefsPDA(human)[0],
+                payoutSplit: findPayoutSplitPDA(human)[0],
+                orgMember: findOrgMemberPDA(human)[0],
+                userWallet: await getAssociatedTokenAddress(USDC_MINT, human),
+                mint: USDC_MINT,
+                feeVaultState: feeVaultStatePDA,
+                feeVault: feeVaultPDA,
+                marketConfig: findMarketConfigPDA()[0],
+                tokenProgram: TOKEN_PROGRAM_ID,
+                associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+                systemProgram: SystemProgram.programId,
+                shard: null, // Optional (sharded escrows)
+                holdback: findHoldbackPDA(human)[0],
+                org: org, // Required when the human belongs to an org
+                orgTreasury: orgTreasury,
+            })
+            .remainingAccounts(await this.payoutSplitLegs(human))
+            .signers([router])
+            .rpc();
+
+        return tx;
+    }
+
+    async cancelBounty(agent: Keypair, taskId: BN): Promise<string> {
+        const [taskPDA] = findTaskPDA(findEscrowPDA(agent.publicKey)[0], taskId);
+
+        const tx = await (this.program.methods as any)
+            .cancelBounty()
+            .accounts({
+                agent: agent.publicKey,
+                task: taskPDA,
+                bounty: findBountyPDA(taskPDA)[0],
+            })
+            .signers([agent])
+            .rpc();
+
+        return tx;
+    }
+
+    // --- Task Milestones (up to 8 individually released payouts) ---
+
+    async setTaskMilestones(
+        agent: Keypair,
+        taskId: BN,
+        human: PublicKey,
+        milestones: { amount: BN; descriptionHash: number[] }[]
+    ): Promise<string> {
+        const [taskPDA] = findTaskPDA(findEscrowPDA(agent.publicKey)[0], taskId);
+
+        const tx = await (this.program.methods as any)
+            .setTaskMilestones(human, milestones)
+            .accounts({
+                agent: agent.publicKey,
+                task: taskPDA,
+                taskMilestones: findTaskMilestonesPDA(taskPDA)[0],
+                systemProgram: SystemProgram.programId,
+            })
+            .signers([agent])
+            .rpc();
+
+        return tx;
+    }
+
+    async releaseMilestone(
+        router: Keypair,
+        agentPubkey: PublicKey,
+        taskId: BN,
+        human: PublicKey,
+        index: number,
+        vaultAddress: PublicKey
+    ): Promise<string> {
+        const [escrowPDA] = findEscrowPDA(agentPubkey);
+        const [milestonesPDA] = findTaskMilestonesPDA(findTaskPDA(escrowPDA, taskId)[0]);
+        const [feeVaultStatePDA] = findFeeVaultStatePDA();
+        const [feeVaultPDA] = findFeeVaultPDA(feeVaultStatePDA);
+
+        const { org, orgTreasury } = await this.orgAccounts(human);
+
+        const tx = await (this.program.methods as any)
+            .releaseMilestone(index)
+            .accounts({
+                router: router.publicKey,
+                escrowAccount: escrowPDA,
+                taskMilestones: milestonesPDA,
+                vault: vaultAddress,
+                human,
+                payoutPrefs: findPayoutPrefsPDA(human)[0],
+                payoutSplit: findPayoutSplitPDA(human)[0],
+                orgMember: findOrgMemberPDA(human)[0],
+                userWallet: await getAssociatedTokenAddress(USDC_MINT, human),
+                mint: USDC_MINT,
+                feeVaultState: feeVaultStatePDA,
+                feeVault: feeVaultPDA,
+                marketConfig: findMarketConfigPDA()[0],
+                tokenProgram: TOKEN_PROGRAM_ID,
+                associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+                systemProgram: SystemProgram.programId,
+                shard: null, // Optional (sharded escrows)
+                holdback: findHoldbackPDA(human)[0],
+                org: org, // Required when the human belongs to an org
+                orgTreasury: orgTreasury,
+            })
+            .remainingAccounts(await this.payoutSplitLegs(human))
+            .signers([router])
+            .rpc();
+
+        return tx;
+    }
+
+    async cancelMilestone(agent: Keypair, taskId: BN, index: number): Promise<string> {
+        const [escrowPDA] = findEscrowPDA(agent.publicKey);
+
+        const tx = await (this.program.methods as any)
+            .cancelMilestone(index)
+            .accounts({
+                agent: agent.publicKey,
+                escrowAccount: escrowPDA,
+                taskMilestones: findTaskMilestonesPDA(findTaskPDA(escrowPDA, taskId)[0])[0],
+            })
+            .signers([agent])
+            .rpc();
+
+        return tx;
+    }
+
+    // --- Escrow Observers (read-side roles receiving enriched settlement events) ---
+
+    async grantObserver(agent: Keypair, observer: PublicKey, tag: number[]): Promise<string> {
+        const [escrowPDA] = findEscrowPDA(agent.publicKey);
+
+        const tx = await (this.program.methods as any)
+            .grantObserver(tag)
+            .accounts({
+                agent: agent.publicKey,
+                escrowAccount: escrowPDA,
+                observer,
+                escrowObserver: findEscrowObserverPDA(escrowPDA, observer)[0],
+                systemProgram: SystemProgram.programId,
+            })
+            .signers([agent])
+            .rpc();
+
+        return tx;
+    }
+
+    async acceptObserver(observer: Keypair, agentPubkey: PublicKey): Promise<string> {
+        const [escrowPDA] = findEscrowPDA(agentPubkey);
+
+        const tx = await (this.program.methods as any)
+            .acceptObserver()
+            .accounts({
+                observer: observer.publicKey,
+                escrowObserver: findEscrowObserverPDA(escrowPDA, observer.publicKey)[0],
+            })
+            .signers([observer])
+            .rpc();
+
+        return tx;
+    }
+
+    async revokeObserver(agent: Keypair, observer: PublicKey): Promise<string> {
+        const [escrowPDA] = findEscrowPDA(agent.publicKey);
+
+        const tx = await (this.program.methods as any)
+            .revokeObserver()
+            .accounts({
+                agent: agent.publicKey,
+                escrowAccount: escrowPDA,
+                escrowObserver: findEscrowObserverPDA(escrowPDA, observer)[0],
+            })
+            .signers([agent])
+            .rpc();
+
+        return tx;
+    }
+
+    // --- Data Consent (humans license anonymized engagement data per scope) ---
+
+    async grantDataConsent(human: Keypair, scopeHash: Buffer, expiry: BN): Promise<string> {
+        const tx = await (this.program.methods as any)
+            .grantDataConsent(Array.from(scopeHash), expiry)
+            .accounts({
+                human: human.publicKey,
+                dataConsent: findDataConsentPDA(human.publicKey, scopeHash)[0],
+                systemProgram: SystemProgram.programId,
+            })
+            .signers([human])
+            .rpc();
+
+        return tx;
+    }
+
+    async revokeDataConsent(human: Keypair, scopeHash: Buffer): Promise<string> {
+        const tx = await (this.program.methods as any)
+            .revokeDataConsent()
+            .accounts({
+                human: human.publicKey,
+                dataConsent: findDataConsentPDA(human.publicKey, scopeHash)[0],
+            })
+            .signers([human])
+            .rpc();
+
+        return tx;
+    }
+
+    // --- Deposit With Authorization (relayer pays, agent signs off-chain) ---
+
+    async depositWithAuthorization(
+        relayer: Keypair,
+        agentPubkey: PublicKey,
+        amount: BN,
+        expiresAt: BN,
+        nonce: BN,
+        agentSignature: Uint8Array,
+        vaultAddress: PublicKey
+    ): Promise<string> {
+        const [escrowPDA] = findEscrowPDA(agentPubkey);
+        const [feeVaultStatePDA] = findFeeVaultStatePDA();
+        const [feeVaultPDA] = findFeeVaultPDA(feeVaultStatePDA);
+        const agentTokenAccount = await getAssociatedTokenAddress(USDC_MINT, agentPubkey);
+
+        // Must sit immediately before the deposit instruction
+        const signatureIx = Ed25519Program.createInstructionWithPublicKey({
+            publicKey: agentPubkey.toBytes(),
+            message: depositAuthorizationMessage(agentPubkey, amount, expiresAt, nonce),
+            signature: agentSignature,
+        });
+
+        const tx = await (this.program.methods as any)
+            .depositWithAuthorization(amount, expiresAt, nonce)
+            .accounts({
+                relayer: relayer.publicKey,
+                agent: agentPubkey,
+                agentTokenAccount: agentTokenAccount,
+                escrowAccount: escrowPDA,
+                depositAuthorization: findDepositAuthPDA(agentPubkey, nonce)[0],
+                marketConfig: findMarketConfigPDA()[0],
+                feeVaultState: feeVaultStatePDA,
+                feeVault: feeVaultPDA,
+                vault: vaultAddress,
+                instructionsSysvar: SYSVAR_INSTRUCTIONS_PUBKEY,
+                tokenProgram: TOKEN_PROGRAM_ID,
+                systemProgram: SystemProgram.programId,
+            })
+            .preInstructions([signatureIx])
+            .signers([relayer])
+            .rpc();
+
+        return tx;
+    }
+
+    // --- Receive With Authorization (any wallet signs off-chain, the agent submits) ---
+
+    /**
+     * Credits a funder's signed transfer authorization to the agent's escrow. The funder
+     * must have approved the escrow PDA as delegate on their USDC account beforehand.
+     */
+    async receiveWithAuthorization(
+        agent: Keypair,
+        from: PublicKey,
+        value: BN,
+        validAfter: BN,
+        validBefore: BN,
+        nonce: Buffer,
+        fromSignature: Uint8Array,
+        vaultAddress: PublicKey
+    ): Promise<string> {
+        const [escrowPDA] = findEscrowPDA(agent.publicKey);
+        const [feeVaultStatePDA] = findFeeVaultStatePDA();
+        const [feeVaultPDA] = findFeeVaultPDA(feeVaultStatePDA);
+
+        // Must sit immediately before the receive instruction
+        const signatureIx = Ed25519Program.createInstructionWithPublicKey({
+            publicKey: from.toBytes(),
+            message: transferAuthorizationMessage(from, agent.publicKey, value, validAfter, validBefore, nonce),
+            signature: fromSignature,
+        });
+
+        const tx = await (this.program.methods as any)
+            .receiveWithAuthorization(value, validAfter, validBefore, [...nonce])
+            .accounts({
+                agent: agent.publicKey,
+                from,
+                fromTokenAccount: await getAssociatedTokenAddress(USDC_MINT, from),
+                escrowAccount: escrowPDA,
+                transferAuthorization: findTransferAuthPDA(from, nonce)[0],
+                marketConfig: findMarketConfigPDA()[0],
+                feeVaultState: feeVaultStatePDA,
+                feeVault: feeVaultPDA,
+                vault: vaultAddress,
+                instructionsSysvar: SYSVAR_INSTRUCTIONS_PUBKEY,
+                tokenProgram: TOKEN_PROGRAM_ID,
+                systemProgram: SystemProgram.programId,
+            })
+            .preInstructions([signatureIx])
+            .signers([agent])
+            .rpc();
+
+        return tx;
+    }
+
+    // --- CCTP Funding (cross-chain USDC burn minted straight into the escrow vault) ---
+
+    async creditCctpDeposit(
+        payer: Keypair,
+        agentPubkey: PublicKey,
+        vaultAddress: PublicKey, // The burn's mintRecipient
+        sourceDomain: number,
+        cctpNonce: BN,
+        receiveMessageIx: TransactionInstruction // MessageTransmitter receive_message with the attestation
+    ): Promise<string> {
+        const [feeVaultStatePDA] = findFeeVaultStatePDA();
+
+        const tx = await (this.program.methods as any)
+            .creditCctpDeposit(sourceDomain, cctpNonce)
+            .accounts({
+                payer: payer.publicKey,
+                agent: agentPubkey,
+                escrowAccount: findEscrowPDA(agentPubkey)[0],
+                cctpDeposit: findCctpDepositPDA(sourceDomain, cctpNonce)[0],
+                marketConfig: findMarketConfigPDA()[0],
+                feeVaultState: feeVaultStatePDA,
+                feeVault: findFeeVaultPDA(feeVaultStatePDA)[0],
+                vault: vaultAddress,
+                instructionsSysvar: SYSVAR_INSTRUCTIONS_PUBKEY,
+                systemProgram: SystemProgram.programId,
+            })
+            // Must sit immediately before the credit instruction
+            .preInstructions([receiveMessageIx])
+            .signers([payer])
+            .rpc();
+
+        return tx;
+    }
+
+    // --- Close Settlement (Router-only) ---
+
+    async closeSettlement(
+        router: Keypair,
+        agentPubkey: PublicKey,
+        humanWallet: PublicKey,
+        vaultAddress: PublicKey,
+        verifiedSeconds: BN,
+        agreedPricePerSecond: BN,
+        nonce: BN,
+        sessionId: BN, // Open session the seconds are billed against
+        builderCode: string | null = null,
+        qualityScore: number | null = null, // 1-100 from the verifier
+        withReceipt: boolean = false, // Router pays rent for a per-settlement receipt
+        apiKeyHash: Buffer | null = null, // Agent API key the spend is attributed to
+        paymentIntentId: BN | null = null, // Agent intent the settlement draws down
+        builderCodeStr: string | null = null, // Referral string; hashed on-chain, replaces builderCode
+        responseHash: number[] | null = null, // Deliverable hash stored on the receipt (needs withReceipt)
+        observer: PublicKey | null = null, // Accepted escrow observer to notify via self-CPI event
+        dataConsentScope: Buffer | null = null, // Human's data consent scope hash this settlement falls under
+        qualityMultiplierBps: number | null = null, // Engagement multiplier within the session's bounds; 10000 = 1x
+        attestationSigner: Keypair | null = null // Registered attestation key vouching for the settlement
+    ): Promise<string> {
+        const [escrowPDA] = findEscrowPDA(agentPubkey);
+        const [configPDA] = findMarketConfigPDA();
+        const [feeVaultStatePDA] = findFeeVaultStatePDA();
+        const [feeVaultPDA] = findFeeVaultPDA(feeVaultStatePDA);
+        // Payout ATA is derived (and created if missing) by the program
+        const userWallet = await getAssociatedTokenAddress(USDC_MINT, humanWallet);
+
+        let builderBalance = SystemProgram.programId; // Default None
+        let builderStats: PublicKey | null = null; // Only passed once initialized
+        if (builderCodeStr) {
+            const code = builderCodeFromString(builderCodeStr);
+            [builderBalance] = PublicKey.findProgramAddressSync(
+                [Buffer.from("builder"), code],
+                PAYMENT_ROUTER_PROGRAM_ID
+            );
+            const [statsPDA] = PublicKey.findProgramAddressSync(
+                [Buffer.from("builder_stats"), code],
+                PAYMENT_ROUTER_PROGRAM_ID
+            );
+            if (await this.connection.getAccountInfo(statsPDA)) {
+                builderStats = statsPDA;
+            }
+        } else if (builderCode) {
+            const [pda] = findBuilderBalancePDA(builderCode);
+            builderBalance = pda;
+            const [statsPDA] = findBuilderStatsPDA(builderCode);
+            if (await this.connection.getAccountInfo(statsPDA)) {
+                builderStats = statsPDA;
+            }
+        }
+
+        const { org, orgTreasury } = await this.orgAccounts(humanWallet);
+        // Verified seconds are scored against the session's category stats, when it has any
+        const [sessionPDA] = findSessionPDA(escrowPDA, sessionId);
+        const session = await (this.program.account as any).session.fetch(sessionPDA);
+
+        // The signature must sit immediately before the settlement and covers the multiplier
+        const attestationIxs = attestationSigner
+            ? [
+                  Ed25519Program.createInstructionWithPrivateKey({
+                      privateKey: attestationSigner.secretKey,
+                      message: settlementAttestationMessage(
+                          agentPubkey,
+                          humanWallet,
+                          verifiedSeconds,
+                          agreedPricePerSecond,
+                          nonce,
+                          qualityMultiplierBps ?? 10_000
+                      ),
+                  }),
+              ]
+            : [];
+
+        const tx = await (this.program.methods as any)
+            .closeSettlement(
+                verifiedSeconds,
+                agreedPricePerSecond,
+                nonce,
+                builderCodeStr ? null : builderCode,
+                qualityScore,
+                // SettlementExtras
+                { builderCodeStr, responseHash, qualityMultiplierBps }
+            )
+            .accounts({
+                router: router.publicKey,
+                escrowAccount: escrowPDA,
+                vault: vaultAddress,
+                human: humanWallet,
+                payoutPrefs: findPayoutPrefsPDA(humanWallet)[0],
+                payoutSplit: findPayoutSplitPDA(humanWallet)[0],
+                orgMember: findOrgMemberPDA(humanWallet)[0],
+                userWallet: userWallet,
+                mint: USDC_MINT,
+                feeVaultState: feeVaultStatePDA,
+                feeVault: feeVaultPDA,
+                builderBalance: builderBalance,
+                builderStats: builderStats,
+                session: sessionPDA,
+                holdback: findHoldbackPDA(humanWallet)[0],
+                attestationKey: attestationSigner ? findAttestationKeyPDA(attestationSigner.publicKey)[0] : null,
+                instructionsSysvar: attestationSigner ? SYSVAR_INSTRUCTIONS_PUBKEY : null,
+                receipt: withReceipt ? findReceiptPDA(escrowPDA, nonce)[0] : null,
+                humanStats: findHumanStatsPDA(humanWallet)[0],
+                agentStats: findAgentStatsPDA(agentPubkey)[0],
+                creditLine: null, // Optional (underwritten escrows)
+                creditCollateral: null, // Optional
+                mintFeeVault: null, // Optional (secondary-mint escrows)
+                marketConfig: configPDA,
+                tokenProgram: TOKEN_PROGRAM_ID,
+                associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+                systemProgram: SystemProgram.programId,
+                relayerStats: findRelayerStatsPDA(router.publicKey)[0],
+                forwardPosition: null, // Optional (pre-purchased seconds)
+                priceIndex: null, // Optional (category price EMA)
+                shard: null, // Optional (sharded escrows)
+                org: org, // Required when the human belongs to an org
+                orgTreasury: orgTreasury,
+                apiKey: apiKeyHash ? findApiKeyPDA(agentPubkey, apiKeyHash)[0] : null,
+                sessionStats: findSessionStatsPDA(Buffer.from(session.category))[0],
+                paymentIntent: paymentIntentId ? findPaymentIntentPDA(escrowPDA, paymentIntentId)[0] : null,
+                observer: observer ? findEscrowObserverPDA(escrowPDA, observer)[0] : null,
+                dataConsent: dataConsentScope ? findDataConsentPDA(humanWallet, dataConsentScope)[0] : null,
+                matcherBalance: null, // Optional (required, with the session, for matched sessions)
+            })
+            .remainingAccounts(await this.payoutSplitLegs(humanWallet))
+            .preInstructions(attestationIxs)
+            .signers([router])
+            .rpc();
+
+        return tx;
+    }
+
+    // --- Matchers (paired the session's agent and human; paid a share of its fees) ---
+
+    async registerMatcher(admin: Keypair, matcherCode: Buffer, matcherWallet: PublicKey): Promise<string> {
+        const tx = await (this.program.methods as any)
+            .registerMatcher([...matcherCode])
+            .accounts({
+                admin: admin.publicKey,
+                marketConfig: findMarketConfigPDA()[0],
+                matcherBalance: findMatcherBalancePDA(matcherCode)[0],
+                matcherWallet,
+                systemProgram: SystemProgram.programId,
+            })
+            .signers([admin])
+            .rpc();
+
+        return tx;
+    }
+
+    async setMatcherShareBps(authority: Keypair, matcherShareBps: number): Promise<string> {
+        const tx = await (this.program.methods as any)
+            .setMatcherShareBps(matcherShareBps)
+            .accounts({
+                authority: authority.publicKey,
+                marketConfig: findMarketConfigPDA()[0],
+            })
+            .signers([authority])
+            .rpc();
+
+        return tx;
+    }
+
+    async claimMatcherBalance(matcherWallet: Keypair, matcherCode: Buffer): Promise<string> {
+        const [feeVaultStatePDA] = findFeeVaultStatePDA();
+
+        const tx = await (this.program.methods as any)
+            .claimMatcherBalance()
+            .accounts({
+                matcherWallet: matcherWallet.publicKey,
+                matcherBalance: findMatcherBalancePDA(matcherCode)[0],
+                matcherTokenAccount: await getAssociatedTokenAddress(USDC_MINT, matcherWallet.publicKey),
+                feeVaultState: feeVaultStatePDA,
+                feeVault: findFeeVaultPDA(feeVaultStatePDA)[0],
+                tokenProgram: TOKEN_PROGRAM_ID,
+            })
+            .signers([matcherWallet])
+            .rpc();
+
+        return tx;
+    }
+
+    // --- Agent Ratings (1-5 stars per settlement receipt, aggregated per agent) ---
+
+    async rateAgent(
+        human: Keypair,
+        receiptPDA: PublicKey,
+        agentPubkey: PublicKey,
+        rating: number
+    ): Promise<string> {
+        const tx = await (this.program.methods as any)
+            .rateAgent(rating)
+            .accounts({
+                rater: human.publicKey,
+                human: human.publicKey,
+                receipt: receiptPDA,
+                agentRating: findAgentRatingPDA(agentPubkey)[0],
+                instructionsSysvar: SYSVAR_INSTRUCTIONS_PUBKEY,
+                systemProgram: SystemProgram.programId,
+            })
+            .signers([human])
+            .rpc();
+
+        return tx;
+    }
+
+    /** Submits a rating the human signed off-chain over agentRatingMessage(receiptPDA, rating). */
+    async rateAgentWithSignature(
+        submitter: Keypair,
+        humanWallet: PublicKey,
+        receiptPDA: PublicKey,
+        agentPubkey: PublicKey,
+        rating: number,
+        humanSignature: Uint8Array
+    ): Promise<string> {
+        // Must sit immediately before the rating instruction
+        const signatureIx = Ed25519Program.createInstructionWithPublicKey({
+            publicKey: humanWallet.toBytes(),
+            message: agentRatingMessage(receiptPDA, rating),
+            signature: humanSignature,
+        });
+
+        const tx = await (this.program.methods as any)
+            .rateAgent(rating)
+            .accounts({
+                rater: submitter.publicKey,
+                human: humanWallet,
+                receipt: receiptPDA,
+                agentRating: findAgentRatingPDA(agentPubkey)[0],
+                instructionsSysvar: SYSVAR_INSTRUCTIONS_PUBKEY,
+                systemProgram: SystemProgram.programId,
+            })
+            .preInstructions([signatureIx])
+            .signers([submitter])
+            .rpc();
+
+        return tx;
+    }
+
+    // --- Blocked Agents (up to 32 agents a human never gets sessions from) ---
+
+    async setBlockedAgents(human: Keypair, agents: PublicKey[]): Promise<string> {
+        const tx = await (this.program.methods as any)
+            .setBlockedAgents(agents)
+            .accounts({
+                human: human.publicKey,
+                blockedAgents: findBlockedAgentsPDA(human.publicKey)[0],
+                systemProgram: SystemProgram.programId,
+            })
+            .signers([human])
+            .rpc();
+
+        return tx;
+    }
+
+    // --- Payout Split (up to 4 extra wallets per human) ---
+
+    async setPayoutSplit(
+        human: Keypair,
+        legs: { wallet: PublicKey; bps: number }[] // Token accounts; empty turns splitting off
+    ): Promise<string> {
+        const tx = await (this.program.methods as any)
+            .setPayoutSplit(legs)
+            .accounts({
+                human: human.publicKey,
+                payoutSplit: findPayoutSplitPDA(human.publicKey)[0],
+                systemProgram: SystemProgram.programId,
+            })
+            .signers([human])
+            .rpc();
+
+        return tx;
+    }
+
+    /** Leg token accounts to pass as remainingAccounts on any payout to `humanWallet`. */
+    async payoutSplitLegs(humanWallet: PublicKey) {
+        const [splitPDA] = findPayoutSplitPDA(humanWallet);
+        const split = await (this.program.account as any).payoutSplit.fetchNullable(splitPDA);
+        return (split?.legs ?? []).map((leg: { wallet: PublicKey }) => ({
+            pubkey: leg.wallet,
+            isSigner: false,
+            isWritable: true,
+        }));
+    }
+
+    // --- API Keys (per-key spend attribution for agents) ---
+
+    async registerApiKey(agent: Keypair, keyHash: Buffer): Promise<string> {
+        const tx = await (this.program.methods as any)
+            .registerApiKey(Array.from(keyHash))
+            .accounts({
+                agent: agent.publicKey,
+                apiKey: findApiKeyPDA(agent.publicKey, keyHash)[0],
+                systemProgram: SystemProgram.programId,
+            })
+            .signers([agent])
+            .rpc();
+
+        return tx;
+    }
+
+    async revokeApiKey(agent: Keypair, keyHash: Buffer): Promise<string> {
+        const tx = await (this.program.methods as any)
+            .revokeApiKey()
+            .accounts({
+                agent: agent.publicKey,
+                apiKey: findApiKeyPDA(agent.publicKey, keyHash)[0],
+            })
+            .signers([agent])
+            .rpc();
+
+        return tx;
+    }
+
+    // --- Payment Intents (agent-signed settlement ceilings per human) ---
+
+    async createPaymentIntent(
+        agent: Keypair,
+        intentId: BN,
+        humanWallet: PublicKey,
+        maxAmount: BN,
+        expiry: BN // Unix timestamp
+    ): Promise<string> {
+        const [escrowPDA] = findEscrowPDA(agent.publicKey);
+        const tx = await (this.program.methods as any)
+            .createPaymentIntent(intentId, humanWallet, maxAmount, expiry)
+            .accounts({
+                agent: agent.publicKey,
+                escrowAccount: escrowPDA,
+                paymentIntent: findPaymentIntentPDA(escrowPDA, intentId)[0],
+                systemProgram: SystemProgram.programId,
+            })
+            .signers([agent])
+            .rpc();
+
+        return tx;
+    }
+
+    async cancelPaymentIntent(agent: Keypair, intentId: BN): Promise<string> {
+        const [escrowPDA] = findEscrowPDA(agent.publicKey);
+        const tx = await (this.program.methods as any)
+            .cancelPaymentIntent()
+            .accounts({
+                agent: agent.publicKey,
+                escrowAccount: escrowPDA,
+                paymentIntent: findPaymentIntentPDA(escrowPDA, intentId)[0],
+            })
+            .signers([agent])
+            .rpc();
+
+        return tx;
+    }
+
+    async setIntentsRequired(agent: Keypair, required: boolean): Promise<string> {
+        const tx = await (this.program.methods as any)
+            .setIntentsRequired(required)
+            .accounts({
+                agent: agent.publicKey,
+                escrowAccount: findEscrowPDA(agent.publicKey)[0],
+            })
+            .signers([agent])
+            .rpc();
+
+        return tx;
+    }
+
+    // --- Orgs (co-ops and agencies taking a fee from members' payouts) ---
+
+    async createOrg(
+        admin: Keypair,
+        orgId: string,
+        feeBps: number,
+        treasury: PublicKey // Token account in the payout mint
+    ): Promise<string> {
+        const tx = await (this.program.methods as any)
+            .createOrg(Array.from(orgIdBytes(orgId)), feeBps, treasury)
+            .accounts({
+                admin: admin.publicKey,
+                org: findOrgPDA(orgId)[0],
+                systemProgram: SystemProgram.programId,
+            })
+            .signers([admin])
+            .rpc();
+
+        return tx;
+    }
+
+    async addOrgMember(admin: Keypair, human: Keypair, orgId: string): Promise<string> {
+        const tx = await (this.program.methods as any)
+            .addOrgMember()
+            .accounts({
+                admin: admin.publicKey,
+                human: human.publicKey,
+                org: findOrgPDA(orgId)[0],
+                orgMember: findOrgMemberPDA(human.publicKey)[0],
+                systemProgram: SystemProgram.programId,
+            })
+            .signers([admin, human])
+            .rpc();
+
+        return tx;
+    }
+
+    async removeOrgMember(caller: Keypair, humanWallet: PublicKey): Promise<string> {
+        const [memberPDA] = findOrgMemberPDA(humanWallet);
+        const member = await (this.program.account as any).orgMember.fetch(memberPDA);
+        const org = await (this.program.account as any).org.fetch(member.org);
+
+        const tx = await (this.program.methods as any)
+            .removeOrgMember()
+            .accounts({
+                caller: caller.publicKey,
+                org: member.org,
+                orgAdmin: org.admin,
+                orgMember: memberPDA,
+            })
+            .signers([caller])
+            .rpc();
+
+        return tx;
+    }
+
+    /** Org and treasury to pass on settlements to `humanWallet`, or nulls if they have no org. */
+    async orgAccounts(humanWallet: PublicKey) {
+        const [memberPDA] = findOrgMemberPDA(humanWallet);
+        const member = await (this.program.account as any).orgMember.fetchNullable(memberPDA);
+        if (!member) {
+            return { org: null, orgTreasury: null };
+        }
+        const org = await (this.program.account as any).org.fetch(member.org);
+        return { org: member.org as PublicKey, orgTreasury: org.treasury as PublicKey };
+    }
+
+    // --- Cohort Application Deposits (posted before joining, returned after leaving) ---
+
+    async postCohortDeposit(human: Keypair, cohortId: Buffer): Promise<string> {
+        const [poolPDA] = findCohortPoolPDA(cohortId);
+        const pool = await (this.program.account as any).cohortPool.fetch(poolPDA);
+
+        const tx = await (this.program.methods as any)
+            .postCohortDeposit()
+            .accounts({
+                human: human.publicKey,
+                cohortPool: poolPDA,
+                cohortVault: pool.vault,
+                cohortDeposit: findCohortDepositPDA(poolPDA, human.publicKey)[0],
+                humanTokenAccount: await getAssociatedTokenAddress(USDC_MINT, human.publicKey),
+                tokenProgram: TOKEN_PROGRAM_ID,
+                systemProgram: SystemProgram.programId,
+            })
+            .signers([human])
+            .rpc();
+
+        return tx;
+    }
+
+    async withdrawCohortDeposit(human: Keypair, cohortId: Buffer): Promise<string> {
+        const [poolPDA] = findCohortPoolPDA(cohortId);
+        const pool = await (this.program.account as any).cohortPool.fetch(poolPDA);
+
+        const tx = await (this.program.methods as any)
+            .withdrawCohortDeposit()
+            .accounts({
+                human: human.publicKey,
+                cohortPool: poolPDA,
+                cohortVault: pool.vault,
+                cohortMember: findCohortMemberPDA(poolPDA, human.publicKey)[0],
+                cohortDeposit: findCohortDepositPDA(poolPDA, human.publicKey)[0],
+                humanTokenAccount: await getAssociatedTokenAddress(USDC_MINT, human.publicKey),
+                tokenProgram: TOKEN_PROGRAM_ID,
+            })
+            .signers([human])
+            .rpc();
+
+        return tx;
+    }
+
+    // --- Settlement Queue (router enqueues, any keeper cranks) ---
+
+    async enqueueSettlement(
+        router: Keypair,
+        agentPubkey: PublicKey,
+        humanWallet: PublicKey,
+        verifiedSeconds: BN,
+        agreedPricePerSecond: BN,
+        nonce: BN,
+        sessionId: BN, // Open session the seconds are billed against
+        attestationSigner?: Keypair // Required while the attestation registry has a live key
+    ): Promise<string> {
+        const [queuePDA] = findSettlementQueuePDA();
+        const [escrowPDA] = findEscrowPDA(agentPubkey);
+        const queue = await (this.program.account as any).settlementQueue.fetch(queuePDA);
+
+        // The signature must sit immediately before the enqueue
+        const attestationIxs = attestationSigner
+            ? [
+                  Ed25519Program.createInstructionWithPrivateKey({
+                      privateKey: attestationSigner.secretKey,
+                      message: settlementAttestationMessage(
+                          agentPubkey,
+                          humanWallet,
+                          verifiedSeconds,
+                          agreedPricePerSecond,
+                          nonce
+                      ),
+                  }),
+              ]
+            : [];
+
+        const tx = await (this.program.methods as any)
+            .enqueueSettlement(verifiedSeconds, agreedPricePerSecond, nonce)
+            .accounts({
+                router: router.publicKey,
+                marketConfig: findMarketConfigPDA()[0],
+                queue: queuePDA,
+                item: findQueuedSettlementPDA(queue.tail)[0],
+                escrowAccount: escrowPDA,
+                human: humanWallet,
+                payoutPrefs: findPayoutPrefsPDA(humanWallet)[0],
+                payoutSplit: findPayoutSplitPDA(humanWallet)[0],
+                orgMember: findOrgMemberPDA(humanWallet)[0],
+                session: findSessionPDA(escrowPDA, sessionId)[0],
+                userWallet: await getAssociatedTokenAddress(USDC_MINT, humanWallet),
+                mint: USDC_MINT,
+                holdback: findHoldbackPDA(humanWallet)[0],
+                tokenProgram: TOKEN_PROGRAM_ID,
+                associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+                systemProgram: SystemProgram.programId,
+                shard: null, // Optional (sharded escrows)
+                attestationKey: attestationSigner ? findAttestationKeyPDA(attestationSigner.publicKey)[0] : null,
+                instructionsSysvar: attestationSigner ? SYSVAR_INSTRUCTIONS_PUBKEY : null,
+            })
+            .preInstructions(attestationIxs)
+            .signers([router])
+            .rpc();
+
+        return tx;
+    }
+
+    /** Cranks up to `count` items from the head of the queue; returns null when it is empty. */
+    async processQueue(cranker: Keypair, count: number): Promise<string | null> {
+        const [queuePDA] = findSettlementQueuePDA();
+        const [feeVaultStatePDA] = findFeeVaultStatePDA();
+        const [feeVaultPDA] = findFeeVaultPDA(feeVaultStatePDA);
+        const queue = await (this.program.account as any).settlementQueue.fetch(queuePDA);
+        const pending = Math.min(count, queue.tail.sub(queue.head).toNumber());
+        if (pending === 0) {
+            return null;
+        }
+
+        const remainingAccounts = [];
+        for (let i = 0; i < pending; i++) {
+            const [itemPDA] = findQueuedSettlementPDA(queue.head.addn(i));
+            const item = await (this.program.account as any).queuedSettlement.fetch(itemPDA);
+            const keys = [
+                itemPDA,
+                item.escrow,
+                await getAssociatedTokenAddress(USDC_MINT, item.escrow, true),
+                await getAssociatedTokenAddress(USDC_MINT, item.human),
+                findHoldbackPDA(item.human)[0],
+            ];
+            remainingAccounts.push(
+                ...keys.map((pubkey) => ({ pubkey, isSigner: false, isWritable: true }))
+            );
+        }
+
+        return (this.program.methods as any)
+            .processQueue(pending)
+            .accounts({
+                cranker: cranker.publicKey,
+                queue: queuePDA,
+                marketConfig: findMarketConfigPDA()[0],
+                feeVaultState: feeVaultStatePDA,
+                feeVault: feeVaultPDA,
+                tokenProgram: TOKEN_PROGRAM_ID,
+            })
+            .remainingAccounts(remainingAccounts)
+            .signers([cranker])
+            .rpc();
+    }
+
+    // --- Group Sessions (one escrow draw and fee for many humans) ---
+
+    async openGroupSession(
+        router: Keypair,
+        agentPubkey: PublicKey,
+        sessionId: BN,
+        pricePerSecond: BN,
+        participants: PublicKey[]
+    ): Promise<string> {
+        const [escrowPDA] = findEscrowPDA(agentPubkey);
+        const tx = await (this.program.methods as any)
+            .openGroupSession(sessionId, pricePerSecond, participants)
+            .accounts({
+                router: router.publicKey,
+                escrowAccount: escrowPDA,
+                groupSession: findGroupSessionPDA(escrowPDA, sessionId)[0],
+                marketConfig: findMarketConfigPDA()[0],
+                systemProgram: SystemProgram.programId,
+                shard: null, // Optional (sharded escrows)
+            })
+            .remainingAccounts(
+                participants.map((human) => ({
+                    pubkey: findBlockedAgentsPDA(human)[0],
+                    isSigner: false,
+                    isWritable: false,
+                }))
+            )
+            .signers([router])
+            .rpc();
+
+        return tx;
+    }
+
+    /** Settles every participant at once; `verifiedSeconds` follows the session's participant order. */
+    async closeGroupSettlement(
+        router: Keypair,
+        agentPubkey: PublicKey,
+        sessionId: BN,
+        nonce: BN,
+        agreedPricePerSecond: BN,
+        verifiedSeconds: BN[],
+        attestationSigner?: Keypair // Required while the attestation registry has a live key
+    ): Promise<string> {
+        const [escrowPDA] = findEscrowPDA(agentPubkey);
+        const [groupSessionPDA] = findGroupSessionPDA(escrowPDA, sessionId);
+        const [feeVaultStatePDA] = findFeeVaultStatePDA();
+        const [feeVaultPDA] = findFeeVaultPDA(feeVaultStatePDA);
+        const group = await (this.program.account as any).groupSession.fetch(groupSessionPDA);
+
+        const remainingAccounts = [];
+        for (const human of group.participants as PublicKey[]) {
+            remainingAccounts.push(
+                { pubkey: await getAssociatedTokenAddress(USDC_MINT, human), isSigner: false, isWritable: true },
+                { pubkey: findPayoutPrefsPDA(human)[0], isSigner: false, isWritable: false },
+                { pubkey: findHoldbackPDA(human)[0], isSigner: false, isWritable: true },
+                { pubkey: findPayoutSplitPDA(human)[0], isSigner: false, isWritable: false },
+                { pubkey: findOrgMemberPDA(human)[0], isSigner: false, isWritable: false }
+            );
+        }
+
+        // One signature per participant, in order, immediately before the settlement
+        const attestationIxs = attestationSigner
+            ? (group.participants as PublicKey[]).map((human, i) =>
+                  Ed25519Program.createInstructionWithPrivateKey({
+                      privateKey: attestationSigner.secretKey,
+                      message: settlementAttestationMessage(
+                          agentPubkey,
+                          human,
+                          verifiedSeconds[i],
+                          agreedPricePerSecond,
+                          nonce
+                      ),
+                  })
+              )
+            : [];
+
+        const tx = await (this.program.methods as any)
+            .closeGroupSettlement(nonce, agreedPricePerSecond, verifiedSeconds)
+            .accounts({
+                router: router.publicKey,
+                escrowAccount: escrowPDA,
+                vault: await getAssociatedTokenAddress(USDC_MINT, escrowPDA, true),
+                groupSession: groupSessionPDA,
+                mint: USDC_MINT,
+                feeVaultState: feeVaultStatePDA,
+                feeVault: feeVaultPDA,
+                marketConfig: findMarketConfigPDA()[0],
+                tokenProgram: TOKEN_PROGRAM_ID,
+                shard: null, // Optional (sharded escrows)
+                attestationKey: attestationSigner ? findAttestationKeyPDA(attestationSigner.publicKey)[0] : null,
+                instructionsSysvar: attestationSigner ? SYSVAR_INSTRUCTIONS_PUBKEY : null,
+            })
+            .remainingAccounts(remainingAccounts)
+            .preInstructions(attestationIxs)
+            .signers([router])
+            .rpc();
+
+        return tx;
+    }
+
+    async closeGroupSession(router: Keypair, agentPubkey: PublicKey, sessionId: BN): Promise<string> {
+        const [escrowPDA] = findEscrowPDA(agentPubkey);
+        const tx = await (this.program.methods as any)
+            .closeGroupSession()
+            .accounts({
+                router: router.publicKey,
+                groupSession: findGroupSessionPDA(escrowPDA, sessionId)[0],
+                escrowAccount: escrowPDA,
+                marketConfig: findMarketConfigPDA()[0],
+                shard: null, // Optional (sharded escrows)
+            })
+            .signers([router])
+            .rpc();
+
+        return tx;
+    }
+
+    // --- Devnet Faucet (programs built with the devnet-faucet feature only) ---
+
+    async faucetDrip(wallet: Keypair, testMint: PublicKey): Promise<string> {
+        const tx = await (this.program.methods as any)
+            .faucetDrip()
+            .accounts({
+                wallet: wallet.publicKey,
+                faucet: findFaucetPDA()[0],
+                faucetClaim: findFaucetClaimPDA(wallet.publicKey)[0],
+                mint: testMint,
+                walletTokenAccount: await getAssociatedTokenAddress(testMint, wallet.publicKey),
+                tokenProgram: TOKEN_PROGRAM_ID,
+                associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+                systemProgram: SystemProgram.programId,
+            })
+            .signers([wallet])
+            .rpc();
+
+        return tx;
+    }
+
+    // --- Utility: Fetch Escrow Account ---
+
+    async getEscrowAccount(agentPubkey: PublicKey): Promise<{
+        agent: PublicKey;
+        balance: BN;
+        bump: number;
+    } | null> {
+        const [escrowPDA] = findEscrowPDA(agentPubkey);
+        try {
+            const account = await this.program.account.escrowAccount.fetch(escrowPDA);
+            return account as any;
+        } catch {
+            return null;
+        }
+    }
+
+    // --- Escrow Statements (monthly summaries, cranked by anyone) ---
+
+    async closeEscrowEpoch(payer: Keypair, agentPubkey: PublicKey): Promise<string> {
+        const [escrowPDA] = findEscrowPDA(agentPubkey);
+        const escrow = await (this.program.account as any).escrowAccount.fetch(escrowPDA);
+        const tx = await (this.program.methods as any)
+            .closeEscrowEpoch()
+            .accounts({
+                payer: payer.publicKey,
+                escrowAccount: escrowPDA,
+                summary: findEscrowSummaryPDA(escrowPDA, escrow.statementCount)[0],
+                systemProgram: SystemProgram.programId,
+            })
+            .signers([payer])
+            .rpc();
+
+        return tx;
+    }
+
+    /** Closed statements in order; each reconciles opening + deposits - settlements + otherNet. */
+    async getEscrowStatements(agentPubkey: PublicKey): Promise<any[]> {
+        const [escrowPDA] = findEscrowPDA(agentPubkey);
+        const escrow = await (this.program.account as any).escrowAccount.fetch(escrowPDA);
+        const pdas = Array.from({ length: escrow.statementCount }, (_, i) => findEscrowSummaryPDA(escrowPDA, i)[0]);
+        return (this.program.account as any).escrowEpochSummary.fetchMultiple(pdas);
+    }
+
+    /** Mints the agent's frozen invoice NFT for closed statement `index`. */
+    async mintInvoice(agent: Keypair, index: number): Promise<string> {
+        const [escrowPDA] = findEscrowPDA(agent.publicKey);
+        const [summary] = findEscrowSummaryPDA(escrowPDA, index);
+        const [invoiceMint] = findInvoiceMintPDA(summary);
+        const tx = await (this.program.methods as any)
+            .mintInvoice()
+            .accounts({
+                agent: agent.publicKey,
+                escrowAccount: escrowPDA,
+                summary,
+                invoice: findInvoicePDA(summary)[0],
+                invoiceMint,
+                agentInvoiceAccount: await getAssociatedTokenAddress(invoiceMint, agent.publicKey),
+                tokenProgram: TOKEN_PROGRAM_ID,
+                associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+                systemProgram: SystemProgram.programId,
+                rent: SYSVAR_RENT_PUBKEY,
+            })
+            .signers([agent])
+            .rpc();
+
+        return tx;
+    }
 
-export function findMarketConfigPDA(): [PublicKey, number] {
-    return PublicKey.findProgramAddressSync(
-        [Buffer.from("market_config")],
-        PAYMENT_ROUTER_PROGRAM_ID
-    );
-}
+    // --- Escrow Snapshots (short-lived standing proofs for underwriters) ---
 
-export function findEscrowPDA(agentPubkey: PublicKey): [PublicKey, number] {
-    return PublicKey.findProgramAddressSync(
-        [Buffer.from("escrow"), agentPubkey.toBuffer()],
-        PAYMENT_ROUTER_PROGRAM_ID
-    );
-}
+    /** Writes a snapshot for the 32-byte `challenge` an underwriter handed the agent. */
+    async snapshotEscrow(agent: Keypair, challenge: Buffer): Promise<string> {
+        const [escrowPDA] = findEscrowPDA(agent.publicKey);
+        const tx = await (this.program.methods as any)
+            .snapshotEscrow([...challenge])
+            .accounts({
+                agent: agent.publicKey,
+                escrowAccount: escrowPDA,
+                snapshot: findEscrowSnapshotPDA(escrowPDA, challenge)[0],
+                systemProgram: SystemProgram.programId,
+            })
+            .signers([agent])
+            .rpc();
 
-export function findFeeVaultStatePDA(): [PublicKey, number] {
-    return PublicKey.findProgramAddressSync(
-        [Buffer.from("fee_vault_state")],
-        PAYMENT_ROUTER_PROGRAM_ID
-    );
-}
+        return tx;
+    }
 
-export function findFeeVaultPDA(feeVaultStatePDA: PublicKey): [PublicKey, number] {
-    return PublicKey.findProgramAddressSync(
-        [Buffer.from("fee_vault"), feeVaultStatePDA.toBuffer()],
-        PAYMENT_ROUTER_PROGRAM_ID
-    );
-}
+    /**
+     * Underwriter side: the snapshot for `challenge`, or null if the agent never wrote one
+     * or it has expired. Deriving the address pins both the program and the agent.
+     */
+    async verifyEscrowSnapshot(agentPubkey: PublicKey, challenge: Buffer): Promise<any | null> {
+        const [escrowPDA] = findEscrowPDA(agentPubkey);
+        const [snapshotPDA] = findEscrowSnapshotPDA(escrowPDA, challenge);
+        const snapshot = await (this.program.account as any).escrowSnapshot.fetchNullable(snapshotPDA);
+        if (!snapshot || snapshot.expiresAt.toNumber() <= Date.now() / 1000) {
+            return null;
+        }
+        return snapshot;
+    }
 
-export function findBuilderBalancePDA(builderCode: string): [PublicKey, number] {
-    const builderCodeBytes = Buffer.alloc(32);
-    const codeBuffer = Buffer.from(builderCode);
-    if (codeBuffer.length <= 32) {
-        codeBuffer.copy(builderCodeBytes);
+    /** Permissionless once expired; the provider wallet pays the fee and rent returns to the agent. */
+    async closeEscrowSnapshot(agentPubkey: PublicKey, challenge: Buffer): Promise<string> {
+        const [escrowPDA] = findEscrowPDA(agentPubkey);
+        const tx = await (this.program.methods as any)
+            .closeEscrowSnapshot()
+            .accounts({
+                rentReceiver: agentPubkey,
+                snapshot: findEscrowSnapshotPDA(escrowPDA, challenge)[0],
+            })
+            .rpc();
+
+        return tx;
     }
-    return PublicKey.findProgramAddressSync(
-        [Buffer.from("builder"), builderCodeBytes],
-        PAYMENT_ROUTER_PROGRAM_ID
-    );
-};
 
-// --- IDL placeholder (would be generated by anchor build) ---
-// In production, import the generated IDL from target/idl/payment_router.json
-// --- IDL placeholder (would be generated by anchor build) ---
-// In production, import the generated IDL from target/idl/payment_router.json
-const IDL_PLACEHOLDER: any = {
-    version: "0.1.0",
-    name: "payment_router",
-    instructions: [],
-    accounts: [],
-};
+    // --- Utility: RPC Queries (memcmp on ACCOUNT_LAYOUT offsets) ---
 
-// --- Main Client Class ---
+    async getEscrowsByMint(mint: PublicKey) {
+        return this.connection.getProgramAccounts(PAYMENT_ROUTER_PROGRAM_ID, {
+            filters: accountFilters("EscrowAccount", "mint", mint),
+        });
+    }
 
-export class PaymentRouterClient {
-    private program: Program;
-    private provider: AnchorProvider;
-    private connection: Connection;
+    async getSessionsForHuman(human: PublicKey) {
+        return this.connection.getProgramAccounts(PAYMENT_ROUTER_PROGRAM_ID, {
+            filters: accountFilters("Session", "human", human),
+        });
+    }
 
-    constructor(provider: AnchorProvider, idl?: Idl) {
-        this.provider = provider;
-        this.connection = provider.connection;
-        this.program = new Program(
-            idl ?? IDL_PLACEHOLDER,
-            PAYMENT_ROUTER_PROGRAM_ID,
-            provider
-        );
+    async getReceiptsForHuman(human: PublicKey) {
+        return this.connection.getProgramAccounts(PAYMENT_ROUTER_PROGRAM_ID, {
+            filters: accountFilters("SettlementReceipt", "human", human),
+        });
     }
 
-    // --- Initialize Market Config ---
+    async getReceiptsForAgent(agentPubkey: PublicKey) {
+        return this.connection.getProgramAccounts(PAYMENT_ROUTER_PROGRAM_ID, {
+            filters: accountFilters("SettlementReceipt", "agent", agentPubkey),
+        });
+    }
 
-    async initializeMarketConfig(
+    // --- Utility: Category Market Rate (EMA price per second, scaled by 1e6) ---
+
+    async getMarketRate(category: Buffer): Promise<BN> {
+        const [priceIndexPDA] = findPriceIndexPDA(category);
+        return (this.program.methods as any)
+            .getMarketRate()
+            .accounts({ priceIndex: priceIndexPDA })
+            .view();
+    }
+
+    // --- Utility: Micro-USD to Payment Mint Base Units ---
+
+    async quoteBaseUnits(microUsd: BN): Promise<BN> {
+        return (this.program.methods as any)
+            .quoteBaseUnits(microUsd)
+            .accounts({ marketConfig: findMarketConfigPDA()[0] })
+            .view();
+    }
+
+    // --- Utility: PDA Derivation Audit (program-side derivations vs. this client's) ---
+
+    async deriveAddresses(agentPubkey: PublicKey, taskId: BN, builderCode: Buffer): Promise<Record<string, PublicKey>> {
+        return (this.program.methods as any)
+            .deriveAddresses(agentPubkey, taskId, Array.from(builderCode))
+            .accounts({ marketConfig: findMarketConfigPDA()[0] })
+            .view();
+    }
+
+    /** Names of addresses this client derives differently from the deployed program. */
+    async auditPdaDerivations(agentPubkey: PublicKey, taskId: BN, builderCode: Buffer): Promise<string[]> {
+        const onChain = await this.deriveAddresses(agentPubkey, taskId, builderCode);
+        const [escrowPDA] = findEscrowPDA(agentPubkey);
+        const [taskPDA] = findTaskPDA(escrowPDA, taskId);
+        const local: Record<string, PublicKey> = {
+            marketConfig: findMarketConfigPDA()[0],
+            feeVaultState: findFeeVaultStatePDA()[0],
+            feeVault: findFeeVaultPDA(findFeeVaultStatePDA()[0])[0],
+            escrow: escrowPDA,
+            escrowVault: await getAssociatedTokenAddress(USDC_MINT, escrowPDA, true),
+            agentStats: findAgentStatsPDA(agentPubkey)[0],
+            recovery: findRecoveryPDA(escrowPDA)[0],
+            creditLine: findCreditLinePDA(escrowPDA)[0],
+            task: taskPDA,
+            taskGate: findTaskGatePDA(taskPDA)[0],
+            taskPolicy: findTaskPolicyPDA(taskPDA)[0],
+            bounty: findBountyPDA(taskPDA)[0],
+            taskMilestones: findTaskMilestonesPDA(taskPDA)[0],
+            builderBalance: PublicKey.findProgramAddressSync(
+                [Buffer.from("builder"), builderCode], PAYMENT_ROUTER_PROGRAM_ID)[0],
+            builderStats: PublicKey.findProgramAddressSync(
+                [Buffer.from("builder_stats"), builderCode], PAYMENT_ROUTER_PROGRAM_ID)[0],
+        };
+        return Object.keys(local).filter((name) => !onChain[name]?.equals(local[name]));
+    }
+
+    // --- Session Length Stats (flags outlier verified seconds for review) ---
+
+    async initSessionStats(
         admin: Keypair,
-        feeBasisPoints: number
+        category: Buffer,
+        flagZScoreE2: BN // z-score x100 (300 = 3 sigma); 0 only records
     ): Promise<string> {
-        const [configPDA] = findMarketConfigPDA();
+        const tx = await (this.program.methods as any)
+            .initSessionStats(Array.from(category), flagZScoreE2)
+            .accounts({
+                admin: admin.publicKey,
+                sessionStats: findSessionStatsPDA(category)[0],
+                marketConfig: findMarketConfigPDA()[0],
+                systemProgram: SystemProgram.programId,
+            })
+            .signers([admin])
+            .rpc();
 
-        const tx = await this.program.methods
-            .initializeMarketConfig(feeBasisPoints)
+        return tx;
+    }
+
+    // --- Spot Market (oracle-posted price per attention-second per category) ---
+
+    async initSpotPrice(admin: Keypair, category: Buffer, oracle: PublicKey): Promise<string> {
+        const tx = await (this.program.methods as any)
+            .initSpotPrice(Array.from(category), oracle)
             .accounts({
                 admin: admin.publicKey,
-                config: configPDA,
+                spotPrice: findSpotPricePDA(category)[0],
+                marketConfig: findMarketConfigPDA()[0],
                 systemProgram: SystemProgram.programId,
             })
             .signers([admin])
@@ -128,24 +2716,53 @@ export class PaymentRouterClient {
         return tx;
     }
 
-    // --- Initialize Fee Vault (NEW) ---
-    async initializeFeeVault(
+    async postSpotPrice(oracle: Keypair, category: Buffer, pricePerSecond: BN): Promise<string> {
+        const tx = await (this.program.methods as any)
+            .postSpotPrice(pricePerSecond)
+            .accounts({
+                oracle: oracle.publicKey,
+                spotPrice: findSpotPricePDA(category)[0],
+                marketConfig: findMarketConfigPDA()[0],
+            })
+            .signers([oracle])
+            .rpc();
+
+        return tx;
+    }
+
+    // --- Liquidity Buffer (treasury-funded, replenished from fees, exposure-capped) ---
+
+    async setLiquidityBufferLimits(
         admin: Keypair,
-        mint: PublicKey
+        maxOutstanding: BN, // 0 = uncapped
+        maxPositionDebt: BN, // 0 = uncapped
+        replenishBps: number
     ): Promise<string> {
-        const [feeVaultStatePDA] = findFeeVaultStatePDA();
-        const [feeVaultPDA] = findFeeVaultPDA(feeVaultStatePDA);
+        const tx = await (this.program.methods as any)
+            .setLiquidityBufferLimits(maxOutstanding, maxPositionDebt, replenishBps)
+            .accounts({
+                admin: admin.publicKey,
+                liquidityBuffer: findLiquidityBufferPDA()[0],
+                marketConfig: findMarketConfigPDA()[0],
+            })
+            .signers([admin])
+            .rpc();
+
+        return tx;
+    }
 
+    async fundLiquidityBufferFromTreasury(admin: Keypair, amount: BN): Promise<string> {
+        const [feeVaultStatePDA] = findFeeVaultStatePDA();
         const tx = await (this.program.methods as any)
-            .initializeFeeVault()
+            .fundLiquidityBufferFromTreasury(amount)
             .accounts({
                 admin: admin.publicKey,
                 feeVaultState: feeVaultStatePDA,
-                feeVault: feeVaultPDA,
-                mint: mint,
-                systemProgram: SystemProgram.programId,
+                feeVault: findFeeVaultPDA(feeVaultStatePDA)[0],
+                liquidityBuffer: findLiquidityBufferPDA()[0],
+                bufferVault: findLiquidityBufferVaultPDA()[0],
+                marketConfig: findMarketConfigPDA()[0],
                 tokenProgram: TOKEN_PROGRAM_ID,
-                rent: SYSVAR_RENT_PUBKEY,
             })
             .signers([admin])
             .rpc();
@@ -153,26 +2770,64 @@ export class PaymentRouterClient {
         return tx;
     }
 
-    // --- Deposit Escrow ---
+    // Permissionless crank
+    async replenishLiquidityBuffer(): Promise<string> {
+        const [feeVaultStatePDA] = findFeeVaultStatePDA();
+        const tx = await (this.program.methods as any)
+            .replenishLiquidityBuffer()
+            .accounts({
+                feeVaultState: feeVaultStatePDA,
+                feeVault: findFeeVaultPDA(feeVaultStatePDA)[0],
+                liquidityBuffer: findLiquidityBufferPDA()[0],
+                bufferVault: findLiquidityBufferVaultPDA()[0],
+                marketConfig: findMarketConfigPDA()[0],
+                tokenProgram: TOKEN_PROGRAM_ID,
+            })
+            .rpc();
 
-    async depositEscrow(
-        agent: Keypair,
-        amount: BN,
-        vaultAddress: PublicKey
-    ): Promise<string> {
-        const [escrowPDA] = findEscrowPDA(agent.publicKey);
-        const agentTokenAccount = await getAssociatedTokenAddress(
-            USDC_MINT,
-            agent.publicKey
-        );
+        return tx;
+    }
 
-        const tx = await this.program.methods
-            .depositEscrow(amount)
+    // Basis points of buffer capital currently lent to LST positions
+    async liquidityBufferUtilization(): Promise<number> {
+        return (this.program.methods as any)
+            .liquidityBufferUtilization()
+            .accounts({
+                liquidityBuffer: findLiquidityBufferPDA()[0],
+                bufferVault: findLiquidityBufferVaultPDA()[0],
+            })
+            .view();
+    }
+
+    // --- LST Collateral (staked SOL backs escrows; the liquidity buffer pays humans in USDC) ---
+
+    async postLstPrice(oracle: Keypair, lstMint: PublicKey, price: BN): Promise<string> {
+        const tx = await (this.program.methods as any)
+            .postLstPrice(price)
+            .accounts({
+                oracle: oracle.publicKey,
+                lstCollateral: findLstCollateralPDA(lstMint)[0],
+                marketConfig: findMarketConfigPDA()[0],
+            })
+            .signers([oracle])
+            .rpc();
+
+        return tx;
+    }
+
+    async depositLst(agent: Keypair, lstMint: PublicKey, amount: BN): Promise<string> {
+        const [escrowPDA] = findEscrowPDA(agent.publicKey);
+        const [lstPosition] = findLstPositionPDA(escrowPDA, lstMint);
+        const tx = await (this.program.methods as any)
+            .depositLst(amount)
             .accounts({
                 agent: agent.publicKey,
-                agentTokenAccount: agentTokenAccount,
+                agentLstAccount: await getAssociatedTokenAddress(lstMint, agent.publicKey),
                 escrowAccount: escrowPDA,
-                vault: vaultAddress,
+                lstCollateral: findLstCollateralPDA(lstMint)[0],
+                lstPosition,
+                lstVault: findLstVaultPDA(lstPosition)[0],
+                lstMint,
                 tokenProgram: TOKEN_PROGRAM_ID,
                 systemProgram: SystemProgram.programId,
                 rent: SYSVAR_RENT_PUBKEY,
@@ -183,62 +2838,107 @@ export class PaymentRouterClient {
         return tx;
     }
 
-    // --- Close Settlement (Router-only) ---
+    async withdrawLst(agent: Keypair, lstMint: PublicKey, amount: BN): Promise<string> {
+        const [escrowPDA] = findEscrowPDA(agent.publicKey);
+        const [lstPosition] = findLstPositionPDA(escrowPDA, lstMint);
+        const tx = await (this.program.methods as any)
+            .withdrawLst(amount)
+            .accounts({
+                agent: agent.publicKey,
+                agentLstAccount: await getAssociatedTokenAddress(lstMint, agent.publicKey),
+                escrowAccount: escrowPDA,
+                lstCollateral: findLstCollateralPDA(lstMint)[0],
+                lstPosition,
+                lstVault: findLstVaultPDA(lstPosition)[0],
+                tokenProgram: TOKEN_PROGRAM_ID,
+            })
+            .signers([agent])
+            .rpc();
 
-    async closeSettlement(
-        router: Keypair,
+        return tx;
+    }
+
+    // Caller is the agent or a market operator; routers send it ahead of a short settlement
+    async drawLstCredit(
+        caller: Keypair,
         agentPubkey: PublicKey,
-        userWallet: PublicKey,
         vaultAddress: PublicKey,
-        verifiedSeconds: BN,
-        agreedPricePerSecond: BN,
-        nonce: BN,
-        builderCode: string | null = null
+        lstMint: PublicKey,
+        amount: BN
     ): Promise<string> {
         const [escrowPDA] = findEscrowPDA(agentPubkey);
-        const [configPDA] = findMarketConfigPDA();
-        const [feeVaultStatePDA] = findFeeVaultStatePDA();
-        const [feeVaultPDA] = findFeeVaultPDA(feeVaultStatePDA);
-
-        let builderBalance = SystemProgram.programId; // Default None
-        if (builderCode) {
-            const [pda] = findBuilderBalancePDA(builderCode);
-            builderBalance = pda;
-        }
-
         const tx = await (this.program.methods as any)
-            .closeSettlement(verifiedSeconds, agreedPricePerSecond, nonce, builderCode)
+            .drawLstCredit(amount)
             .accounts({
-                router: router.publicKey,
+                caller: caller.publicKey,
                 escrowAccount: escrowPDA,
                 vault: vaultAddress,
-                userWallet: userWallet,
-                feeVaultState: feeVaultStatePDA,
-                feeVault: feeVaultPDA,
-                builderBalance: builderBalance,
-                marketConfig: configPDA,
+                lstCollateral: findLstCollateralPDA(lstMint)[0],
+                lstPosition: findLstPositionPDA(escrowPDA, lstMint)[0],
+                liquidityBuffer: findLiquidityBufferPDA()[0],
+                bufferVault: findLiquidityBufferVaultPDA()[0],
+                marketConfig: findMarketConfigPDA()[0],
                 tokenProgram: TOKEN_PROGRAM_ID,
             })
-            .signers([router])
+            .signers([caller])
             .rpc();
 
         return tx;
     }
 
-    // --- Utility: Fetch Escrow Account ---
+    async repayLstDebt(payer: Keypair, agentPubkey: PublicKey, lstMint: PublicKey, amount: BN): Promise<string> {
+        const tx = await (this.program.methods as any)
+            .repayLstDebt(amount)
+            .accounts({
+                payer: payer.publicKey,
+                payerTokenAccount: await getAssociatedTokenAddress(USDC_MINT, payer.publicKey),
+                lstPosition: findLstPositionPDA(findEscrowPDA(agentPubkey)[0], lstMint)[0],
+                liquidityBuffer: findLiquidityBufferPDA()[0],
+                bufferVault: findLiquidityBufferVaultPDA()[0],
+                tokenProgram: TOKEN_PROGRAM_ID,
+            })
+            .signers([payer])
+            .rpc();
 
-    async getEscrowAccount(agentPubkey: PublicKey): Promise<{
-        agent: PublicKey;
-        balance: BN;
-        bump: number;
-    } | null> {
-        const [escrowPDA] = findEscrowPDA(agentPubkey);
-        try {
-            const account = await this.program.account.escrowAccount.fetch(escrowPDA);
-            return account as any;
-        } catch {
-            return null;
-        }
+        return tx;
+    }
+
+    // --- Utility: Solvency Check (vault balances vs. recorded liabilities) ---
+
+    async assertSolvency(
+        builderCodes: string[],
+        holdbackHumans: PublicKey[],
+        escrows: { agent: PublicKey; vault: PublicKey }[],
+        strict = false
+    ): Promise<{
+        feeVaultAmount: BN;
+        feeVaultLiabilities: BN;
+        escrowsChecked: number;
+        shortfall: BN;
+    }> {
+        const [feeVaultStatePDA] = findFeeVaultStatePDA();
+        const [feeVaultPDA] = findFeeVaultPDA(feeVaultStatePDA);
+        const [revenuePoolPDA] = findRevenuePoolPDA();
+        const revenuePool = await this.connection.getAccountInfo(revenuePoolPDA);
+
+        const liabilities = [
+            ...builderCodes.map((code) => findBuilderBalancePDA(code)[0]),
+            ...holdbackHumans.map((human) => findHoldbackPDA(human)[0]),
+        ];
+        const remainingAccounts = [
+            ...liabilities,
+            ...escrows.flatMap(({ agent, vault }) => [findEscrowPDA(agent)[0], vault]),
+        ].map((pubkey) => ({ pubkey, isSigner: false, isWritable: false }));
+
+        return (this.program.methods as any)
+            .assertSolvency(liabilities.length, strict)
+            .accounts({
+                feeVaultState: feeVaultStatePDA,
+                feeVault: feeVaultPDA,
+                revenuePool: revenuePool ? revenuePoolPDA : null,
+            })
+            .remainingAccounts(remainingAccounts)
+            .view();
     }
 
     // --- Utility: Fetch Market Config ---
@@ -257,6 +2957,12 @@ export class PaymentRouterClient {
     }
 }
 
+// Nonce account backing an offline-signed transaction
+export interface DurableNonce {
+    nonceAccount: PublicKey;
+    authority: PublicKey; // Must sign the transaction alongside the agent
+}
+
 // --- Transaction Batching for Sub-cent Fees ---
 
 export class TransactionBatcher {
@@ -314,6 +3020,8 @@ export interface SettlementEvent {
     totalPayout: number;
     fee: number;
     netPayout: number;
+    remainingBalance: number; // Escrow balance after the settlement
+    remainingLocked: number; // Part of remainingBalance the agent can't withdraw
     txSignature: string;
     timestamp: number;
 }
@@ -333,3 +3041,4 @@ export type PaymentRouterEvent = SettlementEvent | EscrowDepositEvent;
 
 export * from "./security";
 export * from "./bridge";
+export * from "./planner";
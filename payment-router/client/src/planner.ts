@@ -0,0 +1,151 @@
+/**
+ * Settlement Batch Planner
+ *
+ * Packs pending settlement instructions into as few transactions as the runtime allows:
+ * - Serialized size within the 1232-byte packet limit
+ * - Account locks within the per-transaction limit, counting lookup table entries
+ * - Compute units within the per-transaction budget
+ *
+ * Returns unsigned v0 transactions with a compute budget prefix, ready for the router
+ * (and any other required signer) to sign and send.
+ */
+
+import {
+    AddressLookupTableAccount,
+    ComputeBudgetProgram,
+    PublicKey,
+    TransactionInstruction,
+    TransactionMessage,
+    VersionedTransaction,
+} from "@solana/web3.js";
+
+// Runtime limits (Solana 1.18)
+export const MAX_TRANSACTION_SIZE = 1232;
+export const MAX_ACCOUNT_LOCKS = 64;
+export const MAX_COMPUTE_UNITS = 1_400_000;
+
+// Measured close_settlement cost with every optional account passed, plus headroom
+export const DEFAULT_SETTLEMENT_COMPUTE_UNITS = 120_000;
+
+export interface PendingSettlement {
+    instruction: TransactionInstruction;
+    // Expected compute units; DEFAULT_SETTLEMENT_COMPUTE_UNITS when omitted
+    computeUnits?: number;
+}
+
+export interface PlannerOptions {
+    payer: PublicKey;
+    recentBlockhash: string;
+    // Tables holding the shared accounts (market config, fee vault, programs, mint)
+    lookupTables?: AddressLookupTableAccount[];
+    // Per-transaction compute cap; defaults to the runtime maximum
+    maxComputeUnits?: number;
+    // Priority fee in micro-lamports per compute unit; omitted = no price instruction
+    priorityFeeMicroLamports?: number;
+}
+
+export interface PlannedTransaction {
+    transaction: VersionedTransaction;
+    // Indices into the input settlements, in submission order
+    settlements: number[];
+    computeUnits: number;
+}
+
+/**
+ * Splits `pending` into valid transactions, preserving input order so increasing
+ * settlement nonces stay increasing on-chain. Throws if a single settlement can't fit
+ * in a transaction on its own.
+ */
+export function planSettlementBatches(
+    pending: PendingSettlement[],
+    options: PlannerOptions
+): PlannedTransaction[] {
+    const maxComputeUnits = options.maxComputeUnits ?? MAX_COMPUTE_UNITS;
+    const planned: PlannedTransaction[] = [];
+    let current: number[] = [];
+    let currentUnits = 0;
+
+    const unitsOf = (index: number) =>
+        pending[index].computeUnits ?? DEFAULT_SETTLEMENT_COMPUTE_UNITS;
+
+    for (let i = 0; i < pending.length; i++) {
+        const units = unitsOf(i);
+        const candidate = [...current, i];
+        const fits =
+            currentUnits + units <= maxComputeUnits &&
+            buildTransaction(pending, candidate, currentUnits + units, options) !== null;
+        if (fits) {
+            current = candidate;
+            currentUnits += units;
+            continue;
+        }
+
+        if (current.length === 0) {
+            throw new Error(`Settlement ${i} does not fit in a transaction on its own`);
+        }
+        planned.push(finish(pending, current, currentUnits, options));
+
+        const alone = units <= maxComputeUnits && buildTransaction(pending, [i], units, options);
+        if (!alone) {
+            throw new Error(`Settlement ${i} does not fit in a transaction on its own`);
+        }
+        current = [i];
+        currentUnits = units;
+    }
+
+    if (current.length > 0) {
+        planned.push(finish(pending, current, currentUnits, options));
+    }
+    return planned;
+}
+
+function finish(
+    pending: PendingSettlement[],
+    settlements: number[],
+    computeUnits: number,
+    options: PlannerOptions
+): PlannedTransaction {
+    const transaction = buildTransaction(pending, settlements, computeUnits, options)!;
+    return { transaction, settlements, computeUnits };
+}
+
+/** The transaction for `settlements`, or null if it breaks a size or lock limit. */
+function buildTransaction(
+    pending: PendingSettlement[],
+    settlements: number[],
+    computeUnits: number,
+    options: PlannerOptions
+): VersionedTransaction | null {
+    const instructions = [ComputeBudgetProgram.setComputeUnitLimit({ units: computeUnits })];
+    if (options.priorityFeeMicroLamports !== undefined) {
+        instructions.push(
+            ComputeBudgetProgram.setComputeUnitPrice({ microLamports: options.priorityFeeMicroLamports })
+        );
+    }
+    instructions.push(...settlements.map((index) => pending[index].instruction));
+
+    try {
+        const message = new TransactionMessage({
+            payerKey: options.payer,
+            recentBlockhash: options.recentBlockhash,
+            instructions,
+        }).compileToV0Message(options.lookupTables ?? []);
+
+        const lookedUp = message.addressTableLookups.reduce(
+            (sum, lookup) => sum + lookup.writableIndexes.length + lookup.readonlyIndexes.length,
+            0
+        );
+        if (message.staticAccountKeys.length + lookedUp > MAX_ACCOUNT_LOCKS) {
+            return null;
+        }
+
+        const transaction = new VersionedTransaction(message);
+        if (transaction.serialize().length > MAX_TRANSACTION_SIZE) {
+            return null;
+        }
+        return transaction;
+    } catch {
+        // Oversized messages fail to compile or serialize
+        return null;
+    }
+}
@@ -0,0 +1,231 @@
+//! Account lists for payment router instructions, filled in from the harness state.
+//! Pair them with the matching `payment_router::instruction::*` args via [`anchor_ix`].
+//!
+//! [`anchor_ix`]: crate::anchor_ix
+
+use anchor_lang::solana_program::sysvar;
+use anchor_spl::associated_token;
+use anchor_spl::token::spl_token;
+use payment_router::accounts;
+use solana_sdk::instruction::{AccountMeta, Instruction};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signer;
+use solana_sdk::system_program;
+
+use crate::*;
+
+impl Harness {
+    pub fn update_market_config_accounts(&self) -> accounts::UpdateMarketConfig {
+        accounts::UpdateMarketConfig { admin: self.admin.pubkey(), market_config: market_config_pda() }
+    }
+
+    /// Fee parameter accounts, signed by the admin while no governance is set.
+    pub fn update_fee_params_accounts(&self) -> accounts::UpdateFeeParams {
+        accounts::UpdateFeeParams { authority: self.admin.pubkey(), market_config: market_config_pda() }
+    }
+
+    pub fn register_builder_accounts(&self, builder_code: &[u8; 32], builder_wallet: &Pubkey) -> accounts::RegisterBuilder {
+        accounts::RegisterBuilder {
+            admin: self.admin.pubkey(),
+            market_config: market_config_pda(),
+            builder_balance: builder_pda(builder_code),
+            builder_wallet: *builder_wallet,
+            system_program: system_program::ID,
+        }
+    }
+
+    pub fn deposit_escrow_accounts(&self, agent: &Pubkey, agent_token_account: &Pubkey) -> accounts::DepositEscrow {
+        accounts::DepositEscrow {
+            agent: *agent,
+            agent_token_account: *agent_token_account,
+            escrow_account: escrow_pda(agent),
+            market_config: market_config_pda(),
+            fee_vault_state: fee_vault_state_pda().0,
+            fee_vault: fee_vault_pda(),
+            vault: self.escrow_vault(agent),
+            token_program: spl_token::ID,
+            system_program: system_program::ID,
+            rent: sysvar::rent::ID,
+            credit_line: None,
+            credit_collateral: None,
+            mint_fee_vault: None,
+        }
+    }
+
+    pub fn withdraw_escrow_accounts(&self, agent: &Pubkey, agent_token_account: &Pubkey) -> accounts::WithdrawEscrow {
+        accounts::WithdrawEscrow {
+            agent: *agent,
+            agent_token_account: *agent_token_account,
+            escrow_account: escrow_pda(agent),
+            vault: self.escrow_vault(agent),
+            token_program: spl_token::ID,
+        }
+    }
+
+    pub fn open_session_accounts(&self, agent: &Pubkey, human: &Pubkey, session_id: u64) -> accounts::OpenSession {
+        let escrow = escrow_pda(agent);
+        accounts::OpenSession {
+            router: self.admin.pubkey(),
+            escrow_account: escrow,
+            human: *human,
+            payout_prefs: payout_prefs_pda(human),
+            availability: availability_pda(human),
+            blocked_agents: blocked_agents_pda(human),
+            session: session_pda(&escrow, session_id),
+            market_config: market_config_pda(),
+            system_program: system_program::ID,
+            shard: None,
+            spot_price: None,
+            task_gate: None,
+            gate_nft_account: None,
+            gate_metadata: None,
+            task_policy: None,
+            attestation_issuer: None,
+            attestation: None,
+            matcher_balance: None,
+        }
+    }
+
+    pub fn close_session_accounts(&self, agent: &Pubkey, session_id: u64) -> accounts::CloseSession {
+        let escrow = escrow_pda(agent);
+        accounts::CloseSession {
+            router: self.admin.pubkey(),
+            session: session_pda(&escrow, session_id),
+            escrow_account: escrow,
+            market_config: market_config_pda(),
+            shard: None,
+        }
+    }
+
+    /// Settlement against an open session, with the holdback account always supplied.
+    pub fn close_settlement_accounts(
+        &self,
+        agent: &Pubkey,
+        human: &Pubkey,
+        session_id: u64,
+        builder_code: Option<&[u8; 32]>,
+    ) -> accounts::CloseSettlement {
+        let escrow = escrow_pda(agent);
+        accounts::CloseSettlement {
+            router: self.admin.pubkey(),
+            escrow_account: escrow,
+            vault: self.escrow_vault(agent),
+            human: *human,
+            payout_prefs: payout_prefs_pda(human),
+            payout_split: payout_split_pda(human),
+            org_member: org_member_pda(human),
+            user_wallet: self.payout_ata(human),
+            mint: self.payment_mint,
+            fee_vault_state: fee_vault_state_pda().0,
+            fee_vault: fee_vault_pda(),
+            builder_balance: builder_code.map(builder_pda),
+            builder_stats: None,
+            session: session_pda(&escrow, session_id),
+            holdback: Some(holdback_pda(human)),
+            attestation_key: None,
+            instructions_sysvar: None,
+            receipt: None,
+            human_stats: None,
+            agent_stats: None,
+            credit_line: None,
+            credit_collateral: None,
+            mint_fee_vault: None,
+            market_config: market_config_pda(),
+            token_program: spl_token::ID,
+            associated_token_program: associated_token::ID,
+            system_program: system_program::ID,
+            relayer_stats: None,
+            forward_position: None,
+            price_index: None,
+            shard: None,
+            org: None,
+            org_treasury: None,
+            api_key: None,
+            session_stats: session_stats_pda(&UNCATEGORIZED),
+            payment_intent: None,
+            observer: None,
+            data_consent: None,
+            matcher_balance: None,
+            event_authority: event_authority_pda(),
+            program: payment_router::ID,
+        }
+    }
+
+    pub fn claim_builder_balance_accounts(
+        &self,
+        builder_code: &[u8; 32],
+        builder_wallet: &Pubkey,
+        builder_token_account: &Pubkey,
+    ) -> accounts::ClaimBuilderBalance {
+        accounts::ClaimBuilderBalance {
+            builder_wallet: *builder_wallet,
+            builder_balance: builder_pda(builder_code),
+            builder_token_account: *builder_token_account,
+            fee_vault_state: fee_vault_state_pda().0,
+            fee_vault: fee_vault_pda(),
+            token_program: spl_token::ID,
+            market_config: None,
+            swap_program: None,
+            swap_output: None,
+        }
+    }
+
+    pub fn claim_protocol_fees_accounts(&self, admin_wallet: &Pubkey) -> accounts::ClaimProtocolFees {
+        accounts::ClaimProtocolFees {
+            admin: self.admin.pubkey(),
+            fee_vault_state: fee_vault_state_pda().0,
+            fee_vault: fee_vault_pda(),
+            admin_wallet: *admin_wallet,
+            market_config: market_config_pda(),
+            token_program: spl_token::ID,
+        }
+    }
+
+    /// Accounts for both `flag_holdback` and `slash_holdback`.
+    pub fn flag_holdback_accounts(&self, human: &Pubkey) -> accounts::FlagHoldback {
+        accounts::FlagHoldback {
+            admin: self.admin.pubkey(),
+            market_config: market_config_pda(),
+            holdback: holdback_pda(human),
+            fee_vault_state: fee_vault_state_pda().0,
+        }
+    }
+
+    pub fn release_holdback_accounts(&self, caller: &Pubkey, human: &Pubkey) -> accounts::ReleaseHoldback {
+        accounts::ReleaseHoldback {
+            caller: *caller,
+            holdback: holdback_pda(human),
+            human: *human,
+            payout_prefs: payout_prefs_pda(human),
+            payout_split: payout_split_pda(human),
+            human_token_account: self.payout_ata(human),
+            fee_vault_state: fee_vault_state_pda().0,
+            fee_vault: fee_vault_pda(),
+            market_config: market_config_pda(),
+            token_program: spl_token::ID,
+        }
+    }
+
+    /// Full `assert_solvency` instruction: the listed builders and holdbacks as fee vault
+    /// liabilities, then each agent's (escrow, vault) pair.
+    pub fn assert_solvency_ix(
+        &self,
+        builder_codes: &[[u8; 32]],
+        humans: &[Pubkey],
+        agents: &[Pubkey],
+        strict: bool,
+    ) -> Instruction {
+        let accounts = accounts::AssertSolvency {
+            fee_vault_state: fee_vault_state_pda().0,
+            fee_vault: fee_vault_pda(),
+            revenue_pool: None,
+        };
+        let liabilities: Vec<Pubkey> =
+            builder_codes.iter().map(builder_pda).chain(humans.iter().map(holdback_pda)).collect();
+        let args = payment_router::instruction::AssertSolvency { liability_count: liabilities.len() as u16, strict };
+        let mut ix = anchor_ix(payment_router::ID, accounts, args);
+        let escrows = agents.iter().flat_map(|agent| [escrow_pda(agent), self.escrow_vault(agent)]);
+        ix.accounts.extend(liabilities.into_iter().chain(escrows).map(|key| AccountMeta::new_readonly(key, false)));
+        ix
+    }
+}
@@ -0,0 +1,385 @@
+//! Harness for running the Attention Marketplace programs under `solana-program-test`.
+//!
+//! Both programs are loaded natively, so tests drive real instruction handlers,
+//! CPIs and SPL token transfers against an in-process bank. Lifecycle scenarios
+//! live in `tests/`; the fuzz targets in `payment-router/fuzz` reuse the harness.
+
+use anchor_lang::solana_program::program_pack::Pack;
+use anchor_lang::solana_program::system_instruction;
+use anchor_lang::{AccountDeserialize, AccountSerialize, InstructionData, ToAccountMetas};
+use anchor_spl::associated_token::{get_associated_token_address, spl_associated_token_account};
+use anchor_spl::token::spl_token;
+use market_common::{pda, seeds};
+use payment_router::{FeeVaultState, MarketConfig};
+use solana_program_test::{processor, BanksClientError, ProgramTest, ProgramTestContext};
+use solana_sdk::account::Account;
+use solana_sdk::account_info::AccountInfo;
+use solana_sdk::clock::Clock;
+use solana_sdk::entrypoint::ProgramResult;
+use solana_sdk::instruction::{Instruction, InstructionError};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signer};
+use solana_sdk::transaction::{Transaction, TransactionError};
+
+mod builders;
+
+// Settlement mint decimals (USDC)
+pub const PAYMENT_DECIMALS: u8 = 6;
+// Session category with no length stats
+pub const UNCATEGORIZED: [u8; 32] = [0; 32];
+// Lamports given to every funded test wallet
+const WALLET_LAMPORTS: u64 = 10_000_000_000;
+
+// Anchor's generated `entry` ties the account slice to the `'info` lifetime, which the
+// builtin processor signature can't express; the slice is leaked for the test's lifetime.
+fn payment_router_entry(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    let accounts = Box::leak(Box::new(accounts.to_vec()));
+    payment_router::entry(program_id, accounts, data)
+}
+
+fn governance_entry(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    let accounts = Box::leak(Box::new(accounts.to_vec()));
+    governance::entry(program_id, accounts, data)
+}
+
+pub fn market_config_pda() -> Pubkey {
+    pda::market_config(&payment_router::ID).0
+}
+
+/// Signer of the program's `emit_cpi!` self-invocations.
+pub fn event_authority_pda() -> Pubkey {
+    Pubkey::find_program_address(&[b"__event_authority"], &payment_router::ID).0
+}
+
+pub fn fee_vault_state_pda() -> (Pubkey, u8) {
+    pda::fee_vault_state(&payment_router::ID)
+}
+
+pub fn fee_vault_pda() -> Pubkey {
+    pda::fee_vault(&payment_router::ID).0
+}
+
+pub fn escrow_pda(agent: &Pubkey) -> Pubkey {
+    pda::escrow(&payment_router::ID, agent).0
+}
+
+pub fn session_pda(escrow: &Pubkey, session_id: u64) -> Pubkey {
+    Pubkey::find_program_address(
+        &[seeds::SESSION, escrow.as_ref(), session_id.to_le_bytes().as_ref()],
+        &payment_router::ID,
+    )
+    .0
+}
+
+pub fn session_stats_pda(category: &[u8; 32]) -> Pubkey {
+    Pubkey::find_program_address(&[seeds::SESSION_STATS, category.as_ref()], &payment_router::ID).0
+}
+
+pub fn holdback_pda(human: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[seeds::HOLDBACK, human.as_ref()], &payment_router::ID).0
+}
+
+pub fn payout_prefs_pda(human: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[seeds::PAYOUT_PREFS, human.as_ref()], &payment_router::ID).0
+}
+
+pub fn payout_split_pda(human: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[seeds::PAYOUT_SPLIT, human.as_ref()], &payment_router::ID).0
+}
+
+pub fn org_member_pda(human: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[seeds::ORG_MEMBER, human.as_ref()], &payment_router::ID).0
+}
+
+pub fn availability_pda(human: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[seeds::AVAILABILITY, human.as_ref()], &payment_router::ID).0
+}
+
+pub fn blocked_agents_pda(human: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[seeds::BLOCKED_AGENTS, human.as_ref()], &payment_router::ID).0
+}
+
+pub fn builder_pda(builder_code: &[u8; 32]) -> Pubkey {
+    pda::builder_balance(&payment_router::ID, builder_code).0
+}
+
+pub fn governance_pda() -> Pubkey {
+    Pubkey::find_program_address(&[seeds::GOVERNANCE], &governance::ID).0
+}
+
+pub fn vote_vault_pda() -> Pubkey {
+    Pubkey::find_program_address(&[seeds::VOTE_VAULT], &governance::ID).0
+}
+
+pub fn proposal_pda(id: u64) -> Pubkey {
+    Pubkey::find_program_address(&[seeds::PROPOSAL, id.to_le_bytes().as_ref()], &governance::ID).0
+}
+
+pub fn vote_record_pda(proposal: &Pubkey, voter: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[seeds::VOTE, proposal.as_ref(), voter.as_ref()], &governance::ID).0
+}
+
+/// Builds an Anchor instruction from a generated `accounts::*` struct and `instruction::*` args.
+pub fn anchor_ix(program_id: Pubkey, accounts: impl ToAccountMetas, args: impl InstructionData) -> Instruction {
+    Instruction {
+        program_id,
+        accounts: accounts.to_account_metas(None),
+        data: args.data(),
+    }
+}
+
+/// Asserts that a transaction failed with the Anchor error `code` (e.g. `ErrorCode::X.into()`).
+pub fn assert_anchor_error(result: Result<(), BanksClientError>, code: u32) {
+    match result.expect_err("transaction should have failed").unwrap() {
+        TransactionError::InstructionError(_, InstructionError::Custom(actual)) => assert_eq!(actual, code),
+        other => panic!("expected custom error {code}, got {other:?}"),
+    }
+}
+
+/// A running bank with both programs deployed and the marketplace initialized.
+pub struct Harness {
+    pub context: ProgramTestContext,
+    // Market config authority; also acts as the settlement router
+    pub admin: Keypair,
+    pub payment_mint: Pubkey,
+}
+
+impl Harness {
+    /// Boots the bank with the market config, fee vault state and fee vault in genesis.
+    ///
+    /// `initialize_market_config` and `initialize_fee_vault` are gated on the hardcoded
+    /// `AUTHORIZED_ADMIN`, whose key tests don't hold, so their accounts are written in
+    /// the state those instructions leave them in, with `admin` as authority.
+    pub async fn start(fee_basis_points: u16) -> Self {
+        Self::start_with_decimals(fee_basis_points, PAYMENT_DECIMALS).await
+    }
+
+    /// Like `start`, with a payment mint of `decimals` decimals instead of USDC's.
+    pub async fn start_with_decimals(fee_basis_points: u16, decimals: u8) -> Self {
+        let mut program_test = ProgramTest::default();
+        program_test.prefer_bpf(false);
+        program_test.add_program("payment_router", payment_router::ID, processor!(payment_router_entry));
+        program_test.add_program("governance", governance::ID, processor!(governance_entry));
+
+        let admin = Keypair::new();
+        let payment_mint = Pubkey::new_unique();
+        program_test.add_account(admin.pubkey(), system_account(WALLET_LAMPORTS));
+
+        let mint = spl_token::state::Mint {
+            mint_authority: Some(admin.pubkey()).into(),
+            decimals,
+            is_initialized: true,
+            ..Default::default()
+        };
+        program_test.add_account(payment_mint, packed_account(mint, spl_token::ID));
+
+        let config = MarketConfig {
+            authority: admin.pubkey(),
+            fee_basis_points,
+            holdback_bps: 0,
+            holdback_period: 0,
+            features: MarketConfig::DEFAULT_FEATURES,
+            swap_program: Pubkey::default(),
+            builder_share_bps: MarketConfig::DEFAULT_BUILDER_SHARE_BPS,
+            governance: Pubkey::default(),
+            payment_mint,
+            payment_decimals: decimals,
+            migration_source: Pubkey::default(),
+            automation_authority: Pubkey::default(),
+            shard_count: 0,
+            heartbeat_timeout: 0,
+            last_heartbeat: 0,
+            standby_authority: Pubkey::default(),
+            failover_delay: 0,
+            holdback_interest_bps_per_day: 0,
+            fee_challenge_window: 0,
+            fee_holiday_start: 0,
+            fee_holiday_end: 0,
+            bump: Pubkey::find_program_address(&[seeds::MARKET_CONFIG], &payment_router::ID).1,
+            velocity_ceiling: 0,
+            velocity_hour: 0,
+            velocity_current: 0,
+            velocity_previous: 0,
+            settlements_paused: false,
+            deposit_caps: [0; 4],
+            matcher_share_bps: 0,
+            quality_multiplier_min_bps: 10_000,
+            quality_multiplier_max_bps: 10_000,
+            live_attestation_keys: 0,
+            attestations_required_at: 0,
+        };
+        program_test.add_account(market_config_pda(), anchor_account(&config, payment_router::ID));
+
+        let (fee_vault_state, bump) = fee_vault_state_pda();
+        let state = FeeVaultState {
+            authority: admin.pubkey(),
+            protocol_balance: 0,
+            total_collected: 0,
+            bump,
+            pending_settlement_fees: 0,
+            pending_recent: 0,
+            pending_window: 0,
+        };
+        program_test.add_account(fee_vault_state, anchor_account(&state, payment_router::ID));
+
+        let fee_vault = spl_token::state::Account {
+            mint: payment_mint,
+            owner: fee_vault_state,
+            state: spl_token::state::AccountState::Initialized,
+            ..Default::default()
+        };
+        program_test.add_account(fee_vault_pda(), packed_account(fee_vault, spl_token::ID));
+
+        let context = program_test.start_with_context().await;
+        Self { context, admin, payment_mint }
+    }
+
+    /// Signs with the context payer plus `signers` and processes `instructions` atomically.
+    pub async fn process(&mut self, instructions: &[Instruction], signers: &[&Keypair]) -> Result<(), BanksClientError> {
+        let blockhash = self.context.banks_client.get_latest_blockhash().await?;
+        let mut all_signers = vec![&self.context.payer];
+        all_signers.extend_from_slice(signers);
+        let tx = Transaction::new_signed_with_payer(
+            instructions,
+            Some(&self.context.payer.pubkey()),
+            &all_signers,
+            blockhash,
+        );
+        self.context.banks_client.process_transaction(tx).await
+    }
+
+    /// Creates a wallet with enough lamports to pay for its own accounts.
+    pub async fn funded_wallet(&mut self) -> Keypair {
+        let wallet = Keypair::new();
+        let ix = system_instruction::transfer(&self.context.payer.pubkey(), &wallet.pubkey(), WALLET_LAMPORTS);
+        self.process(&[ix], &[]).await.unwrap();
+        wallet
+    }
+
+    /// Creates a token account for `owner` (which may be a PDA) at a fresh address.
+    pub async fn token_account(&mut self, mint: &Pubkey, owner: &Pubkey) -> Pubkey {
+        let account = Keypair::new();
+        let rent = self.context.banks_client.get_rent().await.unwrap();
+        let space = spl_token::state::Account::LEN;
+        let ixs = [
+            system_instruction::create_account(
+                &self.context.payer.pubkey(),
+                &account.pubkey(),
+                rent.minimum_balance(space),
+                space as u64,
+                &spl_token::ID,
+            ),
+            spl_token::instruction::initialize_account3(&spl_token::ID, &account.pubkey(), mint, owner).unwrap(),
+        ];
+        self.process(&ixs, &[&account]).await.unwrap();
+        account.pubkey()
+    }
+
+    /// Escrow vault for `agent`: the escrow PDA's ATA in the payment mint.
+    pub fn escrow_vault(&self, agent: &Pubkey) -> Pubkey {
+        get_associated_token_address(&escrow_pda(agent), &self.payment_mint)
+    }
+
+    pub async fn create_escrow_vault(&mut self, agent: &Pubkey) -> Pubkey {
+        let ix = spl_associated_token_account::instruction::create_associated_token_account(
+            &self.context.payer.pubkey(),
+            &escrow_pda(agent),
+            &self.payment_mint,
+            &spl_token::ID,
+        );
+        self.process(&[ix], &[]).await.unwrap();
+        self.escrow_vault(agent)
+    }
+
+    /// Creates a fresh mint with `admin` as its mint authority.
+    pub async fn create_mint(&mut self, decimals: u8) -> Pubkey {
+        let mint = Keypair::new();
+        let rent = self.context.banks_client.get_rent().await.unwrap();
+        let space = spl_token::state::Mint::LEN;
+        let ixs = [
+            system_instruction::create_account(
+                &self.context.payer.pubkey(),
+                &mint.pubkey(),
+                rent.minimum_balance(space),
+                space as u64,
+                &spl_token::ID,
+            ),
+            spl_token::instruction::initialize_mint2(&spl_token::ID, &mint.pubkey(), &self.admin.pubkey(), None, decimals)
+                .unwrap(),
+        ];
+        self.process(&ixs, &[&mint]).await.unwrap();
+        mint.pubkey()
+    }
+
+    pub async fn mint_to(&mut self, mint: &Pubkey, destination: &Pubkey, amount: u64) {
+        let ix = spl_token::instruction::mint_to(&spl_token::ID, mint, destination, &self.admin.pubkey(), &[], amount)
+            .unwrap();
+        let admin = self.admin.insecure_clone();
+        self.process(&[ix], &[&admin]).await.unwrap();
+    }
+
+    /// Token balance of `address`, or 0 if the account doesn't exist yet.
+    pub async fn token_balance(&mut self, address: &Pubkey) -> u64 {
+        match self.context.banks_client.get_account(*address).await.unwrap() {
+            Some(account) => spl_token::state::Account::unpack(&account.data).unwrap().amount,
+            None => 0,
+        }
+    }
+
+    /// Payout ATA `close_settlement` derives for `human` in the payment mint.
+    pub fn payout_ata(&self, human: &Pubkey) -> Pubkey {
+        get_associated_token_address(human, &self.payment_mint)
+    }
+
+    pub async fn account<T: AccountDeserialize>(&mut self, address: &Pubkey) -> T {
+        let account = self.context.banks_client.get_account(*address).await.unwrap()
+            .unwrap_or_else(|| panic!("account {address} does not exist"));
+        T::try_deserialize(&mut account.data.as_slice()).unwrap()
+    }
+
+    /// Overwrites an existing account's data with `state`, keeping its size, owner and
+    /// lamports. For regression tests that need state no instruction would leave behind.
+    pub async fn set_account_state<T: AccountSerialize>(&mut self, address: &Pubkey, state: &T) {
+        let mut account = self.context.banks_client.get_account(*address).await.unwrap()
+            .unwrap_or_else(|| panic!("account {address} does not exist"));
+        let len = account.data.len();
+        account.data.clear();
+        state.try_serialize(&mut account.data).unwrap();
+        account.data.resize(len, 0);
+        self.context.set_account(address, &account.into());
+    }
+
+    pub async fn account_exists(&mut self, address: &Pubkey) -> bool {
+        self.context.banks_client.get_account(*address).await.unwrap().is_some()
+    }
+
+    pub async fn now(&mut self) -> i64 {
+        self.context.banks_client.get_sysvar::<Clock>().await.unwrap().unix_timestamp
+    }
+
+    /// Moves the bank clock forward. Also warps a slot so a transaction retried after
+    /// the wait gets a fresh blockhash instead of being dropped as a duplicate.
+    pub async fn advance_clock(&mut self, seconds: i64) {
+        let mut clock = self.context.banks_client.get_sysvar::<Clock>().await.unwrap();
+        self.context.warp_to_slot(clock.slot + 1).unwrap();
+        clock.slot += 1;
+        clock.unix_timestamp += seconds;
+        self.context.set_sysvar(&clock);
+    }
+}
+
+fn system_account(lamports: u64) -> Account {
+    Account::new(lamports, 0, &solana_sdk::system_program::ID)
+}
+
+fn packed_account<T: Pack>(state: T, owner: Pubkey) -> Account {
+    let mut data = vec![0; T::LEN];
+    T::pack(state, &mut data).unwrap();
+    Account { lamports: WALLET_LAMPORTS, data, owner, executable: false, rent_epoch: 0 }
+}
+
+fn anchor_account<T: AccountSerialize>(state: &T, owner: Pubkey) -> Account {
+    let mut data = Vec::new();
+    state.try_serialize(&mut data).unwrap();
+    Account { lamports: WALLET_LAMPORTS, data, owner, executable: false, rent_epoch: 0 }
+}
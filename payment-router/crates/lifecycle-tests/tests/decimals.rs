@@ -0,0 +1,122 @@
+//! Settlement amounts across payment mint decimals, and rejection of mints the market
+//! hasn't registered.
+
+use anchor_spl::associated_token::get_associated_token_address;
+use lifecycle_tests::*;
+use payment_router::instruction as ix;
+use payment_router::{micro_usd_to_base_units, EscrowAccount, FeeVaultState, MarketConfig, SettlementExtras};
+use solana_sdk::signature::{Keypair, Signer};
+
+// 15% protocol fee
+const FEE_BPS: u16 = 1500;
+// $0.001 per second and a $500 deposit, in micro-USD
+const PRICE_MICRO_USD: u64 = 1_000;
+const DEPOSIT_MICRO_USD: u64 = 500_000_000;
+const SECONDS: u64 = 600;
+
+#[test]
+fn micro_usd_converts_exactly_at_6_and_9_decimals() {
+    assert_eq!(micro_usd_to_base_units(1_234_567, 6).unwrap(), 1_234_567);
+    assert_eq!(micro_usd_to_base_units(1_234_567, 9).unwrap(), 1_234_567_000);
+    assert_eq!(micro_usd_to_base_units(0, 9).unwrap(), 0);
+    // Too large for a 9-decimal mint
+    assert!(micro_usd_to_base_units(u64::MAX / 100, 9).is_err());
+    // Coarser mints take only amounts they can hold exactly
+    assert_eq!(micro_usd_to_base_units(1_230_000, 2).unwrap(), 123);
+    assert!(micro_usd_to_base_units(1_234_567, 2).is_err());
+}
+
+/// Boots a market in a mint of `decimals` decimals with a funded escrow and a session open
+/// for `human`, returning the harness, agent and price per second in base units.
+async fn market_with_session(decimals: u8, human: &Keypair) -> (Harness, Keypair, u64) {
+    let mut h = Harness::start_with_decimals(FEE_BPS, decimals).await;
+    let admin = h.admin.insecure_clone();
+    let mint = h.payment_mint;
+    let program = payment_router::ID;
+
+    let config: MarketConfig = h.account(&market_config_pda()).await;
+    assert_eq!(config.payment_decimals, decimals);
+    let price = config.base_units(PRICE_MICRO_USD).unwrap();
+    let deposit = config.base_units(DEPOSIT_MICRO_USD).unwrap();
+
+    let agent = h.funded_wallet().await;
+    let agent_tokens = h.token_account(&mint, &agent.pubkey()).await;
+    h.mint_to(&mint, &agent_tokens, deposit).await;
+    h.create_escrow_vault(&agent.pubkey()).await;
+    let deposit_ix = anchor_ix(
+        program,
+        h.deposit_escrow_accounts(&agent.pubkey(), &agent_tokens),
+        ix::DepositEscrow { amount: deposit },
+    );
+    h.process(&[deposit_ix], &[&agent]).await.unwrap();
+
+    let open = anchor_ix(
+        program,
+        h.open_session_accounts(&agent.pubkey(), &human.pubkey(), 1),
+        ix::OpenSession { session_id: 1, price_per_second: price, category: UNCATEGORIZED },
+    );
+    h.process(&[open], &[&admin]).await.unwrap();
+    h.advance_clock(SECONDS as i64).await;
+    (h, agent, price)
+}
+
+fn settle_ix(accounts: payment_router::accounts::CloseSettlement, price: u64) -> solana_sdk::instruction::Instruction {
+    anchor_ix(
+        payment_router::ID,
+        accounts,
+        ix::CloseSettlement {
+            verified_seconds: SECONDS,
+            agreed_price_per_second: price,
+            nonce: 1,
+            builder_code_opt: None,
+            quality_score: None,
+            extras: SettlementExtras::default(),
+        },
+    )
+}
+
+#[tokio::test]
+async fn settles_exact_amounts_at_6_and_9_decimals() {
+    for decimals in [6, 9] {
+        let human = Keypair::new();
+        let (mut h, agent, price) = market_with_session(decimals, &human).await;
+        let admin = h.admin.insecure_clone();
+        assert_eq!(price, PRICE_MICRO_USD * 10u64.pow(decimals as u32 - 6));
+
+        let settle = settle_ix(h.close_settlement_accounts(&agent.pubkey(), &human.pubkey(), 1, None), price);
+        h.process(&[settle], &[&admin]).await.unwrap();
+
+        let total = SECONDS * price;
+        let fee = total * FEE_BPS as u64 / 10_000;
+        let deposit = micro_usd_to_base_units(DEPOSIT_MICRO_USD, decimals).unwrap();
+        assert_eq!(h.token_balance(&h.payout_ata(&human.pubkey())).await, total - fee, "{decimals} decimals");
+        assert_eq!(h.token_balance(&fee_vault_pda()).await, fee, "{decimals} decimals");
+        assert_eq!(h.token_balance(&h.escrow_vault(&agent.pubkey())).await, deposit - total, "{decimals} decimals");
+        let escrow: EscrowAccount = h.account(&escrow_pda(&agent.pubkey())).await;
+        assert_eq!(escrow.balance, deposit - total, "{decimals} decimals");
+        let fee_state: FeeVaultState = h.account(&fee_vault_state_pda().0).await;
+        assert_eq!(fee_state.total_collected, fee, "{decimals} decimals");
+    }
+}
+
+#[tokio::test]
+async fn rejects_settlement_in_an_unregistered_mint() {
+    let human = Keypair::new();
+    let (mut h, agent, price) = market_with_session(PAYMENT_DECIMALS, &human).await;
+    let admin = h.admin.insecure_clone();
+
+    // Same decimals as the payment mint, but never registered with the market
+    let unregistered = h.create_mint(PAYMENT_DECIMALS).await;
+    let mut accounts = h.close_settlement_accounts(&agent.pubkey(), &human.pubkey(), 1, None);
+    accounts.mint = unregistered;
+    accounts.user_wallet = get_associated_token_address(&human.pubkey(), &unregistered);
+    assert_anchor_error(
+        h.process(&[settle_ix(accounts, price)], &[&admin]).await,
+        payment_router::ErrorCode::InvalidMint.into(),
+    );
+
+    // Nothing moved: the escrow still holds the full deposit
+    let escrow: EscrowAccount = h.account(&escrow_pda(&agent.pubkey())).await;
+    assert_eq!(escrow.balance, micro_usd_to_base_units(DEPOSIT_MICRO_USD, PAYMENT_DECIMALS).unwrap());
+    assert_eq!(escrow.settlement_nonce, 0);
+}
@@ -0,0 +1,490 @@
+//! Full marketplace lifecycle: deposit → sessions → settlement → claims → disputes,
+//! with token and ledger balances asserted after every step.
+
+use anchor_spl::token::spl_token;
+use lifecycle_tests::*;
+use payment_router::instruction as ix;
+use payment_router::{BuilderBalance, EscrowAccount, FeeVaultState, Holdback, MarketConfig, SettlementExtras};
+use solana_sdk::signature::{Keypair, Signer};
+use solana_sdk::system_program;
+
+// 15% protocol fee
+const FEE_BPS: u16 = 1500;
+// 10% of each human payout held for review, for one hour
+const HOLDBACK_BPS: u16 = 1000;
+const HOLDBACK_PERIOD: i64 = 3600;
+const PRICE_PER_SECOND: u64 = 1_000;
+const DEPOSIT: u64 = 500_000_000;
+
+/// Splits `seconds` of settlement into (human payout, protocol fee, holdback).
+fn split(seconds: u64) -> (u64, u64, u64) {
+    let total = seconds * PRICE_PER_SECOND;
+    let fee = total * FEE_BPS as u64 / 10_000;
+    let holdback = (total - fee) * HOLDBACK_BPS as u64 / 10_000;
+    (total - fee - holdback, fee, holdback)
+}
+
+#[tokio::test]
+async fn full_settlement_lifecycle() {
+    let mut h = Harness::start(FEE_BPS).await;
+    let admin = h.admin.insecure_clone();
+    let mint = h.payment_mint;
+    let program = payment_router::ID;
+
+    // Config: hold back part of every payout, register a builder
+    let args = ix::SetHoldbackParams { holdback_bps: HOLDBACK_BPS, holdback_period: HOLDBACK_PERIOD };
+    let set_holdback = anchor_ix(program, h.update_market_config_accounts(), args);
+    h.process(&[set_holdback], &[&admin]).await.unwrap();
+
+    let builder_code = [7u8; 32];
+    let builder_wallet = h.funded_wallet().await;
+    let register = anchor_ix(
+        program,
+        h.register_builder_accounts(&builder_code, &builder_wallet.pubkey()),
+        ix::RegisterBuilder { builder_code },
+    );
+    h.process(&[register], &[&admin]).await.unwrap();
+
+    let config: MarketConfig = h.account(&market_config_pda()).await;
+    assert_eq!(config.fee_basis_points, FEE_BPS);
+    assert_eq!(config.holdback_bps, HOLDBACK_BPS);
+
+    // Deposit
+    let agent = h.funded_wallet().await;
+    let agent_tokens = h.token_account(&mint, &agent.pubkey()).await;
+    h.mint_to(&mint, &agent_tokens, DEPOSIT * 2).await;
+    let escrow = escrow_pda(&agent.pubkey());
+    let vault = h.create_escrow_vault(&agent.pubkey()).await;
+
+    let deposit = anchor_ix(
+        program,
+        h.deposit_escrow_accounts(&agent.pubkey(), &agent_tokens),
+        ix::DepositEscrow { amount: DEPOSIT },
+    );
+    h.process(&[deposit], &[&agent]).await.unwrap();
+
+    assert_eq!(h.token_balance(&agent_tokens).await, DEPOSIT);
+    assert_eq!(h.token_balance(&vault).await, DEPOSIT);
+    let escrow_state: EscrowAccount = h.account(&escrow).await;
+    assert_eq!(escrow_state.agent, agent.pubkey());
+    assert_eq!(escrow_state.balance, DEPOSIT);
+
+    // Sessions: two humans, each reserving a streaming window from the escrow
+    let alice = Keypair::new();
+    let bob = Keypair::new();
+    let open = |h: &Harness, human: &Keypair, session_id| {
+        anchor_ix(
+            program,
+            h.open_session_accounts(&agent.pubkey(), &human.pubkey(), session_id),
+            ix::OpenSession { session_id, price_per_second: PRICE_PER_SECOND, category: UNCATEGORIZED },
+        )
+    };
+    let ixs = [open(&h, &alice, 1), open(&h, &bob, 2)];
+    h.process(&ixs, &[&admin]).await.unwrap();
+
+    let reserve = PRICE_PER_SECOND * 300;
+    let escrow_state: EscrowAccount = h.account(&escrow).await;
+    assert_eq!(escrow_state.open_sessions, 2);
+    assert_eq!(escrow_state.reserved_balance, 2 * reserve);
+    assert_eq!(escrow_state.withdrawable(), DEPOSIT - 2 * reserve);
+
+    // Settlement
+    h.advance_clock(600).await;
+    let settle = |h: &Harness, human: &Keypair, session_id, nonce, verified_seconds, builder_code: Option<&[u8; 32]>| {
+        anchor_ix(
+            program,
+            h.close_settlement_accounts(&agent.pubkey(), &human.pubkey(), session_id, builder_code),
+            ix::CloseSettlement {
+                verified_seconds,
+                agreed_price_per_second: PRICE_PER_SECOND,
+                nonce,
+                builder_code_opt: builder_code.copied(),
+                quality_score: None,
+                extras: SettlementExtras::default(),
+            },
+        )
+    };
+
+    // Billing past the session's wall-clock time is rejected
+    let overbilled = settle(&h, &alice, 1, 1, 900, None);
+    assert_anchor_error(
+        h.process(&[overbilled], &[&admin]).await,
+        payment_router::ErrorCode::SessionElapsedExceeded.into(),
+    );
+
+    let ixs = [settle(&h, &alice, 1, 1, 600, Some(&builder_code)), settle(&h, &bob, 2, 2, 300, None)];
+    h.process(&ixs, &[&admin]).await.unwrap();
+
+    let (alice_paid, alice_fee, alice_held) = split(600);
+    let (bob_paid, bob_fee, bob_held) = split(300);
+    let builder_share = alice_fee * MarketConfig::DEFAULT_BUILDER_SHARE_BPS as u64 / 10_000;
+    let spent = 600 * PRICE_PER_SECOND + 300 * PRICE_PER_SECOND;
+
+    assert_eq!(h.token_balance(&h.payout_ata(&alice.pubkey())).await, alice_paid);
+    assert_eq!(h.token_balance(&h.payout_ata(&bob.pubkey())).await, bob_paid);
+    assert_eq!(h.token_balance(&vault).await, DEPOSIT - spent);
+    assert_eq!(
+        h.token_balance(&fee_vault_pda()).await,
+        alice_fee + alice_held + bob_fee + bob_held
+    );
+
+    let escrow_state: EscrowAccount = h.account(&escrow).await;
+    assert_eq!(escrow_state.balance, DEPOSIT - spent);
+    assert_eq!(escrow_state.settlement_nonce, 2);
+    let fee_state: FeeVaultState = h.account(&fee_vault_state_pda().0).await;
+    assert_eq!(fee_state.protocol_balance, alice_fee - builder_share + bob_fee);
+    assert_eq!(fee_state.total_collected, alice_fee + bob_fee);
+    let builder: BuilderBalance = h.account(&builder_pda(&builder_code)).await;
+    assert_eq!(builder.balance, builder_share);
+    let holdback: Holdback = h.account(&holdback_pda(&alice.pubkey())).await;
+    assert_eq!(holdback.amount, alice_held);
+
+    // Nonces can't be replayed
+    let replayed = settle(&h, &bob, 2, 2, 1, None);
+    assert_anchor_error(
+        h.process(&[replayed], &[&admin]).await,
+        payment_router::ErrorCode::NonceAlreadyUsed.into(),
+    );
+
+    // Closing sessions frees their reserve and returns rent to the router
+    let ixs = [1, 2].map(|session_id| {
+        anchor_ix(program, h.close_session_accounts(&agent.pubkey(), session_id), ix::CloseSession {})
+    });
+    h.process(&ixs, &[&admin]).await.unwrap();
+    assert!(!h.account_exists(&session_pda(&escrow, 1)).await);
+    let escrow_state: EscrowAccount = h.account(&escrow).await;
+    assert_eq!(escrow_state.open_sessions, 0);
+    assert_eq!(escrow_state.reserved_balance, 0);
+
+    // Claims: builder share
+    let builder_tokens = h.token_account(&mint, &builder_wallet.pubkey()).await;
+    let claim = anchor_ix(
+        program,
+        h.claim_builder_balance_accounts(&builder_code, &builder_wallet.pubkey(), &builder_tokens),
+        ix::ClaimBuilderBalance { swap: None },
+    );
+    h.process(&[claim], &[&builder_wallet]).await.unwrap();
+    assert_eq!(h.token_balance(&builder_tokens).await, builder_share);
+    let builder: BuilderBalance = h.account(&builder_pda(&builder_code)).await;
+    assert_eq!(builder.balance, 0);
+    assert_eq!(builder.total_earned, builder_share);
+
+    // Claims: holdbacks stay locked until the review period ends
+    let release = |h: &Harness, human: &Keypair| {
+        anchor_ix(program, h.release_holdback_accounts(&human.pubkey(), &human.pubkey()), ix::ReleaseHoldback {})
+    };
+    assert_anchor_error(
+        h.process(&[release(&h, &alice)], &[&alice]).await,
+        payment_router::ErrorCode::HoldbackLocked.into(),
+    );
+
+    // Disputes: Bob's holdback is flagged, which blocks release even after the period
+    let flag = anchor_ix(program, h.flag_holdback_accounts(&bob.pubkey()), ix::FlagHoldback { flagged: true });
+    h.process(&[flag], &[&admin]).await.unwrap();
+    h.advance_clock(HOLDBACK_PERIOD).await;
+
+    assert_anchor_error(
+        h.process(&[release(&h, &bob)], &[&bob]).await,
+        payment_router::ErrorCode::HoldbackFlagged.into(),
+    );
+
+    h.process(&[release(&h, &alice)], &[&alice]).await.unwrap();
+    assert_eq!(h.token_balance(&h.payout_ata(&alice.pubkey())).await, alice_paid + alice_held);
+    let holdback: Holdback = h.account(&holdback_pda(&alice.pubkey())).await;
+    assert_eq!(holdback.amount, 0);
+    assert_eq!(holdback.total_held, alice_held);
+
+    // Slashing moves the flagged holdback to the protocol without moving tokens
+    let slash = anchor_ix(program, h.flag_holdback_accounts(&bob.pubkey()), ix::SlashHoldback {});
+    h.process(&[slash], &[&admin]).await.unwrap();
+    let holdback: Holdback = h.account(&holdback_pda(&bob.pubkey())).await;
+    assert_eq!(holdback.amount, 0);
+    assert_eq!(h.token_balance(&h.payout_ata(&bob.pubkey())).await, bob_paid);
+    let fee_state: FeeVaultState = h.account(&fee_vault_state_pda().0).await;
+    let protocol_balance = alice_fee - builder_share + bob_fee + bob_held;
+    assert_eq!(fee_state.protocol_balance, protocol_balance);
+
+    // Vaults still cover every ledger entry
+    let humans = [alice.pubkey(), bob.pubkey()];
+    let solvency = h.assert_solvency_ix(&[builder_code], &humans, &[agent.pubkey()], true);
+    h.process(&[solvency], &[]).await.unwrap();
+
+    // Claims: protocol fees (0 = everything) leave the fee vault empty
+    let treasury = h.token_account(&mint, &admin.pubkey()).await;
+    let claim = anchor_ix(program, h.claim_protocol_fees_accounts(&treasury), ix::ClaimProtocolFees { amount: 0 });
+    h.process(&[claim], &[&admin]).await.unwrap();
+    assert_eq!(h.token_balance(&treasury).await, protocol_balance);
+    assert_eq!(h.token_balance(&fee_vault_pda()).await, 0);
+
+    // The agent withdraws what's left; overdrawing fails
+    let withdraw = |h: &Harness, amount| {
+        anchor_ix(
+            program,
+            h.withdraw_escrow_accounts(&agent.pubkey(), &agent_tokens),
+            ix::WithdrawEscrow { amount },
+        )
+    };
+    let remaining = DEPOSIT - spent;
+    assert_anchor_error(
+        h.process(&[withdraw(&h, remaining + 1)], &[&agent]).await,
+        payment_router::ErrorCode::InsufficientFunds.into(),
+    );
+    h.process(&[withdraw(&h, remaining)], &[&agent]).await.unwrap();
+    assert_eq!(h.token_balance(&vault).await, 0);
+    assert_eq!(h.token_balance(&agent_tokens).await, DEPOSIT + remaining);
+    let escrow_state: EscrowAccount = h.account(&escrow).await;
+    assert_eq!(escrow_state.balance, 0);
+
+    // Every token minted is accounted for
+    let total = h.token_balance(&agent_tokens).await
+        + h.token_balance(&h.payout_ata(&alice.pubkey())).await
+        + h.token_balance(&h.payout_ata(&bob.pubkey())).await
+        + h.token_balance(&builder_tokens).await
+        + h.token_balance(&treasury).await;
+    assert_eq!(total, DEPOSIT * 2);
+}
+
+#[tokio::test]
+async fn governance_takes_over_fee_rate() {
+    let mut h = Harness::start(FEE_BPS).await;
+    let admin = h.admin.insecure_clone();
+    let voting_period = 24 * 60 * 60;
+
+    let vote_mint = h.create_mint(6).await;
+    let payer = h.context.payer.insecure_clone();
+    let ix = anchor_ix(
+        governance::ID,
+        governance::accounts::InitializeGovernance {
+            payer: payer.pubkey(),
+            governance: governance_pda(),
+            vote_mint,
+            vote_vault: vote_vault_pda(),
+            token_program: spl_token::ID,
+            system_program: system_program::ID,
+        },
+        governance::instruction::InitializeGovernance { quorum: 100, voting_period },
+    );
+    h.process(&[ix], &[]).await.unwrap();
+
+    let ix = anchor_ix(
+        payment_router::ID,
+        h.update_market_config_accounts(),
+        ix::SetGovernance { governance: governance_pda() },
+    );
+    h.process(&[ix], &[&admin]).await.unwrap();
+
+    // The admin no longer controls fee parameters
+    let ix = anchor_ix(
+        payment_router::ID,
+        h.update_fee_params_accounts(),
+        ix::SetFeeBasisPoints { fee_basis_points: 0 },
+    );
+    assert_anchor_error(h.process(&[ix], &[&admin]).await, payment_router::ErrorCode::Unauthorized.into());
+
+    let voter = h.funded_wallet().await;
+    let voter_tokens = h.token_account(&vote_mint, &voter.pubkey()).await;
+    h.mint_to(&vote_mint, &voter_tokens, 150).await;
+
+    let proposal = proposal_pda(0);
+    let ix = anchor_ix(
+        governance::ID,
+        governance::accounts::CreateProposal {
+            proposer: voter.pubkey(),
+            governance: governance_pda(),
+            proposal,
+            system_program: system_program::ID,
+        },
+        governance::instruction::CreateProposal { change: governance::ParamChange::FeeBasisPoints(1000) },
+    );
+    h.process(&[ix], &[&voter]).await.unwrap();
+
+    let ix = anchor_ix(
+        governance::ID,
+        governance::accounts::CastVote {
+            voter: voter.pubkey(),
+            governance: governance_pda(),
+            proposal,
+            vote_record: vote_record_pda(&proposal, &voter.pubkey()),
+            voter_token_account: voter_tokens,
+            vote_vault: vote_vault_pda(),
+            token_program: spl_token::ID,
+            system_program: system_program::ID,
+        },
+        governance::instruction::CastVote { amount: 150, approve: true },
+    );
+    h.process(&[ix], &[&voter]).await.unwrap();
+    assert_eq!(h.token_balance(&voter_tokens).await, 0);
+    assert_eq!(h.token_balance(&vote_vault_pda()).await, 150);
+
+    let execute = anchor_ix(
+        governance::ID,
+        governance::accounts::ExecuteProposal {
+            governance: governance_pda(),
+            proposal,
+            market_config: market_config_pda(),
+            payment_router: payment_router::ID,
+        },
+        governance::instruction::ExecuteProposal {},
+    );
+    assert_anchor_error(
+        h.process(std::slice::from_ref(&execute), &[]).await,
+        governance::ErrorCode::VotingOpen.into(),
+    );
+
+    h.advance_clock(voting_period).await;
+    h.process(&[execute], &[]).await.unwrap();
+    let config: MarketConfig = h.account(&market_config_pda()).await;
+    assert_eq!(config.fee_basis_points, 1000);
+
+    // Locked vote tokens come back once voting has ended
+    let ix = anchor_ix(
+        governance::ID,
+        governance::accounts::WithdrawVote {
+            voter: voter.pubkey(),
+            governance: governance_pda(),
+            proposal,
+            vote_record: vote_record_pda(&proposal, &voter.pubkey()),
+            voter_token_account: voter_tokens,
+            vote_vault: vote_vault_pda(),
+            token_program: spl_token::ID,
+        },
+        governance::instruction::WithdrawVote {},
+    );
+    h.process(&[ix], &[&voter]).await.unwrap();
+    assert_eq!(h.token_balance(&voter_tokens).await, 150);
+    assert_eq!(h.token_balance(&vote_vault_pda()).await, 0);
+}
+
+#[tokio::test]
+async fn deposit_never_rebinds_existing_escrow() {
+    let mut h = Harness::start(FEE_BPS).await;
+    let mint = h.payment_mint;
+    let program = payment_router::ID;
+
+    let agent = h.funded_wallet().await;
+    let agent_tokens = h.token_account(&mint, &agent.pubkey()).await;
+    h.mint_to(&mint, &agent_tokens, DEPOSIT).await;
+    let escrow = escrow_pda(&agent.pubkey());
+    h.create_escrow_vault(&agent.pubkey()).await;
+
+    let deposit = |h: &Harness, amount| {
+        anchor_ix(
+            program,
+            h.deposit_escrow_accounts(&agent.pubkey(), &agent_tokens),
+            ix::DepositEscrow { amount },
+        )
+    };
+    h.process(&[deposit(&h, DEPOSIT / 2)], &[&agent]).await.unwrap();
+    let created: EscrowAccount = h.account(&escrow).await;
+
+    // Topping up an existing escrow leaves its owner and bump alone
+    h.process(&[deposit(&h, DEPOSIT / 4)], &[&agent]).await.unwrap();
+    let mut escrow_state: EscrowAccount = h.account(&escrow).await;
+    assert_eq!(escrow_state.agent, agent.pubkey());
+    assert_eq!(escrow_state.bump, created.bump);
+    assert_eq!(escrow_state.balance, DEPOSIT / 2 + DEPOSIT / 4);
+
+    // An escrow recorded under another agent is never rebound to the depositor
+    let stored_agent = Keypair::new().pubkey();
+    escrow_state.agent = stored_agent;
+    h.set_account_state(&escrow, &escrow_state).await;
+    assert_anchor_error(
+        h.process(&[deposit(&h, 1)], &[&agent]).await,
+        payment_router::ErrorCode::Unauthorized.into(),
+    );
+    let escrow_state: EscrowAccount = h.account(&escrow).await;
+    assert_eq!(escrow_state.agent, stored_agent);
+    assert_eq!(escrow_state.balance, DEPOSIT / 2 + DEPOSIT / 4);
+}
+
+/// xorshift64: deterministic chaos schedule without pulling in a rand crate.
+fn next_random(state: &mut u64) -> u64 {
+    *state ^= *state << 13;
+    *state ^= *state >> 7;
+    *state ^= *state << 17;
+    *state
+}
+
+#[tokio::test]
+async fn chaotic_settlement_submissions_never_double_pay() {
+    const SETTLEMENTS: u64 = 24;
+    const SECONDS_EACH: u64 = 10;
+    let mut h = Harness::start(FEE_BPS).await;
+    let admin = h.admin.insecure_clone();
+    let mint = h.payment_mint;
+    let program = payment_router::ID;
+
+    let agent = h.funded_wallet().await;
+    let agent_tokens = h.token_account(&mint, &agent.pubkey()).await;
+    h.mint_to(&mint, &agent_tokens, DEPOSIT).await;
+    let escrow = escrow_pda(&agent.pubkey());
+    h.create_escrow_vault(&agent.pubkey()).await;
+    let deposit = anchor_ix(
+        program,
+        h.deposit_escrow_accounts(&agent.pubkey(), &agent_tokens),
+        ix::DepositEscrow { amount: DEPOSIT },
+    );
+    h.process(&[deposit], &[&agent]).await.unwrap();
+
+    let human = Keypair::new();
+    let open = anchor_ix(
+        program,
+        h.open_session_accounts(&agent.pubkey(), &human.pubkey(), 1),
+        ix::OpenSession { session_id: 1, price_per_second: PRICE_PER_SECOND, category: UNCATEGORIZED },
+    );
+    h.process(&[open], &[&admin]).await.unwrap();
+    h.advance_clock(3600).await;
+
+    // The router emits nonces 1..=N in order; the network drops, duplicates and reorders them
+    let mut rng = 0x9e37_79b9_7f4a_7c15;
+    let mut submissions: Vec<u64> = Vec::new();
+    for nonce in 1..=SETTLEMENTS {
+        // A quarter are dropped, a quarter sent twice
+        match next_random(&mut rng) % 4 {
+            0 => {}
+            1 => submissions.extend([nonce, nonce]),
+            _ => submissions.push(nonce),
+        }
+        let len = submissions.len();
+        // One in three submissions overtakes the one before it
+        if len >= 2 && next_random(&mut rng) % 3 == 2 {
+            submissions.swap(len - 1, len - 2);
+        }
+    }
+
+    let mut highest = 0;
+    let mut accepted = 0;
+    for nonce in submissions {
+        let settle = anchor_ix(
+            program,
+            h.close_settlement_accounts(&agent.pubkey(), &human.pubkey(), 1, None),
+            ix::CloseSettlement {
+                verified_seconds: SECONDS_EACH,
+                agreed_price_per_second: PRICE_PER_SECOND,
+                nonce,
+                builder_code_opt: None,
+                quality_score: None,
+                extras: SettlementExtras::default(),
+            },
+        );
+        // Fresh blockhash, so duplicates reach the program instead of the signature cache
+        h.advance_clock(1).await;
+        let result = h.process(&[settle], &[&admin]).await;
+        if nonce > highest {
+            result.unwrap();
+            highest = nonce;
+            accepted += 1;
+        } else {
+            assert_anchor_error(result, payment_router::ErrorCode::NonceAlreadyUsed.into());
+        }
+    }
+
+    // Exactly the accepted settlements moved money, once each (no holdback configured)
+    let total = SECONDS_EACH * PRICE_PER_SECOND;
+    let fee = total * FEE_BPS as u64 / 10_000;
+    assert_eq!(h.token_balance(&h.payout_ata(&human.pubkey())).await, accepted * (total - fee));
+    assert_eq!(h.token_balance(&fee_vault_pda()).await, accepted * fee);
+    let escrow_state: EscrowAccount = h.account(&escrow).await;
+    assert_eq!(escrow_state.settlement_nonce, highest);
+    assert_eq!(escrow_state.balance, DEPOSIT - accepted * total);
+}
@@ -0,0 +1,236 @@
+//! Seeds, constants and errors shared by the Attention Marketplace programs.
+//!
+//! Off-chain consumers can derive every PDA and interpret fee math from this crate
+//! instead of copying string literals out of each program.
+
+use anchor_lang::prelude::*;
+
+// Denominator for every basis-point value (fees, shares, holdbacks)
+pub const BPS_DENOMINATOR: u64 = 10_000;
+// Largest valid basis-point parameter (100%)
+pub const MAX_BPS: u16 = 10_000;
+
+/// Per-network constants, picked at compile time by the `devnet`, `mainnet` or `localnet`
+/// feature (none selects localnet). The programs forward their own features here and
+/// pick their `declare_id!` the same way.
+pub mod network {
+    use anchor_lang::prelude::Pubkey;
+    #[cfg(any(feature = "devnet", feature = "mainnet"))]
+    use solana_program::pubkey;
+
+    #[cfg(any(
+        all(feature = "devnet", feature = "mainnet"),
+        all(feature = "devnet", feature = "localnet"),
+        all(feature = "mainnet", feature = "localnet"),
+    ))]
+    compile_error!("enable at most one of the `devnet`, `mainnet` and `localnet` features");
+
+    #[cfg(feature = "mainnet")]
+    pub const NAME: &str = "mainnet";
+    // Circle USDC
+    #[cfg(feature = "mainnet")]
+    pub const USDC_MINT: Option<Pubkey> = Some(pubkey!("EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v"));
+    // Delay before a newly added attestation key can sign settlements (48 hours)
+    #[cfg(feature = "mainnet")]
+    pub const ATTESTATION_KEY_DELAY: i64 = 48 * 60 * 60;
+    // Timelock on admin-initiated escrow recovery (30 days)
+    #[cfg(feature = "mainnet")]
+    pub const RECOVERY_TIMELOCK: i64 = 30 * 24 * 60 * 60;
+
+    #[cfg(feature = "devnet")]
+    pub const NAME: &str = "devnet";
+    // Circle's devnet USDC
+    #[cfg(feature = "devnet")]
+    pub const USDC_MINT: Option<Pubkey> = Some(pubkey!("4zMMC9srt5Ri5X14GAgXhaHii3GnPAEERYPJgZJDncDU"));
+    // Short enough for integration partners to exercise key rotation and recovery
+    #[cfg(feature = "devnet")]
+    pub const ATTESTATION_KEY_DELAY: i64 = 60 * 60;
+    #[cfg(feature = "devnet")]
+    pub const RECOVERY_TIMELOCK: i64 = 24 * 60 * 60;
+
+    #[cfg(not(any(feature = "devnet", feature = "mainnet")))]
+    pub const NAME: &str = "localnet";
+    // Local validators mint their own test tokens
+    #[cfg(not(any(feature = "devnet", feature = "mainnet")))]
+    pub const USDC_MINT: Option<Pubkey> = None;
+    #[cfg(not(any(feature = "devnet", feature = "mainnet")))]
+    pub const ATTESTATION_KEY_DELAY: i64 = 60;
+    #[cfg(not(any(feature = "devnet", feature = "mainnet")))]
+    pub const RECOVERY_TIMELOCK: i64 = 60;
+
+    /// Whether `mint` may be the market's settlement mint on this network.
+    pub fn is_canonical_mint(mint: &Pubkey) -> bool {
+        match USDC_MINT {
+            Some(usdc) => usdc == *mint,
+            None => true,
+        }
+    }
+}
+
+/// PDA seed prefixes.
+pub mod seeds {
+    // payment_router
+    pub const MARKET_CONFIG: &[u8] = b"market_config";
+    pub const FEE_VAULT_STATE: &[u8] = b"fee_vault_state";
+    pub const FEE_VAULT: &[u8] = b"fee_vault";
+    pub const MINT_FEE_VAULT: &[u8] = b"mint_fee_vault";
+    pub const ESCROW: &[u8] = b"escrow";
+    pub const ESCROW_POSITION: &[u8] = b"escrow_position";
+    pub const SPONSOR: &[u8] = b"sponsor";
+    pub const SESSION: &[u8] = b"session";
+    pub const TASK: &[u8] = b"task";
+    pub const RECOVERY: &[u8] = b"recovery";
+    pub const BUILDER: &[u8] = b"builder";
+    pub const BUILDER_STATS: &[u8] = b"builder_stats";
+    pub const HOLDBACK: &[u8] = b"holdback";
+    pub const ATTESTATION_KEY: &[u8] = b"attestation_key";
+    pub const PAYOUT_PREFS: &[u8] = b"payout_prefs";
+    pub const PAYOUT_SPLIT: &[u8] = b"payout_split";
+    pub const RECEIPT: &[u8] = b"receipt";
+    pub const HUMAN_STATS: &[u8] = b"human_stats";
+    pub const AGENT_STATS: &[u8] = b"agent_stats";
+    pub const CREDIT_LINE: &[u8] = b"credit_line";
+    pub const CREDIT_COLLATERAL: &[u8] = b"credit_collateral";
+    pub const EMISSIONS: &[u8] = b"emissions";
+    pub const ATTN_MINT: &[u8] = b"attn_mint";
+    pub const REVENUE_POOL: &[u8] = b"revenue_pool";
+    pub const REVENUE_SHARE: &[u8] = b"revenue_share";
+    pub const REVENUE_SHARE_MINT: &[u8] = b"revenue_share_mint";
+    pub const FEE_EPOCH: &[u8] = b"fee_epoch";
+    pub const RELAYER_STATS: &[u8] = b"relayer_stats";
+    pub const FORWARD: &[u8] = b"forward";
+    pub const COHORT: &[u8] = b"cohort";
+    pub const COHORT_VAULT: &[u8] = b"cohort_vault";
+    pub const COHORT_MEMBER: &[u8] = b"cohort_member";
+    pub const COHORT_EPOCH: &[u8] = b"cohort_epoch";
+    pub const COHORT_CLAIM: &[u8] = b"cohort_claim";
+    pub const COHORT_DEPOSIT: &[u8] = b"cohort_deposit";
+    pub const FAUCET: &[u8] = b"faucet";
+    pub const FAUCET_CLAIM: &[u8] = b"faucet_claim";
+    pub const PRICE_INDEX: &[u8] = b"price_index";
+    pub const AVAILABILITY: &[u8] = b"availability";
+    pub const DEPOSIT_AUTH: &[u8] = b"deposit_auth";
+    pub const TRANSFER_AUTH: &[u8] = b"transfer_auth";
+    pub const ESCROW_EXPORT: &[u8] = b"escrow_export";
+    pub const ESCROW_IMPORT: &[u8] = b"escrow_import";
+    pub const SETTLEMENT_QUEUE: &[u8] = b"settlement_queue";
+    pub const QUEUED_SETTLEMENT: &[u8] = b"queued_settlement";
+    pub const SHARD: &[u8] = b"shard";
+    pub const ORG: &[u8] = b"org";
+    pub const ORG_MEMBER: &[u8] = b"org_member";
+    pub const API_KEY: &[u8] = b"api_key";
+    pub const SPOT_PRICE: &[u8] = b"spot_price";
+    pub const SESSION_STATS: &[u8] = b"session_stats";
+    pub const CCTP_DEPOSIT: &[u8] = b"cctp_deposit";
+    pub const PAYMENT_INTENT: &[u8] = b"payment_intent";
+    pub const ESCROW_SUMMARY: &[u8] = b"escrow_summary";
+    pub const TASK_GATE: &[u8] = b"task_gate";
+    pub const TASK_POLICY: &[u8] = b"task_policy";
+    pub const ATTESTATION_ISSUER: &[u8] = b"attestation_issuer";
+    pub const BOUNTY: &[u8] = b"bounty";
+    pub const TASK_MILESTONES: &[u8] = b"task_milestones";
+    pub const ESCROW_OBSERVER: &[u8] = b"escrow_observer";
+    pub const LST_COLLATERAL: &[u8] = b"lst_collateral";
+    pub const LST_POSITION: &[u8] = b"lst_position";
+    pub const LST_VAULT: &[u8] = b"lst_vault";
+    pub const LIQUIDITY_BUFFER: &[u8] = b"liquidity_buffer";
+    pub const LIQUIDITY_BUFFER_VAULT: &[u8] = b"liquidity_buffer_vault";
+    pub const DATA_CONSENT: &[u8] = b"data_consent";
+    pub const INVOICE: &[u8] = b"invoice";
+    pub const INVOICE_MINT: &[u8] = b"invoice_mint";
+    pub const BLOCKED_AGENTS: &[u8] = b"blocked_agents";
+    pub const AGENT_RATING: &[u8] = b"agent_rating";
+    pub const MATCHER: &[u8] = b"matcher";
+    pub const GROUP_SESSION: &[u8] = b"group_session";
+    pub const ESCROW_SNAPSHOT: &[u8] = b"escrow_snapshot";
+
+    // governance
+    pub const GOVERNANCE: &[u8] = b"governance";
+    pub const PROPOSAL: &[u8] = b"proposal";
+    pub const VOTE: &[u8] = b"vote";
+    pub const VOTE_VAULT: &[u8] = b"vote_vault";
+}
+
+/// Canonical payment router PDA derivations, keyed by the deployed program id. The
+/// programs' `seeds = [...]` constraints, the `derive_addresses` view and off-chain
+/// callers should all agree with these; a mismatch surfaces as ConstraintSeeds.
+pub mod pda {
+    use super::seeds;
+    use anchor_lang::prelude::Pubkey;
+
+    pub fn market_config(program_id: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[seeds::MARKET_CONFIG], program_id)
+    }
+
+    pub fn fee_vault_state(program_id: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[seeds::FEE_VAULT_STATE], program_id)
+    }
+
+    /// Primary-mint fee vault, owned by the fee vault state.
+    pub fn fee_vault(program_id: &Pubkey) -> (Pubkey, u8) {
+        let state = fee_vault_state(program_id).0;
+        Pubkey::find_program_address(&[seeds::FEE_VAULT, state.as_ref()], program_id)
+    }
+
+    pub fn escrow(program_id: &Pubkey, agent: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[seeds::ESCROW, agent.as_ref()], program_id)
+    }
+
+    pub fn task(program_id: &Pubkey, escrow: &Pubkey, task_id: u64) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[seeds::TASK, escrow.as_ref(), task_id.to_le_bytes().as_ref()], program_id)
+    }
+
+    pub fn task_gate(program_id: &Pubkey, task: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[seeds::TASK_GATE, task.as_ref()], program_id)
+    }
+
+    pub fn task_policy(program_id: &Pubkey, task: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[seeds::TASK_POLICY, task.as_ref()], program_id)
+    }
+
+    pub fn bounty(program_id: &Pubkey, task: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[seeds::BOUNTY, task.as_ref()], program_id)
+    }
+
+    pub fn task_milestones(program_id: &Pubkey, task: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[seeds::TASK_MILESTONES, task.as_ref()], program_id)
+    }
+
+    pub fn builder_balance(program_id: &Pubkey, builder_code: &[u8; 32]) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[seeds::BUILDER, builder_code.as_ref()], program_id)
+    }
+
+    pub fn builder_stats(program_id: &Pubkey, builder_code: &[u8; 32]) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[seeds::BUILDER_STATS, builder_code.as_ref()], program_id)
+    }
+
+    pub fn agent_stats(program_id: &Pubkey, agent: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[seeds::AGENT_STATS, agent.as_ref()], program_id)
+    }
+
+    pub fn recovery(program_id: &Pubkey, escrow: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[seeds::RECOVERY, escrow.as_ref()], program_id)
+    }
+
+    pub fn credit_line(program_id: &Pubkey, escrow: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[seeds::CREDIT_LINE, escrow.as_ref()], program_id)
+    }
+}
+
+/// Errors common to the marketplace programs. Numbered from 7000 so they never
+/// collide with a program's own `ErrorCode` (which starts at 6000).
+#[error_code(offset = 7000)]
+pub enum CommonError {
+    #[msg("Unauthorized")]
+    Unauthorized,
+    #[msg("Amount must be greater than zero")]
+    InvalidAmount,
+    #[msg("Token account mint does not match")]
+    InvalidMint,
+    #[msg("Token account has the wrong owner")]
+    InvalidTokenAccount,
+    #[msg("Basis points out of range")]
+    InvalidBps,
+    #[msg("Math overflow")]
+    MathOverflow,
+}
@@ -0,0 +1,264 @@
+//! Escrow accounting: arbitrary sequences of deposits, sessions, settlements, withdrawals,
+//! claims and holdback disputes run against the real programs. Instructions are free to
+//! fail; after every step the token vaults must still match the ledger:
+//!
+//! - escrow vault amount == escrow.balance
+//! - fee vault amount == protocol balance + builder balance + outstanding holdbacks
+//! - no tokens are created or destroyed
+//!
+//! Run with `ASAN_OPTIONS=detect_leaks=0`; the harness leaks every instruction's account slice.
+
+#![no_main]
+
+use arbitrary::Arbitrary;
+use libfuzzer_sys::fuzz_target;
+use lifecycle_tests::*;
+use payment_router::instruction as ix;
+use payment_router::{BuilderBalance, EscrowAccount, FeeVaultState, Holdback, SettlementExtras};
+use solana_sdk::instruction::Instruction;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signer};
+
+// Keeps a single input's bank work bounded
+const MAX_OPS: usize = 32;
+const AGENT_FUNDS: u64 = 10_000_000_000;
+const BUILDER_CODE: [u8; 32] = [1; 32];
+
+#[derive(Arbitrary, Debug)]
+enum Op {
+    Deposit { amount: u32 },
+    Withdraw { amount: u32 },
+    OpenSession { human: bool, price_per_second: u16 },
+    CloseSession { human: bool },
+    Settle { human: bool, verified_seconds: u16, price_per_second: u16, builder: bool },
+    Wait { seconds: u16 },
+    ClaimBuilder,
+    ClaimProtocolFees { amount: u32 },
+    ReleaseHoldback { human: bool },
+    FlagHoldback { human: bool, flagged: bool },
+    SlashHoldback { human: bool },
+}
+
+#[derive(Arbitrary, Debug)]
+struct Input {
+    fee_bps: u16,
+    holdback_bps: u16,
+    builder_share_bps: u16,
+    ops: Vec<Op>,
+}
+
+struct World {
+    h: Harness,
+    admin: Keypair,
+    agent: Keypair,
+    agent_tokens: Pubkey,
+    builder_wallet: Keypair,
+    builder_tokens: Pubkey,
+    treasury: Pubkey,
+    humans: [Keypair; 2],
+    // Open session id per human
+    sessions: [Option<u64>; 2],
+    next_session_id: u64,
+    next_nonce: u64,
+}
+
+impl World {
+    async fn new(input: &Input) -> Self {
+        let mut h = Harness::start(input.fee_bps % 10_001).await;
+        let admin = h.admin.insecure_clone();
+        let mint = h.payment_mint;
+        let program = payment_router::ID;
+
+        let holdback = ix::SetHoldbackParams { holdback_bps: input.holdback_bps % 10_001, holdback_period: 60 };
+        let builder_share = ix::SetBuilderShareBps { builder_share_bps: input.builder_share_bps % 10_001 };
+        let builder_wallet = h.funded_wallet().await;
+        let ixs = [
+            anchor_ix(program, h.update_market_config_accounts(), holdback),
+            anchor_ix(program, h.update_fee_params_accounts(), builder_share),
+            anchor_ix(
+                program,
+                h.register_builder_accounts(&BUILDER_CODE, &builder_wallet.pubkey()),
+                ix::RegisterBuilder { builder_code: BUILDER_CODE },
+            ),
+        ];
+        h.process(&ixs, &[&admin]).await.unwrap();
+
+        let agent = h.funded_wallet().await;
+        let agent_tokens = h.token_account(&mint, &agent.pubkey()).await;
+        h.mint_to(&mint, &agent_tokens, AGENT_FUNDS).await;
+        h.create_escrow_vault(&agent.pubkey()).await;
+        let builder_tokens = h.token_account(&mint, &builder_wallet.pubkey()).await;
+        let treasury = h.token_account(&mint, &admin.pubkey()).await;
+
+        Self {
+            h,
+            admin,
+            agent,
+            agent_tokens,
+            builder_wallet,
+            builder_tokens,
+            treasury,
+            humans: [Keypair::new(), Keypair::new()],
+            sessions: [None, None],
+            next_session_id: 0,
+            next_nonce: 1,
+        }
+    }
+
+    /// Runs one operation. Rejected instructions are expected and ignored.
+    async fn apply(&mut self, op: Op) {
+        let program = payment_router::ID;
+        let agent = self.agent.pubkey();
+        let h = &self.h;
+        let (ix, signer): (Instruction, &Keypair) = match op {
+            Op::Deposit { amount } => (
+                anchor_ix(program, h.deposit_escrow_accounts(&agent, &self.agent_tokens), ix::DepositEscrow {
+                    amount: amount as u64,
+                }),
+                &self.agent,
+            ),
+            Op::Withdraw { amount } => (
+                anchor_ix(program, h.withdraw_escrow_accounts(&agent, &self.agent_tokens), ix::WithdrawEscrow {
+                    amount: amount as u64,
+                }),
+                &self.agent,
+            ),
+            Op::OpenSession { human, price_per_second } => {
+                let i = human as usize;
+                if self.sessions[i].is_some() {
+                    return;
+                }
+                let session_id = self.next_session_id;
+                self.next_session_id += 1;
+                let ix = anchor_ix(
+                    program,
+                    h.open_session_accounts(&agent, &self.humans[i].pubkey(), session_id),
+                    ix::OpenSession { session_id, price_per_second: price_per_second as u64, category: UNCATEGORIZED },
+                );
+                if self.h.process(&[ix], &[&self.admin]).await.is_ok() {
+                    self.sessions[i] = Some(session_id);
+                }
+                return;
+            }
+            Op::CloseSession { human } => {
+                let i = human as usize;
+                let Some(session_id) = self.sessions[i] else { return };
+                let ix = anchor_ix(program, h.close_session_accounts(&agent, session_id), ix::CloseSession {});
+                if self.h.process(&[ix], &[&self.admin]).await.is_ok() {
+                    self.sessions[i] = None;
+                }
+                return;
+            }
+            Op::Settle { human, verified_seconds, price_per_second, builder } => {
+                let i = human as usize;
+                let Some(session_id) = self.sessions[i] else { return };
+                let nonce = self.next_nonce;
+                self.next_nonce += 1;
+                let builder_code = builder.then_some(&BUILDER_CODE);
+                (
+                    anchor_ix(
+                        program,
+                        h.close_settlement_accounts(&agent, &self.humans[i].pubkey(), session_id, builder_code),
+                        ix::CloseSettlement {
+                            verified_seconds: verified_seconds as u64,
+                            agreed_price_per_second: price_per_second as u64,
+                            nonce,
+                            builder_code_opt: builder_code.copied(),
+                            quality_score: None,
+                            extras: SettlementExtras::default(),
+                        },
+                    ),
+                    &self.admin,
+                )
+            }
+            Op::Wait { seconds } => {
+                self.h.advance_clock(seconds as i64).await;
+                return;
+            }
+            Op::ClaimBuilder => (
+                anchor_ix(
+                    program,
+                    h.claim_builder_balance_accounts(&BUILDER_CODE, &self.builder_wallet.pubkey(), &self.builder_tokens),
+                    ix::ClaimBuilderBalance { swap: None },
+                ),
+                &self.builder_wallet,
+            ),
+            Op::ClaimProtocolFees { amount } => (
+                anchor_ix(program, h.claim_protocol_fees_accounts(&self.treasury), ix::ClaimProtocolFees {
+                    amount: amount as u64,
+                }),
+                &self.admin,
+            ),
+            Op::ReleaseHoldback { human } => {
+                let human = &self.humans[human as usize];
+                (
+                    anchor_ix(program, h.release_holdback_accounts(&human.pubkey(), &human.pubkey()), ix::ReleaseHoldback {}),
+                    human,
+                )
+            }
+            Op::FlagHoldback { human, flagged } => (
+                anchor_ix(
+                    program,
+                    h.flag_holdback_accounts(&self.humans[human as usize].pubkey()),
+                    ix::FlagHoldback { flagged },
+                ),
+                &self.admin,
+            ),
+            Op::SlashHoldback { human } => (
+                anchor_ix(program, h.flag_holdback_accounts(&self.humans[human as usize].pubkey()), ix::SlashHoldback {}),
+                &self.admin,
+            ),
+        };
+        let _ = self.h.process(&[ix], &[signer]).await;
+    }
+
+    async fn check_invariants(&mut self) {
+        let agent = self.agent.pubkey();
+        let escrow = escrow_pda(&agent);
+        let vault = self.h.escrow_vault(&agent);
+        let vault_amount = self.h.token_balance(&vault).await;
+        if self.h.account_exists(&escrow).await {
+            let escrow: EscrowAccount = self.h.account(&escrow).await;
+            assert_eq!(vault_amount, escrow.balance, "escrow vault drifted from escrow.balance");
+            assert!(escrow.sponsored_balance <= escrow.balance);
+        } else {
+            assert_eq!(vault_amount, 0);
+        }
+
+        let fee_state: FeeVaultState = self.h.account(&fee_vault_state_pda().0).await;
+        let builder: BuilderBalance = self.h.account(&builder_pda(&BUILDER_CODE)).await;
+        let mut owed = fee_state.protocol_balance as u128
+            + fee_state.pending_settlement_fees as u128
+            + builder.balance as u128;
+        for human in &self.humans {
+            let holdback = holdback_pda(&human.pubkey());
+            if self.h.account_exists(&holdback).await {
+                let holdback: Holdback = self.h.account(&holdback).await;
+                owed += holdback.amount as u128;
+            }
+        }
+        let fee_vault_amount = self.h.token_balance(&fee_vault_pda()).await;
+        assert_eq!(fee_vault_amount as u128, owed, "fee vault drifted from protocol + builder + holdbacks");
+
+        let mut supply = vault_amount as u128 + fee_vault_amount as u128;
+        for account in [self.agent_tokens, self.builder_tokens, self.treasury] {
+            supply += self.h.token_balance(&account).await as u128;
+        }
+        for i in 0..self.humans.len() {
+            let ata = self.h.payout_ata(&self.humans[i].pubkey());
+            supply += self.h.token_balance(&ata).await as u128;
+        }
+        assert_eq!(supply, AGENT_FUNDS as u128, "tokens created or destroyed");
+    }
+}
+
+fuzz_target!(|input: Input| {
+    let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+    runtime.block_on(async {
+        let mut world = World::new(&input).await;
+        for op in input.ops.into_iter().take(MAX_OPS) {
+            world.apply(op).await;
+            world.check_invariants().await;
+        }
+    });
+});
@@ -0,0 +1,52 @@
+//! Settlement math: every split of a gross amount must conserve it exactly, never pay
+//! a builder more than the fee, and agree with a u128 reference computation.
+
+#![no_main]
+
+use arbitrary::Arbitrary;
+use libfuzzer_sys::fuzz_target;
+use payment_router::{split_settlement, SettlementSplit};
+
+#[derive(Arbitrary, Debug)]
+struct Input {
+    verified_seconds: u64,
+    price_per_second: u64,
+    fee_bps: u16,
+    holdback_bps: u16,
+    builder_share_bps: Option<u16>,
+}
+
+fn reference(total: u64, bps: u16) -> u64 {
+    (total as u128 * bps as u128 / 10_000) as u64
+}
+
+fuzz_target!(|input: Input| {
+    // close_settlement rejects overflowing products before splitting
+    let Some(total) = input.verified_seconds.checked_mul(input.price_per_second) else {
+        return;
+    };
+    let in_range = |bps: u16| bps <= 10_000;
+    let valid = in_range(input.fee_bps)
+        && in_range(input.holdback_bps)
+        && input.builder_share_bps.is_none_or(in_range);
+
+    let result = split_settlement(
+        total,
+        input.fee_bps as u64,
+        input.holdback_bps as u64,
+        input.builder_share_bps.map(u64::from),
+    );
+    let Ok(split) = result else {
+        assert!(!valid, "valid rates rejected: {input:?}");
+        return;
+    };
+    assert!(valid, "out-of-range rate accepted: {input:?}");
+
+    let SettlementSplit { fee_amount, builder_share, holdback_amount, user_payout } = split;
+    assert_eq!(fee_amount as u128 + holdback_amount as u128 + user_payout as u128, total as u128);
+    assert!(builder_share <= fee_amount);
+
+    assert_eq!(fee_amount, reference(total, input.fee_bps));
+    assert_eq!(holdback_amount, reference(total - fee_amount, input.holdback_bps));
+    assert_eq!(builder_share, input.builder_share_bps.map_or(0, |bps| reference(fee_amount, bps)));
+});
@@ -0,0 +1,322 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer};
+use market_common::{seeds, CommonError, MAX_BPS};
+use payment_router::cpi::accounts::UpdateFeeParams;
+use payment_router::program::PaymentRouter;
+use payment_router::MarketConfig;
+
+declare_id!("JZjhFp4u16pfLsimKMYPo8NThbCKWT6aDuZ1ZANHyS8");
+
+// Shortest voting window a proposal can be given (1 day)
+const MIN_VOTING_PERIOD: i64 = 24 * 60 * 60;
+
+#[program]
+pub mod governance {
+    use super::*;
+
+    /// Creates the governance PDA. Point `market_config.governance` at it with
+    /// `payment_router::set_governance` to hand over fee parameters.
+    pub fn initialize_governance(
+        ctx: Context<InitializeGovernance>,
+        quorum: u64,
+        voting_period: i64,
+    ) -> Result<()> {
+        require!(voting_period >= MIN_VOTING_PERIOD, ErrorCode::InvalidVotingPeriod);
+        let governance = &mut ctx.accounts.governance;
+        governance.vote_mint = ctx.accounts.vote_mint.key();
+        governance.quorum = quorum;
+        governance.voting_period = voting_period;
+        governance.proposal_count = 0;
+        governance.bump = ctx.bumps.governance;
+        Ok(())
+    }
+
+    pub fn create_proposal(ctx: Context<CreateProposal>, change: ParamChange) -> Result<()> {
+        change.validate()?;
+
+        let governance = &mut ctx.accounts.governance;
+        let proposal = &mut ctx.accounts.proposal;
+        proposal.id = governance.proposal_count;
+        proposal.proposer = ctx.accounts.proposer.key();
+        proposal.change = change;
+        proposal.yes_votes = 0;
+        proposal.no_votes = 0;
+        proposal.voting_ends_at = Clock::get()?.unix_timestamp + governance.voting_period;
+        proposal.executed = false;
+        proposal.bump = ctx.bumps.proposal;
+
+        governance.proposal_count += 1;
+        Ok(())
+    }
+
+    /// Locks `amount` vote tokens in the vote vault until the proposal's voting window ends,
+    /// so the same tokens can't vote twice.
+    pub fn cast_vote(ctx: Context<CastVote>, amount: u64, approve: bool) -> Result<()> {
+        require!(amount > 0, CommonError::InvalidAmount);
+        let proposal = &mut ctx.accounts.proposal;
+        require!(Clock::get()?.unix_timestamp < proposal.voting_ends_at, ErrorCode::VotingClosed);
+
+        let cpi_ctx = CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.voter_token_account.to_account_info(),
+                to: ctx.accounts.vote_vault.to_account_info(),
+                authority: ctx.accounts.voter.to_account_info(),
+            },
+        );
+        token::transfer(cpi_ctx, amount)?;
+
+        if approve {
+            proposal.yes_votes = proposal.yes_votes.checked_add(amount).ok_or(CommonError::MathOverflow)?;
+        } else {
+            proposal.no_votes = proposal.no_votes.checked_add(amount).ok_or(CommonError::MathOverflow)?;
+        }
+
+        let record = &mut ctx.accounts.vote_record;
+        record.proposal = proposal.key();
+        record.voter = ctx.accounts.voter.key();
+        record.amount = amount;
+        record.approve = approve;
+        record.bump = ctx.bumps.vote_record;
+        Ok(())
+    }
+
+    /// Returns locked vote tokens once voting has ended and closes the vote record.
+    pub fn withdraw_vote(ctx: Context<WithdrawVote>) -> Result<()> {
+        require!(
+            Clock::get()?.unix_timestamp >= ctx.accounts.proposal.voting_ends_at,
+            ErrorCode::VotingOpen
+        );
+
+        let bump = ctx.accounts.governance.bump;
+        let seeds = &[seeds::GOVERNANCE, &[bump]];
+        let signer = &[&seeds[..]];
+
+        let cpi_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.vote_vault.to_account_info(),
+                to: ctx.accounts.voter_token_account.to_account_info(),
+                authority: ctx.accounts.governance.to_account_info(),
+            },
+            signer,
+        );
+        token::transfer(cpi_ctx, ctx.accounts.vote_record.amount)?;
+        Ok(())
+    }
+
+    /// Applies an approved proposal by calling the router's fee setter with the
+    /// governance PDA as authority. Callable by anyone after voting ends.
+    pub fn execute_proposal(ctx: Context<ExecuteProposal>) -> Result<()> {
+        let proposal = &mut ctx.accounts.proposal;
+        require!(!proposal.executed, ErrorCode::AlreadyExecuted);
+        require!(Clock::get()?.unix_timestamp >= proposal.voting_ends_at, ErrorCode::VotingOpen);
+        require!(
+            proposal.yes_votes > proposal.no_votes
+                && proposal.yes_votes + proposal.no_votes >= ctx.accounts.governance.quorum,
+            ErrorCode::ProposalNotApproved
+        );
+        proposal.executed = true;
+
+        let bump = ctx.accounts.governance.bump;
+        let seeds = &[seeds::GOVERNANCE, &[bump]];
+        let signer = &[&seeds[..]];
+
+        let cpi_ctx = CpiContext::new_with_signer(
+            ctx.accounts.payment_router.to_account_info(),
+            UpdateFeeParams {
+                authority: ctx.accounts.governance.to_account_info(),
+                market_config: ctx.accounts.market_config.to_account_info(),
+            },
+            signer,
+        );
+        match proposal.change {
+            ParamChange::FeeBasisPoints(bps) => payment_router::cpi::set_fee_basis_points(cpi_ctx, bps),
+            ParamChange::BuilderShareBps(bps) => payment_router::cpi::set_builder_share_bps(cpi_ctx, bps),
+            ParamChange::MatcherShareBps(bps) => payment_router::cpi::set_matcher_share_bps(cpi_ctx, bps),
+        }
+    }
+}
+
+#[derive(Accounts)]
+pub struct InitializeGovernance<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + 32 + 8 + 8 + 8 + 1,
+        seeds = [seeds::GOVERNANCE],
+        bump
+    )]
+    pub governance: Account<'info, GovernanceConfig>,
+    pub vote_mint: Account<'info, Mint>,
+    #[account(
+        init,
+        payer = payer,
+        token::mint = vote_mint,
+        token::authority = governance,
+        seeds = [seeds::VOTE_VAULT],
+        bump
+    )]
+    pub vote_vault: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CreateProposal<'info> {
+    #[account(mut)]
+    pub proposer: Signer<'info>,
+    #[account(mut, seeds = [seeds::GOVERNANCE], bump = governance.bump)]
+    pub governance: Account<'info, GovernanceConfig>,
+    #[account(
+        init,
+        payer = proposer,
+        space = 8 + 8 + 32 + 3 + 8 + 8 + 8 + 1 + 1,
+        seeds = [seeds::PROPOSAL, governance.proposal_count.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub proposal: Account<'info, Proposal>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CastVote<'info> {
+    #[account(mut)]
+    pub voter: Signer<'info>,
+    #[account(seeds = [seeds::GOVERNANCE], bump = governance.bump)]
+    pub governance: Account<'info, GovernanceConfig>,
+    #[account(
+        mut,
+        seeds = [seeds::PROPOSAL, proposal.id.to_le_bytes().as_ref()],
+        bump = proposal.bump
+    )]
+    pub proposal: Account<'info, Proposal>,
+    // One record per voter per proposal
+    #[account(
+        init,
+        payer = voter,
+        space = 8 + 32 + 32 + 8 + 1 + 1,
+        seeds = [seeds::VOTE, proposal.key().as_ref(), voter.key().as_ref()],
+        bump
+    )]
+    pub vote_record: Account<'info, VoteRecord>,
+    #[account(
+        mut,
+        constraint = voter_token_account.mint == governance.vote_mint @ CommonError::InvalidMint
+    )]
+    pub voter_token_account: Account<'info, TokenAccount>,
+    #[account(mut, seeds = [seeds::VOTE_VAULT], bump)]
+    pub vote_vault: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawVote<'info> {
+    #[account(mut)]
+    pub voter: Signer<'info>,
+    #[account(seeds = [seeds::GOVERNANCE], bump = governance.bump)]
+    pub governance: Account<'info, GovernanceConfig>,
+    #[account(
+        seeds = [seeds::PROPOSAL, proposal.id.to_le_bytes().as_ref()],
+        bump = proposal.bump
+    )]
+    pub proposal: Account<'info, Proposal>,
+    #[account(
+        mut,
+        close = voter,
+        seeds = [seeds::VOTE, proposal.key().as_ref(), voter.key().as_ref()],
+        bump = vote_record.bump
+    )]
+    pub vote_record: Account<'info, VoteRecord>,
+    #[account(
+        mut,
+        constraint = voter_token_account.owner == voter.key() @ CommonError::InvalidTokenAccount,
+        constraint = voter_token_account.mint == governance.vote_mint @ CommonError::InvalidMint
+    )]
+    pub voter_token_account: Account<'info, TokenAccount>,
+    #[account(mut, seeds = [seeds::VOTE_VAULT], bump)]
+    pub vote_vault: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct ExecuteProposal<'info> {
+    #[account(seeds = [seeds::GOVERNANCE], bump = governance.bump)]
+    pub governance: Account<'info, GovernanceConfig>,
+    #[account(
+        mut,
+        seeds = [seeds::PROPOSAL, proposal.id.to_le_bytes().as_ref()],
+        bump = proposal.bump
+    )]
+    pub proposal: Account<'info, Proposal>,
+    #[account(mut, seeds = [seeds::MARKET_CONFIG], bump, seeds::program = payment_router.key())]
+    pub market_config: Account<'info, MarketConfig>,
+    pub payment_router: Program<'info, PaymentRouter>,
+}
+
+#[account]
+pub struct GovernanceConfig {
+    pub vote_mint: Pubkey,
+    pub quorum: u64,         // Minimum total votes (yes + no) for a proposal to pass
+    pub voting_period: i64,  // Seconds a proposal stays open for voting
+    pub proposal_count: u64, // Next proposal id
+    pub bump: u8,
+}
+
+#[account]
+pub struct Proposal {
+    pub id: u64,
+    pub proposer: Pubkey,
+    pub change: ParamChange,
+    pub yes_votes: u64,
+    pub no_votes: u64,
+    pub voting_ends_at: i64,
+    pub executed: bool,
+    pub bump: u8,
+}
+
+#[account]
+pub struct VoteRecord {
+    pub proposal: Pubkey,
+    pub voter: Pubkey,
+    pub amount: u64, // Vote tokens locked in the vote vault
+    pub approve: bool,
+    pub bump: u8,
+}
+
+/// Router parameter a proposal sets when executed.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub enum ParamChange {
+    FeeBasisPoints(u16),
+    BuilderShareBps(u16),
+    MatcherShareBps(u16),
+}
+
+impl ParamChange {
+    pub fn validate(&self) -> Result<()> {
+        let bps = match self {
+            ParamChange::FeeBasisPoints(bps)
+            | ParamChange::BuilderShareBps(bps)
+            | ParamChange::MatcherShareBps(bps) => *bps,
+        };
+        require!(bps <= MAX_BPS, CommonError::InvalidBps);
+        Ok(())
+    }
+}
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("Voting period is too short")]
+    InvalidVotingPeriod,
+    #[msg("Voting has closed for this proposal")]
+    VotingClosed,
+    #[msg("Voting is still open for this proposal")]
+    VotingOpen,
+    #[msg("Proposal did not pass")]
+    ProposalNotApproved,
+    #[msg("Proposal already executed")]
+    AlreadyExecuted,
+}
@@ -1,12 +1,127 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{self, Token, TokenAccount, Transfer};
+use anchor_lang::system_program;
+use anchor_lang::Discriminator;
+use anchor_lang::solana_program::ed25519_program;
+use anchor_lang::solana_program::keccak;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::program::invoke;
+use anchor_lang::solana_program::program_option::COption;
+use anchor_lang::solana_program::sysvar::instructions::{load_current_index_checked, load_instruction_at_checked};
+use anchor_spl::associated_token::{get_associated_token_address, AssociatedToken};
+use anchor_spl::token::spl_token::instruction::AuthorityType;
+use anchor_spl::token::{self, CloseAccount, FreezeAccount, MintTo, SetAuthority, Token, TokenAccount, Transfer};
+use market_common::{network, pda, seeds, CommonError, BPS_DENOMINATOR, MAX_BPS};
 use solana_program::pubkey;
+use std::cell::RefMut;
 
+// Program id per network profile (see `market_common::network`)
+#[cfg(any(feature = "devnet", feature = "mainnet"))]
 declare_id!("EZPqKzvizknKZmkYC69NgiBeCs1uDVfET1MQpC7tQvin");
+#[cfg(not(any(feature = "devnet", feature = "mainnet")))]
+declare_id!("Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS");
+
+#[cfg(all(feature = "mainnet", feature = "devnet-faucet"))]
+compile_error!("the devnet faucet can't be built into a mainnet artifact");
+
+// Circle CCTP programs on Solana and Solana's CCTP domain id
+const CCTP_MESSAGE_TRANSMITTER: Pubkey = pubkey!("CCTPmbSD7gX1bxKPAmg77w8oFzNFpaQiQUWD43TKaecd");
+const CCTP_TOKEN_MESSENGER_MINTER: Pubkey = pubkey!("CCTPiPYPc6AsJuwueEnWgSgucamXDZwBd53dQ11YiKX3");
+const CCTP_SOLANA_DOMAIN: u32 = 5;
+
+// Metaplex Token Metadata program; owns the metadata accounts NFT gates are checked against
+const TOKEN_METADATA_PROGRAM: Pubkey = pubkey!("metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s");
 
 // Authorized admin for initialization (prevents front-running attacks)
 const AUTHORIZED_ADMIN: Pubkey = pubkey!("4BTmGg6w7wQiqMqJmrHdacKE8gvhqepDAt5WE8o3DtdE");
 
+// Slack allowed between billed seconds and wall-clock session time (clock drift, batching)
+const SESSION_TOLERANCE_SECONDS: u64 = 30;
+// Hard cap on billable seconds for a single session (24 hours)
+const MAX_SESSION_SECONDS: u64 = 86_400;
+// Max extra wallets a human's payout can be split across
+const MAX_SPLIT_LEGS: usize = 4;
+// Cap on the fee an org takes from its members' payouts (50%)
+const MAX_ORG_FEE_BPS: u16 = 5000;
+// Cap on daily interest paid on released holdbacks (1% per day)
+const MAX_HOLDBACK_INTEREST_BPS_PER_DAY: u16 = 100;
+
+// Seconds of streaming reserved from the escrow while a session is open
+const SESSION_RESERVE_SECONDS: u64 = 300;
+// Time past a session's billable window before it can be expired (24 hours)
+const SESSION_EXPIRY_GRACE: i64 = 24 * 60 * 60;
+
+// Max escrows settled in one close_settlement_batch call
+const MAX_BATCH_SETTLEMENTS: usize = 8;
+// Longest referral string accepted in place of a raw builder code
+const MAX_BUILDER_CODE_STR_LEN: usize = 64;
+
+// Delay before a newly added attestation key can sign settlements (48 hours on mainnet)
+const ATTESTATION_KEY_DELAY: i64 = network::ATTESTATION_KEY_DELAY;
+
+// Decimals of the ATTN protocol token
+const ATTN_DECIMALS: u8 = 6;
+
+// Grace period after a task deadline before anyone can crank its refund (24 hours)
+const TASK_REFUND_GRACE: i64 = 24 * 60 * 60;
+// Share of a cranked refund paid to the cranker (0.1%)
+const CRANK_BOUNTY_BPS: u64 = 10;
+
+// Timelock on admin-initiated escrow recovery (30 days on mainnet)
+const RECOVERY_TIMELOCK: i64 = network::RECOVERY_TIMELOCK;
+
+// Max builders created in one register_builders_batch call
+const MAX_BATCH_REGISTRATIONS: usize = 16;
+// Max tasks topped up in one fund_tasks_batch call
+const MAX_BATCH_TASKS: usize = 16;
+// Max milestones in a task's payout plan (released/cancelled are tracked as u8 bitmasks)
+const MAX_TASK_MILESTONES: usize = 8;
+// Max queued settlements executed in one process_queue call
+const MAX_QUEUE_CRANK: u8 = 6;
+// Accounts per queued settlement in process_queue: item, escrow, vault, user wallet, holdback
+const QUEUE_ITEM_ACCOUNTS: usize = 5;
+// Accounts per close_settlement_batch entry: escrow, vault, session
+const BATCH_ENTRY_ACCOUNTS: usize = 3;
+// Accounts per close_group_settlement participant: payout account, prefs, holdback, payout split, org member
+const GROUP_MEMBER_ACCOUNTS: usize = 5;
+
+// Cap on the fee stream sold as revenue-share positions (20%)
+const MAX_REVENUE_SHARE_BPS: u16 = 2000;
+
+// Base signature fee the router pays per settlement transaction
+const SETTLEMENT_TX_FEE_LAMPORTS: u64 = 5_000;
+
+// Weight of each new settlement in a category's price EMA (10%)
+const PRICE_EMA_ALPHA_BPS: u128 = 1000;
+// Fixed-point scale of PriceIndex::ema_price_e6
+const PRICE_EMA_SCALE: u128 = 1_000_000;
+// Oldest spot price a session can open at; oracles post about once a minute
+const SPOT_PRICE_MAX_AGE: i64 = 120;
+// Recent settlements per category that session length percentiles are computed over
+const SESSION_STATS_WINDOW: usize = 64;
+// Samples needed before settlements are scored against the window
+const SESSION_STATS_MIN_SAMPLES: u64 = 16;
+// Decimals of the human-readable quoting unit (1 micro-USD = 1e-6 USD)
+const MICRO_USD_DECIMALS: u8 = 6;
+// Bucket length of the settlement circuit breaker's rolling volume
+const VELOCITY_WINDOW: i64 = 60 * 60;
+// Oldest LST price collateral can be lent against or rebalanced at
+const LST_PRICE_MAX_AGE: i64 = 300;
+// Humans sharing one group session; each costs two settlement accounts, so large groups
+// need an address lookup table
+const MAX_GROUP_PARTICIPANTS: usize = 16;
+// Agents a human can block from opening sessions with them
+const MAX_BLOCKED_AGENTS: usize = 32;
+// KYC tiers an escrow can be attested at; 0 = unverified
+const KYC_TIERS: usize = 4;
+// Seconds an escrow snapshot stays valid for underwriters
+const ESCROW_SNAPSHOT_TTL: i64 = 60 * 60;
+// Engagement multiplier that leaves the agreed price unchanged (1x)
+const QUALITY_MULTIPLIER_NEUTRAL_BPS: u16 = BPS_DENOMINATOR as u16;
+// Ceiling on the engagement multiplier config may allow (2x)
+const MAX_QUALITY_MULTIPLIER_BPS: u16 = 20_000;
+/// Withdrawal amount meaning "everything currently withdrawable"
+pub const WITHDRAW_ALL: u64 = u64::MAX;
+
 #[program]
 pub mod payment_router {
     use super::*;
@@ -15,9 +130,29 @@ pub mod payment_router {
         ctx: Context<InitializeMarketConfig>,
         fee_basis_points: u16,
     ) -> Result<()> {
+        require!(fee_basis_points <= MAX_BPS, ErrorCode::InvalidFeeParams);
+        let mint = &ctx.accounts.payment_mint;
+        ctx.accounts.config.reset(ctx.accounts.admin.key(), mint, fee_basis_points, ctx.bumps.config);
+        Ok(())
+    }
+
+    /// Rewrites the market config from scratch, e.g. after a wrong fee or mint at init.
+    /// Only possible before the fee vault state exists: every escrow-creating path reads
+    /// it, and imports are closed while no migration source is set.
+    pub fn reinitialize_market_config(
+        ctx: Context<ReinitializeMarketConfig>,
+        fee_basis_points: u16,
+    ) -> Result<()> {
+        require!(fee_basis_points <= MAX_BPS, ErrorCode::InvalidFeeParams);
+        let mint = &ctx.accounts.payment_mint;
         let config = &mut ctx.accounts.config;
-        config.authority = ctx.accounts.admin.key();
-        config.fee_basis_points = fee_basis_points;
+        config.reset(ctx.accounts.admin.key(), mint, fee_basis_points, ctx.bumps.config);
+
+        emit!(MarketConfigReinitialized {
+            authority: config.authority,
+            payment_mint: config.payment_mint,
+            fee_basis_points,
+        });
         Ok(())
     }
 
@@ -27,6 +162,9 @@ pub mod payment_router {
         state.protocol_balance = 0;
         state.total_collected = 0;
         state.bump = ctx.bumps.fee_vault_state;
+        state.pending_settlement_fees = 0;
+        state.pending_recent = 0;
+        state.pending_window = 0;
         Ok(())
     }
 
@@ -43,6 +181,11 @@ pub mod payment_router {
         builder.balance = 0;
         builder.total_earned = 0;
         builder.bump = ctx.bumps.builder_balance;
+
+        emit!(BuilderRegistered {
+            builder_code,
+            wallet: builder.wallet,
+        });
         Ok(())
     }
 
@@ -66,23 +209,64 @@ pub mod payment_router {
 
         token::transfer(cpi_ctx, amount)?;
 
+        // An escrow holds a single currency, fixed by its first deposit
+        let escrow_mint = ctx.accounts.mint_fee_vault.as_ref().map(|m| m.mint).unwrap_or_default();
         let escrow = &mut ctx.accounts.escrow_account;
-        escrow.agent = ctx.accounts.agent.key();
+        if escrow.agent == Pubkey::default() {
+            escrow.mint = escrow_mint;
+        }
+        require_keys_eq!(escrow.mint, escrow_mint, ErrorCode::InvalidMint);
+
+        escrow.bind_agent(ctx.accounts.agent.key(), ctx.bumps.escrow_account)?;
         escrow.balance += amount;
-        escrow.bump = ctx.bumps.escrow_account;
+        escrow.record_deposit(amount);
+
+        // Outstanding credit line debt is repaid first from new deposits
+        if let (Some(credit_line), Some(collateral)) =
+            (&mut ctx.accounts.credit_line, &ctx.accounts.credit_collateral)
+        {
+            let repaid = credit_line.repay(amount);
+            if repaid > 0 {
+                transfer_from_escrow_vault(
+                    escrow,
+                    &ctx.accounts.vault,
+                    collateral,
+                    &ctx.accounts.token_program,
+                    repaid,
+                )?;
+                escrow.balance -= repaid;
+                emit!(CreditRepaid {
+                    escrow: escrow.key(),
+                    amount: repaid,
+                    debt: credit_line.debt,
+                });
+            }
+        }
+        ctx.accounts.vault.reload()?;
+        ctx.accounts.market_config.check_deposit_cap(escrow, &ctx.accounts.vault)?;
 
+        emit!(EscrowFunded {
+            agent: escrow.agent,
+            amount,
+            new_balance: escrow.balance,
+            cumulative_deposited: escrow.cumulative_deposited,
+        });
         Ok(())
     }
 
+    /// `amount == WITHDRAW_ALL` takes whatever is withdrawable when the instruction runs,
+    /// so a settlement landing first shrinks the withdrawal instead of failing it.
     pub fn withdraw_escrow(ctx: Context<WithdrawEscrow>, amount: u64) -> Result<()> {
         let escrow = &mut ctx.accounts.escrow_account;
-        require!(escrow.balance >= amount, ErrorCode::InsufficientFunds);
+        let amount = if amount == WITHDRAW_ALL { escrow.withdrawable() } else { amount };
+        // Sponsored funds can only be refunded to their sponsors, and open sessions keep their reserve
+        require!(escrow.withdrawable() >= amount, ErrorCode::InsufficientFunds);
 
         // Seeds for signing
         let agent_key = ctx.accounts.agent.key();
         let bump = escrow.bump;
         let seeds = &[
-            b"escrow",
+            seeds::ESCROW,
             agent_key.as_ref(),
             &[bump],
         ];
@@ -107,64 +291,662 @@ pub mod payment_router {
         Ok(())
     }
 
-    pub fn close_settlement(
-        ctx: Context<CloseSettlement>,
+    /// Closes an emptied escrow and its vault, returning their rent to the agent. Tokens
+    /// sent straight to the vault (never credited to the balance) are swept to the agent
+    /// first. Re-creating the escrow restarts its settlement nonces, so the router must not
+    /// be holding queued or unsent settlements for it.
+    pub fn close_escrow(ctx: Context<CloseEscrow>) -> Result<()> {
+        let escrow = &ctx.accounts.escrow_account;
+        require!(escrow.balance == 0, ErrorCode::EscrowNotEmpty);
+        require!(escrow.open_sessions == 0, ErrorCode::SessionsOpen);
+
+        let dust = ctx.accounts.vault.amount;
+        transfer_from_escrow_vault(
+            escrow,
+            &ctx.accounts.vault,
+            &ctx.accounts.agent_token_account,
+            &ctx.accounts.token_program,
+            dust,
+        )?;
+        let seeds = &[seeds::ESCROW, escrow.agent.as_ref(), &[escrow.bump]];
+        let signer = &[&seeds[..]];
+        token::close_account(CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            CloseAccount {
+                account: ctx.accounts.vault.to_account_info(),
+                destination: ctx.accounts.agent.to_account_info(),
+                authority: escrow.to_account_info(),
+            },
+            signer,
+        ))?;
+
+        emit!(EscrowClosed {
+            agent: escrow.agent,
+            escrow: escrow.key(),
+            dust,
+        });
+        Ok(())
+    }
+
+    /// Writes the escrow's standing (balance, lifetime settled volume, age) into a PDA keyed
+    /// by an underwriter-chosen `challenge`. Only the agent can write it and only this
+    /// program can own it, so an underwriter who finds it at the expected address, unexpired,
+    /// can rely on it without the agent sharing any keys. Each challenge is single-use.
+    pub fn snapshot_escrow(ctx: Context<SnapshotEscrow>, challenge: [u8; 32]) -> Result<()> {
+        let clock = Clock::get()?;
+        let escrow = &ctx.accounts.escrow_account;
+        let snapshot = &mut ctx.accounts.snapshot;
+        snapshot.escrow = escrow.key();
+        snapshot.agent = escrow.agent;
+        snapshot.challenge = challenge;
+        snapshot.balance = escrow.balance;
+        snapshot.total_settled = escrow.total_settled;
+        snapshot.total_deposited = escrow.cumulative_deposited;
+        snapshot.created_at = escrow.created_at;
+        snapshot.taken_at = clock.unix_timestamp;
+        snapshot.taken_slot = clock.slot;
+        snapshot.expires_at = clock.unix_timestamp + ESCROW_SNAPSHOT_TTL;
+        snapshot.bump = ctx.bumps.snapshot;
+
+        emit!(EscrowSnapshotTaken {
+            snapshot: snapshot.key(),
+            escrow: snapshot.escrow,
+            challenge,
+            balance: snapshot.balance,
+            total_settled: snapshot.total_settled,
+            account_age: snapshot.account_age(),
+            expires_at: snapshot.expires_at,
+        });
+        Ok(())
+    }
+
+    /// Permissionless once the snapshot has lapsed; rent goes back to the agent.
+    pub fn close_escrow_snapshot(ctx: Context<CloseEscrowSnapshot>) -> Result<()> {
+        require!(
+            Clock::get()?.unix_timestamp >= ctx.accounts.snapshot.expires_at,
+            ErrorCode::SnapshotNotExpired
+        );
+        Ok(())
+    }
+
+    /// Withdrawal while the router is stale: open sessions' reserves are released too, since
+    /// no one is left to settle them. Sponsored funds stay with their sponsors.
+    pub fn fast_withdraw_escrow(ctx: Context<FastWithdrawEscrow>, amount: u64) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        require!(ctx.accounts.market_config.router_stale(now), ErrorCode::RouterLive);
+
+        let escrow = &ctx.accounts.escrow_account;
+        require!(escrow.balance - escrow.sponsored_balance >= amount, ErrorCode::InsufficientFunds);
+        transfer_from_escrow_vault(
+            escrow,
+            &ctx.accounts.vault,
+            &ctx.accounts.agent_token_account,
+            &ctx.accounts.token_program,
+            amount,
+        )?;
+        let escrow = &mut ctx.accounts.escrow_account;
+        escrow.balance -= amount;
+        escrow.reserved_balance = escrow.reserved_balance.min(escrow.balance - escrow.sponsored_balance);
+
+        emit!(FastWithdrawal {
+            escrow: escrow.key(),
+            amount,
+            last_heartbeat: ctx.accounts.market_config.last_heartbeat,
+        });
+        Ok(())
+    }
+
+    /// With `spot_price`, the session opens at the category's posted spot price instead of
+    /// `price_per_second`, and every settlement against it must use exactly that price.
+    /// `category` selects the session length stats its settlements are scored against
+    /// (zero = uncategorized) and must match the spot price feed's.
+    pub fn open_session(
+        ctx: Context<OpenSession>,
+        session_id: u64,
+        price_per_second: u64,
+        category: [u8; 32],
+    ) -> Result<()> {
+        let clock = Clock::get()?;
+        let price_per_second = match &ctx.accounts.spot_price {
+            Some(spot) => {
+                require!(spot.category == category, ErrorCode::CategoryMismatch);
+                spot.current(clock.unix_timestamp)?
+            }
+            None => price_per_second,
+        };
+        if let Some(prefs) = load_payout_prefs(&ctx.accounts.payout_prefs)? {
+            prefs.check_price(price_per_second)?;
+        }
+        if let Some(blocked) = load_blocked_agents(&ctx.accounts.blocked_agents)? {
+            require!(!blocked.agents.contains(&ctx.accounts.escrow_account.agent), ErrorCode::AgentBlocked);
+        }
+        // Humans who publish a calendar can only be booked inside it
+        if let Some(availability) = load_availability(&ctx.accounts.availability)? {
+            require!(availability.is_available(clock.unix_timestamp), ErrorCode::HumanUnavailable);
+        }
+        // Gated tasks only open for humans holding an NFT from the task's collection
+        if let Some(gate) = &ctx.accounts.task_gate {
+            let (Some(nft_account), Some(metadata)) = (&ctx.accounts.gate_nft_account, &ctx.accounts.gate_metadata)
+            else {
+                return err!(ErrorCode::NftGateRequired);
+            };
+            require_keys_eq!(nft_account.owner, ctx.accounts.human.key(), ErrorCode::NftGateFailed);
+            require!(nft_account.amount >= 1, ErrorCode::NftGateFailed);
+            let (metadata_key, _) = Pubkey::find_program_address(
+                &[b"metadata", TOKEN_METADATA_PROGRAM.as_ref(), nft_account.mint.as_ref()],
+                &TOKEN_METADATA_PROGRAM,
+            );
+            require_keys_eq!(metadata.key(), metadata_key, ErrorCode::NftGateFailed);
+            require_keys_eq!(*metadata.owner, TOKEN_METADATA_PROGRAM, ErrorCode::NftGateFailed);
+            let collection = verified_collection(&metadata.try_borrow_data()?);
+            require!(collection == Some(gate.collection), ErrorCode::NftGateFailed);
+        }
+        // Policy-restricted tasks need a matching attestation from a whitelisted issuer;
+        // only the issuer sees the human's PII
+        if let Some(policy) = &ctx.accounts.task_policy {
+            let (Some(issuer), Some(attestation)) = (&ctx.accounts.attestation_issuer, &ctx.accounts.attestation)
+            else {
+                return err!(ErrorCode::AttestationRequired);
+            };
+            require!(!issuer.revoked, ErrorCode::AttestationIssuerRevoked);
+            require_keys_eq!(*attestation.owner, issuer.program, ErrorCode::AttestationMismatch);
+            let attested = parse_issuer_attestation(&attestation.try_borrow_data()?)
+                .ok_or(ErrorCode::AttestationMismatch)?;
+            require_keys_eq!(attested.subject, ctx.accounts.human.key(), ErrorCode::AttestationMismatch);
+            require!(attested.attestation_type == policy.attestation_type, ErrorCode::AttestationMismatch);
+            require!(attested.policy_hash == policy.policy_hash, ErrorCode::AttestationMismatch);
+            require!(
+                attested.expires_at == 0 || clock.unix_timestamp < attested.expires_at,
+                ErrorCode::AttestationMismatch
+            );
+        }
+
+        // Reserve a short streaming window so the agent can't drain the escrow mid-session
+        let escrow = &mut ctx.accounts.escrow_account;
+        let reserve = price_per_second
+            .checked_mul(SESSION_RESERVE_SECONDS)
+            .ok_or(ErrorCode::MathOverflow)?
+            .min(escrow.withdrawable());
+        escrow.open_sessions = escrow.open_sessions.checked_add(1).ok_or(ErrorCode::MathOverflow)?;
+        escrow.statement_sessions = escrow.statement_sessions.saturating_add(1);
+        escrow.reserved_balance += reserve;
+
+        let session = &mut ctx.accounts.session;
+        session.escrow = escrow.key();
+        session.human = ctx.accounts.human.key();
+        session.session_id = session_id;
+        session.opened_at = clock.unix_timestamp;
+        session.opened_slot = clock.slot;
+        session.total_paid_seconds = 0;
+        session.total_tipped = 0;
+        session.price_per_second = price_per_second;
+        session.reserved = reserve;
+        session.bump = ctx.bumps.session;
+        session.spot_priced = ctx.accounts.spot_price.is_some();
+        session.matcher = ctx.accounts.matcher_balance.as_ref().map(|m| m.key()).unwrap_or_default();
+        session.quality_min_bps = ctx.accounts.market_config.quality_multiplier_min_bps;
+        session.quality_max_bps = ctx.accounts.market_config.quality_multiplier_max_bps;
+        session.category = category;
+        Ok(())
+    }
+
+    pub fn close_session(ctx: Context<CloseSession>) -> Result<()> {
+        let escrow = &mut ctx.accounts.escrow_account;
+        escrow.open_sessions = escrow.open_sessions.saturating_sub(1);
+        escrow.reserved_balance = escrow.reserved_balance.saturating_sub(ctx.accounts.session.reserved);
+        // Rent is returned to the router via the `close` constraint
+        Ok(())
+    }
+
+    /// Opens a session shared by up to MAX_GROUP_PARTICIPANTS humans (a webinar or live
+    /// stream). `remaining_accounts` holds each participant's BlockedAgents PDA, in
+    /// participant order. The reserve covers every participant's streaming window.
+    pub fn open_group_session<'info>(
+        ctx: Context<'_, '_, 'info, 'info, OpenGroupSession<'info>>,
+        session_id: u64,
+        price_per_second: u64,
+        participants: Vec<Pubkey>,
+    ) -> Result<()> {
+        require!(
+            !participants.is_empty() && participants.len() <= MAX_GROUP_PARTICIPANTS,
+            ErrorCode::InvalidGroup
+        );
+        require!(ctx.remaining_accounts.len() == participants.len(), ErrorCode::InvalidGroup);
+        let agent = ctx.accounts.escrow_account.agent;
+        for (i, (human, blocked_info)) in participants.iter().zip(ctx.remaining_accounts).enumerate() {
+            require!(!participants[..i].contains(human), ErrorCode::InvalidGroup);
+            let (expected, _) = Pubkey::find_program_address(&[seeds::BLOCKED_AGENTS, human.as_ref()], ctx.program_id);
+            require_keys_eq!(blocked_info.key(), expected, ErrorCode::InvalidGroup);
+            if let Some(blocked) = load_blocked_agents(blocked_info)? {
+                require!(!blocked.agents.contains(&agent), ErrorCode::AgentBlocked);
+            }
+        }
+
+        let escrow = &mut ctx.accounts.escrow_account;
+        let reserve = price_per_second
+            .checked_mul(SESSION_RESERVE_SECONDS)
+            .and_then(|r| r.checked_mul(participants.len() as u64))
+            .ok_or(ErrorCode::MathOverflow)?
+            .min(escrow.withdrawable());
+        escrow.open_sessions = escrow.open_sessions.checked_add(1).ok_or(ErrorCode::MathOverflow)?;
+        escrow.statement_sessions = escrow.statement_sessions.saturating_add(1);
+        escrow.reserved_balance += reserve;
+
+        let group = &mut ctx.accounts.group_session;
+        group.escrow = escrow.key();
+        group.session_id = session_id;
+        group.opened_at = Clock::get()?.unix_timestamp;
+        group.price_per_second = price_per_second;
+        group.reserved = reserve;
+        group.paid_seconds = vec![0; participants.len()];
+        group.participants = participants;
+        group.bump = ctx.bumps.group_session;
+        Ok(())
+    }
+
+    /// Settles a group session with one escrow draw: `verified_seconds` holds each
+    /// participant's billed seconds, in participant order, all at `agreed_price_per_second`.
+    /// The fee is computed once on the total and the rest split pro rata by seconds, with
+    /// rounding dust going to the fee. Each participant's share is subject to the holdback
+    /// like a single settlement. `remaining_accounts` holds a (payout token account,
+    /// PayoutPrefs, Holdback, PayoutSplit, OrgMember PDA) group per participant; humans with
+    /// a payout split or org are rejected, and builders and matchers don't apply. Attestations,
+    /// when given or required, are one Ed25519 instruction per participant, in order, right
+    /// before this one.
+    pub fn close_group_settlement<'info>(
+        ctx: Context<'_, '_, 'info, 'info, CloseGroupSettlement<'info>>,
+        nonce: u64,
+        agreed_price_per_second: u64,
+        verified_seconds: Vec<u64>,
+    ) -> Result<()> {
+        let group = &mut ctx.accounts.group_session;
+        require!(verified_seconds.len() == group.participants.len(), ErrorCode::InvalidGroup);
+        require!(
+            ctx.remaining_accounts.len() == verified_seconds.len() * GROUP_MEMBER_ACCOUNTS,
+            ErrorCode::InvalidGroup
+        );
+        require!(agreed_price_per_second >= group.price_per_second, ErrorCode::BelowPriceFloor);
+
+        // Every participant's billed seconds are capped like a single session's
+        let now = Clock::get()?.unix_timestamp;
+        let elapsed = now.saturating_sub(group.opened_at).max(0) as u64;
+        let mut total_seconds: u64 = 0;
+        for (paid, seconds) in group.paid_seconds.iter_mut().zip(&verified_seconds) {
+            let paid_seconds = paid.checked_add(*seconds).ok_or(ErrorCode::MathOverflow)?;
+            require!(paid_seconds <= MAX_SESSION_SECONDS, ErrorCode::SessionCapExceeded);
+            require!(
+                paid_seconds <= elapsed.saturating_add(SESSION_TOLERANCE_SECONDS),
+                ErrorCode::SessionElapsedExceeded
+            );
+            *paid = paid_seconds;
+            total_seconds = total_seconds.checked_add(*seconds).ok_or(ErrorCode::MathOverflow)?;
+        }
+
+        let escrow = &mut ctx.accounts.escrow_account;
+        require!(nonce > escrow.settlement_nonce, ErrorCode::NonceAlreadyUsed);
+        escrow.settlement_nonce = nonce;
+        require!(!escrow.intents_required, ErrorCode::PaymentIntentRequired);
+
+        let total_payout = total_seconds.checked_mul(agreed_price_per_second).ok_or(ErrorCode::MathOverflow)?;
+        require!(escrow.balance >= total_payout, ErrorCode::InsufficientFunds);
+        escrow.balance -= total_payout;
+        escrow.sponsored_balance = escrow.sponsored_balance.min(escrow.balance);
+
+        let fee_bps = escrow.fee_bps(&ctx.accounts.market_config, now);
+        let net_total = total_payout - bps_of(total_payout, fee_bps)?;
+
+        let mint = ctx.accounts.mint.key();
+        let holdback_bps = ctx.accounts.market_config.effective_holdback_bps();
+        let release_at = now.saturating_add(ctx.accounts.market_config.holdback_period);
+        let mut paid_out: u64 = 0;
+        let mut held_total: u64 = 0;
+        let recipients = group.participants.iter()
+            .zip(&verified_seconds)
+            .zip(ctx.remaining_accounts.chunks(GROUP_MEMBER_ACCOUNTS));
+        for (i, ((human, seconds), member)) in recipients.enumerate() {
+            // Participant i's attestation sits (participants - i) instructions back
+            let message = settlement_attestation_message(
+                &escrow.key(),
+                human,
+                *seconds,
+                agreed_price_per_second,
+                nonce,
+                QUALITY_MULTIPLIER_NEUTRAL_BPS,
+            );
+            check_settlement_attestation(
+                &ctx.accounts.market_config,
+                ctx.accounts.attestation_key.as_deref(),
+                ctx.accounts.instructions_sysvar.as_deref(),
+                verified_seconds.len() - i,
+                &message,
+                now,
+            )?;
+
+            let payout_account = Account::<TokenAccount>::try_from(&member[0])?;
+            require_keys_eq!(payout_account.owner, *human, ErrorCode::InvalidTokenAccount);
+            require_keys_eq!(payout_account.mint, mint, ErrorCode::InvalidMint);
+            let pdas = [seeds::PAYOUT_PREFS, seeds::HOLDBACK, seeds::PAYOUT_SPLIT, seeds::ORG_MEMBER];
+            for (info, seed) in member[1..].iter().zip(pdas) {
+                let (expected, _) = Pubkey::find_program_address(&[seed, human.as_ref()], ctx.program_id);
+                require_keys_eq!(info.key(), expected, ErrorCode::InvalidGroup);
+            }
+            if let Some(prefs) = load_payout_prefs(&member[1])? {
+                prefs.check_mint(&mint)?;
+                prefs.check_price(agreed_price_per_second)?;
+            }
+            require!(load_payout_split(&member[3])?.is_none(), ErrorCode::GroupPayoutUnsupported);
+            require!(load_org_member(&member[4])?.is_none(), ErrorCode::GroupPayoutUnsupported);
+
+            // total_seconds is nonzero whenever there is anything to share
+            let share = if total_seconds == 0 {
+                0
+            } else {
+                (net_total as u128 * *seconds as u128 / total_seconds as u128) as u64
+            };
+            let holdback_amount = bps_of(share, holdback_bps)?;
+            if holdback_amount > 0 {
+                require!(account_exists(&member[2]), ErrorCode::HoldbackAccountMissing);
+                let mut holdback = Account::<Holdback>::try_from(&member[2])?;
+                holdback.credit(*human, holdback_amount, release_at)?;
+                holdback.exit(ctx.program_id)?;
+            }
+            transfer_from_escrow_vault(
+                escrow,
+                &ctx.accounts.vault,
+                &payout_account,
+                &ctx.accounts.token_program,
+                share - holdback_amount,
+            )?;
+            paid_out += share;
+            held_total += holdback_amount;
+        }
+
+        // Fees and holdbacks both wait in the fee vault
+        let fee_amount = total_payout - paid_out;
+        transfer_from_escrow_vault(
+            escrow,
+            &ctx.accounts.vault,
+            &ctx.accounts.fee_vault,
+            &ctx.accounts.token_program,
+            fee_amount + held_total,
+        )?;
+        escrow.record_settlement(total_payout, fee_amount);
+        if let Some(fee_bps) = escrow.fee_bps_override {
+            emit!(FeeOverrideApplied { escrow: escrow.key(), fee_bps, fee_amount });
+        }
+
+        // No builder on the group path -> Protocol takes all
+        let state = &mut ctx.accounts.fee_vault_state;
+        state.credit_settlement_fees(fee_amount, now, ctx.accounts.market_config.fee_challenge_window);
+        state.total_collected += fee_amount;
+        ctx.accounts.market_config.record_settlement_volume(total_payout, now);
+
+        emit!(GroupSettlementClosed {
+            escrow: escrow.key(),
+            session_id: group.session_id,
+            nonce,
+            participants: group.participants.len() as u8,
+            amount: total_payout,
+            fee_amount,
+            holdback_amount: held_total,
+            escrow_balance: escrow.balance,
+        });
+        Ok(())
+    }
+
+    pub fn close_group_session(ctx: Context<CloseGroupSession>) -> Result<()> {
+        let escrow = &mut ctx.accounts.escrow_account;
+        escrow.open_sessions = escrow.open_sessions.saturating_sub(1);
+        escrow.reserved_balance = escrow.reserved_balance.saturating_sub(ctx.accounts.group_session.reserved);
+        // Rent is returned to the router via the `close` constraint
+        Ok(())
+    }
+
+    /// Closes a session left open past its billable window (hard cap plus tolerance) and
+    /// a grace period for late settlements, releasing its escrow reserve. Signed by the
+    /// admin or the automation authority, so a scheduled thread can sweep stale sessions.
+    pub fn expire_session(ctx: Context<ExpireSession>) -> Result<()> {
+        let session = &ctx.accounts.session;
+        let expires_at = session.opened_at
+            .saturating_add((MAX_SESSION_SECONDS + SESSION_TOLERANCE_SECONDS) as i64)
+            .saturating_add(SESSION_EXPIRY_GRACE);
+        require!(Clock::get()?.unix_timestamp >= expires_at, ErrorCode::SessionNotExpired);
+
+        let escrow = &mut ctx.accounts.escrow_account;
+        escrow.open_sessions = escrow.open_sessions.saturating_sub(1);
+        escrow.reserved_balance = escrow.reserved_balance.saturating_sub(session.reserved);
+
+        emit!(SessionExpired {
+            escrow: escrow.key(),
+            session_id: session.session_id,
+            released: session.reserved,
+        });
+        // Rent is returned to the router via the `close` constraint
+        Ok(())
+    }
+
+    /// Human payout split legs, if the human configured any, are passed in
+    /// `remaining_accounts` in leg order. A human in an org pays the org fee before the split.
+    /// While the router's heartbeat is stale, the human can sign in its place, provided the
+    /// settlement carries an attestation. Referral strings, response hashes and quality
+    /// multipliers travel in `SettlementExtras`.
+    pub fn close_settlement<'info>(
+        ctx: Context<'_, '_, 'info, 'info, CloseSettlement<'info>>,
         verified_seconds: u64,
         agreed_price_per_second: u64,
         nonce: u64,
         builder_code_opt: Option<[u8; 32]>, // Optional builder code
+        quality_score: Option<u8>,          // Optional verifier score (1-100)
+        extras: SettlementExtras,
     ) -> Result<()> {
+        let SettlementExtras { builder_code_str, response_hash, quality_multiplier_bps } = extras;
+        if let Some(score) = quality_score {
+            require!((1..=100).contains(&score), ErrorCode::InvalidQualityScore);
+        }
+        require!(response_hash.is_none() || ctx.accounts.receipt.is_some(), ErrorCode::ReceiptRequired);
+        if let Some(code_str) = &builder_code_str {
+            require!(code_str.len() <= MAX_BUILDER_CODE_STR_LEN, ErrorCode::InvalidBuilderCode);
+            // A raw code passed alongside the string must be its hash
+            let hashed = builder_code_from_str(code_str);
+            require!(builder_code_opt.is_none() || builder_code_opt == Some(hashed), ErrorCode::InvalidBuilderCode);
+        }
+        let builder_code_opt = resolve_builder_code(builder_code_opt, builder_code_str.as_deref());
+
         let escrow = &mut ctx.accounts.escrow_account;
         
         // Validate nonce to prevent replay attacks
         require!(nonce > escrow.settlement_nonce, ErrorCode::NonceAlreadyUsed);
         escrow.settlement_nonce = nonce;
         
+        // Billed seconds can never outrun the session's wall-clock time
+        let session = &mut ctx.accounts.session;
+        // Spot-priced sessions pay the snapshotted price, never a router-chosen one
+        require!(
+            !session.spot_priced || agreed_price_per_second == session.price_per_second,
+            ErrorCode::SpotPriceMismatch
+        );
+        session.bill_seconds(verified_seconds, Clock::get()?.unix_timestamp)?;
+
+        // Engagement may only move the price within the bounds snapshotted at open
+        let quality_multiplier_bps = quality_multiplier_bps.unwrap_or(QUALITY_MULTIPLIER_NEUTRAL_BPS);
+        if quality_multiplier_bps != QUALITY_MULTIPLIER_NEUTRAL_BPS {
+            require!(
+                (session.quality_min_bps..=session.quality_max_bps).contains(&quality_multiplier_bps),
+                ErrorCode::InvalidQualityMultiplier
+            );
+            // Forward positions pay their locked price
+            require!(ctx.accounts.forward_position.is_none(), ErrorCode::InvalidQualityMultiplier);
+        }
+        let paid_price_per_second = agreed_price_per_second as u128 * quality_multiplier_bps as u128
+            / BPS_DENOMINATOR as u128;
+        let paid_price_per_second = u64::try_from(paid_price_per_second).map_err(|_| ErrorCode::MathOverflow)?;
+        // The floor holds after the multiplier, so a sub-1x adjustment can't undercut it
+        require!(paid_price_per_second >= session.price_per_second, ErrorCode::BelowPriceFloor);
+
+        // Attested settlements must be signed by an active registry key in the preceding
+        // instruction; once the registry has a live key, every settlement must be attested
+        let message = settlement_attestation_message(
+            &escrow.key(),
+            &ctx.accounts.human.key(),
+            verified_seconds,
+            agreed_price_per_second,
+            nonce,
+            quality_multiplier_bps,
+        );
+        check_settlement_attestation(
+            &ctx.accounts.market_config,
+            ctx.accounts.attestation_key.as_deref(),
+            ctx.accounts.instructions_sysvar.as_deref(),
+            1,
+            &message,
+            Clock::get()?.unix_timestamp,
+        )?;
+
+        // Escrows in a secondary mint must settle through that mint's fee vault
+        let secondary_mint = ctx.accounts.mint_fee_vault.as_ref().map(|m| m.mint);
+        require_keys_eq!(escrow.mint, secondary_mint.unwrap_or_default(), ErrorCode::InvalidMint);
+
+        // Builder splits can be switched off deployment-wide. Builder balances are
+        // denominated in the primary mint, so secondary-mint fees go to the protocol.
+        let builder_code_opt = builder_code_opt
+            .filter(|_| ctx.accounts.market_config.has_feature(MarketConfig::FEATURE_BUILDER_SPLITS))
+            .filter(|_| secondary_mint.is_none());
+
         // If builder code is provided, builder account MUST exist
         if builder_code_opt.is_some() {
             require!(ctx.accounts.builder_balance.is_some(), ErrorCode::BuilderAccountMissing);
         }
+
+        // A session opened through a matcher must pay that matcher (primary mint only, like builders)
+        let session_matcher = ctx.accounts.session.matcher;
+        let pays_matcher = session_matcher != Pubkey::default() && secondary_mint.is_none();
+        if pays_matcher {
+            let matcher = ctx.accounts.matcher_balance.as_ref().ok_or(ErrorCode::MatcherMismatch)?;
+            require_keys_eq!(matcher.key(), session_matcher, ErrorCode::MatcherMismatch);
+        }
+
+        // Preferences are read from the human's own account rather than trusted from the router
+        let prefs = load_payout_prefs(&ctx.accounts.payout_prefs)?;
+        if let Some(prefs) = &prefs {
+            prefs.check_mint(&ctx.accounts.mint.key())?;
+            prefs.check_price(paid_price_per_second)?;
+        }
+        let tax_lot = prefs.as_ref().and_then(PayoutPrefs::tax_lot);
         
-        let total_payout = verified_seconds.checked_mul(agreed_price_per_second)
+        let total_payout = verified_seconds.checked_mul(paid_price_per_second)
             .ok_or(ErrorCode::MathOverflow)?;
 
+        // Agents that opted into intents only pay what an intent for this human still allows
+        if let Some(intent) = &mut ctx.accounts.payment_intent {
+            intent.spend(total_payout, Clock::get()?.unix_timestamp)?;
+            emit!(PaymentIntentSpent {
+                payment_intent: intent.key(),
+                nonce,
+                amount: total_payout,
+                remaining: intent.remaining,
+            });
+        } else {
+            require!(!escrow.intents_required, ErrorCode::PaymentIntentRequired);
+        }
+
+        // Forward positions pay at their locked price from funds set aside at purchase
+        if let Some(forward) = &mut ctx.accounts.forward_position {
+            require!(Clock::get()?.unix_timestamp < forward.expires_at, ErrorCode::ForwardExpired);
+            require!(agreed_price_per_second == forward.price_per_second, ErrorCode::ForwardPriceMismatch);
+            forward.consume(verified_seconds)?;
+            escrow.balance += total_payout;
+        }
+
+        // Underwritten escrows may draw a shortfall from their credit line
+        if escrow.balance < total_payout {
+            if let (Some(credit_line), Some(collateral)) =
+                (&mut ctx.accounts.credit_line, &ctx.accounts.credit_collateral)
+            {
+                let shortfall = total_payout - escrow.balance;
+                credit_line.draw(shortfall)?;
+                transfer_from_collateral(
+                    credit_line,
+                    collateral,
+                    &ctx.accounts.vault,
+                    &ctx.accounts.token_program,
+                    shortfall,
+                )?;
+                escrow.balance += shortfall;
+                emit!(CreditDrawn {
+                    escrow: escrow.key(),
+                    amount: shortfall,
+                    debt: credit_line.debt,
+                });
+            }
+        }
+
         require!(escrow.balance >= total_payout, ErrorCode::InsufficientFunds);
 
         // Deduct from internal balance
         escrow.balance -= total_payout;
+        // Agent funds are spent first; the sponsored tranche only shrinks once they run out
+        escrow.sponsored_balance = escrow.sponsored_balance.min(escrow.balance);
 
-        // Calculate Fee
-        let fee_bps = ctx.accounts.market_config.fee_basis_points as u64; // e.g. 1500 (15%)
-        let fee_amount = total_payout.checked_mul(fee_bps).unwrap() / 10000;
-        let net_payout = total_payout - fee_amount;
+        // Hold back a slice of the human's payout for quality review
+        // Holdbacks are likewise tracked in the primary mint only
+        let holdback_bps = if secondary_mint.is_some() {
+            0
+        } else {
+            ctx.accounts.market_config.effective_holdback_bps()
+        };
+        // Builder gets builder_share_bps of the fee (default 2000 -> 3% of a 15% fee).
+        // The seeds ["builder", code] ensure we loaded the right account for that code.
+        let builder_share_bps = builder_code_opt
+            .filter(|_| ctx.accounts.builder_balance.is_some())
+            .map(|_| ctx.accounts.market_config.builder_share_bps as u64);
+        let fee_bps = escrow.fee_bps(&ctx.accounts.market_config, Clock::get()?.unix_timestamp);
+        let split = split_settlement(total_payout, fee_bps, holdback_bps, builder_share_bps)?;
+        let SettlementSplit { fee_amount, builder_share, holdback_amount, user_payout } = split;
+        let matcher_share = if pays_matcher {
+            bps_of(fee_amount, ctx.accounts.market_config.matcher_share_bps as u64)?
+        } else {
+            0
+        };
+        escrow.record_settlement(total_payout, fee_amount);
+        ctx.accounts.market_config.record_settlement_volume(total_payout, Clock::get()?.unix_timestamp);
+        if let Some(fee_bps) = escrow.fee_bps_override {
+            emit!(FeeOverrideApplied { escrow: escrow.key(), fee_bps, fee_amount });
+        }
 
         // Seeds for Escrow signing
         let agent_key = escrow.agent.key();
         let escrow_bump = escrow.bump;
         let escrow_seeds = &[
-            b"escrow",
+            seeds::ESCROW,
             agent_key.as_ref(),
             &[escrow_bump],
         ];
         let escrow_signer = &[&escrow_seeds[..]];
 
-        // 1. Transfer Net Payout to User
-        if net_payout > 0 {
-            let transfer_to_user = Transfer {
-                from: ctx.accounts.vault.to_account_info(),
-                to: ctx.accounts.user_wallet.to_account_info(),
-                authority: escrow.to_account_info(),
-            };
-            let cpi_ctx_user = CpiContext::new_with_signer(
-                ctx.accounts.token_program.to_account_info(),
-                transfer_to_user,
-                escrow_signer,
-            );
-            token::transfer(cpi_ctx_user, net_payout)?;
-        }
+        // 1. Transfer Net Payout (less holdback and org fee) to User, across their split legs
+        let payout_split = load_payout_split(&ctx.accounts.payout_split)?;
+        let transfer_to_user = Transfer {
+            from: ctx.accounts.vault.to_account_info(),
+            to: ctx.accounts.user_wallet.to_account_info(),
+            authority: escrow.to_account_info(),
+        };
+        let cpi_ctx_user = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            transfer_to_user,
+            escrow_signer,
+        );
+        let user_payout = take_org_fee(
+            &cpi_ctx_user,
+            &ctx.accounts.org_member,
+            ctx.accounts.org.as_deref_mut(),
+            ctx.accounts.org_treasury.as_deref(),
+            user_payout,
+        )?;
+        pay_human(cpi_ctx_user, payout_split.as_ref(), ctx.remaining_accounts, user_payout)?;
 
-        // 2. Transfer Fees to Fee Vault
-        if fee_amount > 0 {
+        // 2. Transfer Fees and Holdback to Fee Vault
+        let to_fee_vault = fee_amount + holdback_amount;
+        if to_fee_vault > 0 {
             let transfer_to_fee_vault = Transfer {
                 from: ctx.accounts.vault.to_account_info(),
                 to: ctx.accounts.fee_vault.to_account_info(),
@@ -175,48 +957,193 @@ pub mod payment_router {
                 transfer_to_fee_vault,
                 escrow_signer,
             );
-            token::transfer(cpi_ctx_fees, fee_amount)?;
-
-            // 3. Update Balances (Protocol vs Builder)
-            let protocol_share;
-            let builder_share;
-
-            // Logic: 
-            // Total Fee is 15% (1500 bps)
-            // Protocol gets 12% (1200 bps) -> 12/15 of fee
-            // Builder gets 3% (300 bps) -> 3/15 of fee
-            // If no builder, Protocol gets full 15%
-            
-            if let Some(_code) = builder_code_opt {
-                // Check if builder account is present and matches code
+            token::transfer(cpi_ctx_fees, to_fee_vault)?;
+        }
+
+        if holdback_amount > 0 {
+            let release_at = Clock::get()?.unix_timestamp
+                .saturating_add(ctx.accounts.market_config.holdback_period);
+            let holdback = ctx.accounts.holdback.as_mut().ok_or(ErrorCode::HoldbackAccountMissing)?;
+            holdback.credit(ctx.accounts.human.key(), holdback_amount, release_at)?;
+            holdback.bump = ctx.bumps.holdback.ok_or(ErrorCode::HoldbackAccountMissing)?;
+        }
+
+        if fee_amount > 0 {
+            // 3. Update Balances (Protocol vs Builder and Matcher); with neither the protocol takes the full fee
+            let protocol_share = fee_amount - builder_share - matcher_share;
+            if matcher_share > 0 {
+                if let Some(matcher) = &mut ctx.accounts.matcher_balance {
+                    matcher.balance += matcher_share;
+                    matcher.total_earned += matcher_share;
+                    emit!(MatcherPaid {
+                        matcher_code: matcher.matcher_code,
+                        escrow: ctx.accounts.escrow_account.key(),
+                        nonce,
+                        matcher_share,
+                    });
+                }
+            }
+            if builder_share_bps.is_some() {
                 if let Some(builder_balance) = &mut ctx.accounts.builder_balance {
-                     // Note: You might want to verify builder_balance.builder_code == _code
-                     // But strictly relying on the passed Account being correct is also standard Anchor pattern if seeds match.
-                     // The seeds ["builder", code] ensure we loaded the right account for that code.
-
-                     builder_share = fee_amount.checked_mul(3).unwrap() / 15;
-                     protocol_share = fee_amount - builder_share;
-
-                     builder_balance.balance += builder_share;
-                     builder_balance.total_earned += builder_share;
-                } else {
-                     // Builder code passed but account not provided/valid -> Protocol takes all (safety fallback)
-                     protocol_share = fee_amount;
+                    builder_balance.balance += builder_share;
+                    builder_balance.total_earned += builder_share;
+                    if let Some(code_str) = builder_code_str {
+                        emit!(BuilderReferral {
+                            builder_code: builder_balance.builder_code,
+                            builder_code_str: code_str,
+                            escrow: ctx.accounts.escrow_account.key(),
+                            nonce,
+                            builder_share,
+                        });
+                    }
+
+                    if let Some(builder_stats) = &mut ctx.accounts.builder_stats {
+                        let month = month_index(Clock::get()?.unix_timestamp);
+                        builder_stats.record(month, builder_share);
+                    }
                 }
+            }
+
+            if let Some(mint_fee_vault) = &mut ctx.accounts.mint_fee_vault {
+                mint_fee_vault.protocol_balance += protocol_share;
+                mint_fee_vault.total_collected += fee_amount;
             } else {
-                // No builder code -> Protocol takes all
-                protocol_share = fee_amount;
+                let state = &mut ctx.accounts.fee_vault_state;
+                let window = ctx.accounts.market_config.fee_challenge_window;
+                state.credit_settlement_fees(protocol_share, Clock::get()?.unix_timestamp, window);
+                state.total_collected += fee_amount; // Track total volume through vault
             }
+        }
 
-            let state = &mut ctx.accounts.fee_vault_state;
-            state.protocol_balance += protocol_share;
-            state.total_collected += fee_amount; // Track total volume through vault
+        let now = Clock::get()?.unix_timestamp;
+        let agent = ctx.accounts.escrow_account.agent;
+        // Outliers are flagged for fraud review but still paid, and kept out of the window
+        let mut flagged = false;
+        if let Some(mut stats) = load_session_stats_mut(&ctx.accounts.session_stats)? {
+            let z_score_e2 = stats.z_score_e2(verified_seconds);
+            flagged = stats.is_outlier(z_score_e2);
+            if flagged {
+                emit!(SettlementFlagged {
+                    escrow: ctx.accounts.escrow_account.key(),
+                    human: ctx.accounts.human.key(),
+                    nonce,
+                    category: stats.category,
+                    verified_seconds,
+                    median_seconds: stats.median,
+                    z_score_e2,
+                });
+            } else {
+                stats.record(verified_seconds);
+            }
+        }
+        if let (Some(receipt), Some(bump)) = (&mut ctx.accounts.receipt, ctx.bumps.receipt) {
+            receipt.human = ctx.accounts.human.key();
+            receipt.agent = agent;
+            receipt.escrow = ctx.accounts.escrow_account.key();
+            receipt.nonce = nonce;
+            receipt.verified_seconds = verified_seconds;
+            receipt.agreed_price_per_second = agreed_price_per_second;
+            receipt.total_payout = total_payout;
+            receipt.quality_score = quality_score.unwrap_or(0);
+            receipt.settled_at = now;
+            receipt.bump = bump;
+            receipt.flagged = flagged;
+            receipt.response_hash = response_hash.unwrap_or_default();
+            receipt.quality_multiplier_bps = quality_multiplier_bps;
+            if let Some(tax_lot) = &tax_lot {
+                receipt.jurisdiction = tax_lot.jurisdiction;
+                receipt.tax_memo_hash = tax_lot.memo_hash;
+            }
+        }
+        if let (Some(stats), Some(bump)) = (&mut ctx.accounts.human_stats, ctx.bumps.human_stats) {
+            stats.record(ctx.accounts.human.key(), quality_score, bump)?;
+        }
+        if let (Some(stats), Some(bump)) = (&mut ctx.accounts.agent_stats, ctx.bumps.agent_stats) {
+            stats.record(agent, quality_score, bump)?;
+        }
+        if let (Some(stats), Some(bump)) = (&mut ctx.accounts.relayer_stats, ctx.bumps.relayer_stats) {
+            stats.record(ctx.accounts.router.key(), bump);
+        }
+        if let Some(price_index) = &mut ctx.accounts.price_index {
+            price_index.record(agreed_price_per_second, now);
+        }
+        let escrow = &ctx.accounts.escrow_account;
+        emit!(SettlementClosed {
+            escrow: escrow.key(),
+            human: ctx.accounts.human.key(),
+            nonce,
+            amount: total_payout,
+            fee_amount,
+            holdback_amount,
+            escrow_balance: escrow.balance,
+            escrow_locked: escrow.locked(),
+            quality_multiplier_bps,
+        });
+        if let Some(tax_lot) = tax_lot {
+            emit!(PayoutAnnotated {
+                human: ctx.accounts.human.key(),
+                escrow: escrow.key(),
+                nonce,
+                amount: total_payout,
+                jurisdiction: tax_lot.jurisdiction,
+                memo_hash: tax_lot.memo_hash,
+            });
+        }
+        if let Some(api_key) = &mut ctx.accounts.api_key {
+            api_key.record(total_payout, now);
+            emit!(ApiKeySpend {
+                api_key: api_key.key(),
+                key_hash: api_key.key_hash,
+                nonce,
+                amount: total_payout,
+            });
+        }
+        if let Some(consent) = &mut ctx.accounts.data_consent {
+            require!(consent.is_active(now), ErrorCode::DataConsentInactive);
+            consent.settlements += 1;
+            emit!(ConsentedSettlement {
+                consent: consent.key(),
+                human: consent.human,
+                scope_hash: consent.scope_hash,
+                escrow: ctx.accounts.escrow_account.key(),
+                nonce,
+            });
+        }
+        // Self-CPI so observers can index the event from instruction data, unaffected by log truncation
+        if let Some(observer) = ctx.accounts.observer.as_ref().filter(|o| o.accepted) {
+            let event = ObservedSettlement {
+                observer: observer.observer,
+                tag: observer.tag,
+                escrow: ctx.accounts.escrow_account.key(),
+                agent,
+                human: ctx.accounts.human.key(),
+                nonce,
+                verified_seconds,
+                agreed_price_per_second,
+                amount: total_payout,
+                fee_amount,
+                holdback_amount,
+                builder_code: builder_code_opt,
+                quality_score,
+                settled_at: now,
+            };
+            emit_cpi!(event);
         }
 
         Ok(())
     }
 
-    pub fn claim_builder_balance(ctx: Context<ClaimBuilderBalance>) -> Result<()> {
+    /// Pays out the builder's balance in the fee vault mint. With `swap`, the payout is
+    /// then routed through the whitelisted swap venue (accounts in `remaining_accounts`)
+    /// into `swap_output`. If the swap can't run (venue unset or not whitelisted, accounts
+    /// missing, or the invoke is rejected) the builder keeps the plain transfer and
+    /// `BuilderClaimSwapFailed` is emitted. A venue that aborts mid-execution still fails
+    /// the transaction, as the runtime doesn't hand that error back to the caller, and a
+    /// swap that completes below `min_amount_out` reverts with `SlippageExceeded`.
+    pub fn claim_builder_balance<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ClaimBuilderBalance<'info>>,
+        swap: Option<SwapLeg>,
+    ) -> Result<()> {
         let builder = &mut ctx.accounts.builder_balance;
         let amount = builder.balance;
 
@@ -225,7 +1152,7 @@ pub mod payment_router {
         // Seeds for Fee Vault Authority
         let bump = ctx.accounts.fee_vault_state.bump;
         let seeds = &[
-            b"fee_vault_state" as &[u8],
+            seeds::FEE_VAULT_STATE,
             &[bump],
         ];
         
@@ -246,19 +1173,128 @@ pub mod payment_router {
         token::transfer(cpi_ctx, amount)?;
 
         builder.balance = 0;
+
+        if let Some(swap) = swap {
+            let venue = match (
+                ctx.accounts.market_config.as_ref(),
+                ctx.accounts.swap_program.as_ref(),
+                ctx.accounts.swap_output.as_mut(),
+            ) {
+                (Some(market_config), Some(swap_program), Some(swap_output))
+                    if market_config.swap_program != Pubkey::default()
+                        && swap_program.key() == market_config.swap_program =>
+                {
+                    Some((swap_program, swap_output))
+                }
+                _ => None,
+            };
+            // The plain transfer above already paid the builder, so it stands as the fallback
+            let Some((swap_program, swap_output)) = venue else {
+                emit!(BuilderClaimSwapFailed {
+                    builder_code: builder.builder_code,
+                    amount,
+                });
+                return Ok(());
+            };
+
+            // The builder's signature authorizes the venue to spend the claimed tokens
+            let before = swap_output.amount;
+            let accounts = ctx.remaining_accounts.iter()
+                .map(|a| AccountMeta {
+                    pubkey: a.key(),
+                    is_signer: a.is_signer,
+                    is_writable: a.is_writable,
+                })
+                .collect();
+            let mut account_infos = ctx.remaining_accounts.to_vec();
+            account_infos.push(swap_program.to_account_info());
+            if invoke(
+                &Instruction {
+                    program_id: swap_program.key(),
+                    accounts,
+                    data: swap.data,
+                },
+                &account_infos,
+            )
+            .is_err()
+            {
+                emit!(BuilderClaimSwapFailed {
+                    builder_code: builder.builder_code,
+                    amount,
+                });
+                return Ok(());
+            }
+
+            swap_output.reload()?;
+            let received = swap_output.amount.saturating_sub(before);
+            require!(received >= swap.min_amount_out, ErrorCode::SlippageExceeded);
+
+            emit!(BuilderClaimSwapped {
+                builder_code: builder.builder_code,
+                amount_in: amount,
+                output_mint: swap_output.mint,
+                amount_out: received,
+            });
+        }
+        Ok(())
+    }
+
+    /// Registers a matchmaking service. Unlike builders, matchers are attributed per
+    /// session: the router names one at `open_session` and every settlement of that session
+    /// credits it `matcher_share_bps` of the protocol fee.
+    pub fn register_matcher(ctx: Context<RegisterMatcher>, matcher_code: [u8; 32]) -> Result<()> {
+        require!(matcher_code != [0u8; 32], ErrorCode::InvalidBuilderCode);
+
+        let matcher = &mut ctx.accounts.matcher_balance;
+        matcher.matcher_code = matcher_code;
+        matcher.wallet = ctx.accounts.matcher_wallet.key();
+        matcher.balance = 0;
+        matcher.total_earned = 0;
+        matcher.bump = ctx.bumps.matcher_balance;
+
+        emit!(MatcherRegistered {
+            matcher_code,
+            wallet: matcher.wallet,
+        });
+        Ok(())
+    }
+
+    pub fn claim_matcher_balance(ctx: Context<ClaimMatcherBalance>) -> Result<()> {
+        let amount = ctx.accounts.matcher_balance.balance;
+        require!(amount > 0, ErrorCode::NothingToClaim);
+
+        let seeds = &[seeds::FEE_VAULT_STATE, &[ctx.accounts.fee_vault_state.bump]];
+        let signer = &[&seeds[..]];
+        let cpi_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.fee_vault.to_account_info(),
+                to: ctx.accounts.matcher_token_account.to_account_info(),
+                authority: ctx.accounts.fee_vault_state.to_account_info(),
+            },
+            signer,
+        );
+        token::transfer(cpi_ctx, amount)?;
+
+        ctx.accounts.matcher_balance.balance = 0;
         Ok(())
     }
 
-    pub fn claim_protocol_fees(ctx: Context<ClaimProtocolFees>) -> Result<()> {
+    /// Withdraws `amount` of claimable protocol fees (0 = the full balance) to any token
+    /// account of the fee vault's mint. Settlement fees still inside their challenge
+    /// window stay pending and can't be claimed.
+    pub fn claim_protocol_fees(ctx: Context<ClaimProtocolFees>, amount: u64) -> Result<()> {
         let state = &mut ctx.accounts.fee_vault_state;
-        let amount = state.protocol_balance;
+        state.realize_fees(Clock::get()?.unix_timestamp, ctx.accounts.market_config.fee_challenge_window);
+        let amount = if amount == 0 { state.protocol_balance } else { amount };
 
         require!(amount > 0, ErrorCode::NothingToClaim);
+        require!(amount <= state.protocol_balance, ErrorCode::InsufficientFunds);
 
         // Seeds for signing
         let bump = state.bump;
         let seeds = &[
-            b"fee_vault_state" as &[u8],
+            seeds::FEE_VAULT_STATE,
             &[bump],
         ];
         let signer = &[&seeds[..]];
@@ -277,270 +1313,10008 @@ pub mod payment_router {
 
         token::transfer(cpi_ctx, amount)?;
 
-        state.protocol_balance = 0;
+        state.protocol_balance -= amount;
+
+        emit!(ProtocolFeesClaimed {
+            amount,
+            destination: ctx.accounts.admin_wallet.key(),
+            remaining: state.protocol_balance,
+        });
         Ok(())
     }
-}
-
-#[derive(Accounts)]
-pub struct InitializeMarketConfig<'info> {
-    #[account(mut, constraint = admin.key() == AUTHORIZED_ADMIN @ ErrorCode::Unauthorized)]
-    pub admin: Signer<'info>,
-    #[account(
-        init,
-        payer = admin,
-        space = 8 + 32 + 2,
-        seeds = [b"market_config"],
-        bump
-    )]
-    pub config: Account<'info, MarketConfig>,
-    pub system_program: Program<'info, System>,
-}
 
-#[derive(Accounts)]
-pub struct InitializeFeeVault<'info> {
-    #[account(mut, constraint = admin.key() == AUTHORIZED_ADMIN @ ErrorCode::Unauthorized)]
-    pub admin: Signer<'info>,
-    #[account(
-        init,
-        payer = admin,
-        space = 8 + 32 + 8 + 8 + 1,
-        seeds = [b"fee_vault_state"],
-        bump
-    )]
-    pub fee_vault_state: Account<'info, FeeVaultState>,
-    #[account(
-        init,
-        payer = admin,
-        seeds = [b"fee_vault", fee_vault_state.key().as_ref()], 
-        bump,
-        token::mint = mint,
-        token::authority = fee_vault_state,
-    )]
-    pub fee_vault: Account<'info, TokenAccount>,
-    pub mint: Account<'info, token::Mint>,
-    pub system_program: Program<'info, System>,
-    pub token_program: Program<'info, Token>,
-    pub rent: Sysvar<'info, Rent>,
-}
+    pub fn initialize_emissions(
+        ctx: Context<InitializeEmissions>,
+        start_ts: i64,
+        epoch_duration: i64,
+        initial_epoch_emission: u64,
+        decay_bps: u16,
+    ) -> Result<()> {
+        require!(epoch_duration > 0, ErrorCode::InvalidEmissionSchedule);
+        require!(decay_bps <= MAX_BPS, ErrorCode::InvalidEmissionSchedule);
+
+        let schedule = &mut ctx.accounts.emission_schedule;
+        schedule.mint = ctx.accounts.attn_mint.key();
+        schedule.rewards_destination = Pubkey::default(); // Set via set_emissions_destination
+        schedule.start_ts = start_ts;
+        schedule.epoch_duration = epoch_duration;
+        schedule.next_epoch = 0;
+        schedule.next_epoch_emission = initial_epoch_emission;
+        schedule.decay_bps = decay_bps;
+        schedule.total_minted = 0;
+        schedule.bump = ctx.bumps.emission_schedule;
+        Ok(())
+    }
+
+    pub fn set_emissions_destination(ctx: Context<SetEmissionsDestination>) -> Result<()> {
+        let schedule = &mut ctx.accounts.emission_schedule;
+        schedule.rewards_destination = ctx.accounts.rewards_destination.key();
+        Ok(())
+    }
+
+    pub fn mint_epoch_rewards(ctx: Context<MintEpochRewards>) -> Result<()> {
+        let schedule = &mut ctx.accounts.emission_schedule;
+
+        // Epochs are minted strictly in order, and only once they have fully elapsed
+        let epoch = schedule.next_epoch;
+        let epoch_end = (epoch as i64)
+            .checked_add(1)
+            .and_then(|n| n.checked_mul(schedule.epoch_duration))
+            .and_then(|d| d.checked_add(schedule.start_ts))
+            .ok_or(ErrorCode::MathOverflow)?;
+        require!(Clock::get()?.unix_timestamp >= epoch_end, ErrorCode::EmissionEpochNotEnded);
+
+        let amount = schedule.next_epoch_emission;
+        if amount > 0 {
+            let bump = schedule.bump;
+            let seeds = &[
+                seeds::EMISSIONS,
+                &[bump],
+            ];
+            let signer = &[&seeds[..]];
+
+            let mint_to = MintTo {
+                mint: ctx.accounts.attn_mint.to_account_info(),
+                to: ctx.accounts.rewards_destination.to_account_info(),
+                authority: schedule.to_account_info(),
+            };
+            let cpi_ctx = CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                mint_to,
+                signer,
+            );
+            token::mint_to(cpi_ctx, amount)?;
+        }
+
+        schedule.next_epoch = epoch + 1;
+        schedule.total_minted = schedule.total_minted.checked_add(amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        // Geometric decay: each epoch emits (1 - decay) of the previous one
+        schedule.next_epoch_emission = (amount as u128 * (BPS_DENOMINATOR as u128 - schedule.decay_bps as u128) / BPS_DENOMINATOR as u128) as u64;
+
+        emit!(EpochRewardsMinted {
+            epoch,
+            amount,
+            destination: ctx.accounts.rewards_destination.key(),
+            total_minted: schedule.total_minted,
+        });
+        Ok(())
+    }
+
+    pub fn tokenize_escrow(ctx: Context<TokenizeEscrow>) -> Result<()> {
+        let escrow = &mut ctx.accounts.escrow_account;
+        require!(escrow.position_mint == Pubkey::default(), ErrorCode::EscrowTokenized);
+
+        let agent_key = ctx.accounts.agent.key();
+        let bump = escrow.bump;
+        let seeds = &[
+            seeds::ESCROW,
+            agent_key.as_ref(),
+            &[bump],
+        ];
+        let signer = &[&seeds[..]];
+
+        // 1. Mint the single position token to the agent
+        let mint_to = MintTo {
+            mint: ctx.accounts.position_mint.to_account_info(),
+            to: ctx.accounts.agent_position_account.to_account_info(),
+            authority: escrow.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            mint_to,
+            signer,
+        );
+        token::mint_to(cpi_ctx, 1)?;
+
+        // 2. Revoke mint authority so the supply is fixed at one
+        let set_authority = SetAuthority {
+            current_authority: escrow.to_account_info(),
+            account_or_mint: ctx.accounts.position_mint.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            set_authority,
+            signer,
+        );
+        token::set_authority(cpi_ctx, AuthorityType::MintTokens, None)?;
+
+        escrow.position_mint = ctx.accounts.position_mint.key();
+        Ok(())
+    }
+
+    /// Accepts `WITHDRAW_ALL` like `withdraw_escrow`.
+    pub fn withdraw_escrow_as_holder(ctx: Context<WithdrawEscrowAsHolder>, amount: u64) -> Result<()> {
+        let escrow = &mut ctx.accounts.escrow_account;
+        let amount = if amount == WITHDRAW_ALL { escrow.withdrawable() } else { amount };
+        // Sponsored funds can only be refunded to their sponsors, and open sessions keep their reserve
+        require!(escrow.withdrawable() >= amount, ErrorCode::InsufficientFunds);
+
+        // Seeds for signing
+        let agent_key = escrow.agent;
+        let bump = escrow.bump;
+        let seeds = &[
+            seeds::ESCROW,
+            agent_key.as_ref(),
+            &[bump],
+        ];
+        let signer = &[&seeds[..]];
+
+        let transfer_instruction = Transfer {
+            from: ctx.accounts.vault.to_account_info(),
+            to: ctx.accounts.holder_token_account.to_account_info(),
+            authority: escrow.to_account_info(),
+        };
+
+        let cpi_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            transfer_instruction,
+            signer,
+        );
+
+        token::transfer(cpi_ctx, amount)?;
+
+        escrow.balance -= amount;
+
+        Ok(())
+    }
+
+    pub fn sponsor_deposit(ctx: Context<SponsorDeposit>, amount: u64) -> Result<()> {
+        let transfer_instruction = Transfer {
+            from: ctx.accounts.sponsor_token_account.to_account_info(),
+            to: ctx.accounts.vault.to_account_info(),
+            authority: ctx.accounts.sponsor.to_account_info(),
+        };
+
+        let cpi_ctx = CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            transfer_instruction,
+        );
+
+        token::transfer(cpi_ctx, amount)?;
+
+        let escrow = &mut ctx.accounts.escrow_account;
+        escrow.balance = escrow.balance.checked_add(amount).ok_or(ErrorCode::MathOverflow)?;
+        escrow.sponsored_balance = escrow.sponsored_balance.checked_add(amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        escrow.record_deposit(amount);
+        ctx.accounts.vault.reload()?;
+        ctx.accounts.market_config.check_deposit_cap(escrow, &ctx.accounts.vault)?;
+        emit!(EscrowFunded {
+            agent: escrow.agent,
+            amount,
+            new_balance: escrow.balance,
+            cumulative_deposited: escrow.cumulative_deposited,
+        });
+
+        let position = &mut ctx.accounts.sponsor_position;
+        position.escrow = escrow.key();
+        position.sponsor = ctx.accounts.sponsor.key();
+        position.amount = position.amount.checked_add(amount).ok_or(ErrorCode::MathOverflow)?;
+        position.total_sponsored = position.total_sponsored.checked_add(amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        position.bump = ctx.bumps.sponsor_position;
+
+        Ok(())
+    }
+
+    pub fn withdraw_sponsorship(ctx: Context<WithdrawSponsorship>, amount: u64) -> Result<()> {
+        let escrow = &mut ctx.accounts.escrow_account;
+        let position = &mut ctx.accounts.sponsor_position;
+
+        // Sponsored funds are spent last, so the refundable tranche is whatever is left of it
+        let refundable = position.amount.min(escrow.sponsored_balance);
+        require!(amount <= refundable, ErrorCode::InsufficientFunds);
+
+        // Seeds for signing
+        let agent_key = escrow.agent;
+        let bump = escrow.bump;
+        let seeds = &[
+            seeds::ESCROW,
+            agent_key.as_ref(),
+            &[bump],
+        ];
+        let signer = &[&seeds[..]];
+
+        let transfer_instruction = Transfer {
+            from: ctx.accounts.vault.to_account_info(),
+            to: ctx.accounts.sponsor_token_account.to_account_info(),
+            authority: escrow.to_account_info(),
+        };
+
+        let cpi_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            transfer_instruction,
+            signer,
+        );
+
+        token::transfer(cpi_ctx, amount)?;
+
+        escrow.balance -= amount;
+        escrow.sponsored_balance -= amount;
+        position.amount -= amount;
+
+        Ok(())
+    }
+
+    pub fn set_holdback_params(
+        ctx: Context<UpdateMarketConfig>,
+        holdback_bps: u16,
+        holdback_period: i64,
+    ) -> Result<()> {
+        require!(holdback_bps <= MAX_BPS, ErrorCode::InvalidHoldbackParams);
+        require!(holdback_period >= 0, ErrorCode::InvalidHoldbackParams);
+
+        let config = &mut ctx.accounts.market_config;
+        config.holdback_bps = holdback_bps;
+        config.holdback_period = holdback_period;
+        Ok(())
+    }
+
+    /// Settled volume per rolling hour above which settlements pause themselves; 0 = off.
+    /// Deposits and withdrawals are never paused.
+    pub fn set_velocity_ceiling(ctx: Context<UpdateMarketConfig>, velocity_ceiling: u64) -> Result<()> {
+        ctx.accounts.market_config.velocity_ceiling = velocity_ceiling;
+        Ok(())
+    }
+
+    /// Re-opens settlement after the circuit breaker tripped, clearing the volume it saw.
+    pub fn resume_settlements(ctx: Context<UpdateMarketConfig>) -> Result<()> {
+        let config = &mut ctx.accounts.market_config;
+        config.settlements_paused = false;
+        config.velocity_current = 0;
+        config.velocity_previous = 0;
+
+        emit!(SettlementsResumed { authority: ctx.accounts.admin.key() });
+        Ok(())
+    }
+
+    /// Schedules a zero-fee window for settlements in `[start, end)`, replacing any earlier
+    /// one. Pass `start == end` to cancel.
+    pub fn set_fee_holiday(ctx: Context<UpdateFeeParams>, start: i64, end: i64) -> Result<()> {
+        require!(start <= end, ErrorCode::InvalidFeeParams);
+        let config = &mut ctx.accounts.market_config;
+        config.fee_holiday_start = start;
+        config.fee_holiday_end = end;
+
+        emit!(FeeHolidayScheduled { start, end });
+        Ok(())
+    }
+
+    /// Seconds settlement fees stay pending before they become claimable; 0 = immediately.
+    /// Fees already pending restart under the new window.
+    pub fn set_fee_challenge_window(ctx: Context<SetFeeChallengeWindow>, fee_challenge_window: i64) -> Result<()> {
+        require!(fee_challenge_window >= 0, ErrorCode::InvalidFeeParams);
+        let now = Clock::get()?.unix_timestamp;
+        let config = &mut ctx.accounts.market_config;
+        let state = &mut ctx.accounts.fee_vault_state;
+        state.realize_fees(now, config.fee_challenge_window);
+        state.restart_pending(now, fee_challenge_window);
+        config.fee_challenge_window = fee_challenge_window;
+        Ok(())
+    }
+
+    /// Daily interest on holdbacks released after a clean review, paid from protocol fees.
+    pub fn set_holdback_interest(ctx: Context<UpdateMarketConfig>, interest_bps_per_day: u16) -> Result<()> {
+        require!(
+            interest_bps_per_day <= MAX_HOLDBACK_INTEREST_BPS_PER_DAY,
+            ErrorCode::InvalidHoldbackParams
+        );
+        ctx.accounts.market_config.holdback_interest_bps_per_day = interest_bps_per_day;
+        Ok(())
+    }
+
+    pub fn set_features(ctx: Context<UpdateMarketConfig>, features: u64) -> Result<()> {
+        require!(features & !MarketConfig::ALL_FEATURES == 0, ErrorCode::InvalidFeatures);
+        ctx.accounts.market_config.features = features;
+        Ok(())
+    }
+
+    pub fn set_swap_venue(ctx: Context<UpdateMarketConfig>, swap_program: Pubkey) -> Result<()> {
+        ctx.accounts.market_config.swap_program = swap_program;
+        Ok(())
+    }
+
+    /// Names the deployment whose escrow exports `import_escrow` will honour.
+    pub fn set_migration_source(ctx: Context<UpdateMarketConfig>, migration_source: Pubkey) -> Result<()> {
+        ctx.accounts.market_config.migration_source = migration_source;
+        Ok(())
+    }
+
+    /// Authorizes an automation thread's PDA (e.g. a Clockwork thread) to run scheduled
+    /// maintenance that otherwise needs the admin key, currently `expire_session`. The
+    /// other cranks (`process_queue`, `crank_refund_remainder`, `release_holdback`,
+    /// `refund_forward`, `roll_fee_epoch`, `mint_epoch_rewards`) take any signer already.
+    /// Pass the default pubkey to revoke.
+    pub fn set_automation_authority(ctx: Context<UpdateMarketConfig>, automation_authority: Pubkey) -> Result<()> {
+        ctx.accounts.market_config.automation_authority = automation_authority;
+        Ok(())
+    }
+
+    /// Splits agents into `shard_count` key ranges (0 = unsharded). Escrows keep the shard
+    /// they were assigned until `assign_escrow_shard` is run again.
+    pub fn set_shard_count(ctx: Context<UpdateMarketConfig>, shard_count: u8) -> Result<()> {
+        ctx.accounts.market_config.shard_count = shard_count;
+        Ok(())
+    }
+
+    /// Names the settlement worker that owns a shard. Replacing the signer hands the
+    /// shard's escrows over at once; the old worker can no longer settle them.
+    pub fn set_shard_signer(ctx: Context<SetShardSigner>, shard_id: u8, signer: Pubkey) -> Result<()> {
+        require!(shard_id < ctx.accounts.market_config.shard_count, ErrorCode::InvalidShard);
+        let shard = &mut ctx.accounts.shard;
+        shard.shard_id = shard_id;
+        shard.signer = signer;
+        shard.bump = ctx.bumps.shard;
+        Ok(())
+    }
+
+    /// Pins an escrow to the shard its agent key falls in under the current shard count.
+    /// Permissionless, since the result is deterministic; refused while sessions are open
+    /// so a session is always settled by the worker that opened it.
+    pub fn assign_escrow_shard(ctx: Context<AssignEscrowShard>) -> Result<()> {
+        let escrow = &mut ctx.accounts.escrow_account;
+        require!(escrow.open_sessions == 0, ErrorCode::SessionsOpen);
+        escrow.shard_id = ctx.accounts.market_config.shard_for(&escrow.agent);
+
+        emit!(EscrowShardAssigned {
+            escrow: escrow.key(),
+            shard_id: escrow.shard_id,
+        });
+        Ok(())
+    }
+
+    /// Seconds the router may go without a heartbeat before agents can fast-withdraw and
+    /// humans can settle attested work themselves. 0 turns liveness checks off. Also counts
+    /// as a heartbeat, so enabling the check never starts out stale.
+    pub fn set_heartbeat_timeout(ctx: Context<UpdateMarketConfig>, heartbeat_timeout: i64) -> Result<()> {
+        require!(heartbeat_timeout >= 0, ErrorCode::InvalidHeartbeatTimeout);
+        let config = &mut ctx.accounts.market_config;
+        config.heartbeat_timeout = heartbeat_timeout;
+        config.last_heartbeat = Clock::get()?.unix_timestamp;
+        Ok(())
+    }
+
+    /// Names a standby settlement authority that takes over unsharded escrows once the
+    /// router has been stale for `failover_delay` seconds past its heartbeat timeout.
+    /// Requires liveness checks to be on. Pass the default pubkey to remove it.
+    pub fn set_standby_authority(
+        ctx: Context<UpdateMarketConfig>,
+        standby_authority: Pubkey,
+        failover_delay: i64,
+    ) -> Result<()> {
+        require!(failover_delay >= 0, ErrorCode::InvalidHeartbeatTimeout);
+        let config = &mut ctx.accounts.market_config;
+        config.standby_authority = standby_authority;
+        config.failover_delay = failover_delay;
+        Ok(())
+    }
+
+    /// Liveness signal from the settlement authority, expected at least once per
+    /// `heartbeat_timeout`.
+    pub fn router_heartbeat(ctx: Context<RouterHeartbeat>) -> Result<()> {
+        ctx.accounts.market_config.last_heartbeat = Clock::get()?.unix_timestamp;
+        Ok(())
+    }
+
+    /// Hands fee parameters to a governance authority (e.g. the governance program's PDA).
+    /// Pass the default pubkey to return them to the admin.
+    pub fn set_governance(ctx: Context<UpdateMarketConfig>, governance: Pubkey) -> Result<()> {
+        ctx.accounts.market_config.governance = governance;
+        Ok(())
+    }
+
+    pub fn set_fee_basis_points(ctx: Context<UpdateFeeParams>, fee_basis_points: u16) -> Result<()> {
+        require!(fee_basis_points <= MAX_BPS, ErrorCode::InvalidFeeParams);
+        ctx.accounts.market_config.fee_basis_points = fee_basis_points;
+        Ok(())
+    }
+
+    pub fn set_builder_share_bps(ctx: Context<UpdateFeeParams>, builder_share_bps: u16) -> Result<()> {
+        let matcher_share_bps = ctx.accounts.market_config.matcher_share_bps;
+        require!(builder_share_bps <= MAX_BPS - matcher_share_bps, ErrorCode::InvalidFeeParams);
+        ctx.accounts.market_config.builder_share_bps = builder_share_bps;
+        Ok(())
+    }
+
+    /// Matcher's share of the protocol fee on sessions opened through it. Taken alongside
+    /// the builder share, so the two together can't exceed the whole fee.
+    pub fn set_matcher_share_bps(ctx: Context<UpdateFeeParams>, matcher_share_bps: u16) -> Result<()> {
+        let builder_share_bps = ctx.accounts.market_config.builder_share_bps;
+        require!(matcher_share_bps <= MAX_BPS - builder_share_bps, ErrorCode::InvalidFeeParams);
+        ctx.accounts.market_config.matcher_share_bps = matcher_share_bps;
+        Ok(())
+    }
+
+    /// Negotiated take rate for one escrow's settlements; `None` restores the market fee.
+    pub fn set_fee_bps_override(ctx: Context<SetFeeBpsOverride>, fee_bps_override: Option<u16>) -> Result<()> {
+        require!(fee_bps_override.unwrap_or_default() <= MAX_BPS, ErrorCode::InvalidFeeParams);
+        let escrow = &mut ctx.accounts.escrow_account;
+        escrow.fee_bps_override = fee_bps_override;
+
+        emit!(FeeOverrideSet {
+            escrow: escrow.key(),
+            fee_bps_override,
+        });
+        Ok(())
+    }
+
+    /// Bounds on the engagement multiplier the router may apply at checkpoint settlements
+    /// (e.g. 5_000-15_000 for 0.5x-1.5x). Sessions keep the bounds they opened with.
+    pub fn set_quality_multiplier_bounds(
+        ctx: Context<UpdateMarketConfig>,
+        min_bps: u16,
+        max_bps: u16,
+    ) -> Result<()> {
+        require!(
+            min_bps <= QUALITY_MULTIPLIER_NEUTRAL_BPS
+                && (QUALITY_MULTIPLIER_NEUTRAL_BPS..=MAX_QUALITY_MULTIPLIER_BPS).contains(&max_bps),
+            ErrorCode::InvalidQualityMultiplier
+        );
+        let config = &mut ctx.accounts.market_config;
+        config.quality_multiplier_min_bps = min_bps;
+        config.quality_multiplier_max_bps = max_bps;
+        Ok(())
+    }
+
+    /// Escrow balance ceilings indexed by KYC tier, applied to every deposit path. 0 leaves
+    /// a tier uncapped.
+    pub fn set_deposit_caps(ctx: Context<UpdateMarketConfig>, deposit_caps: [u64; KYC_TIERS]) -> Result<()> {
+        ctx.accounts.market_config.deposit_caps = deposit_caps;
+        Ok(())
+    }
+
+    /// Records the KYC tier the admin has verified the escrow's agent at. The escrow must
+    /// exist, so an agent's first deposit is always held to the unverified tier's cap.
+    pub fn set_kyc_tier(ctx: Context<SetKycTier>, kyc_tier: u8) -> Result<()> {
+        require!((kyc_tier as usize) < KYC_TIERS, ErrorCode::InvalidKycTier);
+        let escrow = &mut ctx.accounts.escrow_account;
+        escrow.kyc_tier = kyc_tier;
+
+        emit!(KycTierSet {
+            escrow: escrow.key(),
+            agent: escrow.agent,
+            kyc_tier,
+        });
+        Ok(())
+    }
+
+    /// Human payout split legs, if any, are passed in `remaining_accounts` in leg order.
+    /// Org fees only apply to the immediate payout at settlement; releases are not charged.
+    /// Interest for the review period is added from protocol fees, as far as they cover it.
+    pub fn release_holdback<'info>(ctx: Context<'_, '_, 'info, 'info, ReleaseHoldback<'info>>) -> Result<()> {
+        let holdback = &mut ctx.accounts.holdback;
+        let amount = holdback.amount;
+
+        require!(amount > 0, ErrorCode::NothingToClaim);
+        require!(!holdback.flagged, ErrorCode::HoldbackFlagged);
+        require!(Clock::get()?.unix_timestamp >= holdback.release_at, ErrorCode::HoldbackLocked);
+
+        // Cranks only release once the human's auto-claim threshold is met
+        let prefs = load_payout_prefs(&ctx.accounts.payout_prefs)?;
+        if ctx.accounts.caller.key() != holdback.human {
+            if let Some(prefs) = &prefs {
+                require!(amount >= prefs.auto_claim_threshold, ErrorCode::BelowAutoClaimThreshold);
+            }
+        }
+
+        // Seeds for Fee Vault Authority
+        let bump = ctx.accounts.fee_vault_state.bump;
+        let seeds = &[
+            seeds::FEE_VAULT_STATE,
+            &[bump],
+        ];
+        let signer = &[&seeds[..]];
+
+        let transfer = Transfer {
+            from: ctx.accounts.fee_vault.to_account_info(),
+            to: ctx.accounts.human_token_account.to_account_info(),
+            authority: ctx.accounts.fee_vault_state.to_account_info(),
+        };
+
+        let cpi_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            transfer,
+            signer,
+        );
+
+        let config = &ctx.accounts.market_config;
+        let interest = holdback_interest(amount, config.holdback_interest_bps_per_day, config.holdback_period)?
+            .min(ctx.accounts.fee_vault_state.protocol_balance);
+        ctx.accounts.fee_vault_state.protocol_balance -= interest;
+
+        let payout_split = load_payout_split(&ctx.accounts.payout_split)?;
+        pay_human(cpi_ctx, payout_split.as_ref(), ctx.remaining_accounts, amount + interest)?;
+
+        holdback.amount = 0;
+        if interest > 0 {
+            emit!(HoldbackInterestPaid { human: holdback.human, principal: amount, interest });
+        }
+        if let Some(tax_lot) = prefs.as_ref().and_then(PayoutPrefs::tax_lot) {
+            emit!(PayoutAnnotated {
+                human: holdback.human,
+                escrow: Pubkey::default(),
+                nonce: 0,
+                amount: amount + interest,
+                jurisdiction: tax_lot.jurisdiction,
+                memo_hash: tax_lot.memo_hash,
+            });
+        }
+        Ok(())
+    }
+
+    pub fn flag_holdback(ctx: Context<FlagHoldback>, flagged: bool) -> Result<()> {
+        ctx.accounts.holdback.flagged = flagged;
+        Ok(())
+    }
+
+    pub fn slash_holdback(ctx: Context<FlagHoldback>) -> Result<()> {
+        let holdback = &mut ctx.accounts.holdback;
+        require!(holdback.flagged, ErrorCode::HoldbackNotFlagged);
+
+        // Fraud recovery: the held funds stay in the fee vault and accrue to the protocol
+        let amount = holdback.amount;
+        holdback.amount = 0;
+        ctx.accounts.fee_vault_state.protocol_balance += amount;
+        Ok(())
+    }
+
+    pub fn add_attestation_key(ctx: Context<AddAttestationKey>, key: Pubkey) -> Result<()> {
+        let attestation_key = &mut ctx.accounts.attestation_key;
+        attestation_key.key = key;
+        // New keys only become valid after a delay, so a rogue add can be caught and revoked
+        attestation_key.active_at = Clock::get()?.unix_timestamp + ATTESTATION_KEY_DELAY;
+        attestation_key.revoked = false;
+        attestation_key.bump = ctx.bumps.attestation_key;
+
+        // The first live key switches on mandatory attestations once it activates
+        let config = &mut ctx.accounts.market_config;
+        if config.live_attestation_keys == 0 {
+            config.attestations_required_at = attestation_key.active_at;
+        }
+        config.live_attestation_keys = config.live_attestation_keys.checked_add(1).ok_or(ErrorCode::MathOverflow)?;
+        Ok(())
+    }
+
+    pub fn revoke_attestation_key(ctx: Context<RevokeAttestationKey>) -> Result<()> {
+        // Revocation is immediate
+        ctx.accounts.attestation_key.revoked = true;
+        let config = &mut ctx.accounts.market_config;
+        config.live_attestation_keys = config.live_attestation_keys.saturating_sub(1);
+        if config.live_attestation_keys == 0 {
+            config.attestations_required_at = 0;
+        }
+        Ok(())
+    }
+
+    /// Whitelists a program whose accounts can attest humans against task policies
+    /// (see `parse_issuer_attestation` for the layout it must write).
+    pub fn add_attestation_issuer(ctx: Context<AddAttestationIssuer>, program: Pubkey) -> Result<()> {
+        let issuer = &mut ctx.accounts.attestation_issuer;
+        issuer.program = program;
+        issuer.revoked = false;
+        issuer.bump = ctx.bumps.attestation_issuer;
+        Ok(())
+    }
+
+    pub fn revoke_attestation_issuer(ctx: Context<RevokeAttestationIssuer>) -> Result<()> {
+        ctx.accounts.attestation_issuer.revoked = true;
+        Ok(())
+    }
+
+    /// Settles several agents' escrows to one human. Each entry's escrow, vault and the
+    /// session its seconds are billed against are passed in `remaining_accounts` as
+    /// (escrow_account, vault, session) triples, followed by the human's payout split legs
+    /// if any. Every escrow pays its gross amount into the fee vault and the human receives
+    /// one combined transfer. Attestations, when given or required, are one Ed25519
+    /// instruction per entry, in order, right before this one.
+    /// Builder splits are not supported on this path.
+    pub fn close_settlement_batch<'info>(
+        ctx: Context<'_, '_, 'info, 'info, CloseSettlementBatch<'info>>,
+        entries: Vec<BatchSettlementEntry>,
+    ) -> Result<()> {
+        require!(
+            !entries.is_empty() && entries.len() <= MAX_BATCH_SETTLEMENTS,
+            ErrorCode::InvalidBatch
+        );
+        require!(
+            ctx.remaining_accounts.len() >= entries.len() * BATCH_ENTRY_ACCOUNTS,
+            ErrorCode::InvalidBatch
+        );
+        let (triples, split_legs) = ctx.remaining_accounts.split_at(entries.len() * BATCH_ENTRY_ACCOUNTS);
+
+        let mint = ctx.accounts.mint.key();
+        let prefs = load_payout_prefs(&ctx.accounts.payout_prefs)?;
+        if let Some(prefs) = &prefs {
+            prefs.check_mint(&mint)?;
+        }
+        let now = Clock::get()?.unix_timestamp;
+        let mut fee_total: u64 = 0;
+        let mut net_total: u64 = 0;
+
+        for (i, (entry, triple)) in entries.iter().zip(triples.chunks(BATCH_ENTRY_ACCOUNTS)).enumerate() {
+            let escrow_info = &triple[0];
+            let vault_info = &triple[1];
+
+            // Entry i's attestation sits (entries - i) instructions back, so they run in entry order
+            let message = settlement_attestation_message(
+                &escrow_info.key(),
+                &ctx.accounts.human.key(),
+                entry.verified_seconds,
+                entry.agreed_price_per_second,
+                entry.nonce,
+                QUALITY_MULTIPLIER_NEUTRAL_BPS,
+            );
+            check_settlement_attestation(
+                &ctx.accounts.market_config,
+                ctx.accounts.attestation_key.as_deref(),
+                ctx.accounts.instructions_sysvar.as_deref(),
+                entries.len() - i,
+                &message,
+                now,
+            )?;
+
+            let mut escrow = Account::<EscrowAccount>::try_from(escrow_info)?;
+            let expected_escrow = Pubkey::create_program_address(
+                &[seeds::ESCROW, escrow.agent.as_ref(), &[escrow.bump]],
+                ctx.program_id,
+            ).map_err(|_| ErrorCode::InvalidBatch)?;
+            require_keys_eq!(escrow_info.key(), expected_escrow, ErrorCode::InvalidBatch);
+
+            let vault = Account::<TokenAccount>::try_from(vault_info)?;
+            require_keys_eq!(vault.owner, escrow_info.key(), ErrorCode::InvalidVault);
+            require_keys_eq!(vault.mint, mint, ErrorCode::InvalidMint);
+            // Batches settle in the primary mint only
+            require_keys_eq!(escrow.mint, Pubkey::default(), ErrorCode::InvalidMint);
+            // Batch entries can't reference payment intents
+            require!(!escrow.intents_required, ErrorCode::PaymentIntentRequired);
+            require_keys_eq!(
+                ctx.accounts.router.key(),
+                escrow.settlement_router(&ctx.accounts.market_config, ctx.accounts.shard.as_deref(), now),
+                ErrorCode::Unauthorized
+            );
+
+            if let Some(prefs) = &prefs {
+                prefs.check_price(entry.agreed_price_per_second)?;
+            }
+
+            // Seconds are billed against the session under the same caps as close_settlement
+            let mut session = Account::<Session>::try_from(&triple[2])?;
+            require_keys_eq!(session.escrow, escrow_info.key(), ErrorCode::SessionMismatch);
+            require_keys_eq!(session.human, ctx.accounts.human.key(), ErrorCode::SessionMismatch);
+            session.check_price(entry.agreed_price_per_second)?;
+            session.bill_seconds(entry.verified_seconds, now)?;
+            session.exit(ctx.program_id)?;
+
+            // Validate nonce to prevent replay attacks
+            require!(entry.nonce > escrow.settlement_nonce, ErrorCode::NonceAlreadyUsed);
+            escrow.settlement_nonce = entry.nonce;
+
+            let total_payout = entry.verified_seconds.checked_mul(entry.agreed_price_per_second)
+                .ok_or(ErrorCode::MathOverflow)?;
+            require!(escrow.balance >= total_payout, ErrorCode::InsufficientFunds);
+
+            escrow.balance -= total_payout;
+            escrow.sponsored_balance = escrow.sponsored_balance.min(escrow.balance);
+
+            let fee_amount = bps_of(total_payout, escrow.fee_bps(&ctx.accounts.market_config, now))?;
+            escrow.record_settlement(total_payout, fee_amount);
+            if let Some(fee_bps) = escrow.fee_bps_override {
+                emit!(FeeOverrideApplied { escrow: escrow_info.key(), fee_bps, fee_amount });
+            }
+            fee_total = fee_total.checked_add(fee_amount).ok_or(ErrorCode::MathOverflow)?;
+            net_total = net_total.checked_add(total_payout - fee_amount).ok_or(ErrorCode::MathOverflow)?;
+
+            if total_payout > 0 {
+                let agent_key = escrow.agent;
+                let escrow_bump = escrow.bump;
+                let escrow_seeds = &[
+                    seeds::ESCROW,
+                    agent_key.as_ref(),
+                    &[escrow_bump],
+                ];
+                let escrow_signer = &[&escrow_seeds[..]];
+
+                let transfer_to_fee_vault = Transfer {
+                    from: vault_info.clone(),
+                    to: ctx.accounts.fee_vault.to_account_info(),
+                    authority: escrow_info.clone(),
+                };
+                let cpi_ctx = CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    transfer_to_fee_vault,
+                    escrow_signer,
+                );
+                token::transfer(cpi_ctx, total_payout)?;
+            }
+
+            // Holdback is taken from the combined payout, so it isn't attributed per escrow
+            emit!(SettlementClosed {
+                escrow: escrow_info.key(),
+                human: ctx.accounts.human.key(),
+                nonce: entry.nonce,
+                amount: total_payout,
+                fee_amount,
+                holdback_amount: 0,
+                escrow_balance: escrow.balance,
+                escrow_locked: escrow.locked(),
+                quality_multiplier_bps: QUALITY_MULTIPLIER_NEUTRAL_BPS,
+            });
+            escrow.exit(ctx.program_id)?;
+        }
+
+        // Hold back a slice of the human's payout for quality review
+        let holdback_bps = ctx.accounts.market_config.effective_holdback_bps();
+        let holdback_amount = bps_of(net_total, holdback_bps)?;
+        let user_payout = net_total - holdback_amount;
+
+        // Single transfer of the combined net payout to the human (one per split leg)
+        let bump = ctx.accounts.fee_vault_state.bump;
+        let seeds = &[
+            seeds::FEE_VAULT_STATE,
+            &[bump],
+        ];
+        let signer = &[&seeds[..]];
+
+        let transfer_to_user = Transfer {
+            from: ctx.accounts.fee_vault.to_account_info(),
+            to: ctx.accounts.user_wallet.to_account_info(),
+            authority: ctx.accounts.fee_vault_state.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            transfer_to_user,
+            signer,
+        );
+        let payout_split = load_payout_split(&ctx.accounts.payout_split)?;
+        let user_payout = take_org_fee(
+            &cpi_ctx,
+            &ctx.accounts.org_member,
+            ctx.accounts.org.as_deref_mut(),
+            ctx.accounts.org_treasury.as_deref(),
+            user_payout,
+        )?;
+        pay_human(cpi_ctx, payout_split.as_ref(), split_legs, user_payout)?;
+
+        if holdback_amount > 0 {
+            let release_at = Clock::get()?.unix_timestamp
+                .saturating_add(ctx.accounts.market_config.holdback_period);
+            let holdback = ctx.accounts.holdback.as_mut().ok_or(ErrorCode::HoldbackAccountMissing)?;
+            holdback.credit(ctx.accounts.human.key(), holdback_amount, release_at)?;
+            holdback.bump = ctx.bumps.holdback.ok_or(ErrorCode::HoldbackAccountMissing)?;
+        }
+
+        // No builder on the batch path -> Protocol takes all
+        let state = &mut ctx.accounts.fee_vault_state;
+        state.credit_settlement_fees(fee_total, now, ctx.accounts.market_config.fee_challenge_window);
+        state.total_collected += fee_total;
+        ctx.accounts.market_config.record_settlement_volume(fee_total.saturating_add(net_total), now);
+
+        if let (Some(stats), Some(bump)) = (&mut ctx.accounts.relayer_stats, ctx.bumps.relayer_stats) {
+            stats.record(ctx.accounts.router.key(), bump);
+        }
+
+        Ok(())
+    }
+
+    /// Creates the settlement queue. `tip_lamports` is escrowed by the router with every
+    /// enqueued item and paid, with the item's rent, to whoever cranks it.
+    pub fn initialize_settlement_queue(ctx: Context<InitializeSettlementQueue>, tip_lamports: u64) -> Result<()> {
+        let queue = &mut ctx.accounts.queue;
+        queue.head = 0;
+        queue.tail = 0;
+        queue.tip_lamports = tip_lamports;
+        queue.bump = ctx.bumps.queue;
+        Ok(())
+    }
+
+    /// Records a settlement intent at the back of the queue instead of executing it. The
+    /// seconds are billed against the session now, under the same caps as
+    /// `close_settlement`, and the attestation is checked now as well. The human's payout ATA
+    /// and holdback account are created here so the crank never has to.
+    pub fn enqueue_settlement(
+        ctx: Context<EnqueueSettlement>,
+        verified_seconds: u64,
+        agreed_price_per_second: u64,
+        nonce: u64,
+    ) -> Result<()> {
+        let escrow = &ctx.accounts.escrow_account;
+        require!(nonce > escrow.settlement_nonce, ErrorCode::NonceAlreadyUsed);
+        let amount = verified_seconds.checked_mul(agreed_price_per_second).ok_or(ErrorCode::MathOverflow)?;
+        if let Some(prefs) = load_payout_prefs(&ctx.accounts.payout_prefs)? {
+            prefs.check_price(agreed_price_per_second)?;
+        }
+        // The crank pays a single wallet per item
+        require!(load_payout_split(&ctx.accounts.payout_split)?.is_none(), ErrorCode::QueuePayoutUnsupported);
+        require!(load_org_member(&ctx.accounts.org_member)?.is_none(), ErrorCode::QueuePayoutUnsupported);
+        // Queued items can't reference payment intents
+        require!(!escrow.intents_required, ErrorCode::PaymentIntentRequired);
+
+        // Attested at enqueue, so the permissionless crank needs no signature
+        let message = settlement_attestation_message(
+            &escrow.key(),
+            &ctx.accounts.human.key(),
+            verified_seconds,
+            agreed_price_per_second,
+            nonce,
+            QUALITY_MULTIPLIER_NEUTRAL_BPS,
+        );
+        check_settlement_attestation(
+            &ctx.accounts.market_config,
+            ctx.accounts.attestation_key.as_deref(),
+            ctx.accounts.instructions_sysvar.as_deref(),
+            1,
+            &message,
+            Clock::get()?.unix_timestamp,
+        )?;
+
+        let session = &mut ctx.accounts.session;
+        session.check_price(agreed_price_per_second)?;
+        session.bill_seconds(verified_seconds, Clock::get()?.unix_timestamp)?;
+
+        let holdback = &mut ctx.accounts.holdback;
+        if holdback.human == Pubkey::default() {
+            holdback.human = ctx.accounts.human.key();
+            holdback.bump = ctx.bumps.holdback;
+        }
+
+        let queue = &mut ctx.accounts.queue;
+        let item = &mut ctx.accounts.item;
+        item.seq = queue.tail;
+        item.escrow = escrow.key();
+        item.human = ctx.accounts.human.key();
+        item.amount = amount;
+        item.nonce = nonce;
+        item.enqueued_at = Clock::get()?.unix_timestamp;
+        item.bump = ctx.bumps.item;
+        queue.tail += 1;
+
+        if queue.tip_lamports > 0 {
+            let cpi_ctx = CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                system_program::Transfer {
+                    from: ctx.accounts.router.to_account_info(),
+                    to: ctx.accounts.item.to_account_info(),
+                },
+            );
+            system_program::transfer(cpi_ctx, queue.tip_lamports)?;
+        }
+
+        emit!(SettlementEnqueued {
+            seq: ctx.accounts.item.seq,
+            escrow: ctx.accounts.item.escrow,
+            human: ctx.accounts.item.human,
+            amount,
+        });
+        Ok(())
+    }
+
+    /// Permissionless crank: executes the next `count` queued settlements in FIFO order.
+    /// Each item's accounts are passed in `remaining_accounts` as (item, escrow_account,
+    /// vault, user_wallet, holdback). Items whose nonce went stale or whose escrow can no
+    /// longer cover them are dropped rather than blocking the queue. Every processed item
+    /// is closed to the cranker, paying out its rent and tip.
+    /// Builder splits are not supported on this path. Sessions were billed, and
+    /// attestations checked, when the item was enqueued.
+    pub fn process_queue<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ProcessQueue<'info>>,
+        count: u8,
+    ) -> Result<()> {
+        require!(count > 0 && count <= MAX_QUEUE_CRANK, ErrorCode::InvalidBatch);
+        require!(
+            ctx.remaining_accounts.len() == count as usize * QUEUE_ITEM_ACCOUNTS,
+            ErrorCode::InvalidBatch
+        );
+
+        let mint = ctx.accounts.fee_vault.mint;
+        let now = Clock::get()?.unix_timestamp;
+        let holdback_bps = ctx.accounts.market_config.effective_holdback_bps();
+        let release_at = now.saturating_add(ctx.accounts.market_config.holdback_period);
+        let fee_vault_bump = ctx.accounts.fee_vault_state.bump;
+        let fee_vault_seeds = &[seeds::FEE_VAULT_STATE, &[fee_vault_bump]];
+        let fee_vault_signer = &[&fee_vault_seeds[..]];
+        let mut fee_total: u64 = 0;
+        let mut volume: u64 = 0;
+
+        for group in ctx.remaining_accounts.chunks(QUEUE_ITEM_ACCOUNTS) {
+            let item = Account::<QueuedSettlement>::try_from(&group[0])?;
+            require!(item.seq == ctx.accounts.queue.head, ErrorCode::InvalidQueueItem);
+            require_keys_eq!(group[1].key(), item.escrow, ErrorCode::InvalidQueueItem);
+
+            let mut escrow = Account::<EscrowAccount>::try_from(&group[1])?;
+            let vault = Account::<TokenAccount>::try_from(&group[2])?;
+            require_keys_eq!(vault.owner, item.escrow, ErrorCode::InvalidVault);
+            require_keys_eq!(vault.mint, mint, ErrorCode::InvalidMint);
+            let user_wallet = Account::<TokenAccount>::try_from(&group[3])?;
+            require_keys_eq!(user_wallet.owner, item.human, ErrorCode::InvalidTokenAccount);
+            require_keys_eq!(user_wallet.mint, mint, ErrorCode::InvalidMint);
+            let mut holdback = Account::<Holdback>::try_from(&group[4])?;
+            let expected_holdback = Pubkey::create_program_address(
+                &[seeds::HOLDBACK, item.human.as_ref(), &[holdback.bump]],
+                ctx.program_id,
+            ).map_err(|_| ErrorCode::InvalidQueueItem)?;
+            require_keys_eq!(group[4].key(), expected_holdback, ErrorCode::InvalidQueueItem);
+
+            let settled = item.nonce > escrow.settlement_nonce && escrow.balance >= item.amount;
+            let mut split = None;
+            if settled {
+                escrow.settlement_nonce = item.nonce;
+                escrow.balance -= item.amount;
+                escrow.sponsored_balance = escrow.sponsored_balance.min(escrow.balance);
+
+                let fee_bps = escrow.fee_bps(&ctx.accounts.market_config, now);
+                let settlement = split_settlement(item.amount, fee_bps, holdback_bps, None)?;
+                escrow.record_settlement(item.amount, settlement.fee_amount);
+                volume = volume.saturating_add(item.amount);
+                if let Some(fee_bps) = escrow.fee_bps_override {
+                    emit!(FeeOverrideApplied { escrow: item.escrow, fee_bps, fee_amount: settlement.fee_amount });
+                }
+
+                // Gross into the fee vault, then the human's share back out
+                transfer_from_escrow_vault(
+                    &escrow,
+                    &vault,
+                    &ctx.accounts.fee_vault,
+                    &ctx.accounts.token_program,
+                    item.amount,
+                )?;
+                if settlement.user_payout > 0 {
+                    let cpi_ctx = CpiContext::new_with_signer(
+                        ctx.accounts.token_program.to_account_info(),
+                        Transfer {
+                            from: ctx.accounts.fee_vault.to_account_info(),
+                            to: user_wallet.to_account_info(),
+                            authority: ctx.accounts.fee_vault_state.to_account_info(),
+                        },
+                        fee_vault_signer,
+                    );
+                    token::transfer(cpi_ctx, settlement.user_payout)?;
+                }
+                if settlement.holdback_amount > 0 {
+                    holdback.credit(item.human, settlement.holdback_amount, release_at)?;
+                    holdback.exit(ctx.program_id)?;
+                }
+                fee_total = fee_total.checked_add(settlement.fee_amount).ok_or(ErrorCode::MathOverflow)?;
+                escrow.exit(ctx.program_id)?;
+                split = Some(settlement);
+            }
+
+            emit!(QueuedSettlementProcessed {
+                seq: item.seq,
+                escrow: item.escrow,
+                human: item.human,
+                settled,
+                fee_amount: split.map_or(0, |s| s.fee_amount),
+                user_payout: split.map_or(0, |s| s.user_payout),
+                escrow_balance: escrow.balance,
+                escrow_locked: escrow.locked(),
+            });
+            item.close(ctx.accounts.cranker.to_account_info())?;
+            ctx.accounts.queue.head += 1;
+        }
+
+        // No builder on the queue path -> Protocol takes all
+        let state = &mut ctx.accounts.fee_vault_state;
+        let window = ctx.accounts.market_config.fee_challenge_window;
+        state.credit_settlement_fees(fee_total, now, window);
+        state.total_collected += fee_total;
+        ctx.accounts.market_config.record_settlement_volume(volume, now);
+        Ok(())
+    }
+
+    pub fn init_builder_stats(ctx: Context<InitBuilderStats>) -> Result<()> {
+        let stats = &mut ctx.accounts.builder_stats;
+        stats.builder_code = ctx.accounts.builder_balance.builder_code;
+        stats.latest_month = month_index(Clock::get()?.unix_timestamp);
+        stats.bump = ctx.bumps.builder_stats;
+        Ok(())
+    }
+
+    pub fn create_task(ctx: Context<CreateTask>, task_id: u64, amount: u64, deadline: i64) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        // A deadline of 0 means the task never expires
+        require!(deadline == 0 || deadline > now, ErrorCode::InvalidDeadline);
+
+        let escrow = &mut ctx.accounts.escrow_account;
+        require!(escrow.withdrawable() >= amount, ErrorCode::InsufficientFunds);
+        escrow.balance -= amount;
+
+        let task = &mut ctx.accounts.task;
+        task.escrow = escrow.key();
+        task.agent = escrow.agent;
+        task.task_id = task_id;
+        task.balance = amount;
+        task.deadline = deadline;
+        task.bump = ctx.bumps.task;
+        task.refund_to = Pubkey::default();
+        Ok(())
+    }
+
+    /// Pins where the task's remainder is refunded (e.g. a treasury token account), for both
+    /// `refund_remainder` and the permissionless crank. Omit the account to go back to
+    /// refunding the agent's own token accounts.
+    pub fn set_task_refund_address(ctx: Context<SetTaskRefundAddress>) -> Result<()> {
+        ctx.accounts.task.refund_to = ctx.accounts.refund_account.as_ref().map(|a| a.key()).unwrap_or_default();
+        Ok(())
+    }
+
+    /// Requires humans to hold an NFT from a verified Metaplex `collection` before the router
+    /// can open a session for this task. The router passes the gate to `open_session` for the
+    /// task's sessions; `clear_task_gate` lifts it again.
+    pub fn set_task_gate(ctx: Context<SetTaskGate>, collection: Pubkey) -> Result<()> {
+        let gate = &mut ctx.accounts.task_gate;
+        gate.escrow = ctx.accounts.escrow_account.key();
+        gate.task = ctx.accounts.task.key();
+        gate.collection = collection;
+        gate.bump = ctx.bumps.task_gate;
+        Ok(())
+    }
+
+    pub fn clear_task_gate(_ctx: Context<ClearTaskGate>) -> Result<()> {
+        // Rent is returned to the agent via the `close` constraint
+        Ok(())
+    }
+
+    /// Restricts the task to humans attested against `policy_hash` (e.g. the hash of an
+    /// "18+, EU only" targeting policy) with an attestation of `attestation_type`. As with
+    /// NFT gates, the router passes the policy to `open_session` for the task's sessions.
+    pub fn set_task_policy(
+        ctx: Context<SetTaskPolicy>,
+        policy_hash: [u8; 32],
+        attestation_type: AttestationType,
+    ) -> Result<()> {
+        let policy = &mut ctx.accounts.task_policy;
+        policy.escrow = ctx.accounts.escrow_account.key();
+        policy.task = ctx.accounts.task.key();
+        policy.policy_hash = policy_hash;
+        policy.attestation_type = attestation_type;
+        policy.bump = ctx.bumps.task_policy;
+        Ok(())
+    }
+
+    pub fn clear_task_policy(_ctx: Context<ClearTaskPolicy>) -> Result<()> {
+        // Rent is returned to the agent via the `close` constraint
+        Ok(())
+    }
+
+    /// Commits `amount` of the task's reserve as a fixed bounty for `human`, paid on
+    /// router-attested completion rather than per second.
+    pub fn post_bounty(ctx: Context<PostBounty>, human: Pubkey, amount: u64) -> Result<()> {
+        require!(amount > 0, ErrorCode::InvalidAmount);
+        let task = &mut ctx.accounts.task;
+        require!(task.balance >= amount, ErrorCode::InsufficientFunds);
+        task.balance -= amount;
+
+        let bounty = &mut ctx.accounts.bounty;
+        bounty.escrow = task.escrow;
+        bounty.task = task.key();
+        bounty.human = human;
+        bounty.amount = amount;
+        bounty.paid = 0;
+        bounty.completed_bps = 0;
+        bounty.completion_hash = [0; 32];
+        bounty.bump = ctx.bumps.bounty;
+        Ok(())
+    }
+
+    /// Router attests the bounty is `completed_bps` done (cumulative, in basis points) with
+    /// the hash of the completion evidence. Pays the newly completed share through the
+    /// usual settlement fee path; 10_000 pays out whatever is left. Like `close_settlement`,
+    /// the human's org fee applies and their payout split legs are passed in `remaining_accounts`.
+    pub fn complete_bounty<'info>(
+        ctx: Context<'_, '_, 'info, 'info, CompleteBounty<'info>>,
+        completed_bps: u16,
+        completion_hash: [u8; 32],
+    ) -> Result<()> {
+        let bounty = &mut ctx.accounts.bounty;
+        require!(
+            completed_bps > bounty.completed_bps && completed_bps as u64 <= BPS_DENOMINATOR,
+            ErrorCode::InvalidBountyProgress
+        );
+        if let Some(prefs) = load_payout_prefs(&ctx.accounts.payout_prefs)? {
+            prefs.check_mint(&ctx.accounts.mint.key())?;
+        }
+
+        let earned = if completed_bps as u64 == BPS_DENOMINATOR {
+            bounty.amount
+        } else {
+            (bounty.amount as u128 * completed_bps as u128 / BPS_DENOMINATOR as u128) as u64
+        };
+        let amount = earned - bounty.paid;
+        bounty.paid = earned;
+        bounty.completed_bps = completed_bps;
+        bounty.completion_hash = completion_hash;
+
+        let now = Clock::get()?.unix_timestamp;
+        let escrow = &mut ctx.accounts.escrow_account;
+        let fee_bps = escrow.fee_bps(&ctx.accounts.market_config, now);
+        let holdback_bps = ctx.accounts.market_config.effective_holdback_bps();
+        let SettlementSplit { fee_amount, holdback_amount, user_payout, .. } =
+            split_settlement(amount, fee_bps, holdback_bps, None)?;
+        escrow.record_settlement(amount, fee_amount);
+        ctx.accounts.market_config.record_settlement_volume(amount, now);
+
+        // Net payout (less holdback and org fee) to the human, across their split legs
+        let agent_key = ctx.accounts.escrow_account.agent;
+        let escrow_seeds = &[seeds::ESCROW, agent_key.as_ref(), &[ctx.accounts.escrow_account.bump]];
+        let escrow_signer = &[&escrow_seeds[..]];
+        let cpi_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.vault.to_account_info(),
+                to: ctx.accounts.user_wallet.to_account_info(),
+                authority: ctx.accounts.escrow_account.to_account_info(),
+            },
+            escrow_signer,
+        );
+        let payout_split = load_payout_split(&ctx.accounts.payout_split)?;
+        let user_payout = take_org_fee(
+            &cpi_ctx,
+            &ctx.accounts.org_member,
+            ctx.accounts.org.as_deref_mut(),
+            ctx.accounts.org_treasury.as_deref(),
+            user_payout,
+        )?;
+        pay_human(cpi_ctx, payout_split.as_ref(), ctx.remaining_accounts, user_payout)?;
+        // Fees and holdback both wait in the fee vault
+        transfer_from_escrow_vault(
+            &ctx.accounts.escrow_account,
+            &ctx.accounts.vault,
+            &ctx.accounts.fee_vault,
+            &ctx.accounts.token_program,
+            fee_amount + holdback_amount,
+        )?;
+
+        if holdback_amount > 0 {
+            let release_at = now.saturating_add(ctx.accounts.market_config.holdback_period);
+            let holdback = ctx.accounts.holdback.as_mut().ok_or(ErrorCode::HoldbackAccountMissing)?;
+            holdback.credit(ctx.accounts.human.key(), holdback_amount, release_at)?;
+            holdback.bump = ctx.bumps.holdback.ok_or(ErrorCode::HoldbackAccountMissing)?;
+        }
+
+        let state = &mut ctx.accounts.fee_vault_state;
+        state.credit_settlement_fees(fee_amount, now, ctx.accounts.market_config.fee_challenge_window);
+        state.total_collected += fee_amount;
+
+        emit!(BountyPaid {
+            bounty: ctx.accounts.bounty.key(),
+            human: ctx.accounts.bounty.human,
+            completed_bps,
+            amount,
+            fee: fee_amount,
+            completion_hash,
+        });
+        Ok(())
+    }
+
+    /// Splits a task into up to MAX_TASK_MILESTONES payouts for `human`, funded out of the
+    /// task's reserve. The router releases milestones one at a time.
+    pub fn set_task_milestones(ctx: Context<SetTaskMilestones>, human: Pubkey, milestones: Vec<Milestone>) -> Result<()> {
+        require!(
+            !milestones.is_empty() && milestones.len() <= MAX_TASK_MILESTONES,
+            ErrorCode::InvalidMilestones
+        );
+        let mut total: u64 = 0;
+        for milestone in &milestones {
+            require!(milestone.amount > 0, ErrorCode::InvalidMilestones);
+            total = total.checked_add(milestone.amount).ok_or(ErrorCode::MathOverflow)?;
+        }
+        let task = &mut ctx.accounts.task;
+        require!(task.balance >= total, ErrorCode::InsufficientFunds);
+        task.balance -= total;
+
+        let plan = &mut ctx.accounts.task_milestones;
+        plan.escrow = task.escrow;
+        plan.task = task.key();
+        plan.human = human;
+        plan.milestones = milestones;
+        plan.released_mask = 0;
+        plan.cancelled_mask = 0;
+        plan.bump = ctx.bumps.task_milestones;
+        Ok(())
+    }
+
+    /// Router releases milestone `index` to the human, through the usual settlement fee path.
+    /// The human's org fee applies and their payout split legs are passed in `remaining_accounts`.
+    pub fn release_milestone<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ReleaseMilestone<'info>>,
+        index: u8,
+    ) -> Result<()> {
+        let plan = &mut ctx.accounts.task_milestones;
+        let amount = plan.open_amount(index)?;
+        plan.released_mask |= 1 << index;
+        if let Some(prefs) = load_payout_prefs(&ctx.accounts.payout_prefs)? {
+            prefs.check_mint(&ctx.accounts.mint.key())?;
+        }
+
+        let now = Clock::get()?.unix_timestamp;
+        let escrow = &mut ctx.accounts.escrow_account;
+        let fee_bps = escrow.fee_bps(&ctx.accounts.market_config, now);
+        let holdback_bps = ctx.accounts.market_config.effective_holdback_bps();
+        let SettlementSplit { fee_amount, holdback_amount, user_payout, .. } =
+            split_settlement(amount, fee_bps, holdback_bps, None)?;
+        escrow.record_settlement(amount, fee_amount);
+        ctx.accounts.market_config.record_settlement_volume(amount, now);
+
+        // Net payout (less holdback and org fee) to the human, across their split legs
+        let agent_key = ctx.accounts.escrow_account.agent;
+        let escrow_seeds = &[seeds::ESCROW, agent_key.as_ref(), &[ctx.accounts.escrow_account.bump]];
+        let escrow_signer = &[&escrow_seeds[..]];
+        let cpi_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.vault.to_account_info(),
+                to: ctx.accounts.user_wallet.to_account_info(),
+                authority: ctx.accounts.escrow_account.to_account_info(),
+            },
+            escrow_signer,
+        );
+        let payout_split = load_payout_split(&ctx.accounts.payout_split)?;
+        let user_payout = take_org_fee(
+            &cpi_ctx,
+            &ctx.accounts.org_member,
+            ctx.accounts.org.as_deref_mut(),
+            ctx.accounts.org_treasury.as_deref(),
+            user_payout,
+        )?;
+        pay_human(cpi_ctx, payout_split.as_ref(), ctx.remaining_accounts, user_payout)?;
+        // Fees and holdback both wait in the fee vault
+        transfer_from_escrow_vault(
+            &ctx.accounts.escrow_account,
+            &ctx.accounts.vault,
+            &ctx.accounts.fee_vault,
+            &ctx.accounts.token_program,
+            fee_amount + holdback_amount,
+        )?;
+
+        if holdback_amount > 0 {
+            let release_at = now.saturating_add(ctx.accounts.market_config.holdback_period);
+            let holdback = ctx.accounts.holdback.as_mut().ok_or(ErrorCode::HoldbackAccountMissing)?;
+            holdback.credit(ctx.accounts.human.key(), holdback_amount, release_at)?;
+            holdback.bump = ctx.bumps.holdback.ok_or(ErrorCode::HoldbackAccountMissing)?;
+        }
+
+        let state = &mut ctx.accounts.fee_vault_state;
+        state.credit_settlement_fees(fee_amount, now, ctx.accounts.market_config.fee_challenge_window);
+        state.total_collected += fee_amount;
+
+        emit!(MilestoneReleased {
+            task: ctx.accounts.task_milestones.task,
+            human: ctx.accounts.task_milestones.human,
+            index,
+            amount,
+            fee: fee_amount,
+        });
+        Ok(())
+    }
+
+    /// Agent cancels an unreleased milestone; its amount goes back to the free escrow balance.
+    pub fn cancel_milestone(ctx: Context<CancelMilestone>, index: u8) -> Result<()> {
+        let plan = &mut ctx.accounts.task_milestones;
+        let amount = plan.open_amount(index)?;
+        plan.cancelled_mask |= 1 << index;
+        let escrow = &mut ctx.accounts.escrow_account;
+        escrow.balance = escrow.balance.checked_add(amount).ok_or(ErrorCode::MathOverflow)?;
+        Ok(())
+    }
+
+    /// Closes a bounty, returning its unpaid amount to the task's reserve. Until the task's
+    /// deadline passes, only a fully paid bounty can be closed, so the human's claim holds.
+    pub fn cancel_bounty(ctx: Context<CancelBounty>) -> Result<()> {
+        let bounty = &ctx.accounts.bounty;
+        let task = &mut ctx.accounts.task;
+        let expired = task.deadline != 0 && Clock::get()?.unix_timestamp >= task.deadline;
+        require!(expired || bounty.paid == bounty.amount, ErrorCode::BountyActive);
+        task.balance = task.balance.checked_add(bounty.amount - bounty.paid).ok_or(ErrorCode::MathOverflow)?;
+        // Rent is returned to the agent via the `close` constraint
+        Ok(())
+    }
+
+    /// Offers `observer` (e.g. an analytics provider) a read-side role on the agent's
+    /// escrow. `tag` is an opaque label agreed off-chain, echoed in every observed event.
+    /// The role is inactive until the observer accepts it.
+    pub fn grant_observer(ctx: Context<GrantObserver>, tag: [u8; 32]) -> Result<()> {
+        let grant = &mut ctx.accounts.escrow_observer;
+        grant.escrow = ctx.accounts.escrow_account.key();
+        grant.observer = ctx.accounts.observer.key();
+        grant.tag = tag;
+        grant.accepted = false;
+        grant.bump = ctx.bumps.escrow_observer;
+        Ok(())
+    }
+
+    pub fn accept_observer(ctx: Context<AcceptObserver>) -> Result<()> {
+        ctx.accounts.escrow_observer.accepted = true;
+        Ok(())
+    }
+
+    pub fn revoke_observer(_ctx: Context<RevokeObserver>) -> Result<()> {
+        // Rent is returned to the agent via the `close` constraint
+        Ok(())
+    }
+
+    /// Human consents to licensing anonymized engagement data under `scope_hash` (hash of
+    /// the off-chain terms) until `expiry`. Settlements that pass the consent are counted
+    /// against it and emit ConsentedSettlement. Granting again renews a revoked consent.
+    pub fn grant_data_consent(ctx: Context<GrantDataConsent>, scope_hash: [u8; 32], expiry: i64) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        require!(expiry > now, ErrorCode::InvalidDeadline);
+        let consent = &mut ctx.accounts.data_consent;
+        consent.human = ctx.accounts.human.key();
+        consent.scope_hash = scope_hash;
+        consent.granted_at = now;
+        consent.expiry = expiry;
+        consent.revoked_at = 0;
+        consent.bump = ctx.bumps.data_consent;
+
+        emit!(DataConsentChanged {
+            human: consent.human,
+            scope_hash,
+            expiry,
+            revoked: false,
+        });
+        Ok(())
+    }
+
+    /// Revokes a data consent. The record stays so past settlements remain auditable.
+    pub fn revoke_data_consent(ctx: Context<RevokeDataConsent>) -> Result<()> {
+        let consent = &mut ctx.accounts.data_consent;
+        require!(consent.revoked_at == 0, ErrorCode::DataConsentInactive);
+        consent.revoked_at = Clock::get()?.unix_timestamp;
+
+        emit!(DataConsentChanged {
+            human: consent.human,
+            scope_hash: consent.scope_hash,
+            expiry: consent.expiry,
+            revoked: true,
+        });
+        Ok(())
+    }
+
+    /// Funds several existing tasks with one transfer into the escrow vault. The tasks are
+    /// passed in `remaining_accounts` in the same order as `amounts`; the deposit goes
+    /// straight to their reserves and never touches the free escrow balance, but still
+    /// counts toward the escrow's deposit cap.
+    pub fn fund_tasks_batch<'info>(
+        ctx: Context<'_, '_, 'info, 'info, FundTasksBatch<'info>>,
+        amounts: Vec<u64>,
+    ) -> Result<()> {
+        require!(!amounts.is_empty() && amounts.len() <= MAX_BATCH_TASKS, ErrorCode::InvalidBatch);
+        require!(ctx.remaining_accounts.len() == amounts.len(), ErrorCode::InvalidBatch);
+
+        let escrow_key = ctx.accounts.escrow_account.key();
+        let mut total: u64 = 0;
+        for (amount, task_info) in amounts.iter().zip(ctx.remaining_accounts.iter()) {
+            require!(task_info.is_writable, ErrorCode::InvalidBatch);
+            let mut task = Account::<Task>::try_from(task_info)?;
+            require_keys_eq!(task.escrow, escrow_key, ErrorCode::InvalidTask);
+            task.balance = task.balance.checked_add(*amount).ok_or(ErrorCode::MathOverflow)?;
+            total = total.checked_add(*amount).ok_or(ErrorCode::MathOverflow)?;
+            task.exit(ctx.program_id)?;
+
+            emit!(TaskFunded {
+                escrow: escrow_key,
+                task_id: task.task_id,
+                amount: *amount,
+                balance: task.balance,
+            });
+        }
+        require!(total > 0, ErrorCode::InvalidAmount);
+
+        let transfer_instruction = Transfer {
+            from: ctx.accounts.agent_token_account.to_account_info(),
+            to: ctx.accounts.vault.to_account_info(),
+            authority: ctx.accounts.agent.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            transfer_instruction,
+        );
+        token::transfer(cpi_ctx, total)?;
+
+        // Task reserves sit in the vault, so topping them up counts toward the KYC tier cap
+        ctx.accounts.vault.reload()?;
+        ctx.accounts.market_config.check_deposit_cap(&ctx.accounts.escrow_account, &ctx.accounts.vault)
+    }
+
+    /// Refunds the task's reserve and closes it. An open bounty on the task must be closed
+    /// with `cancel_bounty` first, since it returns its unpaid amount through the task.
+    pub fn refund_remainder(ctx: Context<RefundRemainder>) -> Result<()> {
+        require!(!account_exists(&ctx.accounts.bounty), ErrorCode::BountyActive);
+        let amount = ctx.accounts.task.balance;
+        transfer_from_escrow_vault(
+            &ctx.accounts.escrow_account,
+            &ctx.accounts.vault,
+            &ctx.accounts.agent_token_account,
+            &ctx.accounts.token_program,
+            amount,
+        )?;
+        ctx.accounts.task.balance = 0;
+        Ok(())
+    }
+
+    /// Permissionless refund of an expired task to the agent, paying the cranker a bounty.
+    /// Like `refund_remainder`, it waits until any bounty on the task has been closed.
+    pub fn crank_refund_remainder(ctx: Context<CrankRefundRemainder>) -> Result<()> {
+        require!(!account_exists(&ctx.accounts.bounty), ErrorCode::BountyActive);
+        let task = &ctx.accounts.task;
+        require!(task.deadline != 0, ErrorCode::TaskNotExpired);
+        let now = Clock::get()?.unix_timestamp;
+        require!(now >= task.deadline.saturating_add(TASK_REFUND_GRACE), ErrorCode::TaskNotExpired);
+
+        let bounty = bps_of(task.balance, CRANK_BOUNTY_BPS)?;
+        let refund = task.balance - bounty;
+
+        transfer_from_escrow_vault(
+            &ctx.accounts.escrow_account,
+            &ctx.accounts.vault,
+            &ctx.accounts.agent_token_account,
+            &ctx.accounts.token_program,
+            refund,
+        )?;
+        transfer_from_escrow_vault(
+            &ctx.accounts.escrow_account,
+            &ctx.accounts.vault,
+            &ctx.accounts.cranker_token_account,
+            &ctx.accounts.token_program,
+            bounty,
+        )?;
+        ctx.accounts.task.balance = 0;
+        Ok(())
+    }
+
+    /// Last-resort path for escrows the agent can no longer operate. Starts a long,
+    /// publicly observable timelock after which funds go back to the recorded agent.
+    pub fn initiate_recovery(ctx: Context<InitiateRecovery>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let recovery = &mut ctx.accounts.recovery;
+        recovery.escrow = ctx.accounts.escrow_account.key();
+        recovery.admin = ctx.accounts.admin.key();
+        recovery.executable_at = now + RECOVERY_TIMELOCK;
+        recovery.bump = ctx.bumps.recovery;
+
+        emit!(RecoveryInitiated {
+            escrow: recovery.escrow,
+            agent: ctx.accounts.escrow_account.agent,
+            balance: ctx.accounts.escrow_account.balance,
+            executable_at: recovery.executable_at,
+        });
+        Ok(())
+    }
+
+    pub fn cancel_recovery(ctx: Context<CancelRecovery>) -> Result<()> {
+        emit!(RecoveryCancelled {
+            escrow: ctx.accounts.recovery.escrow,
+        });
+        Ok(())
+    }
+
+    /// Permissionless once the timelock has elapsed; funds only ever go to the agent.
+    pub fn execute_recovery(ctx: Context<ExecuteRecovery>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        require!(now >= ctx.accounts.recovery.executable_at, ErrorCode::RecoveryLocked);
+
+        let escrow = &ctx.accounts.escrow_account;
+        // Sponsored funds stay refundable to their sponsors; open-session reserves stay settleable
+        let amount = escrow.withdrawable();
+        transfer_from_escrow_vault(
+            escrow,
+            &ctx.accounts.vault,
+            &ctx.accounts.agent_token_account,
+            &ctx.accounts.token_program,
+            amount,
+        )?;
+        ctx.accounts.escrow_account.balance -= amount;
+
+        emit!(RecoveryExecuted {
+            escrow: ctx.accounts.escrow_account.key(),
+            agent: ctx.accounts.escrow_account.agent,
+            amount,
+        });
+        Ok(())
+    }
+
+    /// Grows an escrow created under an older layout to the current size. New fields
+    /// are always appended to `EscrowAccount`, so zero-filling the tail sets them to
+    /// their defaults. Callable by the escrow's agent or the market admin, who pays rent.
+    pub fn realloc_escrow(ctx: Context<ReallocEscrow>) -> Result<()> {
+        let escrow_info = ctx.accounts.escrow_account.to_account_info();
+
+        let agent = {
+            let data = escrow_info.try_borrow_data()?;
+            require!(
+                data.len() >= 8 + 32 && data[..8] == EscrowAccount::DISCRIMINATOR,
+                ErrorCode::InvalidEscrow
+            );
+            Pubkey::try_from(&data[8..40]).map_err(|_| ErrorCode::InvalidEscrow)?
+        };
+        let (expected, _) = Pubkey::find_program_address(&[seeds::ESCROW, agent.as_ref()], ctx.program_id);
+        require_keys_eq!(expected, escrow_info.key(), ErrorCode::InvalidEscrow);
+
+        let payer = &ctx.accounts.payer;
+        require!(
+            payer.key() == agent || payer.key() == ctx.accounts.market_config.authority,
+            ErrorCode::Unauthorized
+        );
+
+        if escrow_info.data_len() >= EscrowAccount::SPACE {
+            return Ok(());
+        }
+
+        let rent_due = Rent::get()?
+            .minimum_balance(EscrowAccount::SPACE)
+            .saturating_sub(escrow_info.lamports());
+        if rent_due > 0 {
+            system_program::transfer(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    system_program::Transfer {
+                        from: payer.to_account_info(),
+                        to: escrow_info.clone(),
+                    },
+                ),
+                rent_due,
+            )?;
+        }
+        escrow_info.realloc(EscrowAccount::SPACE, true)?;
+        Ok(())
+    }
+
+    /// Closes the escrow's statement period once its calendar month has ended, writing an
+    /// `EscrowEpochSummary` and opening the next period at the current balance. Anyone may
+    /// crank it and pays the summary's rent. Activity between month end and the crank
+    /// belongs to the period being closed; the summary's timestamps bound it exactly.
+    pub fn close_escrow_epoch(ctx: Context<CloseEscrowEpoch>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let escrow = &mut ctx.accounts.escrow_account;
+        require!(
+            month_index(now) > month_index(escrow.statement_started_at),
+            ErrorCode::StatementPeriodOpen
+        );
+
+        // Whatever deposits and settlements don't explain: withdrawals, task funding,
+        // refunds, and the balance of escrows that predate statements
+        let other_net = escrow.balance as i128 - escrow.statement_opening_balance as i128
+            - escrow.statement_deposits as i128
+            + escrow.statement_settlements as i128;
+
+        let summary = &mut ctx.accounts.summary;
+        summary.escrow = escrow.key();
+        summary.index = escrow.statement_count;
+        summary.started_at = escrow.statement_started_at;
+        summary.closed_at = now;
+        summary.opening_balance = escrow.statement_opening_balance;
+        summary.deposits = escrow.statement_deposits;
+        summary.settlements = escrow.statement_settlements;
+        summary.fees = escrow.statement_fees;
+        summary.other_net = i64::try_from(other_net).map_err(|_| ErrorCode::MathOverflow)?;
+        summary.closing_balance = escrow.balance;
+        summary.bump = ctx.bumps.summary;
+        summary.sessions = escrow.statement_sessions;
+
+        escrow.statement_count += 1;
+        escrow.statement_started_at = now;
+        escrow.statement_opening_balance = escrow.balance;
+        escrow.statement_deposits = 0;
+        escrow.statement_settlements = 0;
+        escrow.statement_fees = 0;
+        escrow.statement_sessions = 0;
+
+        emit!(EscrowEpochClosed {
+            escrow: summary.escrow,
+            index: summary.index,
+            summary: summary.key(),
+            closing_balance: summary.closing_balance,
+        });
+        Ok(())
+    }
+
+    /// Mints the agent a non-transferable invoice NFT for a closed statement period. The
+    /// invoice records the summary's hash, computed here from the summary account, so
+    /// accounting systems can check it against chain state. One invoice per summary; the
+    /// token account is frozen and the mint authority burned.
+    pub fn mint_invoice(ctx: Context<MintInvoice>) -> Result<()> {
+        let summary = &ctx.accounts.summary;
+        let invoice = &mut ctx.accounts.invoice;
+        invoice.summary = summary.key();
+        invoice.escrow = summary.escrow;
+        invoice.mint = ctx.accounts.invoice_mint.key();
+        invoice.summary_hash = summary.hash();
+        invoice.minted_at = Clock::get()?.unix_timestamp;
+        invoice.bump = ctx.bumps.invoice;
+
+        let summary_key = summary.key();
+        let seeds = &[seeds::INVOICE, summary_key.as_ref(), &[invoice.bump]];
+        let signer = &[&seeds[..]];
+        let token_program = ctx.accounts.token_program.to_account_info();
+        token::mint_to(
+            CpiContext::new_with_signer(
+                token_program.clone(),
+                MintTo {
+                    mint: ctx.accounts.invoice_mint.to_account_info(),
+                    to: ctx.accounts.agent_invoice_account.to_account_info(),
+                    authority: invoice.to_account_info(),
+                },
+                signer,
+            ),
+            1,
+        )?;
+        // The program never thaws, so the invoice can't leave the agent's wallet
+        token::freeze_account(CpiContext::new_with_signer(
+            token_program.clone(),
+            FreezeAccount {
+                account: ctx.accounts.agent_invoice_account.to_account_info(),
+                mint: ctx.accounts.invoice_mint.to_account_info(),
+                authority: invoice.to_account_info(),
+            },
+            signer,
+        ))?;
+        token::set_authority(
+            CpiContext::new_with_signer(
+                token_program,
+                SetAuthority {
+                    current_authority: invoice.to_account_info(),
+                    account_or_mint: ctx.accounts.invoice_mint.to_account_info(),
+                },
+                signer,
+            ),
+            AuthorityType::MintTokens,
+            None,
+        )?;
+
+        emit!(InvoiceMinted {
+            escrow: invoice.escrow,
+            summary: invoice.summary,
+            mint: invoice.mint,
+            summary_hash: invoice.summary_hash,
+        });
+        Ok(())
+    }
+
+    pub fn set_payout_prefs(
+        ctx: Context<SetPayoutPrefs>,
+        preferred_mint: Pubkey,
+        auto_claim_threshold: u64,
+        vesting_opt_in: bool,
+        referral_code: [u8; 32],
+        min_price_per_second: u64,
+        tax_lot: TaxLot, // All zeroes clears the annotation
+    ) -> Result<()> {
+        let prefs = &mut ctx.accounts.payout_prefs;
+        prefs.human = ctx.accounts.human.key();
+        prefs.preferred_mint = preferred_mint;
+        prefs.auto_claim_threshold = auto_claim_threshold;
+        prefs.vesting_opt_in = vesting_opt_in;
+        prefs.referral_code = referral_code;
+        prefs.min_price_per_second = min_price_per_second;
+        prefs.bump = ctx.bumps.payout_prefs;
+        prefs.jurisdiction = tax_lot.jurisdiction;
+        prefs.tax_memo_hash = tax_lot.memo_hash;
+        Ok(())
+    }
+
+    /// Grows payout prefs created before the tax-lot fields to the current size so
+    /// set_payout_prefs can load them again. The tail is zero-filled (no annotation).
+    pub fn realloc_payout_prefs(ctx: Context<ReallocPayoutPrefs>) -> Result<()> {
+        let prefs_info = ctx.accounts.payout_prefs.to_account_info();
+        {
+            let data = prefs_info.try_borrow_data()?;
+            require!(
+                data.len() >= 8 + 32 && data[..8] == PayoutPrefs::DISCRIMINATOR,
+                ErrorCode::Unauthorized
+            );
+            require!(data[8..40] == ctx.accounts.human.key().to_bytes(), ErrorCode::Unauthorized);
+        }
+        if prefs_info.data_len() >= PayoutPrefs::SPACE {
+            return Ok(());
+        }
+
+        let rent_due = Rent::get()?
+            .minimum_balance(PayoutPrefs::SPACE)
+            .saturating_sub(prefs_info.lamports());
+        if rent_due > 0 {
+            system_program::transfer(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    system_program::Transfer {
+                        from: ctx.accounts.human.to_account_info(),
+                        to: prefs_info.clone(),
+                    },
+                ),
+                rent_due,
+            )?;
+        }
+        prefs_info.realloc(PayoutPrefs::SPACE, true)?;
+        Ok(())
+    }
+
+    /// Splits the human's payouts across up to MAX_SPLIT_LEGS token accounts (e.g. a cold
+    /// wallet or a manager's share). Whatever the legs don't take goes to the human's own
+    /// payout ATA. Pass no legs to turn splitting off.
+    pub fn set_payout_split(ctx: Context<SetPayoutSplit>, legs: Vec<PayoutLeg>) -> Result<()> {
+        require!(legs.len() <= MAX_SPLIT_LEGS, ErrorCode::InvalidPayoutSplit);
+        require!(legs.iter().all(|leg| leg.bps > 0), ErrorCode::InvalidPayoutSplit);
+        let total_bps: u32 = legs.iter().map(|leg| leg.bps as u32).sum();
+        require!(total_bps <= MAX_BPS as u32, ErrorCode::InvalidPayoutSplit);
+
+        let split = &mut ctx.accounts.payout_split;
+        split.human = ctx.accounts.human.key();
+        split.legs = legs;
+        split.bump = ctx.bumps.payout_split;
+        Ok(())
+    }
+
+    /// Creates an org (a co-op or agency) that takes `fee_bps` of its members' settlement
+    /// payouts into `treasury`, a token account in the payout mint.
+    pub fn create_org(ctx: Context<CreateOrg>, org_id: [u8; 32], fee_bps: u16, treasury: Pubkey) -> Result<()> {
+        require!(fee_bps <= MAX_ORG_FEE_BPS, ErrorCode::InvalidOrgFee);
+        let org = &mut ctx.accounts.org;
+        org.org_id = org_id;
+        org.admin = ctx.accounts.admin.key();
+        org.treasury = treasury;
+        org.fee_bps = fee_bps;
+        org.member_count = 0;
+        org.total_member_payouts = 0;
+        org.total_org_fees = 0;
+        org.settlement_count = 0;
+        org.bump = ctx.bumps.org;
+        Ok(())
+    }
+
+    /// Changes the org's fee and treasury. Members who disagree can leave at any time.
+    pub fn update_org(ctx: Context<UpdateOrg>, fee_bps: u16, treasury: Pubkey) -> Result<()> {
+        require!(fee_bps <= MAX_ORG_FEE_BPS, ErrorCode::InvalidOrgFee);
+        let org = &mut ctx.accounts.org;
+        org.fee_bps = fee_bps;
+        org.treasury = treasury;
+        Ok(())
+    }
+
+    /// Enrolls a human in an org. Both the org admin and the human sign; a human belongs
+    /// to at most one org at a time.
+    pub fn add_org_member(ctx: Context<AddOrgMember>) -> Result<()> {
+        let member = &mut ctx.accounts.org_member;
+        member.org = ctx.accounts.org.key();
+        member.human = ctx.accounts.human.key();
+        member.joined_at = Clock::get()?.unix_timestamp;
+        member.bump = ctx.bumps.org_member;
+
+        let org = &mut ctx.accounts.org;
+        org.member_count = org.member_count.checked_add(1).ok_or(ErrorCode::MathOverflow)?;
+        Ok(())
+    }
+
+    /// Removes a human from their org. Either the human or the org admin can sign; the
+    /// membership rent returns to the org admin, who paid it.
+    pub fn remove_org_member(ctx: Context<RemoveOrgMember>) -> Result<()> {
+        let caller = ctx.accounts.caller.key();
+        require!(
+            caller == ctx.accounts.org_member.human || caller == ctx.accounts.org.admin,
+            ErrorCode::Unauthorized
+        );
+        let org = &mut ctx.accounts.org;
+        org.member_count = org.member_count.saturating_sub(1);
+        Ok(())
+    }
+
+    /// Registers a hashed API key identifier for spend attribution. The router tags
+    /// settlements with it and the key's account accumulates what they cost the agent.
+    pub fn register_api_key(ctx: Context<RegisterApiKey>, key_hash: [u8; 32]) -> Result<()> {
+        let api_key = &mut ctx.accounts.api_key;
+        api_key.agent = ctx.accounts.agent.key();
+        api_key.key_hash = key_hash;
+        api_key.total_spent = 0;
+        api_key.settlement_count = 0;
+        api_key.last_used = 0;
+        api_key.revoked = false;
+        api_key.bump = ctx.bumps.api_key;
+        Ok(())
+    }
+
+    /// Stops settlements from being tagged with the key. Its spend totals are kept.
+    pub fn revoke_api_key(ctx: Context<RevokeApiKey>) -> Result<()> {
+        ctx.accounts.api_key.revoked = true;
+        Ok(())
+    }
+
+    /// Pre-authorizes settlements to `human` of up to `max_amount` in total until `expiry`.
+    /// Settlements that reference the intent draw it down; see `set_intents_required`.
+    pub fn create_payment_intent(
+        ctx: Context<CreatePaymentIntent>,
+        intent_id: u64,
+        human: Pubkey,
+        max_amount: u64,
+        expiry: i64,
+    ) -> Result<()> {
+        require!(max_amount > 0, ErrorCode::InvalidAmount);
+        require!(expiry > Clock::get()?.unix_timestamp, ErrorCode::PaymentIntentExpired);
+
+        let intent = &mut ctx.accounts.payment_intent;
+        intent.escrow = ctx.accounts.escrow_account.key();
+        intent.human = human;
+        intent.intent_id = intent_id;
+        intent.max_amount = max_amount;
+        intent.remaining = max_amount;
+        intent.expires_at = expiry;
+        intent.bump = ctx.bumps.payment_intent;
+        Ok(())
+    }
+
+    /// Withdraws an intent's unspent authorization and returns its rent to the agent.
+    pub fn cancel_payment_intent(_ctx: Context<CancelPaymentIntent>) -> Result<()> {
+        Ok(())
+    }
+
+    /// While set, every settlement of the escrow must reference a live payment intent for
+    /// its human, so the router can only spend what the agent has authorized.
+    pub fn set_intents_required(ctx: Context<SetIntentsRequired>, required: bool) -> Result<()> {
+        ctx.accounts.escrow_account.intents_required = required;
+        Ok(())
+    }
+
+    /// Discretionary bonus from the agent to a session's human, outside per-second pricing.
+    /// The protocol fee still applies; the tip is recorded on the session.
+    pub fn tip_human(ctx: Context<TipHuman>, amount: u64) -> Result<()> {
+        require!(amount > 0, ErrorCode::InvalidAmount);
+        if let Some(prefs) = load_payout_prefs(&ctx.accounts.payout_prefs)? {
+            prefs.check_mint(&ctx.accounts.mint.key())?;
+        }
+
+        let escrow = &mut ctx.accounts.escrow_account;
+        // Sponsored funds and open-session reserves are for metered attention, not bonuses
+        require!(escrow.withdrawable() >= amount, ErrorCode::InsufficientFunds);
+        escrow.balance -= amount;
+
+        let fee_bps = escrow.fee_bps(&ctx.accounts.market_config, Clock::get()?.unix_timestamp);
+        let fee_amount = bps_of(amount, fee_bps)?;
+        let tip_payout = amount - fee_amount;
+        escrow.record_settlement(amount, fee_amount);
+
+        transfer_from_escrow_vault(
+            &ctx.accounts.escrow_account,
+            &ctx.accounts.vault,
+            &ctx.accounts.user_wallet,
+            &ctx.accounts.token_program,
+            tip_payout,
+        )?;
+        transfer_from_escrow_vault(
+            &ctx.accounts.escrow_account,
+            &ctx.accounts.vault,
+            &ctx.accounts.fee_vault,
+            &ctx.accounts.token_program,
+            fee_amount,
+        )?;
+
+        let state = &mut ctx.accounts.fee_vault_state;
+        let window = ctx.accounts.market_config.fee_challenge_window;
+        state.credit_settlement_fees(fee_amount, Clock::get()?.unix_timestamp, window);
+        state.total_collected += fee_amount;
+
+        let session = &mut ctx.accounts.session;
+        session.total_tipped = session.total_tipped.checked_add(amount).ok_or(ErrorCode::MathOverflow)?;
+
+        emit!(HumanTipped {
+            session: session.key(),
+            human: session.human,
+            amount,
+            fee: fee_amount,
+        });
+        Ok(())
+    }
+
+    /// Registers several builders at once. Each entry's BuilderBalance PDA is passed
+    /// in `remaining_accounts`, in the same order as `entries`.
+    pub fn register_builders_batch<'info>(
+        ctx: Context<'_, '_, 'info, 'info, RegisterBuildersBatch<'info>>,
+        entries: Vec<BuilderRegistration>,
+    ) -> Result<()> {
+        require!(
+            !entries.is_empty() && entries.len() <= MAX_BATCH_REGISTRATIONS,
+            ErrorCode::InvalidBatch
+        );
+        require!(ctx.remaining_accounts.len() == entries.len(), ErrorCode::InvalidBatch);
+
+        let rent = Rent::get()?.minimum_balance(BuilderBalance::SPACE);
+        for (entry, builder_info) in entries.iter().zip(ctx.remaining_accounts.iter()) {
+            // Prevent null builder code registration (defense against PDA collision)
+            require!(entry.builder_code != [0u8; 32], ErrorCode::InvalidBuilderCode);
+
+            let (expected, bump) =
+                Pubkey::find_program_address(&[seeds::BUILDER, entry.builder_code.as_ref()], ctx.program_id);
+            require_keys_eq!(builder_info.key(), expected, ErrorCode::InvalidBatch);
+            require!(builder_info.data_is_empty(), ErrorCode::BuilderAlreadyRegistered);
+
+            let seeds = &[seeds::BUILDER, entry.builder_code.as_ref(), &[bump]];
+            create_pda_account(
+                &ctx.accounts.admin.to_account_info(),
+                builder_info,
+                &ctx.accounts.system_program,
+                rent,
+                BuilderBalance::SPACE,
+                ctx.program_id,
+                &[&seeds[..]],
+            )?;
+
+            let builder = BuilderBalance {
+                builder_code: entry.builder_code,
+                wallet: entry.wallet,
+                balance: 0,
+                total_earned: 0,
+                bump,
+            };
+            builder.try_serialize(&mut &mut builder_info.try_borrow_mut_data()?[..])?;
+
+            emit!(BuilderRegistered {
+                builder_code: entry.builder_code,
+                wallet: entry.wallet,
+            });
+        }
+        Ok(())
+    }
+
+    /// Underwriter stakes collateral that settlements for this escrow may draw on when
+    /// the escrow balance runs short. Both the underwriter and the agent must sign.
+    pub fn open_credit_line(ctx: Context<OpenCreditLine>, collateral: u64) -> Result<()> {
+        require!(collateral > 0, ErrorCode::InvalidAmount);
+
+        let transfer = Transfer {
+            from: ctx.accounts.underwriter_token_account.to_account_info(),
+            to: ctx.accounts.credit_collateral.to_account_info(),
+            authority: ctx.accounts.underwriter.to_account_info(),
+        };
+        token::transfer(
+            CpiContext::new(ctx.accounts.token_program.to_account_info(), transfer),
+            collateral,
+        )?;
+
+        let credit_line = &mut ctx.accounts.credit_line;
+        credit_line.escrow = ctx.accounts.escrow_account.key();
+        credit_line.underwriter = ctx.accounts.underwriter.key();
+        credit_line.credit_limit = collateral;
+        credit_line.debt = 0;
+        credit_line.total_drawn = 0;
+        credit_line.total_repaid = 0;
+        credit_line.bump = ctx.bumps.credit_line;
+        Ok(())
+    }
+
+    /// Returns collateral not backing outstanding debt to the underwriter.
+    pub fn withdraw_collateral(ctx: Context<WithdrawCollateral>, amount: u64) -> Result<()> {
+        let credit_line = &mut ctx.accounts.credit_line;
+        require!(
+            credit_line.credit_limit - credit_line.debt >= amount,
+            ErrorCode::InsufficientFunds
+        );
+        credit_line.credit_limit -= amount;
+
+        transfer_from_collateral(
+            &ctx.accounts.credit_line,
+            &ctx.accounts.credit_collateral,
+            &ctx.accounts.underwriter_token_account,
+            &ctx.accounts.token_program,
+            amount,
+        )
+    }
+
+    /// Whitelists a liquid staking token as escrow collateral, or updates its oracle and
+    /// haircut. Collateral lends at the posted price less `haircut_bps`.
+    pub fn whitelist_lst(ctx: Context<WhitelistLst>, oracle: Pubkey, haircut_bps: u16) -> Result<()> {
+        require!(haircut_bps < MAX_BPS, ErrorCode::InvalidFeeParams);
+        let lst = &mut ctx.accounts.lst_collateral;
+        lst.mint = ctx.accounts.lst_mint.key();
+        lst.decimals = ctx.accounts.lst_mint.decimals;
+        lst.oracle = oracle;
+        lst.haircut_bps = haircut_bps;
+        lst.bump = ctx.bumps.lst_collateral;
+        Ok(())
+    }
+
+    /// Posts an LST's price in payment-mint base units per whole token. Draws and
+    /// rebalances need it refreshed within LST_PRICE_MAX_AGE.
+    pub fn post_lst_price(ctx: Context<PostLstPrice>, price: u64) -> Result<()> {
+        require!(price > 0, ErrorCode::InvalidAmount);
+        let lst = &mut ctx.accounts.lst_collateral;
+        lst.price = price;
+        lst.updated_at = Clock::get()?.unix_timestamp;
+
+        emit!(LstPricePosted {
+            mint: lst.mint,
+            price,
+            updated_at: lst.updated_at,
+        });
+        Ok(())
+    }
+
+    /// Creates the protocol liquidity buffer that pays humans on behalf of escrows
+    /// collateralized by LST. Starts without exposure limits or fee replenishment.
+    pub fn init_liquidity_buffer(ctx: Context<InitLiquidityBuffer>) -> Result<()> {
+        let buffer = &mut ctx.accounts.liquidity_buffer;
+        buffer.vault = ctx.accounts.buffer_vault.key();
+        buffer.outstanding = 0;
+        buffer.bump = ctx.bumps.liquidity_buffer;
+        buffer.max_outstanding = 0;
+        buffer.max_position_debt = 0;
+        buffer.replenish_bps = 0;
+        buffer.replenished_through = ctx.accounts.fee_vault_state.total_collected;
+        buffer.total_funded = 0;
+        buffer.total_drawn = 0;
+        buffer.total_repaid = 0;
+        Ok(())
+    }
+
+    /// Caps the buffer's exposure (0 = uncapped) and sets the share of each new protocol
+    /// fee that replenish_liquidity_buffer moves into it.
+    pub fn set_liquidity_buffer_limits(
+        ctx: Context<SetLiquidityBufferLimits>,
+        max_outstanding: u64,
+        max_position_debt: u64,
+        replenish_bps: u16,
+    ) -> Result<()> {
+        require!(replenish_bps <= MAX_BPS, ErrorCode::InvalidFeeParams);
+        let buffer = &mut ctx.accounts.liquidity_buffer;
+        buffer.max_outstanding = max_outstanding;
+        buffer.max_position_debt = max_position_debt;
+        buffer.replenish_bps = replenish_bps;
+        Ok(())
+    }
+
+    /// Adds payment tokens to the liquidity buffer.
+    pub fn fund_liquidity_buffer(ctx: Context<FundLiquidityBuffer>, amount: u64) -> Result<()> {
+        require!(amount > 0, ErrorCode::InvalidAmount);
+        let transfer = Transfer {
+            from: ctx.accounts.funder_token_account.to_account_info(),
+            to: ctx.accounts.buffer_vault.to_account_info(),
+            authority: ctx.accounts.funder.to_account_info(),
+        };
+        token::transfer(
+            CpiContext::new(ctx.accounts.token_program.to_account_info(), transfer),
+            amount,
+        )?;
+        let buffer = &mut ctx.accounts.liquidity_buffer;
+        buffer.total_funded = buffer.total_funded.saturating_add(amount);
+        Ok(())
+    }
+
+    /// Treasury funding: moves claimable protocol fees into the buffer (`amount` 0 = the
+    /// full claimable balance).
+    pub fn fund_liquidity_buffer_from_treasury(
+        ctx: Context<FundLiquidityBufferFromTreasury>,
+        amount: u64,
+    ) -> Result<()> {
+        let state = &mut ctx.accounts.fee_vault_state;
+        state.realize_fees(Clock::get()?.unix_timestamp, ctx.accounts.market_config.fee_challenge_window);
+        let amount = if amount == 0 { state.protocol_balance } else { amount };
+        require!(amount > 0, ErrorCode::NothingToClaim);
+        require!(amount <= state.protocol_balance, ErrorCode::InsufficientFunds);
+
+        transfer_from_fee_vault_to_buffer(
+            &ctx.accounts.fee_vault_state,
+            &ctx.accounts.fee_vault,
+            &ctx.accounts.buffer_vault,
+            &ctx.accounts.token_program,
+            amount,
+        )?;
+        ctx.accounts.fee_vault_state.protocol_balance -= amount;
+        let buffer = &mut ctx.accounts.liquidity_buffer;
+        buffer.total_funded = buffer.total_funded.saturating_add(amount);
+
+        emit!(LiquidityBufferFunded {
+            amount,
+            replenishment: false,
+            outstanding: buffer.outstanding,
+        });
+        Ok(())
+    }
+
+    /// Permissionless crank: moves `replenish_bps` of the protocol fees collected since
+    /// the last replenishment into the buffer, as far as claimable fees allow.
+    pub fn replenish_liquidity_buffer(ctx: Context<ReplenishLiquidityBuffer>) -> Result<()> {
+        let state = &mut ctx.accounts.fee_vault_state;
+        state.realize_fees(Clock::get()?.unix_timestamp, ctx.accounts.market_config.fee_challenge_window);
+        let buffer = &mut ctx.accounts.liquidity_buffer;
+        let collected = state.total_collected.saturating_sub(buffer.replenished_through);
+        let amount = bps_of(collected, buffer.replenish_bps as u64)?.min(state.protocol_balance);
+        require!(amount > 0, ErrorCode::NothingToClaim);
+        buffer.replenished_through = state.total_collected;
+
+        transfer_from_fee_vault_to_buffer(
+            &ctx.accounts.fee_vault_state,
+            &ctx.accounts.fee_vault,
+            &ctx.accounts.buffer_vault,
+            &ctx.accounts.token_program,
+            amount,
+        )?;
+        ctx.accounts.fee_vault_state.protocol_balance -= amount;
+        let buffer = &mut ctx.accounts.liquidity_buffer;
+        buffer.total_funded = buffer.total_funded.saturating_add(amount);
+
+        emit!(LiquidityBufferFunded {
+            amount,
+            replenishment: true,
+            outstanding: buffer.outstanding,
+        });
+        Ok(())
+    }
+
+    /// View: share of the buffer's capital currently lent out, in basis points.
+    pub fn liquidity_buffer_utilization(ctx: Context<LiquidityBufferUtilization>) -> Result<u16> {
+        Ok(ctx.accounts.liquidity_buffer.utilization_bps(ctx.accounts.buffer_vault.amount))
+    }
+
+    /// Agent locks a whitelisted LST as collateral for its escrow.
+    pub fn deposit_lst(ctx: Context<DepositLst>, amount: u64) -> Result<()> {
+        require!(amount > 0, ErrorCode::InvalidAmount);
+        let transfer = Transfer {
+            from: ctx.accounts.agent_lst_account.to_account_info(),
+            to: ctx.accounts.lst_vault.to_account_info(),
+            authority: ctx.accounts.agent.to_account_info(),
+        };
+        token::transfer(
+            CpiContext::new(ctx.accounts.token_program.to_account_info(), transfer),
+            amount,
+        )?;
+
+        let position = &mut ctx.accounts.lst_position;
+        position.escrow = ctx.accounts.escrow_account.key();
+        position.mint = ctx.accounts.lst_collateral.mint;
+        position.amount = position.amount.checked_add(amount).ok_or(ErrorCode::MathOverflow)?;
+        position.bump = ctx.bumps.lst_position;
+        Ok(())
+    }
+
+    /// Returns LST to the agent as long as what remains still covers the position's
+    /// debt after the haircut.
+    pub fn withdraw_lst(ctx: Context<WithdrawLst>, amount: u64) -> Result<()> {
+        let position = &mut ctx.accounts.lst_position;
+        let remaining = position.amount.checked_sub(amount).ok_or(ErrorCode::InsufficientFunds)?;
+        if position.debt > 0 {
+            let now = Clock::get()?.unix_timestamp;
+            require!(
+                ctx.accounts.lst_collateral.credit_value(remaining, now)? >= position.debt,
+                ErrorCode::LstUndercollateralized
+            );
+        }
+        position.amount = remaining;
+
+        transfer_from_lst_vault(
+            &ctx.accounts.lst_position,
+            &ctx.accounts.lst_vault,
+            &ctx.accounts.agent_lst_account,
+            &ctx.accounts.token_program,
+            amount,
+        )
+    }
+
+    /// Fronts `amount` from the liquidity buffer into an LST-backed escrow, up to the
+    /// collateral's lending value. Routers prepend it to settlements the escrow balance
+    /// can't cover, so humans are still paid in the payment mint.
+    pub fn draw_lst_credit(ctx: Context<DrawLstCredit>, amount: u64) -> Result<()> {
+        require!(amount > 0, ErrorCode::InvalidAmount);
+        let now = Clock::get()?.unix_timestamp;
+
+        let position = &mut ctx.accounts.lst_position;
+        let debt = position.debt.checked_add(amount).ok_or(ErrorCode::MathOverflow)?;
+        require!(
+            ctx.accounts.lst_collateral.credit_value(position.amount, now)? >= debt,
+            ErrorCode::LstUndercollateralized
+        );
+        ctx.accounts.liquidity_buffer.draw(amount, debt)?;
+        position.debt = debt;
+
+        transfer_from_liquidity_buffer(
+            &ctx.accounts.liquidity_buffer,
+            &ctx.accounts.buffer_vault,
+            &ctx.accounts.vault,
+            &ctx.accounts.token_program,
+            amount,
+        )?;
+        // Fronted credit sits in the escrow like a deposit, so the KYC tier cap applies
+        let escrow = &mut ctx.accounts.escrow_account;
+        escrow.balance += amount;
+        ctx.accounts.vault.reload()?;
+        ctx.accounts.market_config.check_deposit_cap(escrow, &ctx.accounts.vault)?;
+
+        emit!(LstCreditDrawn {
+            escrow: position.escrow,
+            mint: position.mint,
+            amount,
+            debt,
+        });
+        Ok(())
+    }
+
+    /// Pays down an LST position's debt in payment tokens, back into the buffer.
+    /// Anyone may repay; `amount` is capped at the debt.
+    pub fn repay_lst_debt(ctx: Context<RepayLstDebt>, amount: u64) -> Result<()> {
+        let position = &mut ctx.accounts.lst_position;
+        let amount = amount.min(position.debt);
+        require!(amount > 0, ErrorCode::InvalidAmount);
+
+        let transfer = Transfer {
+            from: ctx.accounts.payer_token_account.to_account_info(),
+            to: ctx.accounts.buffer_vault.to_account_info(),
+            authority: ctx.accounts.payer.to_account_info(),
+        };
+        token::transfer(
+            CpiContext::new(ctx.accounts.token_program.to_account_info(), transfer),
+            amount,
+        )?;
+        position.debt -= amount;
+        ctx.accounts.liquidity_buffer.repay(amount);
+
+        emit!(LstDebtRepaid {
+            escrow: position.escrow,
+            mint: position.mint,
+            amount,
+            lst_sold: 0,
+            debt: position.debt,
+        });
+        Ok(())
+    }
+
+    /// Periodic rebalance: an operator buys `lst_amount` of a position's collateral at
+    /// the oracle price, paying the buffer back and retiring that much debt. Operators
+    /// run it against the most leveraged positions and unwind the LST off-chain. No
+    /// more collateral can be bought than the debt is worth.
+    pub fn rebalance_lst(ctx: Context<RebalanceLst>, lst_amount: u64) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let value = ctx.accounts.lst_collateral.value(lst_amount, now)?;
+        let position = &mut ctx.accounts.lst_position;
+        require!(value > 0 && lst_amount <= position.amount, ErrorCode::InvalidAmount);
+        require!(value <= position.debt, ErrorCode::InvalidAmount);
+
+        let transfer = Transfer {
+            from: ctx.accounts.operator_token_account.to_account_info(),
+            to: ctx.accounts.buffer_vault.to_account_info(),
+            authority: ctx.accounts.operator.to_account_info(),
+        };
+        token::transfer(
+            CpiContext::new(ctx.accounts.token_program.to_account_info(), transfer),
+            value,
+        )?;
+        position.amount -= lst_amount;
+        position.debt -= value;
+        ctx.accounts.liquidity_buffer.repay(value);
+
+        transfer_from_lst_vault(
+            &ctx.accounts.lst_position,
+            &ctx.accounts.lst_vault,
+            &ctx.accounts.operator_lst_account,
+            &ctx.accounts.token_program,
+            lst_amount,
+        )?;
+
+        let position = &ctx.accounts.lst_position;
+        emit!(LstDebtRepaid {
+            escrow: position.escrow,
+            mint: position.mint,
+            amount: value,
+            lst_sold: lst_amount,
+            debt: position.debt,
+        });
+        Ok(())
+    }
+
+    /// Registers an additional settlement currency with its own fee vault and balances.
+    pub fn register_fee_mint(ctx: Context<RegisterFeeMint>) -> Result<()> {
+        let mint_fee_vault = &mut ctx.accounts.mint_fee_vault;
+        mint_fee_vault.mint = ctx.accounts.mint.key();
+        mint_fee_vault.vault = ctx.accounts.mint_vault.key();
+        mint_fee_vault.protocol_balance = 0;
+        mint_fee_vault.total_collected = 0;
+        mint_fee_vault.bump = ctx.bumps.mint_fee_vault;
+        Ok(())
+    }
+
+    /// Per-mint counterpart of claim_protocol_fees (`amount` 0 = the full balance).
+    pub fn claim_mint_protocol_fees(ctx: Context<ClaimMintProtocolFees>, amount: u64) -> Result<()> {
+        let mint_fee_vault = &mut ctx.accounts.mint_fee_vault;
+        let amount = if amount == 0 { mint_fee_vault.protocol_balance } else { amount };
+
+        require!(amount > 0, ErrorCode::NothingToClaim);
+        require!(amount <= mint_fee_vault.protocol_balance, ErrorCode::InsufficientFunds);
+
+        let seeds = &[seeds::MINT_FEE_VAULT, mint_fee_vault.mint.as_ref(), &[mint_fee_vault.bump]];
+        let signer = &[&seeds[..]];
+        let transfer = Transfer {
+            from: ctx.accounts.mint_vault.to_account_info(),
+            to: ctx.accounts.admin_wallet.to_account_info(),
+            authority: mint_fee_vault.to_account_info(),
+        };
+        token::transfer(
+            CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(), transfer, signer),
+            amount,
+        )?;
+
+        mint_fee_vault.protocol_balance -= amount;
+
+        emit!(ProtocolFeesClaimed {
+            amount,
+            destination: ctx.accounts.admin_wallet.key(),
+            remaining: mint_fee_vault.protocol_balance,
+        });
+        Ok(())
+    }
+
+    pub fn init_revenue_pool(ctx: Context<InitRevenuePool>, epoch_duration: i64) -> Result<()> {
+        require!(epoch_duration > 0, ErrorCode::InvalidAmount);
+        let pool = &mut ctx.accounts.revenue_pool;
+        pool.epoch_duration = epoch_duration;
+        pool.current_epoch = 0;
+        pool.epoch_started_at = Clock::get()?.unix_timestamp;
+        pool.epoch_start_collected = ctx.accounts.fee_vault_state.total_collected;
+        pool.active_bps = 0;
+        pool.pending_bps = 0;
+        pool.reserved = 0;
+        pool.position_count = 0;
+        pool.bump = ctx.bumps.revenue_pool;
+        Ok(())
+    }
+
+    /// Mints a transferable position entitling its holder to `bps` of the fee stream for
+    /// `duration_epochs` epochs, starting with the next epoch.
+    pub fn mint_revenue_share(
+        ctx: Context<MintRevenueShare>,
+        bps: u16,
+        duration_epochs: u64,
+    ) -> Result<()> {
+        require!(bps > 0 && duration_epochs > 0, ErrorCode::InvalidAmount);
+        let pool = &mut ctx.accounts.revenue_pool;
+        let committed = pool.active_bps as u32 + pool.pending_bps as u32 + bps as u32;
+        require!(committed <= MAX_REVENUE_SHARE_BPS as u32, ErrorCode::RevenueShareCapExceeded);
+
+        let start_epoch = pool.current_epoch + 1;
+        let end_epoch = start_epoch + duration_epochs;
+
+        let pool_bump = pool.bump;
+        let seeds = &[seeds::REVENUE_POOL, &[pool_bump]];
+        let signer = &[&seeds[..]];
+
+        // Mint the single position token, then burn the mint authority
+        let cpi_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            MintTo {
+                mint: ctx.accounts.nft_mint.to_account_info(),
+                to: ctx.accounts.recipient_nft_account.to_account_info(),
+                authority: pool.to_account_info(),
+            },
+            signer,
+        );
+        token::mint_to(cpi_ctx, 1)?;
+
+        let cpi_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            SetAuthority {
+                current_authority: pool.to_account_info(),
+                account_or_mint: ctx.accounts.nft_mint.to_account_info(),
+            },
+            signer,
+        );
+        token::set_authority(cpi_ctx, AuthorityType::MintTokens, None)?;
+
+        pool.pending_bps += bps;
+        pool.position_count += 1;
+
+        // Drop the position's bps out of the active total when its last epoch closes
+        let end = &mut ctx.accounts.end_fee_epoch;
+        end.epoch = end_epoch;
+        end.expiring_bps += bps;
+        end.bump = ctx.bumps.end_fee_epoch;
+
+        let position = &mut ctx.accounts.revenue_share;
+        position.nft_mint = ctx.accounts.nft_mint.key();
+        position.bps = bps;
+        position.start_epoch = start_epoch;
+        position.end_epoch = end_epoch;
+        position.next_claim_epoch = start_epoch;
+        position.bump = ctx.bumps.revenue_share;
+
+        emit!(RevenueShareMinted {
+            nft_mint: position.nft_mint,
+            recipient: ctx.accounts.recipient.key(),
+            bps,
+            start_epoch,
+            end_epoch,
+        });
+        Ok(())
+    }
+
+    /// Closes the current fee epoch and moves the revenue-share cut of its fees out of
+    /// the protocol balance into the pool. Callable by anyone once the epoch has elapsed.
+    pub fn roll_fee_epoch(ctx: Context<RollFeeEpoch>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let pool = &mut ctx.accounts.revenue_pool;
+        require!(now >= pool.epoch_started_at + pool.epoch_duration, ErrorCode::EpochNotFinished);
+
+        let state = &mut ctx.accounts.fee_vault_state;
+        // Fees past their challenge window belong to this epoch's reserve
+        state.realize_fees(now, ctx.accounts.market_config.fee_challenge_window);
+        let collected = state.total_collected - pool.epoch_start_collected;
+        let owed = (collected as u128 * pool.active_bps as u128 / BPS_DENOMINATOR as u128) as u64;
+        let reserved = owed.min(state.protocol_balance);
+        state.protocol_balance -= reserved;
+        pool.reserved += reserved;
+
+        let closing = &mut ctx.accounts.closing_fee_epoch;
+        closing.epoch = pool.current_epoch;
+        closing.collected = collected;
+        closing.active_bps = pool.active_bps;
+        closing.reserved = reserved;
+        closing.bump = ctx.bumps.closing_fee_epoch;
+
+        let next = &mut ctx.accounts.next_fee_epoch;
+        next.epoch = pool.current_epoch + 1;
+        next.bump = ctx.bumps.next_fee_epoch;
+
+        emit!(FeeEpochRolled {
+            epoch: pool.current_epoch,
+            collected,
+            reserved,
+        });
+
+        pool.current_epoch += 1;
+        pool.epoch_started_at = now;
+        pool.epoch_start_collected = state.total_collected;
+        pool.active_bps = pool.active_bps + pool.pending_bps - next.expiring_bps;
+        pool.pending_bps = 0;
+        Ok(())
+    }
+
+    /// Pays the position holder its cut of each closed epoch, passed as consecutive
+    /// FeeEpoch accounts in `remaining_accounts` starting at `next_claim_epoch`.
+    pub fn claim_revenue_share<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ClaimRevenueShare<'info>>,
+    ) -> Result<()> {
+        let position = &mut ctx.accounts.revenue_share;
+        let pool = &mut ctx.accounts.revenue_pool;
+
+        let mut amount: u64 = 0;
+        let mut epoch = position.next_claim_epoch;
+        for info in ctx.remaining_accounts.iter() {
+            require!(epoch < position.end_epoch && epoch < pool.current_epoch, ErrorCode::InvalidFeeEpoch);
+            let fee_epoch = Account::<FeeEpoch>::try_from(info)?;
+            require!(fee_epoch.epoch == epoch, ErrorCode::InvalidFeeEpoch);
+            require!(fee_epoch.active_bps > 0, ErrorCode::InvalidFeeEpoch);
+            // Pro-rata over the epoch's reserve, which may be capped by the protocol balance
+            let share = (fee_epoch.reserved as u128 * position.bps as u128
+                / fee_epoch.active_bps as u128) as u64;
+            amount = amount.checked_add(share).ok_or(ErrorCode::MathOverflow)?;
+            epoch += 1;
+        }
+        require!(amount > 0, ErrorCode::NothingToClaim);
+
+        let bump = ctx.accounts.fee_vault_state.bump;
+        let seeds = &[seeds::FEE_VAULT_STATE, &[bump]];
+        let signer = &[&seeds[..]];
+
+        let cpi_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.fee_vault.to_account_info(),
+                to: ctx.accounts.holder_token_account.to_account_info(),
+                authority: ctx.accounts.fee_vault_state.to_account_info(),
+            },
+            signer,
+        );
+        token::transfer(cpi_ctx, amount)?;
+
+        pool.reserved -= amount;
+        position.next_claim_epoch = epoch;
+
+        emit!(RevenueShareClaimed {
+            nft_mint: position.nft_mint,
+            holder: ctx.accounts.holder.key(),
+            amount,
+            next_claim_epoch: epoch,
+        });
+        Ok(())
+    }
+
+    /// Pays a relayer back for recorded gas spend out of protocol fees. `lamports` is the
+    /// spend being covered; `amount` is its value in the fee vault mint.
+    pub fn reimburse_relayer(ctx: Context<ReimburseRelayer>, lamports: u64, amount: u64) -> Result<()> {
+        let stats = &mut ctx.accounts.relayer_stats;
+        require!(
+            lamports > 0 && lamports <= stats.lamports_spent - stats.lamports_reimbursed,
+            ErrorCode::InvalidAmount
+        );
+
+        let state = &mut ctx.accounts.fee_vault_state;
+        require!(amount <= state.protocol_balance, ErrorCode::InsufficientFunds);
+
+        let bump = state.bump;
+        let seeds = &[seeds::FEE_VAULT_STATE, &[bump]];
+        let signer = &[&seeds[..]];
+
+        let cpi_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.fee_vault.to_account_info(),
+                to: ctx.accounts.relayer_token_account.to_account_info(),
+                authority: state.to_account_info(),
+            },
+            signer,
+        );
+        token::transfer(cpi_ctx, amount)?;
+
+        state.protocol_balance -= amount;
+        stats.lamports_reimbursed += lamports;
+        stats.amount_reimbursed += amount;
+
+        emit!(RelayerReimbursed {
+            relayer: stats.relayer,
+            lamports,
+            amount,
+            outstanding_lamports: stats.lamports_spent - stats.lamports_reimbursed,
+        });
+        Ok(())
+    }
+
+    /// Sets aside escrow funds for `seconds` of verified attention from `cohort` at a
+    /// locked price. Settlements passing the position consume it; unused seconds are
+    /// refunded to the escrow after `expires_at`.
+    pub fn buy_forward(
+        ctx: Context<BuyForward>,
+        forward_id: u64,
+        cohort: Pubkey,
+        seconds: u64,
+        price_per_second: u64,
+        expires_at: i64,
+    ) -> Result<()> {
+        require!(seconds > 0 && price_per_second > 0, ErrorCode::InvalidAmount);
+        require!(expires_at > Clock::get()?.unix_timestamp, ErrorCode::InvalidDeadline);
+        let cost = seconds.checked_mul(price_per_second).ok_or(ErrorCode::MathOverflow)?;
+
+        let escrow = &mut ctx.accounts.escrow_account;
+        require!(escrow.withdrawable() >= cost, ErrorCode::InsufficientFunds);
+        escrow.balance -= cost;
+
+        let forward = &mut ctx.accounts.forward_position;
+        forward.escrow = escrow.key();
+        forward.agent = ctx.accounts.agent.key();
+        forward.forward_id = forward_id;
+        forward.cohort = cohort;
+        forward.price_per_second = price_per_second;
+        forward.seconds_total = seconds;
+        forward.seconds_used = 0;
+        forward.expires_at = expires_at;
+        forward.bump = ctx.bumps.forward_position;
+
+        emit!(ForwardPurchased {
+            escrow: forward.escrow,
+            forward_id,
+            cohort,
+            seconds,
+            price_per_second,
+            expires_at,
+        });
+        Ok(())
+    }
+
+    /// Returns the unused part of an expired forward to the escrow balance and closes it.
+    /// Callable by anyone; funds only ever go back to the escrow.
+    pub fn refund_forward(ctx: Context<RefundForward>) -> Result<()> {
+        let forward = &ctx.accounts.forward_position;
+        require!(Clock::get()?.unix_timestamp >= forward.expires_at, ErrorCode::ForwardNotExpired);
+
+        let unused = forward.seconds_total - forward.seconds_used;
+        let refund = unused * forward.price_per_second;
+        ctx.accounts.escrow_account.balance += refund;
+
+        emit!(ForwardRefunded {
+            escrow: forward.escrow,
+            forward_id: forward.forward_id,
+            unused_seconds: unused,
+            refund,
+        });
+        Ok(())
+    }
+
+    pub fn create_cohort_pool(ctx: Context<CreateCohortPool>, cohort_id: [u8; 32]) -> Result<()> {
+        let pool = &mut ctx.accounts.cohort_pool;
+        pool.cohort_id = cohort_id;
+        pool.vault = ctx.accounts.cohort_vault.key();
+        pool.member_count = 0;
+        pool.current_epoch = 0;
+        pool.undistributed = 0;
+        pool.bump = ctx.bumps.cohort_pool;
+        pool.application_deposit = 0;
+        Ok(())
+    }
+
+    /// Sets the refundable deposit humans must post (`post_cohort_deposit`) before joining
+    /// the cohort. 0 removes the requirement; deposits already posted are unaffected.
+    pub fn set_cohort_deposit(ctx: Context<SetCohortDeposit>, amount: u64) -> Result<()> {
+        ctx.accounts.cohort_pool.application_deposit = amount;
+        Ok(())
+    }
+
+    /// Locks the cohort's application deposit from the human into the cohort vault. It is
+    /// returned by `withdraw_cohort_deposit` after the human leaves, unless slashed.
+    pub fn post_cohort_deposit(ctx: Context<PostCohortDeposit>) -> Result<()> {
+        let amount = ctx.accounts.cohort_pool.application_deposit;
+        require!(amount > 0, ErrorCode::InvalidAmount);
+        let cpi_ctx = CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.human_token_account.to_account_info(),
+                to: ctx.accounts.cohort_vault.to_account_info(),
+                authority: ctx.accounts.human.to_account_info(),
+            },
+        );
+        token::transfer(cpi_ctx, amount)?;
+
+        let deposit = &mut ctx.accounts.cohort_deposit;
+        deposit.pool = ctx.accounts.cohort_pool.key();
+        deposit.human = ctx.accounts.human.key();
+        deposit.amount = amount;
+        deposit.flagged = false;
+        deposit.bump = ctx.bumps.cohort_deposit;
+        Ok(())
+    }
+
+    /// Dispute hook: a flagged deposit can't be withdrawn and may be slashed.
+    pub fn flag_cohort_deposit(ctx: Context<FlagCohortDeposit>, flagged: bool) -> Result<()> {
+        ctx.accounts.cohort_deposit.flagged = flagged;
+        Ok(())
+    }
+
+    pub fn slash_cohort_deposit(ctx: Context<FlagCohortDeposit>) -> Result<()> {
+        let deposit = &mut ctx.accounts.cohort_deposit;
+        require!(deposit.flagged, ErrorCode::CohortDepositNotFlagged);
+
+        // The slashed deposit stays in the cohort vault and goes to the next epoch's payout
+        let amount = deposit.amount;
+        deposit.amount = 0;
+        let pool = &mut ctx.accounts.cohort_pool;
+        pool.undistributed = pool.undistributed.checked_add(amount).ok_or(ErrorCode::MathOverflow)?;
+        Ok(())
+    }
+
+    /// Returns the human's deposit once they have left the cohort.
+    pub fn withdraw_cohort_deposit(ctx: Context<WithdrawCohortDeposit>) -> Result<()> {
+        require!(ctx.accounts.cohort_member.data_is_empty(), ErrorCode::CohortMemberActive);
+        let deposit = &ctx.accounts.cohort_deposit;
+        require!(!deposit.flagged, ErrorCode::CohortDepositFlagged);
+
+        let pool = &ctx.accounts.cohort_pool;
+        let seeds = &[seeds::COHORT, pool.cohort_id.as_ref(), &[pool.bump]];
+        let signer = &[&seeds[..]];
+        let cpi_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.cohort_vault.to_account_info(),
+                to: ctx.accounts.human_token_account.to_account_info(),
+                authority: pool.to_account_info(),
+            },
+            signer,
+        );
+        token::transfer(cpi_ctx, deposit.amount)?;
+        // Rent is returned to the human via the `close` constraint
+        Ok(())
+    }
+
+    /// Adds the signing human to a cohort. Membership is gated on an Ed25519 signature
+    /// by an active attestation key over `cohort_join_message` in the preceding instruction.
+    pub fn join_cohort(ctx: Context<JoinCohort>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let message = cohort_join_message(&ctx.accounts.cohort_pool.key(), &ctx.accounts.human.key());
+        verify_attestation(&ctx.accounts.attestation_key, &ctx.accounts.instructions_sysvar, 1, &message, now)?;
+
+        let required = ctx.accounts.cohort_pool.application_deposit;
+        if required > 0 {
+            let posted = ctx.accounts.cohort_deposit.as_ref().map_or(0, |d| d.amount);
+            require!(posted >= required, ErrorCode::CohortDepositRequired);
+        }
+
+        let member = &mut ctx.accounts.cohort_member;
+        member.pool = ctx.accounts.cohort_pool.key();
+        member.human = ctx.accounts.human.key();
+        member.joined_at = now;
+        member.bump = ctx.bumps.cohort_member;
+
+        ctx.accounts.cohort_pool.member_count += 1;
+        Ok(())
+    }
+
+    pub fn leave_cohort(ctx: Context<LeaveCohort>) -> Result<()> {
+        // Rent is returned to the human via the `close` constraint
+        let pool = &mut ctx.accounts.cohort_pool;
+        pool.member_count = pool.member_count.saturating_sub(1);
+        Ok(())
+    }
+
+    /// Moves escrow funds into a cohort pool, less the market fee. The pool pays its
+    /// members pro-rata at the next epoch close.
+    pub fn fund_cohort(ctx: Context<FundCohort>, amount: u64) -> Result<()> {
+        require!(amount > 0, ErrorCode::InvalidAmount);
+        let escrow = &mut ctx.accounts.escrow_account;
+        require!(escrow.withdrawable() >= amount, ErrorCode::InsufficientFunds);
+        escrow.balance -= amount;
+
+        let fee_bps = escrow.fee_bps(&ctx.accounts.market_config, Clock::get()?.unix_timestamp);
+        let fee_amount = bps_of(amount, fee_bps)?;
+        let pool_amount = amount - fee_amount;
+
+        transfer_from_escrow_vault(
+            &ctx.accounts.escrow_account,
+            &ctx.accounts.vault,
+            &ctx.accounts.cohort_vault,
+            &ctx.accounts.token_program,
+            pool_amount,
+        )?;
+        transfer_from_escrow_vault(
+            &ctx.accounts.escrow_account,
+            &ctx.accounts.vault,
+            &ctx.accounts.fee_vault,
+            &ctx.accounts.token_program,
+            fee_amount,
+        )?;
+
+        let state = &mut ctx.accounts.fee_vault_state;
+        let window = ctx.accounts.market_config.fee_challenge_window;
+        state.credit_settlement_fees(fee_amount, Clock::get()?.unix_timestamp, window);
+        state.total_collected += fee_amount;
+
+        let pool = &mut ctx.accounts.cohort_pool;
+        pool.undistributed += pool_amount;
+
+        emit!(CohortFunded {
+            pool: pool.key(),
+            escrow: ctx.accounts.escrow_account.key(),
+            amount: pool_amount,
+            fee: fee_amount,
+        });
+        Ok(())
+    }
+
+    /// Closes a cohort epoch with a merkle root over (cohort_epoch, human, amount) leaves
+    /// computed off-chain from member activity. `total` must cover every leaf.
+    pub fn close_cohort_epoch(ctx: Context<CloseCohortEpoch>, merkle_root: [u8; 32], total: u64) -> Result<()> {
+        let pool = &mut ctx.accounts.cohort_pool;
+        require!(total <= pool.undistributed, ErrorCode::InsufficientFunds);
+        pool.undistributed -= total;
+
+        let epoch = &mut ctx.accounts.cohort_epoch;
+        epoch.pool = pool.key();
+        epoch.epoch = pool.current_epoch;
+        epoch.merkle_root = merkle_root;
+        epoch.total = total;
+        epoch.claimed = 0;
+        epoch.bump = ctx.bumps.cohort_epoch;
+
+        pool.current_epoch += 1;
+        Ok(())
+    }
+
+    pub fn claim_cohort(ctx: Context<ClaimCohort>, amount: u64, proof: Vec<[u8; 32]>) -> Result<()> {
+        let epoch = &mut ctx.accounts.cohort_epoch;
+        let leaf = cohort_claim_leaf(&epoch.key(), &ctx.accounts.human.key(), amount);
+        require!(verify_merkle_proof(&proof, &epoch.merkle_root, leaf), ErrorCode::InvalidMerkleProof);
+
+        let claimed = epoch.claimed.checked_add(amount).ok_or(ErrorCode::MathOverflow)?;
+        require!(claimed <= epoch.total, ErrorCode::InsufficientFunds);
+        epoch.claimed = claimed;
+
+        let pool = &ctx.accounts.cohort_pool;
+        let seeds = &[seeds::COHORT, pool.cohort_id.as_ref(), &[pool.bump]];
+        let signer = &[&seeds[..]];
+
+        let cpi_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.cohort_vault.to_account_info(),
+                to: ctx.accounts.human_token_account.to_account_info(),
+                authority: pool.to_account_info(),
+            },
+            signer,
+        );
+        token::transfer(cpi_ctx, amount)?;
+
+        let receipt = &mut ctx.accounts.cohort_claim;
+        receipt.amount = amount;
+        receipt.bump = ctx.bumps.cohort_claim;
+        Ok(())
+    }
+
+    pub fn init_price_index(ctx: Context<InitPriceIndex>, category: [u8; 32]) -> Result<()> {
+        let index = &mut ctx.accounts.price_index;
+        index.category = category;
+        index.ema_price_e6 = 0;
+        index.samples = 0;
+        index.last_updated = 0;
+        index.bump = ctx.bumps.price_index;
+        Ok(())
+    }
+
+    /// Creates a category's spot price feed, posted to by `oracle` (or the admin).
+    pub fn init_spot_price(ctx: Context<InitSpotPrice>, category: [u8; 32], oracle: Pubkey) -> Result<()> {
+        let spot = &mut ctx.accounts.spot_price;
+        spot.category = category;
+        spot.oracle = oracle;
+        spot.price_per_second = 0;
+        spot.updated_at = 0;
+        spot.bump = ctx.bumps.spot_price;
+        Ok(())
+    }
+
+    /// Posts the category's current equilibrium price per attention-second. Sessions
+    /// opened in spot mode snapshot it; it must be refreshed within SPOT_PRICE_MAX_AGE.
+    pub fn post_spot_price(ctx: Context<PostSpotPrice>, price_per_second: u64) -> Result<()> {
+        require!(
+            ctx.accounts.market_config.has_feature(MarketConfig::FEATURE_ORACLE_PRICING),
+            ErrorCode::FeatureDisabled
+        );
+        require!(price_per_second > 0, ErrorCode::InvalidAmount);
+        let spot = &mut ctx.accounts.spot_price;
+        spot.price_per_second = price_per_second;
+        spot.updated_at = Clock::get()?.unix_timestamp;
+
+        emit!(SpotPricePosted {
+            category: spot.category,
+            price_per_second,
+            updated_at: spot.updated_at,
+        });
+        Ok(())
+    }
+
+    /// Creates a category's rolling session length stats. Settlements whose verified seconds
+    /// sit more than `flag_z_score_e2` / 100 standard deviations above the window mean are
+    /// flagged; 0 only records.
+    pub fn init_session_stats(ctx: Context<InitSessionStats>, category: [u8; 32], flag_z_score_e2: u64) -> Result<()> {
+        let mut stats = ctx.accounts.session_stats.load_init()?;
+        stats.category = category;
+        stats.flag_z_score_e2 = flag_z_score_e2;
+        stats.bump = ctx.bumps.session_stats;
+        Ok(())
+    }
+
+    pub fn set_session_stats_threshold(ctx: Context<SetSessionStatsThreshold>, flag_z_score_e2: u64) -> Result<()> {
+        ctx.accounts.session_stats.load_mut()?.flag_z_score_e2 = flag_z_score_e2;
+        Ok(())
+    }
+
+    /// View: returns the category's EMA price per second, scaled by 1e6.
+    pub fn get_market_rate(ctx: Context<GetMarketRate>) -> Result<u64> {
+        Ok(ctx.accounts.price_index.ema_price_e6)
+    }
+
+    /// View: converts a micro-USD price into base units of the configured payment mint.
+    pub fn quote_base_units(ctx: Context<QuoteBaseUnits>, micro_usd: u64) -> Result<u64> {
+        ctx.accounts.market_config.base_units(micro_usd)
+    }
+
+    /// View: the addresses this deployment expects for an agent, one of its tasks and a
+    /// builder code, so clients can check their own derivations against the program's.
+    pub fn derive_addresses(
+        ctx: Context<DeriveAddresses>,
+        agent: Pubkey,
+        task_id: u64,
+        builder_code: [u8; 32],
+    ) -> Result<DerivedAddresses> {
+        let program_id = ctx.program_id;
+        let escrow = pda::escrow(program_id, &agent).0;
+        let task = pda::task(program_id, &escrow, task_id).0;
+        Ok(DerivedAddresses {
+            market_config: pda::market_config(program_id).0,
+            fee_vault_state: pda::fee_vault_state(program_id).0,
+            fee_vault: pda::fee_vault(program_id).0,
+            escrow,
+            escrow_vault: get_associated_token_address(&escrow, &ctx.accounts.market_config.payment_mint),
+            agent_stats: pda::agent_stats(program_id, &agent).0,
+            recovery: pda::recovery(program_id, &escrow).0,
+            credit_line: pda::credit_line(program_id, &escrow).0,
+            task,
+            task_gate: pda::task_gate(program_id, &task).0,
+            task_policy: pda::task_policy(program_id, &task).0,
+            bounty: pda::bounty(program_id, &task).0,
+            task_milestones: pda::task_milestones(program_id, &task).0,
+            builder_balance: pda::builder_balance(program_id, &builder_code).0,
+            builder_stats: pda::builder_stats(program_id, &builder_code).0,
+        })
+    }
+
+    /// Publishes the human's weekly calendar: one 24-bit mask of available UTC hours per
+    /// weekday (index 0 = Sunday), plus the advertised rate for matching.
+    pub fn set_availability(ctx: Context<SetAvailability>, hours: [u32; 7], rate_per_second: u64) -> Result<()> {
+        require!(hours.iter().all(|h| h >> 24 == 0), ErrorCode::InvalidAvailability);
+        let availability = &mut ctx.accounts.availability;
+        availability.human = ctx.accounts.human.key();
+        availability.hours = hours;
+        availability.rate_per_second = rate_per_second;
+        availability.bump = ctx.bumps.availability;
+        Ok(())
+    }
+
+    /// Replaces the agents the human refuses sessions from, up to MAX_BLOCKED_AGENTS.
+    /// Checked by `open_session` whatever the router's own filtering does. Pass no agents
+    /// to unblock everyone.
+    pub fn set_blocked_agents(ctx: Context<SetBlockedAgents>, agents: Vec<Pubkey>) -> Result<()> {
+        require!(agents.len() <= MAX_BLOCKED_AGENTS, ErrorCode::TooManyBlockedAgents);
+        let blocked = &mut ctx.accounts.blocked_agents;
+        blocked.human = ctx.accounts.human.key();
+        blocked.agents = agents;
+        blocked.bump = ctx.bumps.blocked_agents;
+        Ok(())
+    }
+
+    /// Rates the agent of a settled session 1-5 stars, once per receipt, and adds the
+    /// rating to the agent's aggregate. Signed by the human, or submitted by anyone (the
+    /// router, typically) right after an ed25519 instruction carrying the human's signature
+    /// over `agent_rating_message`.
+    pub fn rate_agent(ctx: Context<RateAgent>, rating: u8) -> Result<()> {
+        require!((1..=5).contains(&rating), ErrorCode::InvalidRating);
+        let human = ctx.accounts.human.key();
+        if ctx.accounts.rater.key() != human {
+            let ix_sysvar = &ctx.accounts.instructions_sysvar;
+            let current_index = load_current_index_checked(ix_sysvar)?;
+            require!(current_index > 0, ErrorCode::InvalidAttestation);
+            let signature_ix = load_instruction_at_checked((current_index - 1) as usize, ix_sysvar)?;
+            let message = agent_rating_message(&ctx.accounts.receipt.key(), rating);
+            verify_ed25519_ix(&signature_ix, &human, &message)?;
+        }
+
+        let receipt = &mut ctx.accounts.receipt;
+        require!(receipt.agent_rating == 0, ErrorCode::AlreadyRated);
+        receipt.agent_rating = rating;
+        ctx.accounts.agent_rating.record(receipt.agent, rating, ctx.bumps.agent_rating)?;
+
+        emit!(AgentRated {
+            agent: receipt.agent,
+            human,
+            receipt: receipt.key(),
+            rating,
+        });
+        Ok(())
+    }
+
+    /// Gasless deposit: the agent signs (escrow, amount, expiry, nonce) off-chain and
+    /// pre-approves the escrow PDA as delegate on their token account; any relayer can
+    /// then submit the deposit and pay for the transaction. Primary mint only.
+    pub fn deposit_with_authorization(
+        ctx: Context<DepositWithAuthorization>,
+        amount: u64,
+        expires_at: i64,
+        nonce: u64,
+    ) -> Result<()> {
+        require!(amount > 0, ErrorCode::InvalidAmount);
+        require!(Clock::get()?.unix_timestamp <= expires_at, ErrorCode::AuthorizationExpired);
+
+        let agent = ctx.accounts.agent.key();
+        let ix_sysvar = &ctx.accounts.instructions_sysvar;
+        let current_index = load_current_index_checked(ix_sysvar)?;
+        require!(current_index > 0, ErrorCode::InvalidAttestation);
+        let signature_ix = load_instruction_at_checked((current_index - 1) as usize, ix_sysvar)?;
+        let message = deposit_authorization_message(&ctx.accounts.escrow_account.key(), amount, expires_at, nonce);
+        verify_ed25519_ix(&signature_ix, &agent, &message)?;
+
+        // The nonce account's init is the replay guard
+        let authorization = &mut ctx.accounts.deposit_authorization;
+        authorization.agent = agent;
+        authorization.nonce = nonce;
+        authorization.bump = ctx.bumps.deposit_authorization;
+
+        let escrow = &mut ctx.accounts.escrow_account;
+        require_keys_eq!(escrow.mint, Pubkey::default(), ErrorCode::InvalidMint);
+        escrow.bind_agent(agent, ctx.bumps.escrow_account)?;
+
+        // The escrow PDA moves the funds as the agent's approved delegate
+        let seeds = &[seeds::ESCROW, agent.as_ref(), &[escrow.bump]];
+        let signer = &[&seeds[..]];
+        let cpi_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.agent_token_account.to_account_info(),
+                to: ctx.accounts.vault.to_account_info(),
+                authority: escrow.to_account_info(),
+            },
+            signer,
+        );
+        token::transfer(cpi_ctx, amount)?;
+
+        escrow.balance += amount;
+        escrow.record_deposit(amount);
+        ctx.accounts.vault.reload()?;
+        ctx.accounts.market_config.check_deposit_cap(escrow, &ctx.accounts.vault)?;
+        emit!(EscrowFunded {
+            agent: escrow.agent,
+            amount,
+            new_balance: escrow.balance,
+            cumulative_deposited: escrow.cumulative_deposited,
+        });
+        Ok(())
+    }
+
+    /// ERC-3009 style receive-with-authorization: any wallet (`from`) funds the agent's
+    /// escrow by signing (from, escrow, value, valid_after, valid_before, nonce) off-chain,
+    /// having approved the escrow PDA as delegate on its token account once. As with
+    /// `receiveWithAuthorization`, only the payee (the agent) can submit it, so the
+    /// authorization can't be front-run. Nonces are random 32-byte values, usable once per
+    /// `from`. Credited as an ordinary deposit; primary mint only.
+    pub fn receive_with_authorization(
+        ctx: Context<ReceiveWithAuthorization>,
+        value: u64,
+        valid_after: i64,
+        valid_before: i64,
+        nonce: [u8; 32],
+    ) -> Result<()> {
+        require!(value > 0, ErrorCode::InvalidAmount);
+        let now = Clock::get()?.unix_timestamp;
+        require!(now > valid_after, ErrorCode::AuthorizationNotYetValid);
+        require!(now < valid_before, ErrorCode::AuthorizationExpired);
+
+        let from = ctx.accounts.from.key();
+        let escrow_key = ctx.accounts.escrow_account.key();
+        let ix_sysvar = &ctx.accounts.instructions_sysvar;
+        let current_index = load_current_index_checked(ix_sysvar)?;
+        require!(current_index > 0, ErrorCode::InvalidAttestation);
+        let signature_ix = load_instruction_at_checked((current_index - 1) as usize, ix_sysvar)?;
+        let message = transfer_authorization_message(&from, &escrow_key, value, valid_after, valid_before, &nonce);
+        verify_ed25519_ix(&signature_ix, &from, &message)?;
+
+        // The nonce account's init is the replay guard
+        let authorization = &mut ctx.accounts.transfer_authorization;
+        authorization.from = from;
+        authorization.nonce = nonce;
+        authorization.bump = ctx.bumps.transfer_authorization;
+
+        let agent = ctx.accounts.agent.key();
+        let escrow = &mut ctx.accounts.escrow_account;
+        require_keys_eq!(escrow.mint, Pubkey::default(), ErrorCode::InvalidMint);
+        escrow.bind_agent(agent, ctx.bumps.escrow_account)?;
+
+        // The escrow PDA moves the funds as the funder's approved delegate
+        let seeds = &[seeds::ESCROW, agent.as_ref(), &[escrow.bump]];
+        let signer = &[&seeds[..]];
+        let cpi_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.from_token_account.to_account_info(),
+                to: ctx.accounts.vault.to_account_info(),
+                authority: escrow.to_account_info(),
+            },
+            signer,
+        );
+        token::transfer(cpi_ctx, value)?;
+
+        escrow.balance += value;
+        escrow.record_deposit(value);
+        ctx.accounts.vault.reload()?;
+        ctx.accounts.market_config.check_deposit_cap(escrow, &ctx.accounts.vault)?;
+        emit!(AuthorizationUsed {
+            authorizer: from,
+            nonce,
+            escrow: escrow_key,
+            value,
+        });
+        emit!(EscrowFunded {
+            agent: escrow.agent,
+            amount: value,
+            new_balance: escrow.balance,
+            cumulative_deposited: escrow.cumulative_deposited,
+        });
+        Ok(())
+    }
+
+    /// Cross-chain top-up: credits the USDC that a CCTP `receive_message` in the preceding
+    /// instruction minted into the agent's escrow vault. Anyone can submit the pair; the
+    /// burn message names the vault as mint recipient and fixes the amount.
+    pub fn credit_cctp_deposit(ctx: Context<CreditCctpDeposit>, source_domain: u32, cctp_nonce: u64) -> Result<()> {
+        let ix_sysvar = &ctx.accounts.instructions_sysvar;
+        let current_index = load_current_index_checked(ix_sysvar)?;
+        require!(current_index > 0, ErrorCode::InvalidCctpMessage);
+        let receive_ix = load_instruction_at_checked((current_index - 1) as usize, ix_sysvar)?;
+        let burn = parse_cctp_receive_message(&receive_ix)?;
+        require!(
+            burn.source_domain == source_domain && burn.nonce == cctp_nonce,
+            ErrorCode::InvalidCctpMessage
+        );
+        require_keys_eq!(burn.mint_recipient, ctx.accounts.vault.key(), ErrorCode::InvalidCctpMessage);
+        require!(burn.amount > 0, ErrorCode::InvalidAmount);
+
+        // The deposit record's init is the replay guard
+        let deposit = &mut ctx.accounts.cctp_deposit;
+        deposit.escrow = ctx.accounts.escrow_account.key();
+        deposit.source_domain = source_domain;
+        deposit.nonce = cctp_nonce;
+        deposit.amount = burn.amount;
+        deposit.bump = ctx.bumps.cctp_deposit;
+
+        let escrow = &mut ctx.accounts.escrow_account;
+        require_keys_eq!(escrow.mint, Pubkey::default(), ErrorCode::InvalidMint);
+        escrow.bind_agent(ctx.accounts.agent.key(), ctx.bumps.escrow_account)?;
+        escrow.balance = escrow.balance.checked_add(burn.amount).ok_or(ErrorCode::MathOverflow)?;
+        escrow.record_deposit(burn.amount);
+        ctx.accounts.market_config.check_deposit_cap(escrow, &ctx.accounts.vault)?;
+        emit!(EscrowFunded {
+            agent: escrow.agent,
+            amount: burn.amount,
+            new_balance: escrow.balance,
+            cumulative_deposited: escrow.cumulative_deposited,
+        });
+
+        emit!(CctpDepositCredited {
+            escrow: escrow.key(),
+            source_domain,
+            nonce: cctp_nonce,
+            amount: burn.amount,
+            depositor: burn.message_sender,
+        });
+        Ok(())
+    }
+
+    /// Migration, old side: moves the escrow's withdrawable balance straight into the
+    /// agent's escrow vault on `destination_program` and records a voucher PDA that the
+    /// destination's `import_escrow` credits exactly once.
+    pub fn export_escrow(ctx: Context<ExportEscrow>, export_id: u64, destination_program: Pubkey) -> Result<()> {
+        let agent_key = ctx.accounts.agent.key();
+        let (destination_escrow, _) =
+            Pubkey::find_program_address(&[seeds::ESCROW, agent_key.as_ref()], &destination_program);
+        require_keys_eq!(ctx.accounts.destination_vault.owner, destination_escrow, ErrorCode::InvalidVault);
+
+        let escrow = &mut ctx.accounts.escrow_account;
+        let amount = escrow.withdrawable();
+        require!(amount > 0, ErrorCode::InsufficientFunds);
+        transfer_from_escrow_vault(
+            escrow,
+            &ctx.accounts.vault,
+            &ctx.accounts.destination_vault,
+            &ctx.accounts.token_program,
+            amount,
+        )?;
+        escrow.balance -= amount;
+
+        let voucher = &mut ctx.accounts.escrow_export;
+        voucher.agent = agent_key;
+        voucher.export_id = export_id;
+        voucher.mint = escrow.mint;
+        voucher.amount = amount;
+        voucher.destination_program = destination_program;
+        voucher.destination_vault = ctx.accounts.destination_vault.key();
+        voucher.exported_at = Clock::get()?.unix_timestamp;
+        voucher.bump = ctx.bumps.escrow_export;
+
+        emit!(EscrowExported {
+            agent: agent_key,
+            export_id,
+            amount,
+            destination_program,
+        });
+        Ok(())
+    }
+
+    /// Migration, new side: credits an export voucher written by the configured
+    /// `migration_source`. The import record PDA makes each voucher single-use.
+    pub fn import_escrow(ctx: Context<ImportEscrow>) -> Result<()> {
+        let voucher = {
+            let info = ctx.accounts.escrow_export.to_account_info();
+            let data = info.try_borrow_data()?;
+            EscrowExport::try_deserialize(&mut &data[..])?
+        };
+        require_keys_eq!(voucher.destination_program, crate::ID, ErrorCode::InvalidExport);
+        require_keys_eq!(voucher.agent, ctx.accounts.agent.key(), ErrorCode::InvalidExport);
+
+        let escrow = &mut ctx.accounts.escrow_account;
+        if escrow.agent == Pubkey::default() {
+            escrow.mint = voucher.mint;
+        }
+        require_keys_eq!(escrow.mint, voucher.mint, ErrorCode::InvalidMint);
+        escrow.bind_agent(voucher.agent, ctx.bumps.escrow_account)?;
+        require_keys_eq!(ctx.accounts.vault.key(), voucher.destination_vault, ErrorCode::InvalidVault);
+        escrow.balance += voucher.amount;
+        ctx.accounts.market_config.check_deposit_cap(escrow, &ctx.accounts.vault)?;
+
+        let record = &mut ctx.accounts.escrow_import;
+        record.escrow_export = ctx.accounts.escrow_export.key();
+        record.amount = voucher.amount;
+        record.bump = ctx.bumps.escrow_import;
+
+        emit!(EscrowImported {
+            agent: voucher.agent,
+            export_id: voucher.export_id,
+            amount: voucher.amount,
+            source_program: ctx.accounts.market_config.migration_source,
+        });
+        Ok(())
+    }
+
+    /// View: recomputes what the vaults should hold from program state. `remaining_accounts`
+    /// starts with `liability_count` BuilderBalance / MatcherBalance / Holdback accounts owed
+    /// out of the fee vault, followed by (escrow_account, vault) pairs. A partial liability
+    /// list still gives a lower bound. Escrow vaults may hold more than `escrow.balance`
+    /// (task and forward reservations stay in the vault), so only deficits count. Every
+    /// deficit is emitted as a `SolvencyDiscrepancy`; with `strict` the call fails instead
+    /// of returning.
+    pub fn assert_solvency<'info>(
+        ctx: Context<'_, '_, 'info, 'info, AssertSolvency<'info>>,
+        liability_count: u16,
+        strict: bool,
+    ) -> Result<SolvencyReport> {
+        let liability_count = liability_count as usize;
+        require!(ctx.remaining_accounts.len() >= liability_count, ErrorCode::InvalidSolvencyAccounts);
+        let (liabilities, escrows) = ctx.remaining_accounts.split_at(liability_count);
+        let escrows = escrows.chunks_exact(2);
+        require!(escrows.remainder().is_empty(), ErrorCode::InvalidSolvencyAccounts);
+        let escrows_checked = escrows.len() as u32;
+
+        let state = &ctx.accounts.fee_vault_state;
+        let mut owed = state.protocol_balance as u128 + state.pending_settlement_fees as u128;
+        if let Some(pool) = &ctx.accounts.revenue_pool {
+            owed += pool.reserved as u128;
+        }
+        for info in liabilities {
+            owed += if let Ok(builder) = Account::<BuilderBalance>::try_from(info) {
+                builder.balance as u128
+            } else if let Ok(matcher) = Account::<MatcherBalance>::try_from(info) {
+                matcher.balance as u128
+            } else if let Ok(holdback) = Account::<Holdback>::try_from(info) {
+                holdback.amount as u128
+            } else {
+                return err!(ErrorCode::InvalidSolvencyAccounts);
+            };
+        }
+
+        let fee_vault = &ctx.accounts.fee_vault;
+        let fee_vault_liabilities = u64::try_from(owed).map_err(|_| ErrorCode::MathOverflow)?;
+        let mut shortfall = record_deficit(fee_vault.key(), fee_vault_liabilities, fee_vault.amount);
+
+        for pair in escrows {
+            let escrow_info = &pair[0];
+            let escrow = Account::<EscrowAccount>::try_from(escrow_info)?;
+            let expected_escrow = Pubkey::create_program_address(
+                &[seeds::ESCROW, escrow.agent.as_ref(), &[escrow.bump]],
+                ctx.program_id,
+            ).map_err(|_| ErrorCode::InvalidEscrow)?;
+            require_keys_eq!(escrow_info.key(), expected_escrow, ErrorCode::InvalidEscrow);
+
+            let vault = Account::<TokenAccount>::try_from(&pair[1])?;
+            require_keys_eq!(vault.owner, escrow_info.key(), ErrorCode::InvalidVault);
+            shortfall += record_deficit(vault.key(), escrow.balance, vault.amount);
+        }
+
+        require!(!strict || shortfall == 0, ErrorCode::Insolvent);
+        Ok(SolvencyReport {
+            fee_vault_amount: fee_vault.amount,
+            fee_vault_liabilities,
+            escrows_checked,
+            shortfall: u64::try_from(shortfall).map_err(|_| ErrorCode::MathOverflow)?,
+        })
+    }
+
+    /// Devnet only: hands the test USDC mint's authority to the faucet PDA, which then
+    /// mints `drip_amount` per wallet at most once every `cooldown` seconds. Anchor can't
+    /// cfg out instructions, so builds without `devnet-faucet` reject both faucet calls.
+    pub fn init_faucet(ctx: Context<InitFaucet>, drip_amount: u64, cooldown: i64) -> Result<()> {
+        require!(cfg!(feature = "devnet-faucet"), ErrorCode::FaucetDisabled);
+        require!(drip_amount > 0 && cooldown > 0, ErrorCode::InvalidAmount);
+        let faucet = &mut ctx.accounts.faucet;
+        faucet.mint = ctx.accounts.mint.key();
+        faucet.drip_amount = drip_amount;
+        faucet.cooldown = cooldown;
+        faucet.bump = ctx.bumps.faucet;
+
+        let cpi_ctx = CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            SetAuthority {
+                current_authority: ctx.accounts.admin.to_account_info(),
+                account_or_mint: ctx.accounts.mint.to_account_info(),
+            },
+        );
+        token::set_authority(cpi_ctx, AuthorityType::MintTokens, Some(faucet.key()))
+    }
+
+    /// Devnet only: permissionless drip of test USDC to the signing wallet's ATA.
+    pub fn faucet_drip(ctx: Context<FaucetDrip>) -> Result<()> {
+        require!(cfg!(feature = "devnet-faucet"), ErrorCode::FaucetDisabled);
+        let now = Clock::get()?.unix_timestamp;
+        let faucet = &ctx.accounts.faucet;
+        let claim = &mut ctx.accounts.faucet_claim;
+        require!(
+            claim.last_drip == 0 || now >= claim.last_drip.saturating_add(faucet.cooldown),
+            ErrorCode::FaucetCooldown
+        );
+        claim.last_drip = now;
+        claim.bump = ctx.bumps.faucet_claim;
+
+        let seeds = &[seeds::FAUCET, &[faucet.bump]];
+        let signer = &[&seeds[..]];
+        let cpi_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            MintTo {
+                mint: ctx.accounts.mint.to_account_info(),
+                to: ctx.accounts.wallet_token_account.to_account_info(),
+                authority: faucet.to_account_info(),
+            },
+            signer,
+        );
+        token::mint_to(cpi_ctx, faucet.drip_amount)
+    }
+}
+
+/// Emits a discrepancy when `account` holds less than `expected`; returns the deficit.
+fn record_deficit(account: Pubkey, expected: u64, actual: u64) -> u128 {
+    if actual >= expected {
+        return 0;
+    }
+    emit!(SolvencyDiscrepancy { account, expected, actual });
+    (expected - actual) as u128
+}
+
+/// How a settlement's gross amount is divided. The protocol keeps `fee_amount - builder_share`,
+/// and `fee_amount + holdback_amount + user_payout` always equals the gross amount.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SettlementSplit {
+    pub fee_amount: u64,
+    pub builder_share: u64,
+    pub holdback_amount: u64,
+    pub user_payout: u64,
+}
+
+/// Splits `total_payout` into fee, builder share (when a builder is credited), holdback
+/// and the human's immediate payout. Every rate is in basis points and rounds down.
+pub fn split_settlement(
+    total_payout: u64,
+    fee_bps: u64,
+    holdback_bps: u64,
+    builder_share_bps: Option<u64>,
+) -> Result<SettlementSplit> {
+    let fee_amount = bps_of(total_payout, fee_bps)?;
+    let builder_share = match builder_share_bps {
+        Some(bps) => bps_of(fee_amount, bps)?,
+        None => 0,
+    };
+    let net_payout = total_payout - fee_amount;
+    let holdback_amount = bps_of(net_payout, holdback_bps)?;
+    Ok(SettlementSplit {
+        fee_amount,
+        builder_share,
+        holdback_amount,
+        user_payout: net_payout - holdback_amount,
+    })
+}
+
+/// `amount * bps / 10_000`, rounded down. Fails for rates above 100%.
+pub fn bps_of(amount: u64, bps: u64) -> Result<u64> {
+    require!(bps <= MAX_BPS as u64, CommonError::InvalidBps);
+    // Widened so large amounts can't overflow the intermediate product
+    Ok((amount as u128 * bps as u128 / BPS_DENOMINATOR as u128) as u64)
+}
+
+/// Converts a micro-USD amount into base units of a mint with `decimals` decimals.
+/// Fails rather than rounding when the mint is too coarse to hold the amount exactly.
+pub fn micro_usd_to_base_units(micro_usd: u64, decimals: u8) -> Result<u64> {
+    let scale = |exp: u8| 10u64.checked_pow(exp as u32).ok_or(error!(ErrorCode::MathOverflow));
+    if decimals >= MICRO_USD_DECIMALS {
+        let factor = scale(decimals - MICRO_USD_DECIMALS)?;
+        Ok(micro_usd.checked_mul(factor).ok_or(ErrorCode::MathOverflow)?)
+    } else {
+        let divisor = scale(MICRO_USD_DECIMALS - decimals)?;
+        let (base_units, dust) = (micro_usd / divisor, micro_usd % divisor);
+        require!(dust == 0, ErrorCode::PriceNotRepresentable);
+        Ok(base_units)
+    }
+}
+
+/// Canonical message an attestation key signs for a settlement.
+pub fn settlement_attestation_message(
+    escrow: &Pubkey,
+    human: &Pubkey,
+    verified_seconds: u64,
+    agreed_price_per_second: u64,
+    nonce: u64,
+    quality_multiplier_bps: u16,
+) -> Vec<u8> {
+    let mut message = Vec::with_capacity(32 + 32 + 8 + 8 + 8 + 2);
+    message.extend_from_slice(escrow.as_ref());
+    message.extend_from_slice(human.as_ref());
+    message.extend_from_slice(&verified_seconds.to_le_bytes());
+    message.extend_from_slice(&agreed_price_per_second.to_le_bytes());
+    message.extend_from_slice(&nonce.to_le_bytes());
+    message.extend_from_slice(&quality_multiplier_bps.to_le_bytes());
+    message
+}
+
+/// Checks that the instruction `back` positions before the current one is an Ed25519
+/// signature over `message` by `attestation_key`, which must be active at `now`.
+fn verify_attestation(
+    attestation_key: &AttestationKey,
+    ix_sysvar: &AccountInfo,
+    back: usize,
+    message: &[u8],
+    now: i64,
+) -> Result<()> {
+    require!(
+        !attestation_key.revoked && now >= attestation_key.active_at,
+        ErrorCode::AttestationKeyInactive
+    );
+    let current_index = load_current_index_checked(ix_sysvar)? as usize;
+    require!(back > 0 && current_index >= back, ErrorCode::InvalidAttestation);
+    let attestation_ix = load_instruction_at_checked(current_index - back, ix_sysvar)?;
+    verify_ed25519_ix(&attestation_ix, &attestation_key.key, message)
+}
+
+/// Settlement attestation policy: a supplied registry key must have signed `message` in the
+/// instruction `back` positions before the current one, and a key must be supplied while
+/// `config` requires attestations.
+fn check_settlement_attestation(
+    config: &MarketConfig,
+    attestation_key: Option<&AttestationKey>,
+    ix_sysvar: Option<&AccountInfo>,
+    back: usize,
+    message: &[u8],
+    now: i64,
+) -> Result<()> {
+    match attestation_key {
+        Some(attestation_key) => {
+            let ix_sysvar = ix_sysvar.ok_or(ErrorCode::InvalidAttestation)?;
+            verify_attestation(attestation_key, ix_sysvar, back, message, now)
+        }
+        None => {
+            require!(!config.attestation_required(now), ErrorCode::SettlementAttestationRequired);
+            Ok(())
+        }
+    }
+}
+
+/// Checks that `ix` is an Ed25519 program instruction carrying exactly one
+/// signature by `signer` over `message`, with all data inline.
+pub fn verify_ed25519_ix(ix: &Instruction, signer: &Pubkey, message: &[u8]) -> Result<()> {
+    require_keys_eq!(ix.program_id, ed25519_program::ID, ErrorCode::InvalidAttestation);
+
+    let data = &ix.data;
+    // Header: num_signatures (u8), padding (u8), then 7 u16 offsets
+    require!(data.len() >= 16 && data[0] == 1, ErrorCode::InvalidAttestation);
+    let read_u16 = |i: usize| u16::from_le_bytes([data[i], data[i + 1]]);
+
+    let signature_ix_index = read_u16(4);
+    let public_key_offset = read_u16(6) as usize;
+    let public_key_ix_index = read_u16(8);
+    let message_offset = read_u16(10) as usize;
+    let message_size = read_u16(12) as usize;
+    let message_ix_index = read_u16(14);
+
+    // Everything must live in the Ed25519 instruction itself
+    require!(
+        signature_ix_index == u16::MAX && public_key_ix_index == u16::MAX && message_ix_index == u16::MAX,
+        ErrorCode::InvalidAttestation
+    );
+
+    let public_key = data.get(public_key_offset..public_key_offset + 32)
+        .ok_or(ErrorCode::InvalidAttestation)?;
+    let signed_message = data.get(message_offset..message_offset + message_size)
+        .ok_or(ErrorCode::InvalidAttestation)?;
+
+    require!(public_key == signer.as_ref(), ErrorCode::InvalidAttestation);
+    require!(signed_message == message, ErrorCode::InvalidAttestation);
+    Ok(())
+}
+
+/// Builder code for a human-readable referral string: its SHA-256. Builders register
+/// under this code to be credited for settlements that carry the string.
+pub fn builder_code_from_str(code_str: &str) -> [u8; 32] {
+    anchor_lang::solana_program::hash::hash(code_str.as_bytes()).to_bytes()
+}
+
+/// Settlement's builder code: the hashed referral string if given, else the raw code.
+pub fn resolve_builder_code(builder_code: Option<[u8; 32]>, code_str: Option<&str>) -> Option<[u8; 32]> {
+    code_str.map(builder_code_from_str).or(builder_code)
+}
+
+/// Simple interest on a released holdback for the length of its review period.
+pub fn holdback_interest(amount: u64, bps_per_day: u16, holdback_period: i64) -> Result<u64> {
+    let interest = amount as u128 * bps_per_day as u128 * holdback_period.max(0) as u128
+        / (86_400 * BPS_DENOMINATOR as u128);
+    u64::try_from(interest).map_err(|_| error!(ErrorCode::MathOverflow))
+}
+
+/// Months since January 1970 (UTC) for a unix timestamp.
+pub fn month_index(unix_timestamp: i64) -> u32 {
+    // Civil-from-days (proleptic Gregorian calendar)
+    let z = unix_timestamp.div_euclid(86_400) + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    ((year - 1970) * 12 + (month - 1)).max(0) as u32
+}
+
+/// Creates a program-owned PDA the way Anchor's `init` does: tops up whatever lamports it
+/// already holds to `rent`, then allocates and assigns it. A plain `create_account` fails
+/// on an address someone has pre-funded, which anyone can do to a predictable PDA.
+fn create_pda_account<'info>(
+    payer: &AccountInfo<'info>,
+    target: &AccountInfo<'info>,
+    system_program: &Program<'info, System>,
+    rent: u64,
+    space: usize,
+    owner: &Pubkey,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    let shortfall = rent.saturating_sub(target.lamports());
+    if shortfall > 0 {
+        system_program::transfer(
+            CpiContext::new(
+                system_program.to_account_info(),
+                system_program::Transfer { from: payer.clone(), to: target.clone() },
+            ),
+            shortfall,
+        )?;
+    }
+    system_program::allocate(
+        CpiContext::new_with_signer(
+            system_program.to_account_info(),
+            system_program::Allocate { account_to_allocate: target.clone() },
+            signer_seeds,
+        ),
+        space as u64,
+    )?;
+    system_program::assign(
+        CpiContext::new_with_signer(
+            system_program.to_account_info(),
+            system_program::Assign { account_to_assign: target.clone() },
+            signer_seeds,
+        ),
+        owner,
+    )
+}
+
+/// Transfers `amount` out of an escrow vault, signed by the escrow PDA.
+fn transfer_from_escrow_vault<'info>(
+    escrow: &Account<'info, EscrowAccount>,
+    vault: &Account<'info, TokenAccount>,
+    to: &Account<'info, TokenAccount>,
+    token_program: &Program<'info, Token>,
+    amount: u64,
+) -> Result<()> {
+    if amount == 0 {
+        return Ok(());
+    }
+    let seeds = &[seeds::ESCROW, escrow.agent.as_ref(), &[escrow.bump]];
+    let signer = &[&seeds[..]];
+    let cpi_ctx = CpiContext::new_with_signer(
+        token_program.to_account_info(),
+        Transfer {
+            from: vault.to_account_info(),
+            to: to.to_account_info(),
+            authority: escrow.to_account_info(),
+        },
+        signer,
+    );
+    token::transfer(cpi_ctx, amount)
+}
+
+/// Integer square root, rounded down.
+fn isqrt(n: u128) -> u64 {
+    if n < 2 {
+        return n as u64;
+    }
+    // Newton's method from an overestimate converges monotonically downwards
+    let mut x = n;
+    let mut y = x.div_ceil(2);
+    while y < x {
+        x = y;
+        y = (x + n / x) / 2;
+    }
+    x as u64
+}
+
+/// Reads a human's PayoutPrefs, or None if they never created one.
+fn load_payout_prefs(info: &AccountInfo) -> Result<Option<PayoutPrefs>> {
+    if info.owner != &crate::ID || info.data_is_empty() {
+        return Ok(None);
+    }
+    let data = info.try_borrow_data()?;
+    if data.len() >= PayoutPrefs::SPACE {
+        return Ok(Some(PayoutPrefs::try_deserialize(&mut &data[..])?));
+    }
+    // Older, shorter layout: appended fields read as zero
+    let mut padded = data.to_vec();
+    padded.resize(PayoutPrefs::SPACE, 0);
+    Ok(Some(PayoutPrefs::try_deserialize(&mut &padded[..])?))
+}
+
+/// Reads a human's PayoutSplit, or None if they have no split legs.
+fn load_payout_split(info: &AccountInfo) -> Result<Option<PayoutSplit>> {
+    if info.owner != &crate::ID || info.data_is_empty() {
+        return Ok(None);
+    }
+    let data = info.try_borrow_data()?;
+    let split = PayoutSplit::try_deserialize(&mut &data[..])?;
+    Ok((!split.legs.is_empty()).then_some(split))
+}
+
+/// Whether a program-owned PDA passed as an unchecked account has been initialized.
+fn account_exists(info: &AccountInfo) -> bool {
+    info.owner == &crate::ID && !info.data_is_empty()
+}
+
+/// Borrows a category's SessionLengthStats, or None if the category has none.
+fn load_session_stats_mut<'a>(info: &'a AccountInfo) -> Result<Option<RefMut<'a, SessionLengthStats>>> {
+    if !account_exists(info) {
+        return Ok(None);
+    }
+    let data = info.try_borrow_mut_data()?;
+    let end = 8 + std::mem::size_of::<SessionLengthStats>();
+    require!(
+        data.len() >= end && data[..8] == SessionLengthStats::DISCRIMINATOR,
+        anchor_lang::error::ErrorCode::AccountDiscriminatorMismatch
+    );
+    Ok(Some(RefMut::map(data, |data| bytemuck::from_bytes_mut(&mut data[8..end]))))
+}
+
+/// Reads a human's OrgMember, or None if they are not in an org.
+fn load_org_member(info: &AccountInfo) -> Result<Option<OrgMember>> {
+    if info.owner != &crate::ID || info.data_is_empty() {
+        return Ok(None);
+    }
+    let data = info.try_borrow_data()?;
+    Ok(Some(OrgMember::try_deserialize(&mut &data[..])?))
+}
+
+/// Takes the human's org fee out of `amount` when they belong to an org, paying it to the
+/// org treasury with `cpi`'s source and signer. Returns what is left for the human.
+fn take_org_fee<'info>(
+    cpi: &CpiContext<'_, '_, '_, 'info, Transfer<'info>>,
+    org_member: &AccountInfo<'info>,
+    org: Option<&mut Account<'info, Org>>,
+    org_treasury: Option<&Account<'info, TokenAccount>>,
+    amount: u64,
+) -> Result<u64> {
+    let Some(member) = load_org_member(org_member)? else {
+        return Ok(amount);
+    };
+    let org = org.ok_or(ErrorCode::OrgAccountMissing)?;
+    let treasury = org_treasury.ok_or(ErrorCode::OrgAccountMissing)?;
+    require_keys_eq!(org.key(), member.org, ErrorCode::InvalidOrg);
+    require_keys_eq!(treasury.key(), org.treasury, ErrorCode::InvalidOrg);
+
+    let org_fee = bps_of(amount, org.fee_bps as u64)?;
+    if org_fee > 0 {
+        let fee_cpi = CpiContext::new_with_signer(
+            cpi.program.clone(),
+            Transfer {
+                from: cpi.accounts.from.clone(),
+                to: treasury.to_account_info(),
+                authority: cpi.accounts.authority.clone(),
+            },
+            cpi.signer_seeds,
+        );
+        token::transfer(fee_cpi, org_fee)?;
+    }
+    let member_payout = amount - org_fee;
+    org.total_org_fees = org.total_org_fees.saturating_add(org_fee);
+    org.total_member_payouts = org.total_member_payouts.saturating_add(member_payout);
+    org.settlement_count += 1;
+
+    emit!(OrgFeeTaken {
+        org: org.key(),
+        human: member.human,
+        org_fee,
+        member_payout,
+    });
+    Ok(member_payout)
+}
+
+/// Pays `amount` to a human through `cpi` (whose destination is their own wallet), first
+/// carving out each split leg's share. `leg_accounts` must be the legs' token accounts, in order.
+fn pay_human<'info>(
+    cpi: CpiContext<'_, '_, '_, 'info, Transfer<'info>>,
+    split: Option<&PayoutSplit>,
+    leg_accounts: &[AccountInfo<'info>],
+    amount: u64,
+) -> Result<()> {
+    let legs = split.map_or(&[][..], |s| &s.legs[..]);
+    require!(leg_accounts.len() == legs.len(), ErrorCode::InvalidPayoutSplit);
+
+    let mut remainder = amount;
+    for (leg, leg_account) in legs.iter().zip(leg_accounts) {
+        require_keys_eq!(leg_account.key(), leg.wallet, ErrorCode::InvalidPayoutSplit);
+        let share = bps_of(amount, leg.bps as u64)?;
+        if share > 0 {
+            let leg_cpi = CpiContext::new_with_signer(
+                cpi.program.clone(),
+                Transfer {
+                    from: cpi.accounts.from.clone(),
+                    to: leg_account.clone(),
+                    authority: cpi.accounts.authority.clone(),
+                },
+                cpi.signer_seeds,
+            );
+            token::transfer(leg_cpi, share)?;
+            remainder -= share;
+        }
+    }
+    if remainder > 0 {
+        token::transfer(cpi, remainder)?;
+    }
+    Ok(())
+}
+
+/// Reads a human's Availability, or None if they never published one.
+fn load_availability(info: &AccountInfo) -> Result<Option<Availability>> {
+    if info.owner != &crate::ID || info.data_is_empty() {
+        return Ok(None);
+    }
+    let data = info.try_borrow_data()?;
+    Ok(Some(Availability::try_deserialize(&mut &data[..])?))
+}
+
+fn load_blocked_agents(info: &AccountInfo) -> Result<Option<BlockedAgents>> {
+    if info.owner != &crate::ID || info.data_is_empty() {
+        return Ok(None);
+    }
+    let data = info.try_borrow_data()?;
+    Ok(Some(BlockedAgents::try_deserialize(&mut &data[..])?))
+}
+
+/// Moves `amount` between the credit line's collateral account and another token
+/// account, signed by the credit line PDA.
+fn transfer_from_collateral<'info>(
+    credit_line: &Account<'info, CreditLine>,
+    collateral: &Account<'info, TokenAccount>,
+    to: &Account<'info, TokenAccount>,
+    token_program: &Program<'info, Token>,
+    amount: u64,
+) -> Result<()> {
+    let seeds = &[seeds::CREDIT_LINE, credit_line.escrow.as_ref(), &[credit_line.bump]];
+    let signer = &[&seeds[..]];
+    let cpi_ctx = CpiContext::new_with_signer(
+        token_program.to_account_info(),
+        Transfer {
+            from: collateral.to_account_info(),
+            to: to.to_account_info(),
+            authority: credit_line.to_account_info(),
+        },
+        signer,
+    );
+    token::transfer(cpi_ctx, amount)
+}
+
+/// Moves `amount` of LST out of a position's vault, signed by the position PDA.
+fn transfer_from_lst_vault<'info>(
+    position: &Account<'info, LstPosition>,
+    lst_vault: &Account<'info, TokenAccount>,
+    to: &Account<'info, TokenAccount>,
+    token_program: &Program<'info, Token>,
+    amount: u64,
+) -> Result<()> {
+    let seeds = &[seeds::LST_POSITION, position.escrow.as_ref(), position.mint.as_ref(), &[position.bump]];
+    let signer = &[&seeds[..]];
+    let cpi_ctx = CpiContext::new_with_signer(
+        token_program.to_account_info(),
+        Transfer {
+            from: lst_vault.to_account_info(),
+            to: to.to_account_info(),
+            authority: position.to_account_info(),
+        },
+        signer,
+    );
+    token::transfer(cpi_ctx, amount)
+}
+
+/// Moves `amount` out of the liquidity buffer's vault, signed by the buffer PDA.
+fn transfer_from_liquidity_buffer<'info>(
+    buffer: &Account<'info, LiquidityBuffer>,
+    buffer_vault: &Account<'info, TokenAccount>,
+    to: &Account<'info, TokenAccount>,
+    token_program: &Program<'info, Token>,
+    amount: u64,
+) -> Result<()> {
+    let seeds = &[seeds::LIQUIDITY_BUFFER, &[buffer.bump]];
+    let signer = &[&seeds[..]];
+    let cpi_ctx = CpiContext::new_with_signer(
+        token_program.to_account_info(),
+        Transfer {
+            from: buffer_vault.to_account_info(),
+            to: to.to_account_info(),
+            authority: buffer.to_account_info(),
+        },
+        signer,
+    );
+    token::transfer(cpi_ctx, amount)
+}
+
+/// Moves `amount` of protocol fees from the fee vault into the liquidity buffer,
+/// signed by the fee vault state PDA.
+fn transfer_from_fee_vault_to_buffer<'info>(
+    fee_vault_state: &Account<'info, FeeVaultState>,
+    fee_vault: &Account<'info, TokenAccount>,
+    buffer_vault: &Account<'info, TokenAccount>,
+    token_program: &Program<'info, Token>,
+    amount: u64,
+) -> Result<()> {
+    let seeds = &[seeds::FEE_VAULT_STATE, &[fee_vault_state.bump]];
+    let signer = &[&seeds[..]];
+    let cpi_ctx = CpiContext::new_with_signer(
+        token_program.to_account_info(),
+        Transfer {
+            from: fee_vault.to_account_info(),
+            to: buffer_vault.to_account_info(),
+            authority: fee_vault_state.to_account_info(),
+        },
+        signer,
+    );
+    token::transfer(cpi_ctx, amount)
+}
+
+/// Message a registry key signs to admit `human` into `pool`.
+pub fn cohort_join_message(pool: &Pubkey, human: &Pubkey) -> Vec<u8> {
+    let mut message = Vec::with_capacity(11 + 32 + 32);
+    message.extend_from_slice(b"cohort_join");
+    message.extend_from_slice(pool.as_ref());
+    message.extend_from_slice(human.as_ref());
+    message
+}
+
+/// Leaf committed to in a cohort epoch's merkle root.
+pub fn cohort_claim_leaf(cohort_epoch: &Pubkey, human: &Pubkey, amount: u64) -> [u8; 32] {
+    keccak::hashv(&[cohort_epoch.as_ref(), human.as_ref(), &amount.to_le_bytes()]).to_bytes()
+}
+
+/// Verifies a merkle proof built with sorted-pair keccak hashing.
+pub fn verify_merkle_proof(proof: &[[u8; 32]], root: &[u8; 32], leaf: [u8; 32]) -> bool {
+    let computed = proof.iter().fold(leaf, |node, sibling| {
+        if node <= *sibling {
+            keccak::hashv(&[&node, sibling]).to_bytes()
+        } else {
+            keccak::hashv(&[sibling, &node]).to_bytes()
+        }
+    });
+    computed == *root
+}
+
+/// Message a human signs to have their rating of a settlement submitted for them.
+pub fn agent_rating_message(receipt: &Pubkey, rating: u8) -> Vec<u8> {
+    let mut message = Vec::with_capacity(12 + 32 + 1);
+    message.extend_from_slice(b"agent_rating");
+    message.extend_from_slice(receipt.as_ref());
+    message.push(rating);
+    message
+}
+
+/// Message an agent signs to authorize a relayed deposit into `escrow`.
+pub fn deposit_authorization_message(escrow: &Pubkey, amount: u64, expires_at: i64, nonce: u64) -> Vec<u8> {
+    let mut message = Vec::with_capacity(12 + 32 + 8 + 8 + 8);
+    message.extend_from_slice(b"deposit_auth");
+    message.extend_from_slice(escrow.as_ref());
+    message.extend_from_slice(&amount.to_le_bytes());
+    message.extend_from_slice(&expires_at.to_le_bytes());
+    message.extend_from_slice(&nonce.to_le_bytes());
+    message
+}
+
+/// Bytes a funder signs to authorize `receive_with_authorization` into `escrow`.
+pub fn transfer_authorization_message(
+    from: &Pubkey,
+    escrow: &Pubkey,
+    value: u64,
+    valid_after: i64,
+    valid_before: i64,
+    nonce: &[u8; 32],
+) -> Vec<u8> {
+    let mut message = Vec::with_capacity(17 + 32 + 32 + 8 + 8 + 8 + 32);
+    message.extend_from_slice(b"receive_with_auth");
+    message.extend_from_slice(from.as_ref());
+    message.extend_from_slice(escrow.as_ref());
+    message.extend_from_slice(&value.to_le_bytes());
+    message.extend_from_slice(&valid_after.to_le_bytes());
+    message.extend_from_slice(&valid_before.to_le_bytes());
+    message.extend_from_slice(nonce);
+    message
+}
+
+/// Fields of a CCTP v1 burn message that `credit_cctp_deposit` checks.
+pub struct CctpBurn {
+    pub source_domain: u32,
+    pub nonce: u64,
+    pub mint_recipient: Pubkey,
+    pub amount: u64,
+    pub message_sender: [u8; 32], // Burner on the source chain
+}
+
+/// Decodes a MessageTransmitter `receive_message` instruction carrying a TokenMessenger
+/// burn addressed to Solana. Header integers in the message are big-endian.
+pub fn parse_cctp_receive_message(ix: &Instruction) -> Result<CctpBurn> {
+    require_keys_eq!(ix.program_id, CCTP_MESSAGE_TRANSMITTER, ErrorCode::InvalidCctpMessage);
+    let discriminator = anchor_lang::solana_program::hash::hash(b"global:receive_message");
+    require!(
+        ix.data.len() >= 12 && ix.data[..8] == discriminator.to_bytes()[..8],
+        ErrorCode::InvalidCctpMessage
+    );
+    let len = u32::from_le_bytes(ix.data[8..12].try_into().unwrap()) as usize;
+    require!(ix.data.len() >= 12 + len, ErrorCode::InvalidCctpMessage);
+    let message = &ix.data[12..12 + len];
+
+    // Header (116 bytes), then the burn body (132 bytes)
+    require!(message.len() >= 248, ErrorCode::InvalidCctpMessage);
+    let be_u32 = |at: usize| u32::from_be_bytes(message[at..at + 4].try_into().unwrap());
+    require!(be_u32(8) == CCTP_SOLANA_DOMAIN, ErrorCode::InvalidCctpMessage);
+    require!(message[52..84] == CCTP_TOKEN_MESSENGER_MINTER.to_bytes(), ErrorCode::InvalidCctpMessage);
+    // Amount is a big-endian uint256; anything past u64 can't be a real USDC burn
+    require!(message[184..208].iter().all(|&b| b == 0), ErrorCode::InvalidCctpMessage);
+
+    Ok(CctpBurn {
+        source_domain: be_u32(4),
+        nonce: u64::from_be_bytes(message[12..20].try_into().unwrap()),
+        mint_recipient: Pubkey::new_from_array(message[152..184].try_into().unwrap()),
+        amount: u64::from_be_bytes(message[208..216].try_into().unwrap()),
+        message_sender: message[216..248].try_into().unwrap(),
+    })
+}
+
+/// The verified collection recorded in a Metaplex metadata account, if any. Walks the
+/// Borsh layout up to the `collection` field; unverified collections count as none.
+pub fn verified_collection(data: &[u8]) -> Option<Pubkey> {
+    // Key::MetadataV1
+    if data.first() != Some(&4) {
+        return None;
+    }
+    let mut at = 1 + 32 + 32; // key, update_authority, mint
+    let take = |at: &mut usize, len: usize| -> Option<&[u8]> {
+        let bytes = data.get(*at..at.checked_add(len)?)?;
+        *at += len;
+        Some(bytes)
+    };
+    // name, symbol, uri
+    for _ in 0..3 {
+        let len = u32::from_le_bytes(take(&mut at, 4)?.try_into().ok()?) as usize;
+        take(&mut at, len)?;
+    }
+    take(&mut at, 2)?; // seller_fee_basis_points
+    if take(&mut at, 1)?[0] == 1 {
+        // Creators: address, verified, share
+        let count = u32::from_le_bytes(take(&mut at, 4)?.try_into().ok()?) as usize;
+        take(&mut at, count.checked_mul(34)?)?;
+    }
+    take(&mut at, 2)?; // primary_sale_happened, is_mutable
+    // edition_nonce, token_standard
+    for _ in 0..2 {
+        if take(&mut at, 1)?[0] == 1 {
+            take(&mut at, 1)?;
+        }
+    }
+    if take(&mut at, 1)?[0] != 1 {
+        return None;
+    }
+    let verified = take(&mut at, 1)?[0] == 1;
+    let key = Pubkey::new_from_array(take(&mut at, 32)?.try_into().ok()?);
+    verified.then_some(key)
+}
+
+/// Header an attestation issuer program writes to each attestation account.
+pub struct IssuerAttestation {
+    pub subject: Pubkey, // Attested human wallet
+    pub attestation_type: AttestationType,
+    pub policy_hash: [u8; 32],
+    pub expires_at: i64, // 0 = never
+}
+
+/// Decodes an issuer attestation: the issuer's 8-byte discriminator, then subject (32),
+/// attestation type (1), policy hash (32) and expiry (i64 LE). Trailing data is the issuer's own.
+pub fn parse_issuer_attestation(data: &[u8]) -> Option<IssuerAttestation> {
+    let header = data.get(8..8 + 32 + 1 + 32 + 8)?;
+    Some(IssuerAttestation {
+        subject: Pubkey::new_from_array(header[..32].try_into().ok()?),
+        attestation_type: AttestationType::try_from_slice(&header[32..33]).ok()?,
+        policy_hash: header[33..65].try_into().ok()?,
+        expires_at: i64::from_le_bytes(header[65..73].try_into().ok()?),
+    })
+}
+
+#[derive(Accounts)]
+pub struct InitializeMarketConfig<'info> {
+    #[account(mut, constraint = admin.key() == AUTHORIZED_ADMIN @ ErrorCode::Unauthorized)]
+    pub admin: Signer<'info>,
+    #[account(
+        init,
+        payer = admin,
+        space = MarketConfig::SPACE,
+        seeds = [seeds::MARKET_CONFIG],
+        bump
+    )]
+    pub config: Account<'info, MarketConfig>,
+    // Canonical settlement mint (USDC); every primary vault must hold it
+    #[account(constraint = network::is_canonical_mint(&payment_mint.key()) @ ErrorCode::InvalidMint)]
+    pub payment_mint: Account<'info, token::Mint>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ReinitializeMarketConfig<'info> {
+    #[account(constraint = admin.key() == AUTHORIZED_ADMIN @ ErrorCode::Unauthorized)]
+    pub admin: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [seeds::MARKET_CONFIG],
+        bump,
+        constraint = config.migration_source == Pubkey::default() @ ErrorCode::MarketLive
+    )]
+    pub config: Account<'info, MarketConfig>,
+    /// CHECK: Must still be uninitialized; no escrow can exist before it does
+    #[account(
+        seeds = [seeds::FEE_VAULT_STATE],
+        bump,
+        constraint = fee_vault_state.data_is_empty() @ ErrorCode::MarketLive
+    )]
+    pub fee_vault_state: UncheckedAccount<'info>,
+    #[account(constraint = network::is_canonical_mint(&payment_mint.key()) @ ErrorCode::InvalidMint)]
+    pub payment_mint: Account<'info, token::Mint>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeFeeVault<'info> {
+    #[account(mut, constraint = admin.key() == AUTHORIZED_ADMIN @ ErrorCode::Unauthorized)]
+    pub admin: Signer<'info>,
+    #[account(
+        init,
+        payer = admin,
+        space = FeeVaultState::SPACE,
+        seeds = [seeds::FEE_VAULT_STATE],
+        bump
+    )]
+    pub fee_vault_state: Account<'info, FeeVaultState>,
+    #[account(
+        init,
+        payer = admin,
+        seeds = [seeds::FEE_VAULT, fee_vault_state.key().as_ref()], 
+        bump,
+        token::mint = mint,
+        token::authority = fee_vault_state,
+    )]
+    pub fee_vault: Account<'info, TokenAccount>,
+    // The primary fee vault always holds the canonical mint, even in multi-mint mode
+    #[account(
+        constraint = mint.key() == market_config.payment_mint @ ErrorCode::InvalidMint,
+        constraint = mint.decimals == market_config.payment_decimals @ ErrorCode::InvalidMint
+    )]
+    pub mint: Account<'info, token::Mint>,
+    #[account(seeds = [seeds::MARKET_CONFIG], bump)]
+    pub market_config: Account<'info, MarketConfig>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+#[instruction(builder_code: [u8; 32])]
+pub struct RegisterBuilder<'info> {
+    #[account(mut, constraint = admin.key() == market_config.authority @ ErrorCode::Unauthorized)]
+    pub admin: Signer<'info>,
+    #[account(seeds = [seeds::MARKET_CONFIG], bump)]
+    pub market_config: Account<'info, MarketConfig>,
+    #[account(
+        init,
+        payer = admin,
+        space = BuilderBalance::SPACE,
+        seeds = [seeds::BUILDER, builder_code.as_ref()],
+        bump
+    )]
+    pub builder_balance: Account<'info, BuilderBalance>,
+    /// CHECK: This is the builder's wallet public key that we are registering.
+    pub builder_wallet: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateBuilderWallet<'info> {
+    #[account(mut)]
+    pub old_wallet: Signer<'info>, // Current wallet must sign
+    #[account(
+        mut,
+        constraint = builder_balance.wallet == old_wallet.key() @ ErrorCode::Unauthorized
+    )]
+    pub builder_balance: Account<'info, BuilderBalance>,
+    /// CHECK: New wallet address
+    pub new_wallet: UncheckedAccount<'info>,
+}
+
+
+#[derive(Accounts)]
+pub struct DepositEscrow<'info> {
+    #[account(mut)]
+    pub agent: Signer<'info>,
+    #[account(mut)]
+    pub agent_token_account: Account<'info, TokenAccount>, 
+    #[account(
+        init_if_needed,
+        payer = agent,
+        space = EscrowAccount::SPACE,
+        seeds = [seeds::ESCROW, agent.key().as_ref()],
+        bump
+    )]
+    pub escrow_account: Account<'info, EscrowAccount>,
+    // Fee Vault reference for mint validation (the mint's own vault for secondary mints)
+    #[account(seeds = [seeds::MARKET_CONFIG], bump)]
+    pub market_config: Box<Account<'info, MarketConfig>>,
+    #[account(seeds = [seeds::FEE_VAULT_STATE], bump)]
+    pub fee_vault_state: Account<'info, FeeVaultState>,
+    #[account(
+        constraint = mint_fee_vault.as_ref()
+            .map_or(fee_vault.owner == fee_vault_state.key(), |m| fee_vault.key() == m.vault)
+            @ ErrorCode::InvalidMint
+    )]
+    pub fee_vault: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = vault.owner == escrow_account.key() @ ErrorCode::InvalidVault,
+        constraint = vault.mint == fee_vault.mint @ ErrorCode::InvalidMint
+    )]
+    pub vault: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+
+    // Optional Credit Line (repaid from the deposit before crediting the balance)
+    #[account(
+        mut,
+        seeds = [seeds::CREDIT_LINE, escrow_account.key().as_ref()],
+        bump = credit_line.bump
+    )]
+    pub credit_line: Option<Account<'info, CreditLine>>,
+    #[account(
+        mut,
+        constraint = credit_collateral.owner == credit_line.as_ref().map(|c| c.key()).unwrap_or_default()
+            @ ErrorCode::InvalidTokenAccount
+    )]
+    pub credit_collateral: Option<Account<'info, TokenAccount>>,
+
+    // Optional Mint Fee Vault (escrows funded in a registered secondary mint)
+    #[account(
+        seeds = [seeds::MINT_FEE_VAULT, mint_fee_vault.mint.as_ref()],
+        bump = mint_fee_vault.bump
+    )]
+    pub mint_fee_vault: Option<Account<'info, MintFeeVault>>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawEscrow<'info> {
+    #[account(mut)]
+    pub agent: Signer<'info>,
+    #[account(mut)]
+    pub agent_token_account: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = escrow_account.position_mint == Pubkey::default() @ ErrorCode::EscrowTokenized,
+        seeds = [seeds::ESCROW, agent.key().as_ref()],
+        bump = escrow_account.bump
+    )]
+    pub escrow_account: Account<'info, EscrowAccount>,
+    #[account(
+        mut,
+        constraint = vault.owner == escrow_account.key() @ ErrorCode::InvalidVault
+    )]
+    pub vault: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct CloseEscrow<'info> {
+    #[account(mut)]
+    pub agent: Signer<'info>,
+    #[account(
+        mut,
+        constraint = agent_token_account.owner == agent.key() @ ErrorCode::InvalidTokenAccount,
+        constraint = agent_token_account.mint == vault.mint @ ErrorCode::InvalidTokenAccount
+    )]
+    pub agent_token_account: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        close = agent,
+        constraint = escrow_account.position_mint == Pubkey::default() @ ErrorCode::EscrowTokenized,
+        seeds = [seeds::ESCROW, agent.key().as_ref()],
+        bump = escrow_account.bump
+    )]
+    pub escrow_account: Account<'info, EscrowAccount>,
+    #[account(
+        mut,
+        constraint = vault.owner == escrow_account.key() @ ErrorCode::InvalidVault
+    )]
+    pub vault: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+#[instruction(challenge: [u8; 32])]
+pub struct SnapshotEscrow<'info> {
+    #[account(mut)]
+    pub agent: Signer<'info>,
+    #[account(
+        seeds = [seeds::ESCROW, agent.key().as_ref()],
+        bump = escrow_account.bump
+    )]
+    pub escrow_account: Account<'info, EscrowAccount>,
+    #[account(
+        init,
+        payer = agent,
+        space = EscrowSnapshot::SPACE,
+        seeds = [seeds::ESCROW_SNAPSHOT, escrow_account.key().as_ref(), challenge.as_ref()],
+        bump
+    )]
+    pub snapshot: Account<'info, EscrowSnapshot>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CloseEscrowSnapshot<'info> {
+    /// CHECK: Rent refund destination recorded in the snapshot.
+    #[account(mut, address = snapshot.agent)]
+    pub rent_receiver: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        close = rent_receiver,
+        seeds = [seeds::ESCROW_SNAPSHOT, snapshot.escrow.as_ref(), snapshot.challenge.as_ref()],
+        bump = snapshot.bump
+    )]
+    pub snapshot: Account<'info, EscrowSnapshot>,
+}
+
+#[derive(Accounts)]
+pub struct FastWithdrawEscrow<'info> {
+    pub agent: Signer<'info>,
+    #[account(
+        mut,
+        constraint = agent_token_account.owner == agent.key() @ ErrorCode::InvalidTokenAccount,
+        constraint = agent_token_account.mint == vault.mint @ ErrorCode::InvalidTokenAccount
+    )]
+    pub agent_token_account: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = escrow_account.position_mint == Pubkey::default() @ ErrorCode::EscrowTokenized,
+        seeds = [seeds::ESCROW, agent.key().as_ref()],
+        bump = escrow_account.bump
+    )]
+    pub escrow_account: Account<'info, EscrowAccount>,
+    #[account(
+        mut,
+        constraint = vault.owner == escrow_account.key() @ ErrorCode::InvalidVault
+    )]
+    pub vault: Account<'info, TokenAccount>,
+    #[account(seeds = [seeds::MARKET_CONFIG], bump)]
+    pub market_config: Account<'info, MarketConfig>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+#[instruction(session_id: u64)]
+pub struct OpenSession<'info> {
+    #[account(
+        mut,
+        constraint = router.key() == escrow_account.settlement_router(&market_config, shard.as_deref(), Clock::get()?.unix_timestamp)
+            @ ErrorCode::Unauthorized
+    )]
+    pub router: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [seeds::ESCROW, escrow_account.agent.as_ref()],
+        bump = escrow_account.bump
+    )]
+    pub escrow_account: Account<'info, EscrowAccount>,
+    /// CHECK: Human's wallet public key the session is opened for.
+    pub human: UncheckedAccount<'info>,
+    /// CHECK: Human's PayoutPrefs PDA; may be uninitialized.
+    #[account(seeds = [seeds::PAYOUT_PREFS, human.key().as_ref()], bump)]
+    pub payout_prefs: UncheckedAccount<'info>,
+    /// CHECK: Human's Availability PDA; may be uninitialized.
+    #[account(seeds = [seeds::AVAILABILITY, human.key().as_ref()], bump)]
+    pub availability: UncheckedAccount<'info>,
+    /// CHECK: Human's BlockedAgents PDA; may be uninitialized.
+    #[account(seeds = [seeds::BLOCKED_AGENTS, human.key().as_ref()], bump)]
+    pub blocked_agents: UncheckedAccount<'info>,
+    #[account(
+        init,
+        payer = router,
+        space = 8 + 32 + 32 + 8 + 8 + 8 + 8 + 8 + 8 + 8 + 1 + 1 + 32 + 2 + 2 + 32,
+        seeds = [seeds::SESSION, escrow_account.key().as_ref(), session_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub session: Account<'info, Session>,
+    #[account(seeds = [seeds::MARKET_CONFIG], bump)]
+    pub market_config: Account<'info, MarketConfig>,
+    pub system_program: Program<'info, System>,
+
+    // Optional Shard (required to settle an escrow pinned to a shard)
+    #[account(seeds = [seeds::SHARD, &[shard.shard_id]], bump = shard.bump)]
+    pub shard: Option<Account<'info, Shard>>,
+
+    // Optional Spot Price (opens the session at the category's posted price)
+    #[account(seeds = [seeds::SPOT_PRICE, spot_price.category.as_ref()], bump = spot_price.bump)]
+    pub spot_price: Option<Box<Account<'info, SpotPrice>>>,
+
+    // Optional Task Gate (the session is for a task that requires a collection NFT),
+    // with the human's NFT token account and its Metaplex metadata account
+    #[account(
+        constraint = task_gate.escrow == escrow_account.key() @ ErrorCode::InvalidTask,
+        seeds = [seeds::TASK_GATE, task_gate.task.as_ref()],
+        bump = task_gate.bump
+    )]
+    pub task_gate: Option<Box<Account<'info, TaskGate>>>,
+    pub gate_nft_account: Option<Box<Account<'info, TokenAccount>>>,
+    /// CHECK: Checked against the NFT mint's metadata PDA and parsed in `open_session`.
+    pub gate_metadata: Option<UncheckedAccount<'info>>,
+
+    // Optional Task Policy (the session is for an attestation-restricted task), with the
+    // whitelisted issuer and the human's attestation account written by it
+    #[account(
+        constraint = task_policy.escrow == escrow_account.key() @ ErrorCode::InvalidTask,
+        seeds = [seeds::TASK_POLICY, task_policy.task.as_ref()],
+        bump = task_policy.bump
+    )]
+    pub task_policy: Option<Box<Account<'info, TaskPolicy>>>,
+    #[account(
+        seeds = [seeds::ATTESTATION_ISSUER, attestation_issuer.program.as_ref()],
+        bump = attestation_issuer.bump
+    )]
+    pub attestation_issuer: Option<Box<Account<'info, AttestationIssuer>>>,
+    /// CHECK: Owner is checked against the issuer program and the data parsed in `open_session`.
+    pub attestation: Option<UncheckedAccount<'info>>,
+
+    // Optional Matcher (the service that paired this agent and human; paid at settlement)
+    #[account(
+        seeds = [seeds::MATCHER, matcher_balance.matcher_code.as_ref()],
+        bump = matcher_balance.bump
+    )]
+    pub matcher_balance: Option<Box<Account<'info, MatcherBalance>>>,
+}
+
+#[derive(Accounts)]
+pub struct CloseSession<'info> {
+    #[account(
+        mut,
+        constraint = router.key() == escrow_account.settlement_router(&market_config, shard.as_deref(), Clock::get()?.unix_timestamp)
+            @ ErrorCode::Unauthorized
+    )]
+    pub router: Signer<'info>,
+    #[account(
+        mut,
+        close = router,
+        seeds = [seeds::SESSION, session.escrow.as_ref(), session.session_id.to_le_bytes().as_ref()],
+        bump = session.bump
+    )]
+    pub session: Account<'info, Session>,
+    #[account(
+        mut,
+        address = session.escrow @ ErrorCode::InvalidEscrow
+    )]
+    pub escrow_account: Account<'info, EscrowAccount>,
+    #[account(seeds = [seeds::MARKET_CONFIG], bump)]
+    pub market_config: Account<'info, MarketConfig>,
+
+    // Optional Shard (required to settle an escrow pinned to a shard)
+    #[account(seeds = [seeds::SHARD, &[shard.shard_id]], bump = shard.bump)]
+    pub shard: Option<Account<'info, Shard>>,
+}
+
+#[derive(Accounts)]
+#[instruction(session_id: u64, price_per_second: u64, participants: Vec<Pubkey>)]
+pub struct OpenGroupSession<'info> {
+    #[account(
+        mut,
+        constraint = router.key() == escrow_account.settlement_router(&market_config, shard.as_deref(), Clock::get()?.unix_timestamp)
+            @ ErrorCode::Unauthorized
+    )]
+    pub router: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [seeds::ESCROW, escrow_account.agent.as_ref()],
+        bump = escrow_account.bump
+    )]
+    pub escrow_account: Account<'info, EscrowAccount>,
+    #[account(
+        init,
+        payer = router,
+        space = GroupSession::space(participants.len()),
+        seeds = [seeds::GROUP_SESSION, escrow_account.key().as_ref(), session_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub group_session: Box<Account<'info, GroupSession>>,
+    #[account(seeds = [seeds::MARKET_CONFIG], bump)]
+    pub market_config: Account<'info, MarketConfig>,
+    pub system_program: Program<'info, System>,
+
+    // Optional Shard (required to settle an escrow pinned to a shard)
+    #[account(seeds = [seeds::SHARD, &[shard.shard_id]], bump = shard.bump)]
+    pub shard: Option<Account<'info, Shard>>,
+}
+
+#[derive(Accounts)]
+pub struct CloseGroupSettlement<'info> {
+    #[account(
+        mut,
+        constraint = router.key() == escrow_account.settlement_router(&market_config, shard.as_deref(), Clock::get()?.unix_timestamp)
+            @ ErrorCode::Unauthorized
+    )]
+    pub router: Signer<'info>,
+    // Group sessions settle in the primary mint only
+    #[account(
+        mut,
+        constraint = escrow_account.mint == Pubkey::default() @ ErrorCode::InvalidMint,
+        seeds = [seeds::ESCROW, escrow_account.agent.as_ref()],
+        bump = escrow_account.bump
+    )]
+    pub escrow_account: Account<'info, EscrowAccount>,
+    #[account(
+        mut,
+        constraint = vault.owner == escrow_account.key() @ ErrorCode::InvalidVault,
+        constraint = vault.mint == fee_vault.mint @ ErrorCode::InvalidMint
+    )]
+    pub vault: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = group_session.escrow == escrow_account.key() @ ErrorCode::SessionMismatch,
+        seeds = [seeds::GROUP_SESSION, group_session.escrow.as_ref(), group_session.session_id.to_le_bytes().as_ref()],
+        bump = group_session.bump
+    )]
+    pub group_session: Box<Account<'info, GroupSession>>,
+    #[account(
+        constraint = mint.key() == fee_vault.mint @ ErrorCode::InvalidMint,
+        constraint = market_config.accepts_mint(&mint) @ ErrorCode::InvalidMint
+    )]
+    pub mint: Account<'info, token::Mint>,
+    #[account(
+        mut,
+        seeds = [seeds::FEE_VAULT_STATE],
+        bump = fee_vault_state.bump
+    )]
+    pub fee_vault_state: Account<'info, FeeVaultState>,
+    #[account(
+        mut,
+        constraint = fee_vault.owner == fee_vault_state.key()
+    )]
+    pub fee_vault: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        seeds = [seeds::MARKET_CONFIG],
+        bump,
+        constraint = !market_config.settlements_paused @ ErrorCode::SettlementsPaused
+    )]
+    pub market_config: Account<'info, MarketConfig>,
+    pub token_program: Program<'info, Token>,
+
+    // Optional Shard (required to settle an escrow pinned to a shard)
+    #[account(seeds = [seeds::SHARD, &[shard.shard_id]], bump = shard.bump)]
+    pub shard: Option<Account<'info, Shard>>,
+    // Optional attestation (verified against the key registry; required while it has a live key)
+    #[account(
+        seeds = [seeds::ATTESTATION_KEY, attestation_key.key.as_ref()],
+        bump = attestation_key.bump
+    )]
+    pub attestation_key: Option<Account<'info, AttestationKey>>,
+    /// CHECK: Instructions sysvar, used to introspect the attestation signature.
+    #[account(address = anchor_lang::solana_program::sysvar::instructions::ID)]
+    pub instructions_sysvar: Option<UncheckedAccount<'info>>,
+}
+
+#[derive(Accounts)]
+pub struct CloseGroupSession<'info> {
+    #[account(
+        mut,
+        constraint = router.key() == escrow_account.settlement_router(&market_config, shard.as_deref(), Clock::get()?.unix_timestamp)
+            @ ErrorCode::Unauthorized
+    )]
+    pub router: Signer<'info>,
+    #[account(
+        mut,
+        close = router,
+        seeds = [seeds::GROUP_SESSION, group_session.escrow.as_ref(), group_session.session_id.to_le_bytes().as_ref()],
+        bump = group_session.bump
+    )]
+    pub group_session: Box<Account<'info, GroupSession>>,
+    #[account(
+        mut,
+        address = group_session.escrow @ ErrorCode::InvalidEscrow
+    )]
+    pub escrow_account: Account<'info, EscrowAccount>,
+    #[account(seeds = [seeds::MARKET_CONFIG], bump)]
+    pub market_config: Account<'info, MarketConfig>,
+
+    // Optional Shard (required to settle an escrow pinned to a shard)
+    #[account(seeds = [seeds::SHARD, &[shard.shard_id]], bump = shard.bump)]
+    pub shard: Option<Account<'info, Shard>>,
+}
+
+#[derive(Accounts)]
+pub struct ExpireSession<'info> {
+    #[account(constraint = market_config.is_operator(&operator.key()) @ ErrorCode::Unauthorized)]
+    pub operator: Signer<'info>,
+    /// CHECK: Router that paid the session's rent; receives it back.
+    #[account(mut, address = market_config.authority)]
+    pub router: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        close = router,
+        seeds = [seeds::SESSION, session.escrow.as_ref(), session.session_id.to_le_bytes().as_ref()],
+        bump = session.bump
+    )]
+    pub session: Account<'info, Session>,
+    #[account(
+        mut,
+        address = session.escrow @ ErrorCode::InvalidEscrow
+    )]
+    pub escrow_account: Account<'info, EscrowAccount>,
+    #[account(seeds = [seeds::MARKET_CONFIG], bump)]
+    pub market_config: Account<'info, MarketConfig>,
+}
+
+/// Optional close_settlement arguments.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default)]
+pub struct SettlementExtras {
+    pub builder_code_str: Option<String>,    // Referral string, hashed on-chain into the builder code
+    pub response_hash: Option<[u8; 32]>,     // Hash of the human's deliverable; stored on the receipt, which must be created
+    pub quality_multiplier_bps: Option<u16>, // Engagement multiplier within the session's bounds, 10_000 = 1x
+}
+
+#[derive(Accounts)]
+#[instruction(
+    verified_seconds: u64,
+    agreed_price_per_second: u64,
+    nonce: u64,
+    builder_code_opt: Option<[u8; 32]>,
+    quality_score: Option<u8>,
+    extras: SettlementExtras
+)]
+#[event_cpi]
+pub struct CloseSettlement<'info> {
+    // While the router is stale, the human may settle their own attested work
+    #[account(
+        mut,
+        constraint = router.key() == escrow_account.settlement_router(&market_config, shard.as_deref(), Clock::get()?.unix_timestamp)
+            || (router.key() == human.key()
+                && attestation_key.is_some()
+                && market_config.router_stale(Clock::get()?.unix_timestamp))
+    )]
+    pub router: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [seeds::ESCROW, escrow_account.agent.as_ref()],
+        bump = escrow_account.bump
+    )]
+    pub escrow_account: Account<'info, EscrowAccount>,
+    // Mint is checked up front so a mismatched fee vault can't fail the settlement mid-way
+    #[account(
+        mut,
+        constraint = vault.owner == escrow_account.key() @ ErrorCode::InvalidVault,
+        constraint = vault.mint == fee_vault.mint @ ErrorCode::InvalidMint
+    )]
+    pub vault: Account<'info, TokenAccount>,
+    /// CHECK: Human's wallet public key. Only used as the authority of the payout ATA.
+    pub human: UncheckedAccount<'info>,
+    /// CHECK: Human's PayoutPrefs PDA; may be uninitialized. Required so the router can't skip it.
+    #[account(seeds = [seeds::PAYOUT_PREFS, human.key().as_ref()], bump)]
+    pub payout_prefs: UncheckedAccount<'info>,
+    /// CHECK: Human's PayoutSplit PDA; may be uninitialized. Required so the router can't skip it.
+    #[account(seeds = [seeds::PAYOUT_SPLIT, human.key().as_ref()], bump)]
+    pub payout_split: UncheckedAccount<'info>,
+    /// CHECK: Human's OrgMember PDA; may be uninitialized. Required so the router can't skip it.
+    #[account(seeds = [seeds::ORG_MEMBER, human.key().as_ref()], bump)]
+    pub org_member: UncheckedAccount<'info>,
+    // Session the seconds are billed against; required so its caps and price floor can't be skipped
+    #[account(
+        mut,
+        constraint = session.escrow == escrow_account.key() @ ErrorCode::SessionMismatch,
+        constraint = session.human == human.key() @ ErrorCode::SessionMismatch
+    )]
+    pub session: Box<Account<'info, Session>>,
+    // Payout ATA is derived on-chain so the router can't pay into the wrong token account
+    #[account(
+        init_if_needed,
+        payer = router,
+        associated_token::mint = mint,
+        associated_token::authority = human,
+    )]
+    pub user_wallet: Account<'info, TokenAccount>,
+    #[account(
+        constraint = mint.key() == vault.mint @ ErrorCode::InvalidMint,
+        constraint = market_config.accepts_mint(&mint) @ ErrorCode::InvalidMint
+    )]
+    pub mint: Account<'info, token::Mint>,
+    
+    // Fee Vault Accounts
+    #[account(
+        mut,
+        seeds = [seeds::FEE_VAULT_STATE],
+        bump = fee_vault_state.bump
+    )]
+    pub fee_vault_state: Account<'info, FeeVaultState>,
+    // Primary fee vault, or the registered vault of a secondary mint
+    #[account(
+        mut,
+        constraint = mint_fee_vault.as_ref()
+            .map_or(fee_vault.owner == fee_vault_state.key(), |m| fee_vault.key() == m.vault)
+            @ ErrorCode::InvalidMint
+    )]
+    pub fee_vault: Account<'info, TokenAccount>,
+    
+    // Optional Builder Balance (only needed if builder_code provided)
+    #[account(
+        mut,
+        seeds = [
+            seeds::BUILDER,
+            resolve_builder_code(builder_code_opt, extras.builder_code_str.as_deref()).unwrap_or([0; 32]).as_ref()
+        ],
+        bump = builder_balance.bump
+    )]
+    pub builder_balance: Option<Account<'info, BuilderBalance>>,
+
+    // Optional Builder Stats (monthly revenue buckets for leaderboards)
+    #[account(
+        mut,
+        seeds = [
+            seeds::BUILDER_STATS,
+            resolve_builder_code(builder_code_opt, extras.builder_code_str.as_deref()).unwrap_or([0; 32]).as_ref()
+        ],
+        bump
+    )]
+    pub builder_stats: Option<Account<'info, BuilderStats>>,
+
+    // Optional Holdback (required when market_config.holdback_bps > 0)
+    #[account(
+        init_if_needed,
+        payer = router,
+        space = Holdback::SPACE,
+        seeds = [seeds::HOLDBACK, human.key().as_ref()],
+        bump
+    )]
+    pub holdback: Option<Account<'info, Holdback>>,
+
+    // Optional attestation (verified against the key registry)
+    #[account(
+        seeds = [seeds::ATTESTATION_KEY, attestation_key.key.as_ref()],
+        bump = attestation_key.bump
+    )]
+    pub attestation_key: Option<Account<'info, AttestationKey>>,
+    /// CHECK: Instructions sysvar, used to introspect the attestation signature.
+    #[account(address = anchor_lang::solana_program::sysvar::instructions::ID)]
+    pub instructions_sysvar: Option<UncheckedAccount<'info>>,
+
+    // Optional Receipt (one per escrow and nonce)
+    #[account(
+        init,
+        payer = router,
+        space = 8 + 32 + 32 + 32 + 8 + 8 + 8 + 8 + 1 + 8 + 1 + 1 + 32 + 4 + 32 + 1 + 2,
+        seeds = [seeds::RECEIPT, escrow_account.key().as_ref(), nonce.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub receipt: Option<Box<Account<'info, SettlementReceipt>>>,
+
+    // Optional Quality Stats (settlement counts and quality score aggregates)
+    #[account(
+        init_if_needed,
+        payer = router,
+        space = 8 + 32 + 8 + 8 + 8 + 1,
+        seeds = [seeds::HUMAN_STATS, human.key().as_ref()],
+        bump
+    )]
+    pub human_stats: Option<Box<Account<'info, QualityStats>>>,
+    #[account(
+        init_if_needed,
+        payer = router,
+        space = 8 + 32 + 8 + 8 + 8 + 1,
+        seeds = [seeds::AGENT_STATS, escrow_account.agent.as_ref()],
+        bump
+    )]
+    pub agent_stats: Option<Box<Account<'info, QualityStats>>>,
+
+    // Optional Credit Line (covers shortfalls for underwritten escrows)
+    #[account(
+        mut,
+        seeds = [seeds::CREDIT_LINE, escrow_account.key().as_ref()],
+        bump = credit_line.bump
+    )]
+    pub credit_line: Option<Box<Account<'info, CreditLine>>>,
+    #[account(
+        mut,
+        constraint = credit_collateral.owner == credit_line.as_ref().map(|c| c.key()).unwrap_or_default()
+            @ ErrorCode::InvalidTokenAccount
+    )]
+    pub credit_collateral: Option<Box<Account<'info, TokenAccount>>>,
+
+    // Optional Mint Fee Vault (required for escrows in a secondary mint)
+    #[account(
+        mut,
+        seeds = [seeds::MINT_FEE_VAULT, mint_fee_vault.mint.as_ref()],
+        bump = mint_fee_vault.bump
+    )]
+    pub mint_fee_vault: Option<Box<Account<'info, MintFeeVault>>>,
+
+    #[account(
+        mut,
+        seeds = [seeds::MARKET_CONFIG],
+        bump,
+        constraint = !market_config.settlements_paused @ ErrorCode::SettlementsPaused
+    )]
+    pub market_config: Account<'info, MarketConfig>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+
+    // Optional Relayer Stats (tracks the router's gas spend for reimbursement)
+    #[account(
+        init_if_needed,
+        payer = router,
+        space = 8 + 32 + 8 + 8 + 8 + 8 + 1,
+        seeds = [seeds::RELAYER_STATS, router.key().as_ref()],
+        bump
+    )]
+    pub relayer_stats: Option<Box<Account<'info, RelayerStats>>>,
+
+    // Optional Forward Position (seconds pre-purchased at a locked price)
+    #[account(
+        mut,
+        constraint = forward_position.escrow == escrow_account.key() @ ErrorCode::InvalidEscrow,
+        seeds = [seeds::FORWARD, escrow_account.key().as_ref(), forward_position.forward_id.to_le_bytes().as_ref()],
+        bump = forward_position.bump
+    )]
+    pub forward_position: Option<Box<Account<'info, ForwardPosition>>>,
+
+    // Optional Price Index (category the settlement counts toward)
+    #[account(
+        mut,
+        seeds = [seeds::PRICE_INDEX, price_index.category.as_ref()],
+        bump = price_index.bump
+    )]
+    pub price_index: Option<Box<Account<'info, PriceIndex>>>,
+
+    // Optional Shard (required to settle an escrow pinned to a shard)
+    #[account(seeds = [seeds::SHARD, &[shard.shard_id]], bump = shard.bump)]
+    pub shard: Option<Account<'info, Shard>>,
+    // Optional Org and its treasury (required when the human belongs to an org)
+    #[account(mut, seeds = [seeds::ORG, org.org_id.as_ref()], bump = org.bump)]
+    pub org: Option<Box<Account<'info, Org>>>,
+    #[account(mut)]
+    pub org_treasury: Option<Box<Account<'info, TokenAccount>>>,
+
+    // Optional API key the settlement's spend is attributed to
+    #[account(
+        mut,
+        constraint = !api_key.revoked @ ErrorCode::ApiKeyRevoked,
+        seeds = [seeds::API_KEY, escrow_account.agent.as_ref(), api_key.key_hash.as_ref()],
+        bump = api_key.bump
+    )]
+    pub api_key: Option<Box<Account<'info, ApiKey>>>,
+
+    /// CHECK: SessionLengthStats PDA of the session's category; may be uninitialized. Required
+    /// so the router can't skip scoring.
+    #[account(mut, seeds = [seeds::SESSION_STATS, session.category.as_ref()], bump)]
+    pub session_stats: UncheckedAccount<'info>,
+
+    // Optional Payment Intent (required when escrow_account.intents_required)
+    #[account(
+        mut,
+        constraint = payment_intent.human == human.key() @ ErrorCode::PaymentIntentMismatch,
+        seeds = [seeds::PAYMENT_INTENT, escrow_account.key().as_ref(), payment_intent.intent_id.to_le_bytes().as_ref()],
+        bump = payment_intent.bump
+    )]
+    pub payment_intent: Option<Box<Account<'info, PaymentIntent>>>,
+
+    // Optional Observer (accepted grants get an enriched self-CPI event)
+    #[account(
+        constraint = observer.escrow == escrow_account.key() @ ErrorCode::InvalidEscrow,
+        seeds = [seeds::ESCROW_OBSERVER, escrow_account.key().as_ref(), observer.observer.as_ref()],
+        bump = observer.bump
+    )]
+    pub observer: Option<Box<Account<'info, EscrowObserver>>>,
+
+    // Optional Data Consent (the human's grant covering data derived from this session)
+    #[account(
+        mut,
+        constraint = data_consent.human == human.key() @ ErrorCode::Unauthorized,
+        seeds = [seeds::DATA_CONSENT, human.key().as_ref(), data_consent.scope_hash.as_ref()],
+        bump = data_consent.bump
+    )]
+    pub data_consent: Option<Box<Account<'info, DataConsent>>>,
+
+    // Optional Matcher Balance (required when the session was opened through a matcher)
+    #[account(
+        mut,
+        seeds = [seeds::MATCHER, matcher_balance.matcher_code.as_ref()],
+        bump = matcher_balance.bump
+    )]
+    pub matcher_balance: Option<Box<Account<'info, MatcherBalance>>>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimBuilderBalance<'info> {
+    #[account(mut)]
+    pub builder_wallet: Signer<'info>,
+    #[account(
+        mut,
+        constraint = builder_balance.wallet == builder_wallet.key() @ ErrorCode::Unauthorized,
+        seeds = [seeds::BUILDER, builder_balance.builder_code.as_ref()],
+        bump = builder_balance.bump
+    )]
+    pub builder_balance: Account<'info, BuilderBalance>,
+    
+    #[account(
+        mut,
+        constraint = builder_token_account.owner == builder_wallet.key(),
+        constraint = builder_token_account.mint == fee_vault.mint
+    )]
+    pub builder_token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [seeds::FEE_VAULT_STATE],
+        bump = fee_vault_state.bump
+    )]
+    pub fee_vault_state: Account<'info, FeeVaultState>,
+    #[account(
+        mut,
+        constraint = fee_vault.owner == fee_vault_state.key()
+    )]
+    pub fee_vault: Account<'info, TokenAccount>,
+    
+    pub token_program: Program<'info, Token>,
+
+    // Optional swap leg (venue must match market_config.swap_program)
+    #[account(seeds = [seeds::MARKET_CONFIG], bump)]
+    pub market_config: Option<Account<'info, MarketConfig>>,
+    /// CHECK: Swap venue program; checked against market_config.swap_program.
+    #[account(executable)]
+    pub swap_program: Option<UncheckedAccount<'info>>,
+    #[account(
+        mut,
+        constraint = swap_output.owner == builder_wallet.key() @ ErrorCode::InvalidTokenAccount
+    )]
+    pub swap_output: Option<Account<'info, TokenAccount>>,
+}
+
+#[derive(Accounts)]
+#[instruction(matcher_code: [u8; 32])]
+pub struct RegisterMatcher<'info> {
+    #[account(mut, constraint = admin.key() == market_config.authority @ ErrorCode::Unauthorized)]
+    pub admin: Signer<'info>,
+    #[account(seeds = [seeds::MARKET_CONFIG], bump)]
+    pub market_config: Account<'info, MarketConfig>,
+    #[account(
+        init,
+        payer = admin,
+        space = MatcherBalance::SPACE,
+        seeds = [seeds::MATCHER, matcher_code.as_ref()],
+        bump
+    )]
+    pub matcher_balance: Account<'info, MatcherBalance>,
+    /// CHECK: The matcher's wallet public key that we are registering.
+    pub matcher_wallet: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimMatcherBalance<'info> {
+    pub matcher_wallet: Signer<'info>,
+    #[account(
+        mut,
+        constraint = matcher_balance.wallet == matcher_wallet.key() @ ErrorCode::Unauthorized,
+        seeds = [seeds::MATCHER, matcher_balance.matcher_code.as_ref()],
+        bump = matcher_balance.bump
+    )]
+    pub matcher_balance: Account<'info, MatcherBalance>,
+    #[account(
+        mut,
+        constraint = matcher_token_account.owner == matcher_wallet.key() @ ErrorCode::InvalidTokenAccount,
+        constraint = matcher_token_account.mint == fee_vault.mint @ ErrorCode::InvalidMint
+    )]
+    pub matcher_token_account: Account<'info, TokenAccount>,
+    #[account(seeds = [seeds::FEE_VAULT_STATE], bump = fee_vault_state.bump)]
+    pub fee_vault_state: Account<'info, FeeVaultState>,
+    #[account(
+        mut,
+        constraint = fee_vault.owner == fee_vault_state.key()
+    )]
+    pub fee_vault: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct SwapLeg {
+    pub data: Vec<u8>,        // Venue instruction data
+    pub min_amount_out: u64,  // Slippage bound in the output mint
+}
+
+#[derive(Accounts)]
+pub struct ClaimProtocolFees<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    #[account(
+        mut,
+        constraint = fee_vault_state.authority == admin.key() @ ErrorCode::Unauthorized,
+        seeds = [seeds::FEE_VAULT_STATE],
+        bump = fee_vault_state.bump
+    )]
+    pub fee_vault_state: Account<'info, FeeVaultState>,
+    #[account(
+        mut,
+        constraint = fee_vault.owner == fee_vault_state.key()
+    )]
+    pub fee_vault: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = admin_wallet.mint == fee_vault.mint @ ErrorCode::InvalidMint
+    )]
+    pub admin_wallet: Account<'info, TokenAccount>,
+    #[account(seeds = [seeds::MARKET_CONFIG], bump)]
+    pub market_config: Account<'info, MarketConfig>,
+    
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeEmissions<'info> {
+    #[account(mut, constraint = admin.key() == market_config.authority @ ErrorCode::Unauthorized)]
+    pub admin: Signer<'info>,
+    #[account(seeds = [seeds::MARKET_CONFIG], bump)]
+    pub market_config: Account<'info, MarketConfig>,
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + 32 + 32 + 8 + 8 + 8 + 8 + 2 + 8 + 1,
+        seeds = [seeds::EMISSIONS],
+        bump
+    )]
+    pub emission_schedule: Account<'info, EmissionSchedule>,
+    // ATTN mint; only the emission schedule PDA can mint
+    #[account(
+        init,
+        payer = admin,
+        seeds = [seeds::ATTN_MINT],
+        bump,
+        mint::decimals = ATTN_DECIMALS,
+        mint::authority = emission_schedule,
+    )]
+    pub attn_mint: Account<'info, token::Mint>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct SetEmissionsDestination<'info> {
+    #[account(constraint = admin.key() == market_config.authority @ ErrorCode::Unauthorized)]
+    pub admin: Signer<'info>,
+    #[account(seeds = [seeds::MARKET_CONFIG], bump)]
+    pub market_config: Account<'info, MarketConfig>,
+    #[account(
+        mut,
+        seeds = [seeds::EMISSIONS],
+        bump = emission_schedule.bump
+    )]
+    pub emission_schedule: Account<'info, EmissionSchedule>,
+    #[account(constraint = rewards_destination.mint == emission_schedule.mint @ ErrorCode::InvalidMint)]
+    pub rewards_destination: Account<'info, TokenAccount>,
+}
+
+#[derive(Accounts)]
+pub struct MintEpochRewards<'info> {
+    #[account(
+        mut,
+        seeds = [seeds::EMISSIONS],
+        bump = emission_schedule.bump
+    )]
+    pub emission_schedule: Account<'info, EmissionSchedule>,
+    #[account(
+        mut,
+        constraint = attn_mint.key() == emission_schedule.mint @ ErrorCode::InvalidMint
+    )]
+    pub attn_mint: Account<'info, token::Mint>,
+    #[account(
+        mut,
+        constraint = rewards_destination.key() == emission_schedule.rewards_destination @ ErrorCode::InvalidEmissionsDestination
+    )]
+    pub rewards_destination: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct TokenizeEscrow<'info> {
+    #[account(mut)]
+    pub agent: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [seeds::ESCROW, agent.key().as_ref()],
+        bump = escrow_account.bump
+    )]
+    pub escrow_account: Account<'info, EscrowAccount>,
+    #[account(
+        init,
+        payer = agent,
+        seeds = [seeds::ESCROW_POSITION, escrow_account.key().as_ref()],
+        bump,
+        mint::decimals = 0,
+        mint::authority = escrow_account,
+    )]
+    pub position_mint: Account<'info, token::Mint>,
+    #[account(
+        init_if_needed,
+        payer = agent,
+        associated_token::mint = position_mint,
+        associated_token::authority = agent,
+    )]
+    pub agent_position_account: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawEscrowAsHolder<'info> {
+    pub holder: Signer<'info>,
+    // Whoever holds the position token controls the escrow's refund rights
+    #[account(
+        constraint = position_token_account.owner == holder.key() @ ErrorCode::Unauthorized,
+        constraint = position_token_account.mint == escrow_account.position_mint @ ErrorCode::InvalidPosition,
+        constraint = position_token_account.amount == 1 @ ErrorCode::InvalidPosition
+    )]
+    pub position_token_account: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = escrow_account.position_mint != Pubkey::default() @ ErrorCode::InvalidPosition,
+        seeds = [seeds::ESCROW, escrow_account.agent.as_ref()],
+        bump = escrow_account.bump
+    )]
+    pub escrow_account: Account<'info, EscrowAccount>,
+    #[account(
+        mut,
+        constraint = vault.owner == escrow_account.key() @ ErrorCode::InvalidVault
+    )]
+    pub vault: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = holder_token_account.owner == holder.key() @ ErrorCode::Unauthorized,
+        constraint = holder_token_account.mint == vault.mint @ ErrorCode::InvalidMint
+    )]
+    pub holder_token_account: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct SponsorDeposit<'info> {
+    #[account(mut)]
+    pub sponsor: Signer<'info>,
+    #[account(mut)]
+    pub sponsor_token_account: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        seeds = [seeds::ESCROW, escrow_account.agent.as_ref()],
+        bump = escrow_account.bump
+    )]
+    pub escrow_account: Account<'info, EscrowAccount>,
+    #[account(
+        init_if_needed,
+        payer = sponsor,
+        space = 8 + 32 + 32 + 8 + 8 + 1,
+        seeds = [seeds::SPONSOR, escrow_account.key().as_ref(), sponsor.key().as_ref()],
+        bump
+    )]
+    pub sponsor_position: Account<'info, SponsorPosition>,
+    // Fee Vault reference for mint validation
+    #[account(seeds = [seeds::MARKET_CONFIG], bump)]
+    pub market_config: Box<Account<'info, MarketConfig>>,
+    #[account(seeds = [seeds::FEE_VAULT_STATE], bump)]
+    pub fee_vault_state: Account<'info, FeeVaultState>,
+    #[account(constraint = fee_vault.owner == fee_vault_state.key())]
+    pub fee_vault: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = vault.owner == escrow_account.key() @ ErrorCode::InvalidVault,
+        constraint = vault.mint == fee_vault.mint @ ErrorCode::InvalidMint
+    )]
+    pub vault: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawSponsorship<'info> {
+    pub sponsor: Signer<'info>,
+    #[account(
+        mut,
+        constraint = sponsor_token_account.owner == sponsor.key() @ ErrorCode::Unauthorized,
+        constraint = sponsor_token_account.mint == vault.mint @ ErrorCode::InvalidMint
+    )]
+    pub sponsor_token_account: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        seeds = [seeds::ESCROW, escrow_account.agent.as_ref()],
+        bump = escrow_account.bump
+    )]
+    pub escrow_account: Account<'info, EscrowAccount>,
+    #[account(
+        mut,
+        seeds = [seeds::SPONSOR, escrow_account.key().as_ref(), sponsor.key().as_ref()],
+        bump = sponsor_position.bump
+    )]
+    pub sponsor_position: Account<'info, SponsorPosition>,
+    #[account(
+        mut,
+        constraint = vault.owner == escrow_account.key() @ ErrorCode::InvalidVault
+    )]
+    pub vault: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateMarketConfig<'info> {
+    #[account(constraint = admin.key() == market_config.authority @ ErrorCode::Unauthorized)]
+    pub admin: Signer<'info>,
+    #[account(mut, seeds = [seeds::MARKET_CONFIG], bump)]
+    pub market_config: Account<'info, MarketConfig>,
+}
+
+#[derive(Accounts)]
+pub struct RouterHeartbeat<'info> {
+    #[account(constraint = router.key() == market_config.authority @ ErrorCode::Unauthorized)]
+    pub router: Signer<'info>,
+    #[account(mut, seeds = [seeds::MARKET_CONFIG], bump)]
+    pub market_config: Account<'info, MarketConfig>,
+}
+
+#[derive(Accounts)]
+#[instruction(shard_id: u8)]
+pub struct SetShardSigner<'info> {
+    #[account(mut, constraint = admin.key() == market_config.authority @ ErrorCode::Unauthorized)]
+    pub admin: Signer<'info>,
+    #[account(seeds = [seeds::MARKET_CONFIG], bump)]
+    pub market_config: Account<'info, MarketConfig>,
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = Shard::SPACE,
+        seeds = [seeds::SHARD, &[shard_id]],
+        bump
+    )]
+    pub shard: Account<'info, Shard>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct AssignEscrowShard<'info> {
+    #[account(
+        mut,
+        seeds = [seeds::ESCROW, escrow_account.agent.as_ref()],
+        bump = escrow_account.bump
+    )]
+    pub escrow_account: Account<'info, EscrowAccount>,
+    #[account(seeds = [seeds::MARKET_CONFIG], bump)]
+    pub market_config: Account<'info, MarketConfig>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateFeeParams<'info> {
+    // Governance, once set, replaces the admin for fee parameters
+    #[account(constraint = authority.key() == market_config.fee_authority() @ ErrorCode::Unauthorized)]
+    pub authority: Signer<'info>,
+    #[account(mut, seeds = [seeds::MARKET_CONFIG], bump)]
+    pub market_config: Account<'info, MarketConfig>,
+}
+
+#[derive(Accounts)]
+pub struct SetFeeChallengeWindow<'info> {
+    #[account(constraint = authority.key() == market_config.fee_authority() @ ErrorCode::Unauthorized)]
+    pub authority: Signer<'info>,
+    #[account(mut, seeds = [seeds::MARKET_CONFIG], bump)]
+    pub market_config: Account<'info, MarketConfig>,
+    #[account(mut, seeds = [seeds::FEE_VAULT_STATE], bump = fee_vault_state.bump)]
+    pub fee_vault_state: Account<'info, FeeVaultState>,
+}
+
+#[derive(Accounts)]
+pub struct SetFeeBpsOverride<'info> {
+    #[account(constraint = admin.key() == market_config.authority @ ErrorCode::Unauthorized)]
+    pub admin: Signer<'info>,
+    #[account(seeds = [seeds::MARKET_CONFIG], bump)]
+    pub market_config: Account<'info, MarketConfig>,
+    #[account(
+        mut,
+        seeds = [seeds::ESCROW, escrow_account.agent.as_ref()],
+        bump = escrow_account.bump
+    )]
+    pub escrow_account: Account<'info, EscrowAccount>,
+}
+
+#[derive(Accounts)]
+pub struct SetKycTier<'info> {
+    #[account(constraint = admin.key() == market_config.authority @ ErrorCode::Unauthorized)]
+    pub admin: Signer<'info>,
+    #[account(seeds = [seeds::MARKET_CONFIG], bump)]
+    pub market_config: Account<'info, MarketConfig>,
+    #[account(
+        mut,
+        seeds = [seeds::ESCROW, escrow_account.agent.as_ref()],
+        bump = escrow_account.bump
+    )]
+    pub escrow_account: Account<'info, EscrowAccount>,
+}
+
+#[derive(Accounts)]
+pub struct ReleaseHoldback<'info> {
+    pub caller: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [seeds::HOLDBACK, human.key().as_ref()],
+        bump = holdback.bump
+    )]
+    pub holdback: Account<'info, Holdback>,
+    /// CHECK: Human's wallet public key; payouts only go to its ATA.
+    pub human: UncheckedAccount<'info>,
+    /// CHECK: Human's PayoutPrefs PDA; may be uninitialized.
+    #[account(seeds = [seeds::PAYOUT_PREFS, human.key().as_ref()], bump)]
+    pub payout_prefs: UncheckedAccount<'info>,
+    /// CHECK: Human's PayoutSplit PDA; may be uninitialized. Required so cranks can't skip it.
+    #[account(seeds = [seeds::PAYOUT_SPLIT, human.key().as_ref()], bump)]
+    pub payout_split: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        associated_token::mint = fee_vault.mint,
+        associated_token::authority = human,
+    )]
+    pub human_token_account: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        seeds = [seeds::FEE_VAULT_STATE],
+        bump = fee_vault_state.bump
+    )]
+    pub fee_vault_state: Account<'info, FeeVaultState>,
+    #[account(
+        mut,
+        constraint = fee_vault.owner == fee_vault_state.key()
+    )]
+    pub fee_vault: Account<'info, TokenAccount>,
+    #[account(seeds = [seeds::MARKET_CONFIG], bump)]
+    pub market_config: Account<'info, MarketConfig>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct FlagHoldback<'info> {
+    #[account(constraint = admin.key() == market_config.authority @ ErrorCode::Unauthorized)]
+    pub admin: Signer<'info>,
+    #[account(seeds = [seeds::MARKET_CONFIG], bump)]
+    pub market_config: Account<'info, MarketConfig>,
+    #[account(
+        mut,
+        seeds = [seeds::HOLDBACK, holdback.human.as_ref()],
+        bump = holdback.bump
+    )]
+    pub holdback: Account<'info, Holdback>,
+    #[account(
+        mut,
+        seeds = [seeds::FEE_VAULT_STATE],
+        bump = fee_vault_state.bump
+    )]
+    pub fee_vault_state: Account<'info, FeeVaultState>,
+}
+
+#[derive(Accounts)]
+#[instruction(key: Pubkey)]
+pub struct AddAttestationKey<'info> {
+    #[account(mut, constraint = admin.key() == market_config.authority @ ErrorCode::Unauthorized)]
+    pub admin: Signer<'info>,
+    #[account(mut, seeds = [seeds::MARKET_CONFIG], bump)]
+    pub market_config: Account<'info, MarketConfig>,
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + 32 + 8 + 1 + 1,
+        seeds = [seeds::ATTESTATION_KEY, key.as_ref()],
+        bump
+    )]
+    pub attestation_key: Account<'info, AttestationKey>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RevokeAttestationKey<'info> {
+    #[account(constraint = admin.key() == market_config.authority @ ErrorCode::Unauthorized)]
+    pub admin: Signer<'info>,
+    #[account(mut, seeds = [seeds::MARKET_CONFIG], bump)]
+    pub market_config: Account<'info, MarketConfig>,
+    #[account(
+        mut,
+        constraint = !attestation_key.revoked @ ErrorCode::AttestationKeyInactive,
+        seeds = [seeds::ATTESTATION_KEY, attestation_key.key.as_ref()],
+        bump = attestation_key.bump
+    )]
+    pub attestation_key: Account<'info, AttestationKey>,
+}
+
+#[derive(Accounts)]
+#[instruction(program: Pubkey)]
+pub struct AddAttestationIssuer<'info> {
+    #[account(mut, constraint = admin.key() == market_config.authority @ ErrorCode::Unauthorized)]
+    pub admin: Signer<'info>,
+    #[account(seeds = [seeds::MARKET_CONFIG], bump)]
+    pub market_config: Account<'info, MarketConfig>,
+    #[account(
+        init,
+        payer = admin,
+        space = AttestationIssuer::SPACE,
+        seeds = [seeds::ATTESTATION_ISSUER, program.as_ref()],
+        bump
+    )]
+    pub attestation_issuer: Account<'info, AttestationIssuer>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RevokeAttestationIssuer<'info> {
+    #[account(constraint = admin.key() == market_config.authority @ ErrorCode::Unauthorized)]
+    pub admin: Signer<'info>,
+    #[account(seeds = [seeds::MARKET_CONFIG], bump)]
+    pub market_config: Account<'info, MarketConfig>,
+    #[account(
+        mut,
+        seeds = [seeds::ATTESTATION_ISSUER, attestation_issuer.program.as_ref()],
+        bump = attestation_issuer.bump
+    )]
+    pub attestation_issuer: Account<'info, AttestationIssuer>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct BatchSettlementEntry {
+    pub verified_seconds: u64,
+    pub agreed_price_per_second: u64,
+    pub nonce: u64,
+}
+
+#[derive(Accounts)]
+pub struct CloseSettlementBatch<'info> {
+    // Checked against every escrow's settlement router in the handler
+    #[account(mut)]
+    pub router: Signer<'info>,
+    /// CHECK: Human's wallet public key. Only used as the authority of the payout ATA.
+    pub human: UncheckedAccount<'info>,
+    /// CHECK: Human's PayoutPrefs PDA; may be uninitialized. Required so the router can't skip it.
+    #[account(seeds = [seeds::PAYOUT_PREFS, human.key().as_ref()], bump)]
+    pub payout_prefs: UncheckedAccount<'info>,
+    /// CHECK: Human's PayoutSplit PDA; may be uninitialized. Required so the router can't skip it.
+    #[account(seeds = [seeds::PAYOUT_SPLIT, human.key().as_ref()], bump)]
+    pub payout_split: UncheckedAccount<'info>,
+    /// CHECK: Human's OrgMember PDA; may be uninitialized. Required so the router can't skip it.
+    #[account(seeds = [seeds::ORG_MEMBER, human.key().as_ref()], bump)]
+    pub org_member: UncheckedAccount<'info>,
+    #[account(
+        init_if_needed,
+        payer = router,
+        associated_token::mint = mint,
+        associated_token::authority = human,
+    )]
+    pub user_wallet: Account<'info, TokenAccount>,
+    #[account(
+        constraint = mint.key() == fee_vault.mint @ ErrorCode::InvalidMint,
+        constraint = market_config.accepts_mint(&mint) @ ErrorCode::InvalidMint
+    )]
+    pub mint: Account<'info, token::Mint>,
+    #[account(
+        mut,
+        seeds = [seeds::FEE_VAULT_STATE],
+        bump = fee_vault_state.bump
+    )]
+    pub fee_vault_state: Account<'info, FeeVaultState>,
+    #[account(
+        mut,
+        constraint = fee_vault.owner == fee_vault_state.key()
+    )]
+    pub fee_vault: Account<'info, TokenAccount>,
+    // Optional Holdback (required when market_config.holdback_bps > 0)
+    #[account(
+        init_if_needed,
+        payer = router,
+        space = Holdback::SPACE,
+        seeds = [seeds::HOLDBACK, human.key().as_ref()],
+        bump
+    )]
+    pub holdback: Option<Account<'info, Holdback>>,
+    #[account(
+        mut,
+        seeds = [seeds::MARKET_CONFIG],
+        bump,
+        constraint = !market_config.settlements_paused @ ErrorCode::SettlementsPaused
+    )]
+    pub market_config: Account<'info, MarketConfig>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+
+    // Optional Relayer Stats (tracks the router's gas spend for reimbursement)
+    #[account(
+        init_if_needed,
+        payer = router,
+        space = 8 + 32 + 8 + 8 + 8 + 8 + 1,
+        seeds = [seeds::RELAYER_STATS, router.key().as_ref()],
+        bump
+    )]
+    pub relayer_stats: Option<Box<Account<'info, RelayerStats>>>,
+
+    // Optional Shard (required to settle an escrow pinned to a shard)
+    #[account(seeds = [seeds::SHARD, &[shard.shard_id]], bump = shard.bump)]
+    pub shard: Option<Account<'info, Shard>>,
+    // Optional Org and its treasury (required when the human belongs to an org)
+    #[account(mut, seeds = [seeds::ORG, org.org_id.as_ref()], bump = org.bump)]
+    pub org: Option<Box<Account<'info, Org>>>,
+    #[account(mut)]
+    pub org_treasury: Option<Box<Account<'info, TokenAccount>>>,
+    // Optional attestation (verified against the key registry; required while it has a live key)
+    #[account(
+        seeds = [seeds::ATTESTATION_KEY, attestation_key.key.as_ref()],
+        bump = attestation_key.bump
+    )]
+    pub attestation_key: Option<Account<'info, AttestationKey>>,
+    /// CHECK: Instructions sysvar, used to introspect the attestation signature.
+    #[account(address = anchor_lang::solana_program::sysvar::instructions::ID)]
+    pub instructions_sysvar: Option<UncheckedAccount<'info>>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeSettlementQueue<'info> {
+    #[account(mut, constraint = admin.key() == market_config.authority @ ErrorCode::Unauthorized)]
+    pub admin: Signer<'info>,
+    #[account(seeds = [seeds::MARKET_CONFIG], bump)]
+    pub market_config: Account<'info, MarketConfig>,
+    #[account(
+        init,
+        payer = admin,
+        space = SettlementQueue::SPACE,
+        seeds = [seeds::SETTLEMENT_QUEUE],
+        bump
+    )]
+    pub queue: Account<'info, SettlementQueue>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct EnqueueSettlement<'info> {
+    #[account(
+        mut,
+        constraint = router.key() == escrow_account.settlement_router(&market_config, shard.as_deref(), Clock::get()?.unix_timestamp)
+            @ ErrorCode::Unauthorized
+    )]
+    pub router: Signer<'info>,
+    #[account(seeds = [seeds::MARKET_CONFIG], bump)]
+    pub market_config: Box<Account<'info, MarketConfig>>,
+    #[account(mut, seeds = [seeds::SETTLEMENT_QUEUE], bump = queue.bump)]
+    pub queue: Account<'info, SettlementQueue>,
+    #[account(
+        init,
+        payer = router,
+        space = QueuedSettlement::SPACE,
+        seeds = [seeds::QUEUED_SETTLEMENT, queue.tail.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub item: Account<'info, QueuedSettlement>,
+    // Queued settlements pay out in the primary mint only
+    #[account(
+        constraint = escrow_account.mint == Pubkey::default() @ ErrorCode::InvalidMint,
+        seeds = [seeds::ESCROW, escrow_account.agent.as_ref()],
+        bump = escrow_account.bump
+    )]
+    pub escrow_account: Box<Account<'info, EscrowAccount>>,
+    /// CHECK: Human's wallet public key. Only used as the authority of the payout ATA.
+    pub human: UncheckedAccount<'info>,
+    /// CHECK: Human's PayoutPrefs PDA; may be uninitialized. Required so the router can't skip it.
+    #[account(seeds = [seeds::PAYOUT_PREFS, human.key().as_ref()], bump)]
+    pub payout_prefs: UncheckedAccount<'info>,
+    /// CHECK: Human's PayoutSplit PDA; may be uninitialized. Required so the router can't skip it.
+    #[account(seeds = [seeds::PAYOUT_SPLIT, human.key().as_ref()], bump)]
+    pub payout_split: UncheckedAccount<'info>,
+    /// CHECK: Human's OrgMember PDA; may be uninitialized. Required so the router can't skip it.
+    #[account(seeds = [seeds::ORG_MEMBER, human.key().as_ref()], bump)]
+    pub org_member: UncheckedAccount<'info>,
+    // Session the seconds are billed against
+    #[account(
+        mut,
+        constraint = session.escrow == escrow_account.key() @ ErrorCode::SessionMismatch,
+        constraint = session.human == human.key() @ ErrorCode::SessionMismatch
+    )]
+    pub session: Box<Account<'info, Session>>,
+    #[account(
+        init_if_needed,
+        payer = router,
+        associated_token::mint = mint,
+        associated_token::authority = human,
+    )]
+    pub user_wallet: Box<Account<'info, TokenAccount>>,
+    #[account(constraint = market_config.accepts_mint(&mint) @ ErrorCode::InvalidMint)]
+    pub mint: Box<Account<'info, token::Mint>>,
+    #[account(
+        init_if_needed,
+        payer = router,
+        space = Holdback::SPACE,
+        seeds = [seeds::HOLDBACK, human.key().as_ref()],
+        bump
+    )]
+    pub holdback: Box<Account<'info, Holdback>>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+
+    // Optional Shard (required to settle an escrow pinned to a shard)
+    #[account(seeds = [seeds::SHARD, &[shard.shard_id]], bump = shard.bump)]
+    pub shard: Option<Account<'info, Shard>>,
+    // Optional attestation (verified against the key registry; required while it has a live key)
+    #[account(
+        seeds = [seeds::ATTESTATION_KEY, attestation_key.key.as_ref()],
+        bump = attestation_key.bump
+    )]
+    pub attestation_key: Option<Account<'info, AttestationKey>>,
+    /// CHECK: Instructions sysvar, used to introspect the attestation signature.
+    #[account(address = anchor_lang::solana_program::sysvar::instructions::ID)]
+    pub instructions_sysvar: Option<UncheckedAccount<'info>>,
+}
+
+#[derive(Accounts)]
+pub struct ProcessQueue<'info> {
+    #[account(mut)]
+    pub cranker: Signer<'info>,
+    #[account(mut, seeds = [seeds::SETTLEMENT_QUEUE], bump = queue.bump)]
+    pub queue: Account<'info, SettlementQueue>,
+    #[account(
+        mut,
+        seeds = [seeds::MARKET_CONFIG],
+        bump,
+        constraint = !market_config.settlements_paused @ ErrorCode::SettlementsPaused
+    )]
+    pub market_config: Box<Account<'info, MarketConfig>>,
+    #[account(
+        mut,
+        seeds = [seeds::FEE_VAULT_STATE],
+        bump = fee_vault_state.bump
+    )]
+    pub fee_vault_state: Account<'info, FeeVaultState>,
+    #[account(
+        mut,
+        constraint = fee_vault.owner == fee_vault_state.key()
+    )]
+    pub fee_vault: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct InitBuilderStats<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        seeds = [seeds::BUILDER, builder_balance.builder_code.as_ref()],
+        bump = builder_balance.bump
+    )]
+    pub builder_balance: Account<'info, BuilderBalance>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + 32 + 8 * 12 + 4 + 1,
+        seeds = [seeds::BUILDER_STATS, builder_balance.builder_code.as_ref()],
+        bump
+    )]
+    pub builder_stats: Account<'info, BuilderStats>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(task_id: u64)]
+pub struct CreateTask<'info> {
+    #[account(mut)]
+    pub agent: Signer<'info>,
+    #[account(
+        mut,
+        constraint = escrow_account.position_mint == Pubkey::default() @ ErrorCode::EscrowTokenized,
+        seeds = [seeds::ESCROW, agent.key().as_ref()],
+        bump = escrow_account.bump
+    )]
+    pub escrow_account: Account<'info, EscrowAccount>,
+    #[account(
+        init,
+        payer = agent,
+        space = Task::SPACE,
+        seeds = [seeds::TASK, escrow_account.key().as_ref(), task_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub task: Account<'info, Task>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct FundTasksBatch<'info> {
+    pub agent: Signer<'info>,
+    #[account(mut)]
+    pub agent_token_account: Account<'info, TokenAccount>,
+    #[account(
+        constraint = escrow_account.position_mint == Pubkey::default() @ ErrorCode::EscrowTokenized,
+        constraint = escrow_account.mint == mint_fee_vault.as_ref().map(|m| m.mint).unwrap_or_default()
+            @ ErrorCode::InvalidMint,
+        seeds = [seeds::ESCROW, agent.key().as_ref()],
+        bump = escrow_account.bump
+    )]
+    pub escrow_account: Account<'info, EscrowAccount>,
+    // Fee Vault reference for mint validation (the mint's own vault for secondary mints)
+    #[account(seeds = [seeds::FEE_VAULT_STATE], bump = fee_vault_state.bump)]
+    pub fee_vault_state: Account<'info, FeeVaultState>,
+    #[account(
+        constraint = mint_fee_vault.as_ref()
+            .map_or(fee_vault.owner == fee_vault_state.key(), |m| fee_vault.key() == m.vault)
+            @ ErrorCode::InvalidMint
+    )]
+    pub fee_vault: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = vault.owner == escrow_account.key() @ ErrorCode::InvalidVault,
+        constraint = vault.mint == fee_vault.mint @ ErrorCode::InvalidMint
+    )]
+    pub vault: Account<'info, TokenAccount>,
+    #[account(seeds = [seeds::MARKET_CONFIG], bump)]
+    pub market_config: Box<Account<'info, MarketConfig>>,
+    pub token_program: Program<'info, Token>,
+
+    // Optional Mint Fee Vault (escrows funded in a registered secondary mint)
+    #[account(
+        seeds = [seeds::MINT_FEE_VAULT, mint_fee_vault.mint.as_ref()],
+        bump = mint_fee_vault.bump
+    )]
+    pub mint_fee_vault: Option<Account<'info, MintFeeVault>>,
+}
+
+#[derive(Accounts)]
+pub struct RefundRemainder<'info> {
+    #[account(mut)]
+    pub agent: Signer<'info>,
+    #[account(
+        seeds = [seeds::ESCROW, agent.key().as_ref()],
+        bump = escrow_account.bump
+    )]
+    pub escrow_account: Account<'info, EscrowAccount>,
+    #[account(
+        mut,
+        close = agent,
+        has_one = agent,
+        constraint = task.escrow == escrow_account.key() @ ErrorCode::InvalidTask,
+        seeds = [seeds::TASK, escrow_account.key().as_ref(), task.task_id.to_le_bytes().as_ref()],
+        bump = task.bump
+    )]
+    pub task: Account<'info, Task>,
+    /// CHECK: The task's Bounty PDA; may be uninitialized. Required so an open bounty can't be skipped.
+    #[account(seeds = [seeds::BOUNTY, task.key().as_ref()], bump)]
+    pub bounty: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        constraint = vault.owner == escrow_account.key() @ ErrorCode::InvalidVault
+    )]
+    pub vault: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = task.accepts_refund_to(&agent_token_account) @ ErrorCode::InvalidTokenAccount,
+        constraint = agent_token_account.mint == vault.mint @ ErrorCode::InvalidTokenAccount
+    )]
+    pub agent_token_account: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct SetTaskRefundAddress<'info> {
+    pub agent: Signer<'info>,
+    #[account(
+        seeds = [seeds::ESCROW, agent.key().as_ref()],
+        bump = escrow_account.bump
+    )]
+    pub escrow_account: Account<'info, EscrowAccount>,
+    #[account(
+        mut,
+        has_one = agent,
+        constraint = task.escrow == escrow_account.key() @ ErrorCode::InvalidTask,
+        seeds = [seeds::TASK, escrow_account.key().as_ref(), task.task_id.to_le_bytes().as_ref()],
+        bump = task.bump
+    )]
+    pub task: Account<'info, Task>,
+    #[account(constraint = vault.owner == escrow_account.key() @ ErrorCode::InvalidVault)]
+    pub vault: Account<'info, TokenAccount>,
+    // Must be in the escrow's mint so refunds can't be stranded
+    #[account(constraint = refund_account.mint == vault.mint @ ErrorCode::InvalidTokenAccount)]
+    pub refund_account: Option<Account<'info, TokenAccount>>,
+}
+
+#[derive(Accounts)]
+pub struct SetTaskGate<'info> {
+    #[account(mut)]
+    pub agent: Signer<'info>,
+    #[account(
+        seeds = [seeds::ESCROW, agent.key().as_ref()],
+        bump = escrow_account.bump
+    )]
+    pub escrow_account: Account<'info, EscrowAccount>,
+    #[account(
+        has_one = agent,
+        constraint = task.escrow == escrow_account.key() @ ErrorCode::InvalidTask,
+        seeds = [seeds::TASK, escrow_account.key().as_ref(), task.task_id.to_le_bytes().as_ref()],
+        bump = task.bump
+    )]
+    pub task: Account<'info, Task>,
+    #[account(
+        init_if_needed,
+        payer = agent,
+        space = TaskGate::SPACE,
+        seeds = [seeds::TASK_GATE, task.key().as_ref()],
+        bump
+    )]
+    pub task_gate: Account<'info, TaskGate>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClearTaskGate<'info> {
+    #[account(mut)]
+    pub agent: Signer<'info>,
+    #[account(
+        seeds = [seeds::ESCROW, agent.key().as_ref()],
+        bump = escrow_account.bump
+    )]
+    pub escrow_account: Account<'info, EscrowAccount>,
+    #[account(
+        mut,
+        close = agent,
+        constraint = task_gate.escrow == escrow_account.key() @ ErrorCode::InvalidTask,
+        seeds = [seeds::TASK_GATE, task_gate.task.as_ref()],
+        bump = task_gate.bump
+    )]
+    pub task_gate: Account<'info, TaskGate>,
+}
+
+#[derive(Accounts)]
+pub struct SetTaskPolicy<'info> {
+    #[account(mut)]
+    pub agent: Signer<'info>,
+    #[account(
+        seeds = [seeds::ESCROW, agent.key().as_ref()],
+        bump = escrow_account.bump
+    )]
+    pub escrow_account: Account<'info, EscrowAccount>,
+    #[account(
+        has_one = agent,
+        constraint = task.escrow == escrow_account.key() @ ErrorCode::InvalidTask,
+        seeds = [seeds::TASK, escrow_account.key().as_ref(), task.task_id.to_le_bytes().as_ref()],
+        bump = task.bump
+    )]
+    pub task: Account<'info, Task>,
+    #[account(
+        init_if_needed,
+        payer = agent,
+        space = TaskPolicy::SPACE,
+        seeds = [seeds::TASK_POLICY, task.key().as_ref()],
+        bump
+    )]
+    pub task_policy: Account<'info, TaskPolicy>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClearTaskPolicy<'info> {
+    #[account(mut)]
+    pub agent: Signer<'info>,
+    #[account(
+        seeds = [seeds::ESCROW, agent.key().as_ref()],
+        bump = escrow_account.bump
+    )]
+    pub escrow_account: Account<'info, EscrowAccount>,
+    #[account(
+        mut,
+        close = agent,
+        constraint = task_policy.escrow == escrow_account.key() @ ErrorCode::InvalidTask,
+        seeds = [seeds::TASK_POLICY, task_policy.task.as_ref()],
+        bump = task_policy.bump
+    )]
+    pub task_policy: Account<'info, TaskPolicy>,
+}
+
+#[derive(Accounts)]
+pub struct PostBounty<'info> {
+    #[account(mut)]
+    pub agent: Signer<'info>,
+    #[account(
+        seeds = [seeds::ESCROW, agent.key().as_ref()],
+        bump = escrow_account.bump
+    )]
+    pub escrow_account: Account<'info, EscrowAccount>,
+    #[account(
+        mut,
+        has_one = agent,
+        constraint = task.escrow == escrow_account.key() @ ErrorCode::InvalidTask,
+        seeds = [seeds::TASK, escrow_account.key().as_ref(), task.task_id.to_le_bytes().as_ref()],
+        bump = task.bump
+    )]
+    pub task: Account<'info, Task>,
+    #[account(
+        init,
+        payer = agent,
+        space = Bounty::SPACE,
+        seeds = [seeds::BOUNTY, task.key().as_ref()],
+        bump
+    )]
+    pub bounty: Account<'info, Bounty>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CompleteBounty<'info> {
+    #[account(
+        mut,
+        constraint = router.key() == escrow_account.settlement_router(&market_config, shard.as_deref(), Clock::get()?.unix_timestamp)
+            @ ErrorCode::Unauthorized
+    )]
+    pub router: Signer<'info>,
+    #[account(
+        mut,
+        address = bounty.escrow @ ErrorCode::InvalidEscrow
+    )]
+    pub escrow_account: Box<Account<'info, EscrowAccount>>,
+    #[account(
+        mut,
+        seeds = [seeds::BOUNTY, bounty.task.as_ref()],
+        bump = bounty.bump
+    )]
+    pub bounty: Account<'info, Bounty>,
+    #[account(
+        mut,
+        constraint = vault.owner == escrow_account.key() @ ErrorCode::InvalidVault,
+        constraint = vault.mint == fee_vault.mint @ ErrorCode::InvalidMint
+    )]
+    pub vault: Box<Account<'info, TokenAccount>>,
+    /// CHECK: Human's wallet public key. Only used as the authority of the payout ATA.
+    #[account(address = bounty.human @ ErrorCode::Unauthorized)]
+    pub human: UncheckedAccount<'info>,
+    /// CHECK: Human's PayoutPrefs PDA; may be uninitialized.
+    #[account(seeds = [seeds::PAYOUT_PREFS, human.key().as_ref()], bump)]
+    pub payout_prefs: UncheckedAccount<'info>,
+    /// CHECK: Human's PayoutSplit PDA; may be uninitialized. Required so the router can't skip it.
+    #[account(seeds = [seeds::PAYOUT_SPLIT, human.key().as_ref()], bump)]
+    pub payout_split: UncheckedAccount<'info>,
+    /// CHECK: Human's OrgMember PDA; may be uninitialized. Required so the router can't skip it.
+    #[account(seeds = [seeds::ORG_MEMBER, human.key().as_ref()], bump)]
+    pub org_member: UncheckedAccount<'info>,
+    #[account(
+        init_if_needed,
+        payer = router,
+        associated_token::mint = mint,
+        associated_token::authority = human,
+    )]
+    pub user_wallet: Box<Account<'info, TokenAccount>>,
+    #[account(
+        constraint = mint.key() == fee_vault.mint @ ErrorCode::InvalidMint,
+        constraint = market_config.accepts_mint(&mint) @ ErrorCode::InvalidMint
+    )]
+    pub mint: Box<Account<'info, token::Mint>>,
+    #[account(
+        mut,
+        seeds = [seeds::FEE_VAULT_STATE],
+        bump = fee_vault_state.bump
+    )]
+    pub fee_vault_state: Box<Account<'info, FeeVaultState>>,
+    #[account(
+        mut,
+        constraint = fee_vault.owner == fee_vault_state.key()
+    )]
+    pub fee_vault: Box<Account<'info, TokenAccount>>,
+    #[account(
+        mut,
+        seeds = [seeds::MARKET_CONFIG],
+        bump,
+        constraint = !market_config.settlements_paused @ ErrorCode::SettlementsPaused
+    )]
+    pub market_config: Box<Account<'info, MarketConfig>>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+
+    // Optional Shard (required to settle an escrow pinned to a shard)
+    #[account(seeds = [seeds::SHARD, &[shard.shard_id]], bump = shard.bump)]
+    pub shard: Option<Account<'info, Shard>>,
+
+    // Optional Holdback (required when market_config.holdback_bps > 0)
+    #[account(
+        init_if_needed,
+        payer = router,
+        space = Holdback::SPACE,
+        seeds = [seeds::HOLDBACK, human.key().as_ref()],
+        bump
+    )]
+    pub holdback: Option<Account<'info, Holdback>>,
+
+    // Optional Org and its treasury (required when the human belongs to an org)
+    #[account(mut, seeds = [seeds::ORG, org.org_id.as_ref()], bump = org.bump)]
+    pub org: Option<Box<Account<'info, Org>>>,
+    #[account(mut)]
+    pub org_treasury: Option<Box<Account<'info, TokenAccount>>>,
+}
+
+#[derive(Accounts)]
+pub struct SetTaskMilestones<'info> {
+    #[account(mut)]
+    pub agent: Signer<'info>,
+    #[account(
+        mut,
+        has_one = agent,
+        seeds = [seeds::TASK, task.escrow.as_ref(), task.task_id.to_le_bytes().as_ref()],
+        bump = task.bump
+    )]
+    pub task: Account<'info, Task>,
+    #[account(
+        init,
+        payer = agent,
+        space = TaskMilestones::SPACE,
+        seeds = [seeds::TASK_MILESTONES, task.key().as_ref()],
+        bump
+    )]
+    pub task_milestones: Account<'info, TaskMilestones>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ReleaseMilestone<'info> {
+    #[account(
+        mut,
+        constraint = router.key() == escrow_account.settlement_router(&market_config, shard.as_deref(), Clock::get()?.unix_timestamp)
+            @ ErrorCode::Unauthorized
+    )]
+    pub router: Signer<'info>,
+    #[account(
+        mut,
+        address = task_milestones.escrow @ ErrorCode::InvalidEscrow
+    )]
+    pub escrow_account: Box<Account<'info, EscrowAccount>>,
+    #[account(
+        mut,
+        seeds = [seeds::TASK_MILESTONES, task_milestones.task.as_ref()],
+        bump = task_milestones.bump
+    )]
+    pub task_milestones: Box<Account<'info, TaskMilestones>>,
+    #[account(
+        mut,
+        constraint = vault.owner == escrow_account.key() @ ErrorCode::InvalidVault,
+        constraint = vault.mint == fee_vault.mint @ ErrorCode::InvalidMint
+    )]
+    pub vault: Box<Account<'info, TokenAccount>>,
+    /// CHECK: Human's wallet public key. Only used as the authority of the payout ATA.
+    #[account(address = task_milestones.human @ ErrorCode::Unauthorized)]
+    pub human: UncheckedAccount<'info>,
+    /// CHECK: Human's PayoutPrefs PDA; may be uninitialized.
+    #[account(seeds = [seeds::PAYOUT_PREFS, human.key().as_ref()], bump)]
+    pub payout_prefs: UncheckedAccount<'info>,
+    /// CHECK: Human's PayoutSplit PDA; may be uninitialized. Required so the router can't skip it.
+    #[account(seeds = [seeds::PAYOUT_SPLIT, human.key().as_ref()], bump)]
+    pub payout_split: UncheckedAccount<'info>,
+    /// CHECK: Human's OrgMember PDA; may be uninitialized. Required so the router can't skip it.
+    #[account(seeds = [seeds::ORG_MEMBER, human.key().as_ref()], bump)]
+    pub org_member: UncheckedAccount<'info>,
+    #[account(
+        init_if_needed,
+        payer = router,
+        associated_token::mint = mint,
+        associated_token::authority = human,
+    )]
+    pub user_wallet: Box<Account<'info, TokenAccount>>,
+    #[account(
+        constraint = mint.key() == fee_vault.mint @ ErrorCode::InvalidMint,
+        constraint = market_config.accepts_mint(&mint) @ ErrorCode::InvalidMint
+    )]
+    pub mint: Box<Account<'info, token::Mint>>,
+    #[account(
+        mut,
+        seeds = [seeds::FEE_VAULT_STATE],
+        bump = fee_vault_state.bump
+    )]
+    pub fee_vault_state: Box<Account<'info, FeeVaultState>>,
+    #[account(
+        mut,
+        constraint = fee_vault.owner == fee_vault_state.key()
+    )]
+    pub fee_vault: Box<Account<'info, TokenAccount>>,
+    #[account(
+        mut,
+        seeds = [seeds::MARKET_CONFIG],
+        bump,
+        constraint = !market_config.settlements_paused @ ErrorCode::SettlementsPaused
+    )]
+    pub market_config: Box<Account<'info, MarketConfig>>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+
+    // Optional Shard (required to settle an escrow pinned to a shard)
+    #[account(seeds = [seeds::SHARD, &[shard.shard_id]], bump = shard.bump)]
+    pub shard: Option<Account<'info, Shard>>,
+
+    // Optional Holdback (required when market_config.holdback_bps > 0)
+    #[account(
+        init_if_needed,
+        payer = router,
+        space = Holdback::SPACE,
+        seeds = [seeds::HOLDBACK, human.key().as_ref()],
+        bump
+    )]
+    pub holdback: Option<Account<'info, Holdback>>,
+
+    // Optional Org and its treasury (required when the human belongs to an org)
+    #[account(mut, seeds = [seeds::ORG, org.org_id.as_ref()], bump = org.bump)]
+    pub org: Option<Box<Account<'info, Org>>>,
+    #[account(mut)]
+    pub org_treasury: Option<Box<Account<'info, TokenAccount>>>,
+}
+
+#[derive(Accounts)]
+pub struct CancelMilestone<'info> {
+    pub agent: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [seeds::ESCROW, agent.key().as_ref()],
+        bump = escrow_account.bump
+    )]
+    pub escrow_account: Account<'info, EscrowAccount>,
+    #[account(
+        mut,
+        constraint = task_milestones.escrow == escrow_account.key() @ ErrorCode::InvalidTask,
+        seeds = [seeds::TASK_MILESTONES, task_milestones.task.as_ref()],
+        bump = task_milestones.bump
+    )]
+    pub task_milestones: Account<'info, TaskMilestones>,
+}
+
+#[derive(Accounts)]
+pub struct CancelBounty<'info> {
+    #[account(mut)]
+    pub agent: Signer<'info>,
+    #[account(
+        mut,
+        has_one = agent,
+        seeds = [seeds::TASK, task.escrow.as_ref(), task.task_id.to_le_bytes().as_ref()],
+        bump = task.bump
+    )]
+    pub task: Account<'info, Task>,
+    #[account(
+        mut,
+        close = agent,
+        constraint = bounty.task == task.key() @ ErrorCode::InvalidTask,
+        seeds = [seeds::BOUNTY, task.key().as_ref()],
+        bump = bounty.bump
+    )]
+    pub bounty: Account<'info, Bounty>,
+}
+
+#[derive(Accounts)]
+pub struct GrantObserver<'info> {
+    #[account(mut)]
+    pub agent: Signer<'info>,
+    #[account(
+        seeds = [seeds::ESCROW, agent.key().as_ref()],
+        bump = escrow_account.bump
+    )]
+    pub escrow_account: Account<'info, EscrowAccount>,
+    /// CHECK: Observer wallet; only recorded
+    pub observer: UncheckedAccount<'info>,
+    #[account(
+        init_if_needed,
+        payer = agent,
+        space = EscrowObserver::SPACE,
+        seeds = [seeds::ESCROW_OBSERVER, escrow_account.key().as_ref(), observer.key().as_ref()],
+        bump
+    )]
+    pub escrow_observer: Account<'info, EscrowObserver>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptObserver<'info> {
+    pub observer: Signer<'info>,
+    #[account(
+        mut,
+        has_one = observer,
+        seeds = [seeds::ESCROW_OBSERVER, escrow_observer.escrow.as_ref(), observer.key().as_ref()],
+        bump = escrow_observer.bump
+    )]
+    pub escrow_observer: Account<'info, EscrowObserver>,
+}
+
+#[derive(Accounts)]
+pub struct RevokeObserver<'info> {
+    #[account(mut)]
+    pub agent: Signer<'info>,
+    #[account(
+        seeds = [seeds::ESCROW, agent.key().as_ref()],
+        bump = escrow_account.bump
+    )]
+    pub escrow_account: Account<'info, EscrowAccount>,
+    #[account(
+        mut,
+        close = agent,
+        constraint = escrow_observer.escrow == escrow_account.key() @ ErrorCode::InvalidEscrow,
+        seeds = [seeds::ESCROW_OBSERVER, escrow_account.key().as_ref(), escrow_observer.observer.as_ref()],
+        bump = escrow_observer.bump
+    )]
+    pub escrow_observer: Account<'info, EscrowObserver>,
+}
+
+#[derive(Accounts)]
+#[instruction(scope_hash: [u8; 32])]
+pub struct GrantDataConsent<'info> {
+    #[account(mut)]
+    pub human: Signer<'info>,
+    #[account(
+        init_if_needed,
+        payer = human,
+        space = DataConsent::SPACE,
+        seeds = [seeds::DATA_CONSENT, human.key().as_ref(), scope_hash.as_ref()],
+        bump
+    )]
+    pub data_consent: Account<'info, DataConsent>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RevokeDataConsent<'info> {
+    pub human: Signer<'info>,
+    #[account(
+        mut,
+        has_one = human @ ErrorCode::Unauthorized,
+        seeds = [seeds::DATA_CONSENT, human.key().as_ref(), data_consent.scope_hash.as_ref()],
+        bump = data_consent.bump
+    )]
+    pub data_consent: Account<'info, DataConsent>,
+}
+
+#[derive(Accounts)]
+pub struct CrankRefundRemainder<'info> {
+    pub cranker: Signer<'info>,
+    #[account(
+        mut,
+        constraint = cranker_token_account.mint == vault.mint @ ErrorCode::InvalidTokenAccount
+    )]
+    pub cranker_token_account: Account<'info, TokenAccount>,
+    /// CHECK: Original agent; receives the task's rent.
+    #[account(mut, address = escrow_account.agent)]
+    pub agent: UncheckedAccount<'info>,
+    #[account(
+        seeds = [seeds::ESCROW, escrow_account.agent.as_ref()],
+        bump = escrow_account.bump
+    )]
+    pub escrow_account: Account<'info, EscrowAccount>,
+    #[account(
+        mut,
+        close = agent,
+        has_one = agent,
+        constraint = task.escrow == escrow_account.key() @ ErrorCode::InvalidTask,
+        seeds = [seeds::TASK, escrow_account.key().as_ref(), task.task_id.to_le_bytes().as_ref()],
+        bump = task.bump
+    )]
+    pub task: Account<'info, Task>,
+    /// CHECK: The task's Bounty PDA; may be uninitialized. Required so an open bounty can't be skipped.
+    #[account(seeds = [seeds::BOUNTY, task.key().as_ref()], bump)]
+    pub bounty: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        constraint = vault.owner == escrow_account.key() @ ErrorCode::InvalidVault
+    )]
+    pub vault: Account<'info, TokenAccount>,
+    // Refunds go to the task's registered refund account, or else the agent's own
+    #[account(
+        mut,
+        constraint = task.accepts_refund_to(&agent_token_account) @ ErrorCode::InvalidTokenAccount,
+        constraint = agent_token_account.mint == vault.mint @ ErrorCode::InvalidTokenAccount
+    )]
+    pub agent_token_account: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct InitiateRecovery<'info> {
+    #[account(mut, constraint = admin.key() == market_config.authority @ ErrorCode::Unauthorized)]
+    pub admin: Signer<'info>,
+    #[account(seeds = [seeds::MARKET_CONFIG], bump)]
+    pub market_config: Account<'info, MarketConfig>,
+    #[account(
+        constraint = escrow_account.position_mint == Pubkey::default() @ ErrorCode::EscrowTokenized,
+        seeds = [seeds::ESCROW, escrow_account.agent.as_ref()],
+        bump = escrow_account.bump
+    )]
+    pub escrow_account: Account<'info, EscrowAccount>,
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + 32 + 32 + 8 + 1,
+        seeds = [seeds::RECOVERY, escrow_account.key().as_ref()],
+        bump
+    )]
+    pub recovery: Account<'info, Recovery>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CancelRecovery<'info> {
+    #[account(constraint = admin.key() == market_config.authority @ ErrorCode::Unauthorized)]
+    pub admin: Signer<'info>,
+    #[account(seeds = [seeds::MARKET_CONFIG], bump)]
+    pub market_config: Account<'info, MarketConfig>,
+    /// CHECK: Rent refund destination recorded at initiation.
+    #[account(mut, address = recovery.admin)]
+    pub rent_receiver: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        close = rent_receiver,
+        seeds = [seeds::RECOVERY, recovery.escrow.as_ref()],
+        bump = recovery.bump
+    )]
+    pub recovery: Account<'info, Recovery>,
+}
+
+#[derive(Accounts)]
+pub struct ExecuteRecovery<'info> {
+    #[account(
+        mut,
+        seeds = [seeds::ESCROW, escrow_account.agent.as_ref()],
+        bump = escrow_account.bump
+    )]
+    pub escrow_account: Account<'info, EscrowAccount>,
+    /// CHECK: Rent refund destination recorded at initiation.
+    #[account(mut, address = recovery.admin)]
+    pub rent_receiver: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        close = rent_receiver,
+        constraint = recovery.escrow == escrow_account.key() @ ErrorCode::InvalidRecovery,
+        seeds = [seeds::RECOVERY, escrow_account.key().as_ref()],
+        bump = recovery.bump
+    )]
+    pub recovery: Account<'info, Recovery>,
+    #[account(
+        mut,
+        constraint = vault.owner == escrow_account.key() @ ErrorCode::InvalidVault
+    )]
+    pub vault: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = agent_token_account.owner == escrow_account.agent @ ErrorCode::InvalidTokenAccount,
+        constraint = agent_token_account.mint == vault.mint @ ErrorCode::InvalidTokenAccount
+    )]
+    pub agent_token_account: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct ReallocEscrow<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    /// CHECK: May use an older, smaller layout, so it is validated by hand (owner,
+    /// discriminator and PDA) instead of being deserialized.
+    #[account(mut, owner = crate::ID)]
+    pub escrow_account: UncheckedAccount<'info>,
+    #[account(seeds = [seeds::MARKET_CONFIG], bump)]
+    pub market_config: Account<'info, MarketConfig>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CloseEscrowEpoch<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [seeds::ESCROW, escrow_account.agent.as_ref()],
+        bump = escrow_account.bump
+    )]
+    pub escrow_account: Account<'info, EscrowAccount>,
+    #[account(
+        init,
+        payer = payer,
+        space = EscrowEpochSummary::SPACE,
+        seeds = [
+            seeds::ESCROW_SUMMARY,
+            escrow_account.key().as_ref(),
+            escrow_account.statement_count.to_le_bytes().as_ref()
+        ],
+        bump
+    )]
+    pub summary: Account<'info, EscrowEpochSummary>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MintInvoice<'info> {
+    #[account(mut)]
+    pub agent: Signer<'info>,
+    #[account(
+        seeds = [seeds::ESCROW, agent.key().as_ref()],
+        bump = escrow_account.bump
+    )]
+    pub escrow_account: Account<'info, EscrowAccount>,
+    #[account(
+        constraint = summary.escrow == escrow_account.key() @ ErrorCode::InvalidEscrow,
+        seeds = [seeds::ESCROW_SUMMARY, escrow_account.key().as_ref(), summary.index.to_le_bytes().as_ref()],
+        bump = summary.bump
+    )]
+    pub summary: Account<'info, EscrowEpochSummary>,
+    #[account(
+        init,
+        payer = agent,
+        space = Invoice::SPACE,
+        seeds = [seeds::INVOICE, summary.key().as_ref()],
+        bump
+    )]
+    pub invoice: Account<'info, Invoice>,
+    #[account(
+        init,
+        payer = agent,
+        seeds = [seeds::INVOICE_MINT, summary.key().as_ref()],
+        bump,
+        mint::decimals = 0,
+        mint::authority = invoice,
+        mint::freeze_authority = invoice,
+    )]
+    pub invoice_mint: Account<'info, token::Mint>,
+    #[account(
+        init,
+        payer = agent,
+        associated_token::mint = invoice_mint,
+        associated_token::authority = agent,
+    )]
+    pub agent_invoice_account: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct SetPayoutPrefs<'info> {
+    #[account(mut)]
+    pub human: Signer<'info>,
+    #[account(
+        init_if_needed,
+        payer = human,
+        space = PayoutPrefs::SPACE,
+        seeds = [seeds::PAYOUT_PREFS, human.key().as_ref()],
+        bump
+    )]
+    pub payout_prefs: Account<'info, PayoutPrefs>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ReallocPayoutPrefs<'info> {
+    #[account(mut)]
+    pub human: Signer<'info>,
+    /// CHECK: May use the older, smaller layout, so it is validated by hand (owner,
+    /// discriminator and human) instead of being deserialized.
+    #[account(
+        mut,
+        owner = crate::ID,
+        seeds = [seeds::PAYOUT_PREFS, human.key().as_ref()],
+        bump
+    )]
+    pub payout_prefs: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetPayoutSplit<'info> {
+    #[account(mut)]
+    pub human: Signer<'info>,
+    #[account(
+        init_if_needed,
+        payer = human,
+        space = PayoutSplit::SPACE,
+        seeds = [seeds::PAYOUT_SPLIT, human.key().as_ref()],
+        bump
+    )]
+    pub payout_split: Account<'info, PayoutSplit>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(key_hash: [u8; 32])]
+pub struct RegisterApiKey<'info> {
+    #[account(mut)]
+    pub agent: Signer<'info>,
+    #[account(
+        init,
+        payer = agent,
+        space = ApiKey::SPACE,
+        seeds = [seeds::API_KEY, agent.key().as_ref(), key_hash.as_ref()],
+        bump
+    )]
+    pub api_key: Account<'info, ApiKey>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RevokeApiKey<'info> {
+    pub agent: Signer<'info>,
+    #[account(
+        mut,
+        has_one = agent @ ErrorCode::Unauthorized,
+        seeds = [seeds::API_KEY, agent.key().as_ref(), api_key.key_hash.as_ref()],
+        bump = api_key.bump
+    )]
+    pub api_key: Account<'info, ApiKey>,
+}
+
+#[derive(Accounts)]
+#[instruction(intent_id: u64)]
+pub struct CreatePaymentIntent<'info> {
+    #[account(mut)]
+    pub agent: Signer<'info>,
+    #[account(
+        seeds = [seeds::ESCROW, agent.key().as_ref()],
+        bump = escrow_account.bump
+    )]
+    pub escrow_account: Account<'info, EscrowAccount>,
+    #[account(
+        init,
+        payer = agent,
+        space = PaymentIntent::SPACE,
+        seeds = [seeds::PAYMENT_INTENT, escrow_account.key().as_ref(), intent_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub payment_intent: Account<'info, PaymentIntent>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CancelPaymentIntent<'info> {
+    #[account(mut)]
+    pub agent: Signer<'info>,
+    #[account(
+        seeds = [seeds::ESCROW, agent.key().as_ref()],
+        bump = escrow_account.bump
+    )]
+    pub escrow_account: Account<'info, EscrowAccount>,
+    #[account(
+        mut,
+        close = agent,
+        seeds = [seeds::PAYMENT_INTENT, escrow_account.key().as_ref(), payment_intent.intent_id.to_le_bytes().as_ref()],
+        bump = payment_intent.bump
+    )]
+    pub payment_intent: Account<'info, PaymentIntent>,
+}
+
+#[derive(Accounts)]
+pub struct SetIntentsRequired<'info> {
+    pub agent: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [seeds::ESCROW, agent.key().as_ref()],
+        bump = escrow_account.bump
+    )]
+    pub escrow_account: Account<'info, EscrowAccount>,
+}
+
+#[derive(Accounts)]
+#[instruction(org_id: [u8; 32])]
+pub struct CreateOrg<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    #[account(
+        init,
+        payer = admin,
+        space = Org::SPACE,
+        seeds = [seeds::ORG, org_id.as_ref()],
+        bump
+    )]
+    pub org: Account<'info, Org>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateOrg<'info> {
+    #[account(constraint = admin.key() == org.admin @ ErrorCode::Unauthorized)]
+    pub admin: Signer<'info>,
+    #[account(mut, seeds = [seeds::ORG, org.org_id.as_ref()], bump = org.bump)]
+    pub org: Account<'info, Org>,
+}
+
+#[derive(Accounts)]
+pub struct AddOrgMember<'info> {
+    #[account(mut, constraint = admin.key() == org.admin @ ErrorCode::Unauthorized)]
+    pub admin: Signer<'info>,
+    pub human: Signer<'info>,
+    #[account(mut, seeds = [seeds::ORG, org.org_id.as_ref()], bump = org.bump)]
+    pub org: Account<'info, Org>,
+    #[account(
+        init,
+        payer = admin,
+        space = OrgMember::SPACE,
+        seeds = [seeds::ORG_MEMBER, human.key().as_ref()],
+        bump
+    )]
+    pub org_member: Account<'info, OrgMember>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RemoveOrgMember<'info> {
+    pub caller: Signer<'info>,
+    #[account(mut, seeds = [seeds::ORG, org.org_id.as_ref()], bump = org.bump)]
+    pub org: Account<'info, Org>,
+    /// CHECK: Receives the membership rent; must be the org admin
+    #[account(mut, address = org.admin @ ErrorCode::Unauthorized)]
+    pub org_admin: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        close = org_admin,
+        has_one = org @ ErrorCode::InvalidOrg,
+        seeds = [seeds::ORG_MEMBER, org_member.human.as_ref()],
+        bump = org_member.bump
+    )]
+    pub org_member: Account<'info, OrgMember>,
+}
+
+#[derive(Accounts)]
+pub struct TipHuman<'info> {
+    #[account(mut)]
+    pub agent: Signer<'info>,
+    #[account(
+        mut,
+        constraint = escrow_account.position_mint == Pubkey::default() @ ErrorCode::EscrowTokenized,
+        seeds = [seeds::ESCROW, agent.key().as_ref()],
+        bump = escrow_account.bump
+    )]
+    pub escrow_account: Account<'info, EscrowAccount>,
+    #[account(
+        mut,
+        constraint = vault.owner == escrow_account.key() @ ErrorCode::InvalidVault,
+        constraint = vault.mint == fee_vault.mint @ ErrorCode::InvalidMint
+    )]
+    pub vault: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = session.escrow == escrow_account.key() @ ErrorCode::SessionMismatch,
+        constraint = session.human == human.key() @ ErrorCode::SessionMismatch,
+        seeds = [seeds::SESSION, escrow_account.key().as_ref(), session.session_id.to_le_bytes().as_ref()],
+        bump = session.bump
+    )]
+    pub session: Account<'info, Session>,
+    /// CHECK: Human's wallet public key. Only used as the authority of the payout ATA.
+    pub human: UncheckedAccount<'info>,
+    /// CHECK: Human's PayoutPrefs PDA; may be uninitialized.
+    #[account(seeds = [seeds::PAYOUT_PREFS, human.key().as_ref()], bump)]
+    pub payout_prefs: UncheckedAccount<'info>,
+    #[account(
+        init_if_needed,
+        payer = agent,
+        associated_token::mint = mint,
+        associated_token::authority = human,
+    )]
+    pub user_wallet: Account<'info, TokenAccount>,
+    #[account(
+        constraint = mint.key() == fee_vault.mint @ ErrorCode::InvalidMint,
+        constraint = market_config.accepts_mint(&mint) @ ErrorCode::InvalidMint
+    )]
+    pub mint: Account<'info, token::Mint>,
+    #[account(
+        mut,
+        seeds = [seeds::FEE_VAULT_STATE],
+        bump = fee_vault_state.bump
+    )]
+    pub fee_vault_state: Account<'info, FeeVaultState>,
+    #[account(
+        mut,
+        constraint = fee_vault.owner == fee_vault_state.key()
+    )]
+    pub fee_vault: Account<'info, TokenAccount>,
+    #[account(seeds = [seeds::MARKET_CONFIG], bump)]
+    pub market_config: Account<'info, MarketConfig>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct BuilderRegistration {
+    pub builder_code: [u8; 32],
+    pub wallet: Pubkey,
+}
+
+#[derive(Accounts)]
+pub struct RegisterBuildersBatch<'info> {
+    #[account(mut, constraint = admin.key() == market_config.authority @ ErrorCode::Unauthorized)]
+    pub admin: Signer<'info>,
+    #[account(seeds = [seeds::MARKET_CONFIG], bump)]
+    pub market_config: Account<'info, MarketConfig>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct OpenCreditLine<'info> {
+    #[account(mut)]
+    pub underwriter: Signer<'info>,
+    pub agent: Signer<'info>,
+    #[account(
+        mut,
+        constraint = underwriter_token_account.owner == underwriter.key() @ ErrorCode::InvalidTokenAccount
+    )]
+    pub underwriter_token_account: Account<'info, TokenAccount>,
+    #[account(
+        seeds = [seeds::ESCROW, agent.key().as_ref()],
+        bump = escrow_account.bump
+    )]
+    pub escrow_account: Account<'info, EscrowAccount>,
+    #[account(
+        init,
+        payer = underwriter,
+        space = 8 + 32 + 32 + 8 + 8 + 8 + 8 + 1,
+        seeds = [seeds::CREDIT_LINE, escrow_account.key().as_ref()],
+        bump
+    )]
+    pub credit_line: Account<'info, CreditLine>,
+    #[account(
+        init,
+        payer = underwriter,
+        seeds = [seeds::CREDIT_COLLATERAL, credit_line.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = credit_line,
+    )]
+    pub credit_collateral: Account<'info, TokenAccount>,
+    #[account(constraint = mint.key() == fee_vault.mint @ ErrorCode::InvalidMint)]
+    pub mint: Account<'info, token::Mint>,
+    #[account(seeds = [seeds::FEE_VAULT_STATE], bump)]
+    pub fee_vault_state: Account<'info, FeeVaultState>,
+    #[account(constraint = fee_vault.owner == fee_vault_state.key())]
+    pub fee_vault: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawCollateral<'info> {
+    pub underwriter: Signer<'info>,
+    #[account(
+        mut,
+        constraint = underwriter_token_account.owner == underwriter.key() @ ErrorCode::InvalidTokenAccount
+    )]
+    pub underwriter_token_account: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        has_one = underwriter @ ErrorCode::Unauthorized,
+        seeds = [seeds::CREDIT_LINE, credit_line.escrow.as_ref()],
+        bump = credit_line.bump
+    )]
+    pub credit_line: Account<'info, CreditLine>,
+    #[account(
+        mut,
+        seeds = [seeds::CREDIT_COLLATERAL, credit_line.key().as_ref()],
+        bump
+    )]
+    pub credit_collateral: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct WhitelistLst<'info> {
+    #[account(mut, constraint = admin.key() == market_config.authority @ ErrorCode::Unauthorized)]
+    pub admin: Signer<'info>,
+    #[account(constraint = lst_mint.key() != market_config.payment_mint @ ErrorCode::InvalidMint)]
+    pub lst_mint: Account<'info, token::Mint>,
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = LstCollateral::SPACE,
+        seeds = [seeds::LST_COLLATERAL, lst_mint.key().as_ref()],
+        bump
+    )]
+    pub lst_collateral: Account<'info, LstCollateral>,
+    #[account(seeds = [seeds::MARKET_CONFIG], bump)]
+    pub market_config: Account<'info, MarketConfig>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct PostLstPrice<'info> {
+    #[account(
+        constraint = oracle.key() == lst_collateral.oracle || oracle.key() == market_config.authority
+            @ ErrorCode::Unauthorized
+    )]
+    pub oracle: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [seeds::LST_COLLATERAL, lst_collateral.mint.as_ref()],
+        bump = lst_collateral.bump
+    )]
+    pub lst_collateral: Account<'info, LstCollateral>,
+    #[account(seeds = [seeds::MARKET_CONFIG], bump)]
+    pub market_config: Account<'info, MarketConfig>,
+}
+
+#[derive(Accounts)]
+pub struct InitLiquidityBuffer<'info> {
+    #[account(mut, constraint = admin.key() == market_config.authority @ ErrorCode::Unauthorized)]
+    pub admin: Signer<'info>,
+    #[account(
+        init,
+        payer = admin,
+        space = LiquidityBuffer::SPACE,
+        seeds = [seeds::LIQUIDITY_BUFFER],
+        bump
+    )]
+    pub liquidity_buffer: Account<'info, LiquidityBuffer>,
+    #[account(
+        init,
+        payer = admin,
+        seeds = [seeds::LIQUIDITY_BUFFER_VAULT],
+        bump,
+        token::mint = mint,
+        token::authority = liquidity_buffer,
+    )]
+    pub buffer_vault: Account<'info, TokenAccount>,
+    #[account(constraint = mint.key() == market_config.payment_mint @ ErrorCode::InvalidMint)]
+    pub mint: Account<'info, token::Mint>,
+    #[account(seeds = [seeds::FEE_VAULT_STATE], bump = fee_vault_state.bump)]
+    pub fee_vault_state: Account<'info, FeeVaultState>,
+    #[account(seeds = [seeds::MARKET_CONFIG], bump)]
+    pub market_config: Account<'info, MarketConfig>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct SetLiquidityBufferLimits<'info> {
+    #[account(constraint = admin.key() == market_config.authority @ ErrorCode::Unauthorized)]
+    pub admin: Signer<'info>,
+    #[account(mut, seeds = [seeds::LIQUIDITY_BUFFER], bump = liquidity_buffer.bump)]
+    pub liquidity_buffer: Account<'info, LiquidityBuffer>,
+    #[account(seeds = [seeds::MARKET_CONFIG], bump)]
+    pub market_config: Account<'info, MarketConfig>,
+}
+
+#[derive(Accounts)]
+pub struct FundLiquidityBuffer<'info> {
+    pub funder: Signer<'info>,
+    #[account(
+        mut,
+        constraint = funder_token_account.owner == funder.key() @ ErrorCode::InvalidTokenAccount
+    )]
+    pub funder_token_account: Account<'info, TokenAccount>,
+    #[account(mut, seeds = [seeds::LIQUIDITY_BUFFER], bump = liquidity_buffer.bump)]
+    pub liquidity_buffer: Account<'info, LiquidityBuffer>,
+    #[account(mut, address = liquidity_buffer.vault @ ErrorCode::InvalidVault)]
+    pub buffer_vault: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct FundLiquidityBufferFromTreasury<'info> {
+    pub admin: Signer<'info>,
+    #[account(
+        mut,
+        constraint = fee_vault_state.authority == admin.key() @ ErrorCode::Unauthorized,
+        seeds = [seeds::FEE_VAULT_STATE],
+        bump = fee_vault_state.bump
+    )]
+    pub fee_vault_state: Account<'info, FeeVaultState>,
+    #[account(
+        mut,
+        constraint = fee_vault.owner == fee_vault_state.key(),
+        constraint = fee_vault.mint == buffer_vault.mint @ ErrorCode::InvalidMint
+    )]
+    pub fee_vault: Account<'info, TokenAccount>,
+    #[account(mut, seeds = [seeds::LIQUIDITY_BUFFER], bump = liquidity_buffer.bump)]
+    pub liquidity_buffer: Account<'info, LiquidityBuffer>,
+    #[account(mut, address = liquidity_buffer.vault @ ErrorCode::InvalidVault)]
+    pub buffer_vault: Account<'info, TokenAccount>,
+    #[account(seeds = [seeds::MARKET_CONFIG], bump)]
+    pub market_config: Account<'info, MarketConfig>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct ReplenishLiquidityBuffer<'info> {
+    #[account(mut, seeds = [seeds::FEE_VAULT_STATE], bump = fee_vault_state.bump)]
+    pub fee_vault_state: Account<'info, FeeVaultState>,
+    #[account(
+        mut,
+        constraint = fee_vault.owner == fee_vault_state.key(),
+        constraint = fee_vault.mint == buffer_vault.mint @ ErrorCode::InvalidMint
+    )]
+    pub fee_vault: Account<'info, TokenAccount>,
+    #[account(mut, seeds = [seeds::LIQUIDITY_BUFFER], bump = liquidity_buffer.bump)]
+    pub liquidity_buffer: Account<'info, LiquidityBuffer>,
+    #[account(mut, address = liquidity_buffer.vault @ ErrorCode::InvalidVault)]
+    pub buffer_vault: Account<'info, TokenAccount>,
+    #[account(seeds = [seeds::MARKET_CONFIG], bump)]
+    pub market_config: Account<'info, MarketConfig>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct LiquidityBufferUtilization<'info> {
+    #[account(seeds = [seeds::LIQUIDITY_BUFFER], bump = liquidity_buffer.bump)]
+    pub liquidity_buffer: Account<'info, LiquidityBuffer>,
+    #[account(address = liquidity_buffer.vault @ ErrorCode::InvalidVault)]
+    pub buffer_vault: Account<'info, TokenAccount>,
+}
+
+#[derive(Accounts)]
+pub struct DepositLst<'info> {
+    #[account(mut)]
+    pub agent: Signer<'info>,
+    #[account(
+        mut,
+        constraint = agent_lst_account.owner == agent.key() @ ErrorCode::InvalidTokenAccount
+    )]
+    pub agent_lst_account: Account<'info, TokenAccount>,
+    #[account(
+        seeds = [seeds::ESCROW, agent.key().as_ref()],
+        bump = escrow_account.bump
+    )]
+    pub escrow_account: Account<'info, EscrowAccount>,
+    #[account(
+        seeds = [seeds::LST_COLLATERAL, lst_collateral.mint.as_ref()],
+        bump = lst_collateral.bump
+    )]
+    pub lst_collateral: Account<'info, LstCollateral>,
+    #[account(
+        init_if_needed,
+        payer = agent,
+        space = LstPosition::SPACE,
+        seeds = [seeds::LST_POSITION, escrow_account.key().as_ref(), lst_collateral.mint.as_ref()],
+        bump
+    )]
+    pub lst_position: Account<'info, LstPosition>,
+    #[account(
+        init_if_needed,
+        payer = agent,
+        seeds = [seeds::LST_VAULT, lst_position.key().as_ref()],
+        bump,
+        token::mint = lst_mint,
+        token::authority = lst_position,
+    )]
+    pub lst_vault: Account<'info, TokenAccount>,
+    #[account(address = lst_collateral.mint @ ErrorCode::InvalidMint)]
+    pub lst_mint: Account<'info, token::Mint>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawLst<'info> {
+    pub agent: Signer<'info>,
+    #[account(
+        mut,
+        constraint = agent_lst_account.owner == agent.key() @ ErrorCode::InvalidTokenAccount
+    )]
+    pub agent_lst_account: Account<'info, TokenAccount>,
+    #[account(
+        seeds = [seeds::ESCROW, agent.key().as_ref()],
+        bump = escrow_account.bump
+    )]
+    pub escrow_account: Account<'info, EscrowAccount>,
+    #[account(
+        seeds = [seeds::LST_COLLATERAL, lst_collateral.mint.as_ref()],
+        bump = lst_collateral.bump
+    )]
+    pub lst_collateral: Account<'info, LstCollateral>,
+    #[account(
+        mut,
+        seeds = [seeds::LST_POSITION, escrow_account.key().as_ref(), lst_collateral.mint.as_ref()],
+        bump = lst_position.bump
+    )]
+    pub lst_position: Account<'info, LstPosition>,
+    #[account(
+        mut,
+        seeds = [seeds::LST_VAULT, lst_position.key().as_ref()],
+        bump
+    )]
+    pub lst_vault: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct DrawLstCredit<'info> {
+    #[account(
+        constraint = caller.key() == escrow_account.agent || market_config.is_operator(&caller.key())
+            @ ErrorCode::Unauthorized
+    )]
+    pub caller: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [seeds::ESCROW, escrow_account.agent.as_ref()],
+        bump = escrow_account.bump
+    )]
+    pub escrow_account: Account<'info, EscrowAccount>,
+    #[account(
+        mut,
+        constraint = vault.owner == escrow_account.key() @ ErrorCode::InvalidVault,
+        constraint = vault.mint == market_config.payment_mint @ ErrorCode::InvalidMint
+    )]
+    pub vault: Account<'info, TokenAccount>,
+    #[account(
+        seeds = [seeds::LST_COLLATERAL, lst_collateral.mint.as_ref()],
+        bump = lst_collateral.bump
+    )]
+    pub lst_collateral: Account<'info, LstCollateral>,
+    #[account(
+        mut,
+        seeds = [seeds::LST_POSITION, escrow_account.key().as_ref(), lst_collateral.mint.as_ref()],
+        bump = lst_position.bump
+    )]
+    pub lst_position: Account<'info, LstPosition>,
+    #[account(mut, seeds = [seeds::LIQUIDITY_BUFFER], bump = liquidity_buffer.bump)]
+    pub liquidity_buffer: Account<'info, LiquidityBuffer>,
+    #[account(mut, address = liquidity_buffer.vault @ ErrorCode::InvalidVault)]
+    pub buffer_vault: Account<'info, TokenAccount>,
+    #[account(seeds = [seeds::MARKET_CONFIG], bump)]
+    pub market_config: Account<'info, MarketConfig>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct RepayLstDebt<'info> {
+    pub payer: Signer<'info>,
+    #[account(
+        mut,
+        constraint = payer_token_account.owner == payer.key() @ ErrorCode::InvalidTokenAccount
+    )]
+    pub payer_token_account: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        seeds = [seeds::LST_POSITION, lst_position.escrow.as_ref(), lst_position.mint.as_ref()],
+        bump = lst_position.bump
+    )]
+    pub lst_position: Account<'info, LstPosition>,
+    #[account(mut, seeds = [seeds::LIQUIDITY_BUFFER], bump = liquidity_buffer.bump)]
+    pub liquidity_buffer: Account<'info, LiquidityBuffer>,
+    #[account(mut, address = liquidity_buffer.vault @ ErrorCode::InvalidVault)]
+    pub buffer_vault: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct RebalanceLst<'info> {
+    #[account(constraint = market_config.is_operator(&operator.key()) @ ErrorCode::Unauthorized)]
+    pub operator: Signer<'info>,
+    #[account(
+        mut,
+        constraint = operator_token_account.owner == operator.key() @ ErrorCode::InvalidTokenAccount
+    )]
+    pub operator_token_account: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = operator_lst_account.owner == operator.key() @ ErrorCode::InvalidTokenAccount,
+        constraint = operator_lst_account.mint == lst_collateral.mint @ ErrorCode::InvalidMint
+    )]
+    pub operator_lst_account: Account<'info, TokenAccount>,
+    #[account(
+        seeds = [seeds::LST_COLLATERAL, lst_collateral.mint.as_ref()],
+        bump = lst_collateral.bump
+    )]
+    pub lst_collateral: Account<'info, LstCollateral>,
+    #[account(
+        mut,
+        seeds = [seeds::LST_POSITION, lst_position.escrow.as_ref(), lst_collateral.mint.as_ref()],
+        bump = lst_position.bump
+    )]
+    pub lst_position: Account<'info, LstPosition>,
+    #[account(
+        mut,
+        seeds = [seeds::LST_VAULT, lst_position.key().as_ref()],
+        bump
+    )]
+    pub lst_vault: Account<'info, TokenAccount>,
+    #[account(mut, seeds = [seeds::LIQUIDITY_BUFFER], bump = liquidity_buffer.bump)]
+    pub liquidity_buffer: Account<'info, LiquidityBuffer>,
+    #[account(mut, address = liquidity_buffer.vault @ ErrorCode::InvalidVault)]
+    pub buffer_vault: Account<'info, TokenAccount>,
+    #[account(seeds = [seeds::MARKET_CONFIG], bump)]
+    pub market_config: Account<'info, MarketConfig>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct RegisterFeeMint<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    #[account(
+        constraint = fee_vault_state.authority == admin.key() @ ErrorCode::Unauthorized,
+        seeds = [seeds::FEE_VAULT_STATE],
+        bump = fee_vault_state.bump
+    )]
+    pub fee_vault_state: Account<'info, FeeVaultState>,
+    // Primary fee vault; its mint is already handled by FeeVaultState
+    #[account(
+        seeds = [seeds::FEE_VAULT, fee_vault_state.key().as_ref()],
+        bump,
+        constraint = fee_vault.mint != mint.key() @ ErrorCode::InvalidMint
+    )]
+    pub fee_vault: Account<'info, TokenAccount>,
+    pub mint: Account<'info, token::Mint>,
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + 32 + 32 + 8 + 8 + 1,
+        seeds = [seeds::MINT_FEE_VAULT, mint.key().as_ref()],
+        bump
+    )]
+    pub mint_fee_vault: Account<'info, MintFeeVault>,
+    #[account(
+        init,
+        payer = admin,
+        seeds = [seeds::FEE_VAULT, mint_fee_vault.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = mint_fee_vault,
+    )]
+    pub mint_vault: Account<'info, TokenAccount>,
+    // Secondary mints are only accepted in multi-mint mode
+    #[account(
+        seeds = [seeds::MARKET_CONFIG],
+        bump,
+        constraint = market_config.has_feature(MarketConfig::FEATURE_MULTI_MINT) @ ErrorCode::InvalidMint
+    )]
+    pub market_config: Account<'info, MarketConfig>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimMintProtocolFees<'info> {
+    pub admin: Signer<'info>,
+    #[account(
+        constraint = fee_vault_state.authority == admin.key() @ ErrorCode::Unauthorized,
+        seeds = [seeds::FEE_VAULT_STATE],
+        bump = fee_vault_state.bump
+    )]
+    pub fee_vault_state: Account<'info, FeeVaultState>,
+    #[account(
+        mut,
+        seeds = [seeds::MINT_FEE_VAULT, mint_fee_vault.mint.as_ref()],
+        bump = mint_fee_vault.bump
+    )]
+    pub mint_fee_vault: Account<'info, MintFeeVault>,
+    #[account(mut, address = mint_fee_vault.vault)]
+    pub mint_vault: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = admin_wallet.mint == mint_fee_vault.mint @ ErrorCode::InvalidMint
+    )]
+    pub admin_wallet: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct InitRevenuePool<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    #[account(
+        constraint = fee_vault_state.authority == admin.key() @ ErrorCode::Unauthorized,
+        seeds = [seeds::FEE_VAULT_STATE],
+        bump = fee_vault_state.bump
+    )]
+    pub fee_vault_state: Account<'info, FeeVaultState>,
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + 8 + 8 + 8 + 8 + 2 + 2 + 8 + 8 + 1,
+        seeds = [seeds::REVENUE_POOL],
+        bump
+    )]
+    pub revenue_pool: Account<'info, RevenuePool>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(bps: u16, duration_epochs: u64)]
+pub struct MintRevenueShare<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    #[account(
+        constraint = fee_vault_state.authority == admin.key() @ ErrorCode::Unauthorized,
+        seeds = [seeds::FEE_VAULT_STATE],
+        bump = fee_vault_state.bump
+    )]
+    pub fee_vault_state: Account<'info, FeeVaultState>,
+    #[account(mut, seeds = [seeds::REVENUE_POOL], bump = revenue_pool.bump)]
+    pub revenue_pool: Account<'info, RevenuePool>,
+    /// CHECK: Wallet receiving the position token (e.g. the financing party).
+    pub recipient: UncheckedAccount<'info>,
+    #[account(
+        init,
+        payer = admin,
+        seeds = [seeds::REVENUE_SHARE_MINT, revenue_pool.position_count.to_le_bytes().as_ref()],
+        bump,
+        mint::decimals = 0,
+        mint::authority = revenue_pool,
+    )]
+    pub nft_mint: Account<'info, token::Mint>,
+    #[account(
+        init_if_needed,
+        payer = admin,
+        associated_token::mint = nft_mint,
+        associated_token::authority = recipient,
+    )]
+    pub recipient_nft_account: Account<'info, TokenAccount>,
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + 32 + 2 + 8 + 8 + 8 + 1,
+        seeds = [seeds::REVENUE_SHARE, nft_mint.key().as_ref()],
+        bump
+    )]
+    pub revenue_share: Account<'info, RevenueShare>,
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = FeeEpoch::SPACE,
+        seeds = [seeds::FEE_EPOCH, (revenue_pool.current_epoch + 1 + duration_epochs).to_le_bytes().as_ref()],
+        bump
+    )]
+    pub end_fee_epoch: Account<'info, FeeEpoch>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct RollFeeEpoch<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, seeds = [seeds::REVENUE_POOL], bump = revenue_pool.bump)]
+    pub revenue_pool: Account<'info, RevenuePool>,
+    #[account(mut, seeds = [seeds::FEE_VAULT_STATE], bump = fee_vault_state.bump)]
+    pub fee_vault_state: Account<'info, FeeVaultState>,
+    #[account(seeds = [seeds::MARKET_CONFIG], bump)]
+    pub market_config: Account<'info, MarketConfig>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = FeeEpoch::SPACE,
+        seeds = [seeds::FEE_EPOCH, revenue_pool.current_epoch.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub closing_fee_epoch: Account<'info, FeeEpoch>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = FeeEpoch::SPACE,
+        seeds = [seeds::FEE_EPOCH, (revenue_pool.current_epoch + 1).to_le_bytes().as_ref()],
+        bump
+    )]
+    pub next_fee_epoch: Account<'info, FeeEpoch>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimRevenueShare<'info> {
+    pub holder: Signer<'info>,
+    // Whoever holds the position token receives the revenue share
+    #[account(
+        constraint = holder_nft_account.owner == holder.key() @ ErrorCode::Unauthorized,
+        constraint = holder_nft_account.mint == revenue_share.nft_mint @ ErrorCode::InvalidPosition,
+        constraint = holder_nft_account.amount == 1 @ ErrorCode::InvalidPosition
+    )]
+    pub holder_nft_account: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        seeds = [seeds::REVENUE_SHARE, revenue_share.nft_mint.as_ref()],
+        bump = revenue_share.bump
+    )]
+    pub revenue_share: Account<'info, RevenueShare>,
+    #[account(mut, seeds = [seeds::REVENUE_POOL], bump = revenue_pool.bump)]
+    pub revenue_pool: Account<'info, RevenuePool>,
+    #[account(seeds = [seeds::FEE_VAULT_STATE], bump = fee_vault_state.bump)]
+    pub fee_vault_state: Account<'info, FeeVaultState>,
+    #[account(
+        mut,
+        seeds = [seeds::FEE_VAULT, fee_vault_state.key().as_ref()],
+        bump
+    )]
+    pub fee_vault: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = holder_token_account.mint == fee_vault.mint @ ErrorCode::InvalidMint
+    )]
+    pub holder_token_account: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct ReimburseRelayer<'info> {
+    pub admin: Signer<'info>,
+    #[account(
+        mut,
+        constraint = fee_vault_state.authority == admin.key() @ ErrorCode::Unauthorized,
+        seeds = [seeds::FEE_VAULT_STATE],
+        bump = fee_vault_state.bump
+    )]
+    pub fee_vault_state: Account<'info, FeeVaultState>,
+    #[account(
+        mut,
+        seeds = [seeds::FEE_VAULT, fee_vault_state.key().as_ref()],
+        bump
+    )]
+    pub fee_vault: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        seeds = [seeds::RELAYER_STATS, relayer_stats.relayer.as_ref()],
+        bump = relayer_stats.bump
+    )]
+    pub relayer_stats: Account<'info, RelayerStats>,
+    #[account(
+        mut,
+        constraint = relayer_token_account.owner == relayer_stats.relayer @ ErrorCode::InvalidTokenAccount,
+        constraint = relayer_token_account.mint == fee_vault.mint @ ErrorCode::InvalidMint
+    )]
+    pub relayer_token_account: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+#[instruction(forward_id: u64)]
+pub struct BuyForward<'info> {
+    #[account(mut)]
+    pub agent: Signer<'info>,
+    #[account(
+        mut,
+        constraint = escrow_account.position_mint == Pubkey::default() @ ErrorCode::EscrowTokenized,
+        seeds = [seeds::ESCROW, agent.key().as_ref()],
+        bump = escrow_account.bump
+    )]
+    pub escrow_account: Account<'info, EscrowAccount>,
+    #[account(
+        init,
+        payer = agent,
+        space = 8 + 32 + 32 + 8 + 32 + 8 + 8 + 8 + 8 + 1,
+        seeds = [seeds::FORWARD, escrow_account.key().as_ref(), forward_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub forward_position: Account<'info, ForwardPosition>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RefundForward<'info> {
+    #[account(
+        mut,
+        seeds = [seeds::ESCROW, escrow_account.agent.as_ref()],
+        bump = escrow_account.bump
+    )]
+    pub escrow_account: Account<'info, EscrowAccount>,
+    #[account(
+        mut,
+        close = agent,
+        constraint = forward_position.escrow == escrow_account.key() @ ErrorCode::InvalidEscrow,
+        seeds = [seeds::FORWARD, escrow_account.key().as_ref(), forward_position.forward_id.to_le_bytes().as_ref()],
+        bump = forward_position.bump
+    )]
+    pub forward_position: Account<'info, ForwardPosition>,
+    /// CHECK: Receives the position's rent; must be the agent that bought it.
+    #[account(mut, address = forward_position.agent @ ErrorCode::Unauthorized)]
+    pub agent: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(cohort_id: [u8; 32])]
+pub struct CreateCohortPool<'info> {
+    #[account(mut, constraint = admin.key() == market_config.authority @ ErrorCode::Unauthorized)]
+    pub admin: Signer<'info>,
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + 32 + 32 + 4 + 8 + 8 + 1 + 8,
+        seeds = [seeds::COHORT, cohort_id.as_ref()],
+        bump
+    )]
+    pub cohort_pool: Account<'info, CohortPool>,
+    #[account(
+        init,
+        payer = admin,
+        seeds = [seeds::COHORT_VAULT, cohort_pool.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = cohort_pool,
+    )]
+    pub cohort_vault: Account<'info, TokenAccount>,
+    #[account(address = market_config.payment_mint @ ErrorCode::InvalidMint)]
+    pub mint: Account<'info, token::Mint>,
+    #[account(seeds = [seeds::MARKET_CONFIG], bump)]
+    pub market_config: Account<'info, MarketConfig>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct JoinCohort<'info> {
+    #[account(mut)]
+    pub human: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [seeds::COHORT, cohort_pool.cohort_id.as_ref()],
+        bump = cohort_pool.bump
+    )]
+    pub cohort_pool: Account<'info, CohortPool>,
+    #[account(
+        init,
+        payer = human,
+        space = 8 + 32 + 32 + 8 + 1,
+        seeds = [seeds::COHORT_MEMBER, cohort_pool.key().as_ref(), human.key().as_ref()],
+        bump
+    )]
+    pub cohort_member: Account<'info, CohortMember>,
+    #[account(
+        seeds = [seeds::ATTESTATION_KEY, attestation_key.key.as_ref()],
+        bump = attestation_key.bump
+    )]
+    pub attestation_key: Account<'info, AttestationKey>,
+    /// CHECK: Instructions sysvar, used to introspect the attestation signature.
+    #[account(address = anchor_lang::solana_program::sysvar::instructions::ID)]
+    pub instructions_sysvar: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+
+    // Optional Cohort Deposit (required when the cohort asks for an application deposit)
+    #[account(
+        seeds = [seeds::COHORT_DEPOSIT, cohort_pool.key().as_ref(), human.key().as_ref()],
+        bump = cohort_deposit.bump
+    )]
+    pub cohort_deposit: Option<Account<'info, CohortDeposit>>,
+}
+
+#[derive(Accounts)]
+pub struct SetCohortDeposit<'info> {
+    #[account(constraint = admin.key() == market_config.authority @ ErrorCode::Unauthorized)]
+    pub admin: Signer<'info>,
+    #[account(seeds = [seeds::MARKET_CONFIG], bump)]
+    pub market_config: Account<'info, MarketConfig>,
+    #[account(
+        mut,
+        seeds = [seeds::COHORT, cohort_pool.cohort_id.as_ref()],
+        bump = cohort_pool.bump
+    )]
+    pub cohort_pool: Account<'info, CohortPool>,
+}
+
+#[derive(Accounts)]
+pub struct PostCohortDeposit<'info> {
+    #[account(mut)]
+    pub human: Signer<'info>,
+    #[account(
+        seeds = [seeds::COHORT, cohort_pool.cohort_id.as_ref()],
+        bump = cohort_pool.bump
+    )]
+    pub cohort_pool: Account<'info, CohortPool>,
+    #[account(mut, address = cohort_pool.vault)]
+    pub cohort_vault: Account<'info, TokenAccount>,
+    #[account(
+        init,
+        payer = human,
+        space = CohortDeposit::SPACE,
+        seeds = [seeds::COHORT_DEPOSIT, cohort_pool.key().as_ref(), human.key().as_ref()],
+        bump
+    )]
+    pub cohort_deposit: Account<'info, CohortDeposit>,
+    #[account(
+        mut,
+        constraint = human_token_account.owner == human.key() @ ErrorCode::InvalidTokenAccount,
+        constraint = human_token_account.mint == cohort_vault.mint @ ErrorCode::InvalidMint
+    )]
+    pub human_token_account: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts for both `flag_cohort_deposit` and `slash_cohort_deposit`.
+#[derive(Accounts)]
+pub struct FlagCohortDeposit<'info> {
+    #[account(constraint = admin.key() == market_config.authority @ ErrorCode::Unauthorized)]
+    pub admin: Signer<'info>,
+    #[account(seeds = [seeds::MARKET_CONFIG], bump)]
+    pub market_config: Account<'info, MarketConfig>,
+    #[account(
+        mut,
+        seeds = [seeds::COHORT, cohort_pool.cohort_id.as_ref()],
+        bump = cohort_pool.bump
+    )]
+    pub cohort_pool: Account<'info, CohortPool>,
+    #[account(
+        mut,
+        seeds = [seeds::COHORT_DEPOSIT, cohort_pool.key().as_ref(), cohort_deposit.human.as_ref()],
+        bump = cohort_deposit.bump
+    )]
+    pub cohort_deposit: Account<'info, CohortDeposit>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawCohortDeposit<'info> {
+    #[account(mut)]
+    pub human: Signer<'info>,
+    #[account(
+        seeds = [seeds::COHORT, cohort_pool.cohort_id.as_ref()],
+        bump = cohort_pool.bump
+    )]
+    pub cohort_pool: Account<'info, CohortPool>,
+    #[account(mut, address = cohort_pool.vault)]
+    pub cohort_vault: Account<'info, TokenAccount>,
+    /// CHECK: Human's CohortMember PDA; must be closed (the human has left).
+    #[account(seeds = [seeds::COHORT_MEMBER, cohort_pool.key().as_ref(), human.key().as_ref()], bump)]
+    pub cohort_member: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        close = human,
+        seeds = [seeds::COHORT_DEPOSIT, cohort_pool.key().as_ref(), human.key().as_ref()],
+        bump = cohort_deposit.bump
+    )]
+    pub cohort_deposit: Account<'info, CohortDeposit>,
+    #[account(
+        mut,
+        constraint = human_token_account.owner == human.key() @ ErrorCode::InvalidTokenAccount,
+        constraint = human_token_account.mint == cohort_vault.mint @ ErrorCode::InvalidMint
+    )]
+    pub human_token_account: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct LeaveCohort<'info> {
+    #[account(mut)]
+    pub human: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [seeds::COHORT, cohort_pool.cohort_id.as_ref()],
+        bump = cohort_pool.bump
+    )]
+    pub cohort_pool: Account<'info, CohortPool>,
+    #[account(
+        mut,
+        close = human,
+        seeds = [seeds::COHORT_MEMBER, cohort_pool.key().as_ref(), human.key().as_ref()],
+        bump = cohort_member.bump
+    )]
+    pub cohort_member: Account<'info, CohortMember>,
+}
+
+#[derive(Accounts)]
+pub struct FundCohort<'info> {
+    pub agent: Signer<'info>,
+    #[account(
+        mut,
+        constraint = escrow_account.position_mint == Pubkey::default() @ ErrorCode::EscrowTokenized,
+        constraint = escrow_account.mint == Pubkey::default() @ ErrorCode::InvalidMint,
+        seeds = [seeds::ESCROW, agent.key().as_ref()],
+        bump = escrow_account.bump
+    )]
+    pub escrow_account: Account<'info, EscrowAccount>,
+    #[account(
+        mut,
+        constraint = vault.owner == escrow_account.key() @ ErrorCode::InvalidVault,
+        constraint = vault.mint == fee_vault.mint @ ErrorCode::InvalidMint
+    )]
+    pub vault: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        seeds = [seeds::COHORT, cohort_pool.cohort_id.as_ref()],
+        bump = cohort_pool.bump
+    )]
+    pub cohort_pool: Account<'info, CohortPool>,
+    #[account(mut, address = cohort_pool.vault)]
+    pub cohort_vault: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        seeds = [seeds::FEE_VAULT_STATE],
+        bump = fee_vault_state.bump
+    )]
+    pub fee_vault_state: Account<'info, FeeVaultState>,
+    #[account(
+        mut,
+        seeds = [seeds::FEE_VAULT, fee_vault_state.key().as_ref()],
+        bump
+    )]
+    pub fee_vault: Account<'info, TokenAccount>,
+    #[account(seeds = [seeds::MARKET_CONFIG], bump)]
+    pub market_config: Account<'info, MarketConfig>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct CloseCohortEpoch<'info> {
+    #[account(mut, constraint = router.key() == market_config.authority @ ErrorCode::Unauthorized)]
+    pub router: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [seeds::COHORT, cohort_pool.cohort_id.as_ref()],
+        bump = cohort_pool.bump
+    )]
+    pub cohort_pool: Account<'info, CohortPool>,
+    #[account(
+        init,
+        payer = router,
+        space = 8 + 32 + 8 + 32 + 8 + 8 + 1,
+        seeds = [seeds::COHORT_EPOCH, cohort_pool.key().as_ref(), cohort_pool.current_epoch.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub cohort_epoch: Account<'info, CohortEpoch>,
+    #[account(seeds = [seeds::MARKET_CONFIG], bump)]
+    pub market_config: Account<'info, MarketConfig>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitFaucet<'info> {
+    #[account(mut, constraint = admin.key() == market_config.authority @ ErrorCode::Unauthorized)]
+    pub admin: Signer<'info>,
+    #[account(seeds = [seeds::MARKET_CONFIG], bump)]
+    pub market_config: Account<'info, MarketConfig>,
+    #[account(
+        init,
+        payer = admin,
+        space = Faucet::SPACE,
+        seeds = [seeds::FAUCET],
+        bump
+    )]
+    pub faucet: Account<'info, Faucet>,
+    // The admin must currently hold the mint authority
+    #[account(mut, address = market_config.payment_mint @ ErrorCode::InvalidMint)]
+    pub mint: Account<'info, token::Mint>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct FaucetDrip<'info> {
+    #[account(mut)]
+    pub wallet: Signer<'info>,
+    #[account(seeds = [seeds::FAUCET], bump = faucet.bump)]
+    pub faucet: Account<'info, Faucet>,
+    #[account(
+        init_if_needed,
+        payer = wallet,
+        space = FaucetClaim::SPACE,
+        seeds = [seeds::FAUCET_CLAIM, wallet.key().as_ref()],
+        bump
+    )]
+    pub faucet_claim: Account<'info, FaucetClaim>,
+    #[account(mut, address = faucet.mint @ ErrorCode::InvalidMint)]
+    pub mint: Account<'info, token::Mint>,
+    #[account(
+        init_if_needed,
+        payer = wallet,
+        associated_token::mint = mint,
+        associated_token::authority = wallet,
+    )]
+    pub wallet_token_account: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimCohort<'info> {
+    #[account(mut)]
+    pub human: Signer<'info>,
+    #[account(
+        seeds = [seeds::COHORT, cohort_pool.cohort_id.as_ref()],
+        bump = cohort_pool.bump
+    )]
+    pub cohort_pool: Account<'info, CohortPool>,
+    #[account(mut, address = cohort_pool.vault)]
+    pub cohort_vault: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        seeds = [seeds::COHORT_EPOCH, cohort_pool.key().as_ref(), cohort_epoch.epoch.to_le_bytes().as_ref()],
+        bump = cohort_epoch.bump
+    )]
+    pub cohort_epoch: Account<'info, CohortEpoch>,
+    // Only current members can claim
+    #[account(
+        seeds = [seeds::COHORT_MEMBER, cohort_pool.key().as_ref(), human.key().as_ref()],
+        bump = cohort_member.bump
+    )]
+    pub cohort_member: Account<'info, CohortMember>,
+    // One claim per member per epoch
+    #[account(
+        init,
+        payer = human,
+        space = 8 + 8 + 1,
+        seeds = [seeds::COHORT_CLAIM, cohort_epoch.key().as_ref(), human.key().as_ref()],
+        bump
+    )]
+    pub cohort_claim: Account<'info, CohortClaim>,
+    #[account(
+        mut,
+        constraint = human_token_account.owner == human.key() @ ErrorCode::InvalidTokenAccount,
+        constraint = human_token_account.mint == cohort_vault.mint @ ErrorCode::InvalidMint
+    )]
+    pub human_token_account: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(category: [u8; 32])]
+pub struct InitSessionStats<'info> {
+    #[account(mut, constraint = admin.key() == market_config.authority @ ErrorCode::Unauthorized)]
+    pub admin: Signer<'info>,
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + std::mem::size_of::<SessionLengthStats>(),
+        seeds = [seeds::SESSION_STATS, category.as_ref()],
+        bump
+    )]
+    pub session_stats: AccountLoader<'info, SessionLengthStats>,
+    #[account(seeds = [seeds::MARKET_CONFIG], bump)]
+    pub market_config: Account<'info, MarketConfig>,
+    pub system_program: Program<'info, System>,
+}
 
 #[derive(Accounts)]
-#[instruction(builder_code: [u8; 32])]
-pub struct RegisterBuilder<'info> {
+pub struct SetSessionStatsThreshold<'info> {
+    #[account(constraint = admin.key() == market_config.authority @ ErrorCode::Unauthorized)]
+    pub admin: Signer<'info>,
+    #[account(seeds = [seeds::MARKET_CONFIG], bump)]
+    pub market_config: Account<'info, MarketConfig>,
+    #[account(mut)]
+    pub session_stats: AccountLoader<'info, SessionLengthStats>,
+}
+
+#[derive(Accounts)]
+#[instruction(category: [u8; 32])]
+pub struct InitSpotPrice<'info> {
     #[account(mut, constraint = admin.key() == market_config.authority @ ErrorCode::Unauthorized)]
     pub admin: Signer<'info>,
-    #[account(seeds = [b"market_config"], bump)]
+    #[account(
+        init,
+        payer = admin,
+        space = SpotPrice::SPACE,
+        seeds = [seeds::SPOT_PRICE, category.as_ref()],
+        bump
+    )]
+    pub spot_price: Account<'info, SpotPrice>,
+    #[account(seeds = [seeds::MARKET_CONFIG], bump)]
+    pub market_config: Account<'info, MarketConfig>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct PostSpotPrice<'info> {
+    #[account(
+        constraint = oracle.key() == spot_price.oracle || oracle.key() == market_config.authority
+            @ ErrorCode::Unauthorized
+    )]
+    pub oracle: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [seeds::SPOT_PRICE, spot_price.category.as_ref()],
+        bump = spot_price.bump
+    )]
+    pub spot_price: Account<'info, SpotPrice>,
+    #[account(seeds = [seeds::MARKET_CONFIG], bump)]
     pub market_config: Account<'info, MarketConfig>,
+}
+
+#[derive(Accounts)]
+#[instruction(category: [u8; 32])]
+pub struct InitPriceIndex<'info> {
+    #[account(mut, constraint = admin.key() == market_config.authority @ ErrorCode::Unauthorized)]
+    pub admin: Signer<'info>,
     #[account(
         init,
         payer = admin,
-        space = 8 + 32 + 32 + 8 + 8 + 1,
-        seeds = [b"builder", builder_code.as_ref()],
+        space = 8 + 32 + 8 + 8 + 8 + 1,
+        seeds = [seeds::PRICE_INDEX, category.as_ref()],
         bump
     )]
-    pub builder_balance: Account<'info, BuilderBalance>,
-    /// CHECK: This is the builder's wallet public key that we are registering.
-    pub builder_wallet: UncheckedAccount<'info>,
+    pub price_index: Account<'info, PriceIndex>,
+    #[account(seeds = [seeds::MARKET_CONFIG], bump)]
+    pub market_config: Account<'info, MarketConfig>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct QuoteBaseUnits<'info> {
+    #[account(seeds = [seeds::MARKET_CONFIG], bump = market_config.bump)]
+    pub market_config: Account<'info, MarketConfig>,
+}
+
+#[derive(Accounts)]
+pub struct DeriveAddresses<'info> {
+    #[account(seeds = [seeds::MARKET_CONFIG], bump = market_config.bump)]
+    pub market_config: Account<'info, MarketConfig>,
+}
+
+/// Returned by derive_addresses; see market_common::pda for the seeds.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct DerivedAddresses {
+    pub market_config: Pubkey,
+    pub fee_vault_state: Pubkey,
+    pub fee_vault: Pubkey,
+    pub escrow: Pubkey,
+    pub escrow_vault: Pubkey, // Escrow's associated account for the payment mint
+    pub agent_stats: Pubkey,
+    pub recovery: Pubkey,
+    pub credit_line: Pubkey,
+    pub task: Pubkey,
+    pub task_gate: Pubkey,
+    pub task_policy: Pubkey,
+    pub bounty: Pubkey,
+    pub task_milestones: Pubkey,
+    pub builder_balance: Pubkey,
+    pub builder_stats: Pubkey,
+}
+
+#[derive(Accounts)]
+pub struct GetMarketRate<'info> {
+    #[account(
+        seeds = [seeds::PRICE_INDEX, price_index.category.as_ref()],
+        bump = price_index.bump
+    )]
+    pub price_index: Account<'info, PriceIndex>,
+}
+
+#[derive(Accounts)]
+pub struct AssertSolvency<'info> {
+    #[account(seeds = [seeds::FEE_VAULT_STATE], bump = fee_vault_state.bump)]
+    pub fee_vault_state: Account<'info, FeeVaultState>,
+    #[account(constraint = fee_vault.owner == fee_vault_state.key() @ ErrorCode::InvalidVault)]
+    pub fee_vault: Account<'info, TokenAccount>,
+    // Reserved revenue-share payouts, when the pool has been initialized
+    #[account(seeds = [seeds::REVENUE_POOL], bump = revenue_pool.bump)]
+    pub revenue_pool: Option<Account<'info, RevenuePool>>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct SolvencyReport {
+    pub fee_vault_amount: u64,
+    pub fee_vault_liabilities: u64, // Protocol + revenue pool + the listed builders and holdbacks
+    pub escrows_checked: u32,
+    pub shortfall: u64,             // Summed deficit across every checked vault; 0 when solvent
+}
+
+#[derive(Accounts)]
+pub struct SetAvailability<'info> {
+    #[account(mut)]
+    pub human: Signer<'info>,
+    #[account(
+        init_if_needed,
+        payer = human,
+        space = 8 + 32 + 4 * 7 + 8 + 1,
+        seeds = [seeds::AVAILABILITY, human.key().as_ref()],
+        bump
+    )]
+    pub availability: Account<'info, Availability>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RateAgent<'info> {
+    #[account(mut)]
+    pub rater: Signer<'info>,
+    /// CHECK: The receipt's human; must sign unless their signature is verified instead.
+    #[account(address = receipt.human @ ErrorCode::Unauthorized)]
+    pub human: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        seeds = [seeds::RECEIPT, receipt.escrow.as_ref(), receipt.nonce.to_le_bytes().as_ref()],
+        bump = receipt.bump
+    )]
+    pub receipt: Box<Account<'info, SettlementReceipt>>,
+    #[account(
+        init_if_needed,
+        payer = rater,
+        space = AgentRating::SPACE,
+        seeds = [seeds::AGENT_RATING, receipt.agent.as_ref()],
+        bump
+    )]
+    pub agent_rating: Account<'info, AgentRating>,
+    /// CHECK: Instructions sysvar, used to introspect the human's signature.
+    #[account(address = anchor_lang::solana_program::sysvar::instructions::ID)]
+    pub instructions_sysvar: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetBlockedAgents<'info> {
+    #[account(mut)]
+    pub human: Signer<'info>,
+    #[account(
+        init_if_needed,
+        payer = human,
+        space = BlockedAgents::SPACE,
+        seeds = [seeds::BLOCKED_AGENTS, human.key().as_ref()],
+        bump
+    )]
+    pub blocked_agents: Account<'info, BlockedAgents>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(amount: u64, expires_at: i64, nonce: u64)]
+pub struct DepositWithAuthorization<'info> {
+    #[account(mut)]
+    pub relayer: Signer<'info>,
+    /// CHECK: Proven by the ed25519 signature over the deposit authorization.
+    pub agent: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        constraint = agent_token_account.owner == agent.key() @ ErrorCode::InvalidTokenAccount,
+        constraint = agent_token_account.delegate == COption::Some(escrow_account.key()) @ ErrorCode::InvalidTokenAccount
+    )]
+    pub agent_token_account: Account<'info, TokenAccount>,
+    #[account(
+        init_if_needed,
+        payer = relayer,
+        space = EscrowAccount::SPACE,
+        seeds = [seeds::ESCROW, agent.key().as_ref()],
+        bump
+    )]
+    pub escrow_account: Account<'info, EscrowAccount>,
+    #[account(
+        init,
+        payer = relayer,
+        space = 8 + 32 + 8 + 1,
+        seeds = [seeds::DEPOSIT_AUTH, agent.key().as_ref(), nonce.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub deposit_authorization: Account<'info, DepositAuthorization>,
+    #[account(seeds = [seeds::MARKET_CONFIG], bump)]
+    pub market_config: Box<Account<'info, MarketConfig>>,
+    #[account(seeds = [seeds::FEE_VAULT_STATE], bump)]
+    pub fee_vault_state: Account<'info, FeeVaultState>,
+    #[account(constraint = fee_vault.owner == fee_vault_state.key() @ ErrorCode::InvalidMint)]
+    pub fee_vault: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = vault.owner == escrow_account.key() @ ErrorCode::InvalidVault,
+        constraint = vault.mint == fee_vault.mint @ ErrorCode::InvalidMint
+    )]
+    pub vault: Account<'info, TokenAccount>,
+    /// CHECK: Instructions sysvar, used to introspect the agent's signature.
+    #[account(address = anchor_lang::solana_program::sysvar::instructions::ID)]
+    pub instructions_sysvar: UncheckedAccount<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(value: u64, valid_after: i64, valid_before: i64, nonce: [u8; 32])]
+pub struct ReceiveWithAuthorization<'info> {
+    // The payee submits, as with ERC-3009's receiveWithAuthorization
+    #[account(mut)]
+    pub agent: Signer<'info>,
+    /// CHECK: Proven by the ed25519 signature over the transfer authorization.
+    pub from: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        constraint = from_token_account.owner == from.key() @ ErrorCode::InvalidTokenAccount,
+        constraint = from_token_account.delegate == COption::Some(escrow_account.key()) @ ErrorCode::InvalidTokenAccount
+    )]
+    pub from_token_account: Account<'info, TokenAccount>,
+    #[account(
+        init_if_needed,
+        payer = agent,
+        space = EscrowAccount::SPACE,
+        seeds = [seeds::ESCROW, agent.key().as_ref()],
+        bump
+    )]
+    pub escrow_account: Account<'info, EscrowAccount>,
+    #[account(
+        init,
+        payer = agent,
+        space = 8 + 32 + 32 + 1,
+        seeds = [seeds::TRANSFER_AUTH, from.key().as_ref(), nonce.as_ref()],
+        bump
+    )]
+    pub transfer_authorization: Account<'info, TransferAuthorization>,
+    #[account(seeds = [seeds::MARKET_CONFIG], bump)]
+    pub market_config: Box<Account<'info, MarketConfig>>,
+    #[account(seeds = [seeds::FEE_VAULT_STATE], bump)]
+    pub fee_vault_state: Account<'info, FeeVaultState>,
+    #[account(constraint = fee_vault.owner == fee_vault_state.key() @ ErrorCode::InvalidMint)]
+    pub fee_vault: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = vault.owner == escrow_account.key() @ ErrorCode::InvalidVault,
+        constraint = vault.mint == fee_vault.mint @ ErrorCode::InvalidMint
+    )]
+    pub vault: Account<'info, TokenAccount>,
+    /// CHECK: Instructions sysvar, used to introspect the funder's signature.
+    #[account(address = anchor_lang::solana_program::sysvar::instructions::ID)]
+    pub instructions_sysvar: UncheckedAccount<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(source_domain: u32, cctp_nonce: u64)]
+pub struct CreditCctpDeposit<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    /// CHECK: Escrow owner; the vault the burn minted into must belong to their escrow.
+    pub agent: UncheckedAccount<'info>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = EscrowAccount::SPACE,
+        seeds = [seeds::ESCROW, agent.key().as_ref()],
+        bump
+    )]
+    pub escrow_account: Account<'info, EscrowAccount>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + 32 + 4 + 8 + 8 + 1,
+        seeds = [seeds::CCTP_DEPOSIT, source_domain.to_le_bytes().as_ref(), cctp_nonce.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub cctp_deposit: Account<'info, CctpDeposit>,
+    #[account(seeds = [seeds::MARKET_CONFIG], bump)]
+    pub market_config: Box<Account<'info, MarketConfig>>,
+    #[account(seeds = [seeds::FEE_VAULT_STATE], bump)]
+    pub fee_vault_state: Account<'info, FeeVaultState>,
+    #[account(constraint = fee_vault.owner == fee_vault_state.key() @ ErrorCode::InvalidMint)]
+    pub fee_vault: Account<'info, TokenAccount>,
+    #[account(
+        constraint = vault.owner == escrow_account.key() @ ErrorCode::InvalidVault,
+        constraint = vault.mint == fee_vault.mint @ ErrorCode::InvalidMint
+    )]
+    pub vault: Account<'info, TokenAccount>,
+    /// CHECK: Instructions sysvar, used to introspect the CCTP receive_message.
+    #[account(address = anchor_lang::solana_program::sysvar::instructions::ID)]
+    pub instructions_sysvar: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(export_id: u64)]
+pub struct ExportEscrow<'info> {
+    #[account(mut)]
+    pub agent: Signer<'info>,
+    #[account(
+        mut,
+        constraint = escrow_account.position_mint == Pubkey::default() @ ErrorCode::EscrowTokenized,
+        seeds = [seeds::ESCROW, agent.key().as_ref()],
+        bump = escrow_account.bump
+    )]
+    pub escrow_account: Account<'info, EscrowAccount>,
+    #[account(
+        mut,
+        constraint = vault.owner == escrow_account.key() @ ErrorCode::InvalidVault
+    )]
+    pub vault: Account<'info, TokenAccount>,
+    // Vault of the agent's escrow PDA on the destination program
+    #[account(
+        mut,
+        constraint = destination_vault.mint == vault.mint @ ErrorCode::InvalidMint
+    )]
+    pub destination_vault: Account<'info, TokenAccount>,
+    #[account(
+        init,
+        payer = agent,
+        space = 8 + 32 + 8 + 32 + 8 + 32 + 32 + 8 + 1,
+        seeds = [seeds::ESCROW_EXPORT, agent.key().as_ref(), export_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub escrow_export: Account<'info, EscrowExport>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ImportEscrow<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    /// CHECK: Only used as the escrow seed; must match the voucher's agent.
+    pub agent: UncheckedAccount<'info>,
+    #[account(
+        seeds = [seeds::MARKET_CONFIG],
+        bump,
+        constraint = market_config.migration_source != Pubkey::default() @ ErrorCode::InvalidExport
+    )]
+    pub market_config: Account<'info, MarketConfig>,
+    /// CHECK: EscrowExport voucher owned by the migration source program; deserialized in the handler.
+    #[account(owner = market_config.migration_source @ ErrorCode::InvalidExport)]
+    pub escrow_export: UncheckedAccount<'info>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + 32 + 8 + 1,
+        seeds = [seeds::ESCROW_IMPORT, escrow_export.key().as_ref()],
+        bump
+    )]
+    pub escrow_import: Account<'info, EscrowImport>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = EscrowAccount::SPACE,
+        seeds = [seeds::ESCROW, agent.key().as_ref()],
+        bump
+    )]
+    pub escrow_account: Account<'info, EscrowAccount>,
+    // Vault the export already paid into; checked against the voucher in the handler
+    #[account(constraint = vault.owner == escrow_account.key() @ ErrorCode::InvalidVault)]
+    pub vault: Account<'info, TokenAccount>,
     pub system_program: Program<'info, System>,
 }
 
-#[derive(Accounts)]
-pub struct UpdateBuilderWallet<'info> {
-    #[account(mut)]
-    pub old_wallet: Signer<'info>, // Current wallet must sign
-    #[account(
-        mut,
-        constraint = builder_balance.wallet == old_wallet.key() @ ErrorCode::Unauthorized
-    )]
-    pub builder_balance: Account<'info, BuilderBalance>,
-    /// CHECK: New wallet address
-    pub new_wallet: UncheckedAccount<'info>,
+#[account]
+pub struct MarketConfig {
+    pub authority: Pubkey,
+    pub fee_basis_points: u16,
+    pub holdback_bps: u16,     // Share of each human payout held for quality review
+    pub holdback_period: i64,  // Seconds before held funds are released
+    pub features: u64,         // Bitfield of MarketConfig::FEATURE_* flags
+    pub swap_program: Pubkey,  // Whitelisted swap venue for builder claims (default = none)
+    pub builder_share_bps: u16, // Builder's share of the protocol fee
+    pub governance: Pubkey,    // Authority over fee parameters; default = admin
+    pub payment_mint: Pubkey,  // Canonical settlement mint, fixed at init
+    pub payment_decimals: u8,
+    pub migration_source: Pubkey, // Program whose escrow exports this deployment imports
+    pub automation_authority: Pubkey, // Automation thread PDA allowed to run maintenance; default = none
+    pub shard_count: u8,       // Settlement shards agents are split across; 0 = unsharded
+    pub heartbeat_timeout: i64, // Seconds without a router heartbeat before fallbacks open; 0 = off
+    pub last_heartbeat: i64,
+    pub standby_authority: Pubkey, // Failover settlement authority; default = none
+    pub failover_delay: i64,       // Seconds the router must stay stale before the standby takes over
+    pub holdback_interest_bps_per_day: u16, // Paid from protocol fees on cleanly released holdbacks
+    pub fee_challenge_window: i64, // Seconds settlement fees stay pending before they can be claimed
+    pub fee_holiday_start: i64,    // Settlements in [start, end) pay no protocol fee
+    pub fee_holiday_end: i64,
+    pub bump: u8,
+    pub velocity_ceiling: u64,   // Rolling-hour settled volume that trips the breaker; 0 = off
+    pub velocity_hour: i64,      // Hour index velocity_current belongs to
+    pub velocity_current: u64,   // Volume settled during velocity_hour
+    pub velocity_previous: u64,  // Volume settled during the hour before
+    pub settlements_paused: bool, // Set by the breaker; cleared by resume_settlements
+    pub deposit_caps: [u64; KYC_TIERS], // Escrow vault ceiling per KYC tier, reserves included; 0 = uncapped
+    pub matcher_share_bps: u16,   // Matcher's share of the protocol fee on sessions it brokered
+    pub quality_multiplier_min_bps: u16, // Engagement multiplier bounds for new sessions; 10_000 = 1x
+    pub quality_multiplier_max_bps: u16,
+    pub live_attestation_keys: u16,   // Registry keys added and not yet revoked
+    pub attestations_required_at: i64, // Activation of the first live key; settlements need one from then
+}
+
+impl MarketConfig {
+    pub const FEATURE_DELAYED_RELEASE: u64 = 1 << 0;
+    pub const FEATURE_BUILDER_SPLITS: u64 = 1 << 1;
+    pub const FEATURE_ORACLE_PRICING: u64 = 1 << 2;
+    pub const FEATURE_PERMISSIONED_AGENTS: u64 = 1 << 3;
+    pub const FEATURE_TOKEN2022: u64 = 1 << 4;
+    pub const FEATURE_MULTI_MINT: u64 = 1 << 5;
+
+    pub const ALL_FEATURES: u64 = Self::FEATURE_DELAYED_RELEASE
+        | Self::FEATURE_BUILDER_SPLITS
+        | Self::FEATURE_ORACLE_PRICING
+        | Self::FEATURE_PERMISSIONED_AGENTS
+        | Self::FEATURE_TOKEN2022
+        | Self::FEATURE_MULTI_MINT;
+    // Subsystems that predate the bitfield stay on for new deployments
+    pub const DEFAULT_FEATURES: u64 = Self::FEATURE_DELAYED_RELEASE | Self::FEATURE_BUILDER_SPLITS;
+    // 3% of a 15% fee
+    pub const DEFAULT_BUILDER_SHARE_BPS: u16 = 2000;
+
+    pub const SPACE: usize =
+        8 + 32 + 2 + 2 + 8 + 8 + 32 + 2 + 32 + 32 + 1 + 32 + 32 + 1 + 8 + 8 + 32 + 8 + 2 + 8 + 8 + 8 + 1
+            + 8 + 8 + 8 + 8 + 1 + 8 * KYC_TIERS + 2 + 2 + 2 + 2 + 8;
+
+    /// Fresh config as left by `initialize_market_config`.
+    pub fn reset(&mut self, authority: Pubkey, payment_mint: &Account<token::Mint>, fee_basis_points: u16, bump: u8) {
+        *self = Self {
+            authority,
+            fee_basis_points,
+            holdback_bps: 0,
+            holdback_period: 0,
+            features: Self::DEFAULT_FEATURES,
+            swap_program: Pubkey::default(),
+            builder_share_bps: Self::DEFAULT_BUILDER_SHARE_BPS,
+            governance: Pubkey::default(),
+            payment_mint: payment_mint.key(),
+            payment_decimals: payment_mint.decimals,
+            migration_source: Pubkey::default(),
+            automation_authority: Pubkey::default(),
+            shard_count: 0,
+            heartbeat_timeout: 0,
+            last_heartbeat: 0,
+            standby_authority: Pubkey::default(),
+            failover_delay: 0,
+            holdback_interest_bps_per_day: 0,
+            fee_challenge_window: 0,
+            fee_holiday_start: 0,
+            fee_holiday_end: 0,
+            bump,
+            velocity_ceiling: 0,
+            velocity_hour: 0,
+            velocity_current: 0,
+            velocity_previous: 0,
+            settlements_paused: false,
+            deposit_caps: [0; KYC_TIERS],
+            matcher_share_bps: 0,
+            quality_multiplier_min_bps: QUALITY_MULTIPLIER_NEUTRAL_BPS,
+            quality_multiplier_max_bps: QUALITY_MULTIPLIER_NEUTRAL_BPS,
+            live_attestation_keys: 0,
+            attestations_required_at: 0,
+        };
+    }
+
+    /// Settled volume over the last hour: this hour's bucket plus the part of the
+    /// previous hour's that still falls inside the window, assuming an even spread.
+    pub fn settlement_velocity(&self, now: i64) -> u64 {
+        let (current, previous) = match now.div_euclid(VELOCITY_WINDOW) - self.velocity_hour {
+            0 => (self.velocity_current, self.velocity_previous),
+            1 => (0, self.velocity_current),
+            _ => (0, 0),
+        };
+        let remaining = (VELOCITY_WINDOW - now.rem_euclid(VELOCITY_WINDOW)) as u128;
+        let carried = previous as u128 * remaining / VELOCITY_WINDOW as u128;
+        current.saturating_add(carried as u64)
+    }
+
+    /// Adds settled volume to the breaker. Crossing the ceiling pauses further settlements;
+    /// the settlement that crossed it still lands, so the pause persists.
+    pub fn record_settlement_volume(&mut self, amount: u64, now: i64) {
+        let hour = now.div_euclid(VELOCITY_WINDOW);
+        if hour != self.velocity_hour {
+            self.velocity_previous = if hour == self.velocity_hour + 1 { self.velocity_current } else { 0 };
+            self.velocity_current = 0;
+            self.velocity_hour = hour;
+        }
+        self.velocity_current = self.velocity_current.saturating_add(amount);
+
+        let velocity = self.settlement_velocity(now);
+        if self.velocity_ceiling > 0 && !self.settlements_paused && velocity > self.velocity_ceiling {
+            self.settlements_paused = true;
+            emit!(CircuitBreakerTripped { velocity, ceiling: self.velocity_ceiling, tripped_at: now });
+        }
+    }
+
+    /// Rejects a deposit that left `escrow` above the balance cap of its KYC tier. The cap
+    /// measures everything in its `vault`, so task, forward and bounty reserves count too;
+    /// reload the vault after the deposit's transfer.
+    pub fn check_deposit_cap(&self, escrow: &EscrowAccount, vault: &TokenAccount) -> Result<()> {
+        let cap = self.deposit_caps[escrow.kyc_tier as usize];
+        require!(cap == 0 || vault.amount <= cap, ErrorCode::DepositCapExceeded);
+        Ok(())
+    }
+
+    /// Whether settlements must carry a registry attestation: once a key has been added and
+    /// become active, until every key is revoked. Rotate by adding before revoking.
+    pub fn attestation_required(&self, now: i64) -> bool {
+        self.live_attestation_keys > 0 && now >= self.attestations_required_at
+    }
+
+    /// Shard owning `agent`: shards split the key space into contiguous ranges by first byte.
+    pub fn shard_for(&self, agent: &Pubkey) -> Option<u8> {
+        (self.shard_count > 0).then(|| (agent.to_bytes()[0] as u16 * self.shard_count as u16 / 256) as u8)
+    }
+
+    /// Whether `now` falls in the scheduled zero-fee window.
+    pub fn fee_holiday(&self, now: i64) -> bool {
+        now >= self.fee_holiday_start && now < self.fee_holiday_end
+    }
+
+    /// Whether the router has missed its heartbeat, unlocking the agent and human fallbacks.
+    pub fn router_stale(&self, now: i64) -> bool {
+        self.heartbeat_timeout > 0 && now > self.last_heartbeat.saturating_add(self.heartbeat_timeout)
+    }
+
+    /// Settles unsharded escrows: the admin, or the standby once the admin's heartbeat has
+    /// been stale for `failover_delay`. The admin takes back over with its next heartbeat.
+    pub fn settlement_authority(&self, now: i64) -> Pubkey {
+        let failed_over = self.standby_authority != Pubkey::default()
+            && self.router_stale(now.saturating_sub(self.failover_delay));
+        if failed_over {
+            self.standby_authority
+        } else {
+            self.authority
+        }
+    }
+
+    /// `micro_usd` expressed in base units of the payment mint.
+    pub fn base_units(&self, micro_usd: u64) -> Result<u64> {
+        micro_usd_to_base_units(micro_usd, self.payment_decimals)
+    }
+
+    /// Admin, or the automation authority when one is set.
+    pub fn is_operator(&self, key: &Pubkey) -> bool {
+        *key == self.authority
+            || (self.automation_authority != Pubkey::default() && *key == self.automation_authority)
+    }
+
+    pub fn fee_authority(&self) -> Pubkey {
+        if self.governance == Pubkey::default() {
+            self.authority
+        } else {
+            self.governance
+        }
+    }
+
+    pub fn has_feature(&self, flag: u64) -> bool {
+        self.features & flag == flag
+    }
+
+    /// Settlement mints must be the canonical payment mint unless multi-mint mode is on.
+    pub fn accepts_mint(&self, mint: &Account<token::Mint>) -> bool {
+        self.has_feature(Self::FEATURE_MULTI_MINT)
+            || (mint.key() == self.payment_mint && mint.decimals == self.payment_decimals)
+    }
+
+    pub fn effective_holdback_bps(&self) -> u64 {
+        if self.has_feature(Self::FEATURE_DELAYED_RELEASE) {
+            self.holdback_bps as u64
+        } else {
+            0
+        }
+    }
+}
+
+#[account]
+pub struct FeeVaultState {
+    pub authority: Pubkey,
+    pub protocol_balance: u64, // Claimable fees: past their challenge window
+    pub total_collected: u64,
+    pub bump: u8,
+    pub pending_settlement_fees: u64, // Settlement fees still inside the challenge window
+    pub pending_recent: u64,          // Part of pending credited during pending_window
+    pub pending_window: i64,          // Index of the challenge window pending_recent belongs to
+}
+
+impl FeeVaultState {
+    pub const SPACE: usize = 8 + 32 + 8 + 8 + 1 + 8 + 8 + 8;
+
+    /// Moves pending fees credited before the previous challenge window to the claimable
+    /// balance, so every fee stays pending for at least one full window.
+    pub fn realize_fees(&mut self, now: i64, window: i64) {
+        let matured = if window == 0 {
+            self.pending_settlement_fees
+        } else {
+            let current = now / window;
+            if current <= self.pending_window {
+                return;
+            }
+            let matured = if current == self.pending_window + 1 {
+                self.pending_settlement_fees - self.pending_recent
+            } else {
+                self.pending_settlement_fees
+            };
+            self.pending_window = current;
+            matured
+        };
+        self.protocol_balance += matured;
+        self.pending_settlement_fees -= matured;
+        self.pending_recent = 0;
+    }
+
+    pub fn credit_settlement_fees(&mut self, amount: u64, now: i64, window: i64) {
+        self.realize_fees(now, window);
+        if window == 0 {
+            self.protocol_balance += amount;
+        } else {
+            self.pending_settlement_fees += amount;
+            self.pending_recent += amount;
+        }
+    }
+
+    /// Treats everything pending as freshly credited under `window`.
+    pub fn restart_pending(&mut self, now: i64, window: i64) {
+        if window > 0 {
+            self.pending_recent = self.pending_settlement_fees;
+            self.pending_window = now / window;
+        }
+    }
+}
+
+#[account]
+pub struct BuilderBalance {
+    pub builder_code: [u8; 32],
+    pub wallet: Pubkey,
+    pub balance: u64,
+    pub total_earned: u64,
+    pub bump: u8,
+}
+
+impl BuilderBalance {
+    pub const SPACE: usize = 8 + 32 + 32 + 8 + 8 + 1;
+}
+
+/// Fee share owed to a matchmaking service, held in the fee vault like builder balances.
+#[account]
+pub struct MatcherBalance {
+    pub matcher_code: [u8; 32],
+    pub wallet: Pubkey,
+    pub balance: u64,
+    pub total_earned: u64,
+    pub bump: u8,
+}
+
+impl MatcherBalance {
+    pub const SPACE: usize = 8 + 32 + 32 + 8 + 8 + 1;
+}
+
+// Layout is committed for RPC memcmp filters: agent at offset 8, mint at 97
+#[account]
+pub struct EscrowAccount {
+    pub agent: Pubkey,
+    pub balance: u64,
+    pub settlement_nonce: u64,  // Prevents replay attacks
+    pub bump: u8,
+    pub position_mint: Pubkey,  // Set once tokenized; default = agent-controlled
+    pub sponsored_balance: u64, // Part of balance refundable to sponsors, not the agent
+    pub mint: Pubkey,           // Secondary settlement mint; default = primary fee vault mint
+    pub open_sessions: u16,     // Sessions opened against this escrow and not yet closed
+    pub reserved_balance: u64,  // Part of balance held back for open sessions
+    pub fee_bps_override: Option<u16>, // Negotiated settlement fee; None = market fee
+    pub shard_id: Option<u8>,          // Settlement shard; None = settled by the market authority
+    pub intents_required: bool,        // Settlements must reference a live PaymentIntent
+    // Open statement period, rolled into an EscrowEpochSummary by close_escrow_epoch
+    pub statement_count: u32,          // Summaries written so far; index of the open period
+    pub statement_started_at: i64,
+    pub statement_opening_balance: u64,
+    pub statement_deposits: u64,
+    pub statement_settlements: u64,    // Gross, fees included
+    pub statement_fees: u64,
+    pub cumulative_deposited: u64,     // Every deposit since creation; emitted with EscrowFunded
+    pub statement_sessions: u32,       // Sessions opened in the open statement period
+    pub kyc_tier: u8,                  // Admin-attested; selects the market's deposit cap
+    pub total_settled: u64,            // Every settlement since creation, fees included
+    pub created_at: i64,               // 0 = created before this was tracked
+    // New fields must be appended here; realloc_escrow zero-fills them on old accounts
+}
+
+impl EscrowAccount {
+    pub const SPACE: usize = 8 + 32 + 8 + 8 + 1 + 32 + 8 + 32 + 2 + 8 + 3 + 2 + 1 + 4 + 8 + 8 + 8 + 8 + 8 + 8 + 4 + 1 + 8 + 8;
+
+    /// Only signer allowed to open sessions on and settle this escrow: its shard's worker,
+    /// or the market's settlement authority while unsharded. Default (unsignable) if the
+    /// wrong shard is given.
+    pub fn settlement_router(&self, config: &MarketConfig, shard: Option<&Shard>, now: i64) -> Pubkey {
+        match self.shard_id {
+            None => config.settlement_authority(now),
+            Some(id) => shard.filter(|s| s.shard_id == id).map(|s| s.signer).unwrap_or_default(),
+        }
+    }
+
+    /// Binds a freshly created escrow (from an `init_if_needed` path) to its agent, or checks
+    /// that an existing one already belongs to them. An existing bump is never rewritten.
+    pub fn bind_agent(&mut self, agent: Pubkey, bump: u8) -> Result<()> {
+        if self.agent == Pubkey::default() {
+            self.agent = agent;
+            self.bump = bump;
+            self.created_at = Clock::get()?.unix_timestamp;
+        } else {
+            require_keys_eq!(self.agent, agent, ErrorCode::Unauthorized);
+        }
+        Ok(())
+    }
+
+    /// Fee rate charged on this escrow's settlements; nothing during a fee holiday.
+    pub fn fee_bps(&self, config: &MarketConfig, now: i64) -> u64 {
+        if config.fee_holiday(now) {
+            return 0;
+        }
+        self.fee_bps_override.unwrap_or(config.fee_basis_points) as u64
+    }
+
+    /// Balance the agent (or position holder) may withdraw.
+    pub fn withdrawable(&self) -> u64 {
+        self.balance
+            .saturating_sub(self.sponsored_balance)
+            .saturating_sub(self.reserved_balance)
+    }
+
+    /// Balance that can't be withdrawn: the sponsored tranche and open-session reserves.
+    pub fn locked(&self) -> u64 {
+        self.balance - self.withdrawable()
+    }
+
+    pub fn record_deposit(&mut self, amount: u64) {
+        self.statement_deposits = self.statement_deposits.saturating_add(amount);
+        self.cumulative_deposited = self.cumulative_deposited.saturating_add(amount);
+    }
+
+    pub fn record_settlement(&mut self, amount: u64, fee_amount: u64) {
+        self.statement_settlements = self.statement_settlements.saturating_add(amount);
+        self.statement_fees = self.statement_fees.saturating_add(fee_amount);
+        self.total_settled = self.total_settled.saturating_add(amount);
+    }
+}
+
+/// Short-lived proof of an escrow's standing, written by its agent for an underwriter.
+#[account]
+pub struct EscrowSnapshot {
+    pub escrow: Pubkey,
+    pub agent: Pubkey,
+    pub challenge: [u8; 32], // Chosen by the underwriter, so the snapshot can't be reused
+    pub balance: u64,
+    pub total_settled: u64,
+    pub total_deposited: u64,
+    pub created_at: i64, // Escrow creation; 0 = predates tracking
+    pub taken_at: i64,
+    pub taken_slot: u64,
+    pub expires_at: i64,
+    pub bump: u8,
+}
+
+impl EscrowSnapshot {
+    pub const SPACE: usize = 8 + 32 + 32 + 32 + 8 + 8 + 8 + 8 + 8 + 8 + 8 + 1;
+
+    /// Seconds the escrow had existed when the snapshot was taken; 0 if unknown.
+    pub fn account_age(&self) -> i64 {
+        if self.created_at == 0 {
+            0
+        } else {
+            self.taken_at - self.created_at
+        }
+    }
+}
+
+// Layout is committed for RPC memcmp filters: escrow at offset 8, human at 40
+#[account]
+pub struct Session {
+    pub escrow: Pubkey,
+    pub human: Pubkey,
+    pub session_id: u64,
+    pub opened_at: i64,
+    pub opened_slot: u64,
+    pub total_paid_seconds: u64, // Monotonic, bounded by wall-clock elapsed time
+    pub total_tipped: u64,       // Discretionary bonuses paid via tip_human
+    pub price_per_second: u64,   // Price agreed at open; settlements can't pay less
+    pub reserved: u64,           // Escrow balance reserved at open, released on close
+    pub bump: u8,
+    pub spot_priced: bool,       // price_per_second was snapshotted from a SpotPrice; settlements must match it
+    pub matcher: Pubkey,         // MatcherBalance credited at settlement; default = none
+    pub quality_min_bps: u16,    // Engagement multiplier bounds snapshotted from config at open
+    pub quality_max_bps: u16,
+    pub category: [u8; 32],      // SessionLengthStats category; zero = uncategorized
+}
+
+impl Session {
+    /// Adds `verified_seconds` to the session's billed total, which can never outrun its
+    /// wall-clock time (plus tolerance) or the per-session cap.
+    pub fn bill_seconds(&mut self, verified_seconds: u64, now: i64) -> Result<()> {
+        let paid_seconds = self.total_paid_seconds.checked_add(verified_seconds)
+            .ok_or(ErrorCode::MathOverflow)?;
+        require!(paid_seconds <= MAX_SESSION_SECONDS, ErrorCode::SessionCapExceeded);
+
+        let elapsed = now.saturating_sub(self.opened_at).max(0) as u64;
+        require!(
+            paid_seconds <= elapsed.saturating_add(SESSION_TOLERANCE_SECONDS),
+            ErrorCode::SessionElapsedExceeded
+        );
+        self.total_paid_seconds = paid_seconds;
+        Ok(())
+    }
+
+    /// Price checks for paths without an engagement multiplier: spot-priced sessions pay
+    /// the snapshotted price, others at least the price agreed at open.
+    pub fn check_price(&self, agreed_price_per_second: u64) -> Result<()> {
+        require!(
+            !self.spot_priced || agreed_price_per_second == self.price_per_second,
+            ErrorCode::SpotPriceMismatch
+        );
+        require!(agreed_price_per_second >= self.price_per_second, ErrorCode::BelowPriceFloor);
+        Ok(())
+    }
+}
+
+/// Session shared by several humans, settled with one escrow draw per checkpoint.
+#[account]
+pub struct GroupSession {
+    pub escrow: Pubkey,
+    pub session_id: u64,
+    pub opened_at: i64,
+    pub price_per_second: u64, // Floor for every participant
+    pub reserved: u64,         // Escrow balance reserved at open, released on close
+    pub participants: Vec<Pubkey>, // At most MAX_GROUP_PARTICIPANTS
+    pub paid_seconds: Vec<u64>,    // Billed seconds so far, per participant
+    pub bump: u8,
+}
+
+impl GroupSession {
+    pub fn space(participants: usize) -> usize {
+        8 + 32 + 8 + 8 + 8 + 8 + 4 + participants * 32 + 4 + participants * 8 + 1
+    }
+}
+
+#[account]
+pub struct EmissionSchedule {
+    pub mint: Pubkey,
+    pub rewards_destination: Pubkey, // Fuel tank / staking pool token account
+    pub start_ts: i64,
+    pub epoch_duration: i64,
+    pub next_epoch: u64,
+    pub next_epoch_emission: u64,
+    pub decay_bps: u16,
+    pub total_minted: u64,
+    pub bump: u8,
+}
+
+#[account]
+pub struct SponsorPosition {
+    pub escrow: Pubkey,
+    pub sponsor: Pubkey,
+    pub amount: u64,          // Outstanding refundable contribution
+    pub total_sponsored: u64,
+    pub bump: u8,
+}
+
+#[account]
+pub struct Holdback {
+    pub human: Pubkey,
+    pub amount: u64,       // Held in the fee vault until release_at
+    pub release_at: i64,   // Rolls forward with every new holdback
+    pub total_held: u64,
+    pub flagged: bool,     // Fraud-flagged holdbacks are never auto-released
+    pub bump: u8,
+}
+
+impl Holdback {
+    pub const SPACE: usize = 8 + 32 + 8 + 8 + 8 + 1 + 1;
+
+    pub fn credit(&mut self, human: Pubkey, amount: u64, release_at: i64) -> Result<()> {
+        self.human = human;
+        self.amount = self.amount.checked_add(amount).ok_or(ErrorCode::MathOverflow)?;
+        self.total_held = self.total_held.checked_add(amount).ok_or(ErrorCode::MathOverflow)?;
+        self.release_at = release_at;
+        Ok(())
+    }
+}
+
+#[account]
+pub struct Shard {
+    pub shard_id: u8,
+    pub signer: Pubkey, // Settlement worker that exclusively owns this shard's escrows
+    pub bump: u8,
+}
+
+impl Shard {
+    pub const SPACE: usize = 8 + 1 + 32 + 1;
+}
+
+#[account]
+pub struct SettlementQueue {
+    pub head: u64,         // Seq of the next item to process
+    pub tail: u64,         // Seq the next enqueued item gets
+    pub tip_lamports: u64, // Paid to the cranker per processed item, on top of rent
+    pub bump: u8,
+}
+
+impl SettlementQueue {
+    pub const SPACE: usize = 8 + 8 + 8 + 8 + 1;
+}
+
+#[account]
+pub struct QueuedSettlement {
+    pub seq: u64,
+    pub escrow: Pubkey,
+    pub human: Pubkey,
+    pub amount: u64, // verified_seconds * agreed_price_per_second
+    pub nonce: u64,
+    pub enqueued_at: i64,
+    pub bump: u8,
+}
+
+impl QueuedSettlement {
+    pub const SPACE: usize = 8 + 8 + 32 + 32 + 8 + 8 + 8 + 1;
+}
+
+#[account]
+pub struct AttestationKey {
+    pub key: Pubkey,
+    pub active_at: i64,  // Adds are timelocked
+    pub revoked: bool,   // Revocations are instant
+    pub bump: u8,
+}
+
+#[account]
+pub struct BuilderStats {
+    pub builder_code: [u8; 32],
+    pub monthly_earned: [u64; 12], // Ring buffer of the last 12 months, indexed by month % 12
+    pub latest_month: u32,         // Months since 1970-01 of the newest bucket
+    pub bump: u8,
+}
+
+impl BuilderStats {
+    pub fn record(&mut self, month: u32, amount: u64) {
+        if month > self.latest_month {
+            // Clear buckets for months with no settlements since the last update
+            let skipped = (month - self.latest_month).min(12);
+            for i in 0..skipped {
+                self.monthly_earned[((month - i) % 12) as usize] = 0;
+            }
+            self.latest_month = month;
+        } else if self.latest_month - month >= 12 {
+            return;
+        }
+        let bucket = &mut self.monthly_earned[(month % 12) as usize];
+        *bucket = bucket.saturating_add(amount);
+    }
+}
+
+#[account]
+pub struct Task {
+    pub escrow: Pubkey,
+    pub agent: Pubkey,
+    pub task_id: u64,
+    pub balance: u64, // Escrow funds reserved for this task
+    pub deadline: i64, // 0 = no deadline
+    pub bump: u8,
+    pub refund_to: Pubkey, // Registered refund token account; default = any of the agent's own
+}
+
+impl Task {
+    pub const SPACE: usize = 8 + 32 + 32 + 8 + 8 + 8 + 1 + 32;
+
+    /// Whether the task's remainder may be refunded into `account`.
+    pub fn accepts_refund_to(&self, account: &Account<TokenAccount>) -> bool {
+        if self.refund_to == Pubkey::default() {
+            account.owner == self.agent
+        } else {
+            account.key() == self.refund_to
+        }
+    }
+}
+
+#[account]
+pub struct TaskGate {
+    pub escrow: Pubkey,
+    pub task: Pubkey,
+    pub collection: Pubkey, // Verified Metaplex collection the human must hold an NFT from
+    pub bump: u8,
+}
+
+impl TaskGate {
+    pub const SPACE: usize = 8 + 32 + 32 + 32 + 1;
+}
+
+/// What an issuer attests about a human for a task policy.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum AttestationType {
+    AgeOver18,
+    Region,
+    AgeOver18AndRegion,
+}
+
+#[account]
+pub struct TaskPolicy {
+    pub escrow: Pubkey,
+    pub task: Pubkey,
+    pub policy_hash: [u8; 32], // Hash of the off-chain targeting policy attestations refer to
+    pub attestation_type: AttestationType,
+    pub bump: u8,
+}
+
+impl TaskPolicy {
+    pub const SPACE: usize = 8 + 32 + 32 + 32 + 1 + 1;
+}
+
+#[account]
+pub struct AttestationIssuer {
+    pub program: Pubkey, // Owner of the attestation accounts this issuer writes
+    pub revoked: bool,
+    pub bump: u8,
+}
+
+impl AttestationIssuer {
+    pub const SPACE: usize = 8 + 32 + 1 + 1;
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct Milestone {
+    pub amount: u64,
+    pub description_hash: [u8; 32], // Hash of the off-chain milestone description
+}
+
+#[account]
+pub struct TaskMilestones {
+    pub escrow: Pubkey,
+    pub task: Pubkey,
+    pub human: Pubkey,
+    pub milestones: Vec<Milestone>, // At most MAX_TASK_MILESTONES
+    pub released_mask: u8,          // Bit i set once milestone i is paid out
+    pub cancelled_mask: u8,         // Bit i set once milestone i is refunded to the escrow
+    pub bump: u8,
+}
+
+impl TaskMilestones {
+    pub const SPACE: usize = 8 + 32 + 32 + 32 + 4 + MAX_TASK_MILESTONES * (8 + 32) + 1 + 1 + 1;
+
+    /// Amount of milestone `index`, provided it is neither released nor cancelled.
+    pub fn open_amount(&self, index: u8) -> Result<u64> {
+        let milestone = self.milestones.get(index as usize).ok_or(ErrorCode::InvalidMilestones)?;
+        require!((self.released_mask | self.cancelled_mask) & (1 << index) == 0, ErrorCode::MilestoneSettled);
+        Ok(milestone.amount)
+    }
+}
+
+#[account]
+pub struct Bounty {
+    pub escrow: Pubkey,
+    pub task: Pubkey,
+    pub human: Pubkey,
+    pub amount: u64,               // Committed out of the task's reserve
+    pub paid: u64,                 // Gross paid so far, fees included
+    pub completed_bps: u16,        // Cumulative completion attested by the router
+    pub completion_hash: [u8; 32], // Evidence hash from the latest completion
+    pub bump: u8,
+}
+
+impl Bounty {
+    pub const SPACE: usize = 8 + 32 + 32 + 32 + 8 + 8 + 2 + 32 + 1;
+}
+
+/// Read-side role on one escrow, granted by the agent and accepted by the observer.
+#[account]
+pub struct EscrowObserver {
+    pub escrow: Pubkey,
+    pub observer: Pubkey,
+    pub tag: [u8; 32], // Agreed label echoed in observed events
+    pub accepted: bool,
+    pub bump: u8,
+}
+
+impl EscrowObserver {
+    pub const SPACE: usize = 8 + 32 + 32 + 32 + 1 + 1;
+}
+
+#[account]
+pub struct DataConsent {
+    pub human: Pubkey,
+    pub scope_hash: [u8; 32], // Hash of the off-chain licensing terms
+    pub granted_at: i64,
+    pub expiry: i64,
+    pub revoked_at: i64,  // 0 while in force
+    pub settlements: u64, // Settlements that referenced this consent
+    pub bump: u8,
+}
+
+impl DataConsent {
+    pub const SPACE: usize = 8 + 32 + 32 + 8 + 8 + 8 + 8 + 1;
+
+    pub fn is_active(&self, now: i64) -> bool {
+        self.revoked_at == 0 && now < self.expiry
+    }
+}
+
+#[account]
+pub struct Recovery {
+    pub escrow: Pubkey,
+    pub admin: Pubkey, // Initiator; receives the rent back
+    pub executable_at: i64,
+    pub bump: u8,
+}
+
+#[account]
+pub struct PayoutPrefs {
+    pub human: Pubkey,
+    pub preferred_mint: Pubkey,    // Default = accept any mint
+    pub auto_claim_threshold: u64, // Min holdback that others may release on the human's behalf
+    pub vesting_opt_in: bool,
+    pub referral_code: [u8; 32],
+    pub min_price_per_second: u64, // Settlements below this price are rejected
+    pub bump: u8,
+    // New fields must be appended here; loaders zero-fill them on older accounts
+    pub jurisdiction: [u8; 4],   // ISO 3166 code, e.g. b"US\0\0" or b"USCA"; zero = unset
+    pub tax_memo_hash: [u8; 32], // Hash of the human's own income classification memo
+}
+
+/// Tax-lot annotation copied from PayoutPrefs onto receipts and payout events.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct TaxLot {
+    pub jurisdiction: [u8; 4],
+    pub memo_hash: [u8; 32],
+}
+
+impl PayoutPrefs {
+    pub const SPACE: usize = 8 + 32 + 32 + 8 + 1 + 32 + 8 + 1 + 4 + 32;
+
+    /// The human's tax-lot annotation, if they set one.
+    pub fn tax_lot(&self) -> Option<TaxLot> {
+        (self.jurisdiction != [0; 4] || self.tax_memo_hash != [0; 32]).then_some(TaxLot {
+            jurisdiction: self.jurisdiction,
+            memo_hash: self.tax_memo_hash,
+        })
+    }
+
+    pub fn check_mint(&self, mint: &Pubkey) -> Result<()> {
+        require!(
+            self.preferred_mint == Pubkey::default() || self.preferred_mint == *mint,
+            ErrorCode::PreferredMintMismatch
+        );
+        Ok(())
+    }
+
+    pub fn check_price(&self, price_per_second: u64) -> Result<()> {
+        require!(price_per_second >= self.min_price_per_second, ErrorCode::BelowPriceFloor);
+        Ok(())
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct PayoutLeg {
+    pub wallet: Pubkey, // Token account in the payout mint
+    pub bps: u16,       // Share of each payout
+}
+
+#[account]
+pub struct PayoutSplit {
+    pub human: Pubkey,
+    pub legs: Vec<PayoutLeg>, // At most MAX_SPLIT_LEGS; empty = no split
+    pub bump: u8,
+}
+
+impl PayoutSplit {
+    pub const SPACE: usize = 8 + 32 + 4 + MAX_SPLIT_LEGS * (32 + 2) + 1;
+}
+
+#[account]
+pub struct Org {
+    pub org_id: [u8; 32],
+    pub admin: Pubkey,
+    pub treasury: Pubkey, // Token account receiving the org fee, in the payout mint
+    pub fee_bps: u16,     // Share of each member's immediate payout; holdback releases are not charged
+    pub member_count: u32,
+    pub total_member_payouts: u64, // Paid to members after the org fee
+    pub total_org_fees: u64,
+    pub settlement_count: u64,
+    pub bump: u8,
+}
+
+impl Org {
+    pub const SPACE: usize = 8 + 32 + 32 + 32 + 2 + 4 + 8 + 8 + 8 + 1;
+}
+
+#[account]
+pub struct OrgMember {
+    pub org: Pubkey,
+    pub human: Pubkey,
+    pub joined_at: i64,
+    pub bump: u8,
+}
+
+impl OrgMember {
+    pub const SPACE: usize = 8 + 32 + 32 + 8 + 1;
+}
+
+#[account]
+pub struct ApiKey {
+    pub agent: Pubkey,
+    pub key_hash: [u8; 32], // Hash of the agent's off-chain API key identifier
+    pub total_spent: u64,   // Gross settlement amounts tagged with this key
+    pub settlement_count: u64,
+    pub last_used: i64,
+    pub revoked: bool,
+    pub bump: u8,
+}
+
+impl ApiKey {
+    pub const SPACE: usize = 8 + 32 + 32 + 8 + 8 + 8 + 1 + 1;
+
+    pub fn record(&mut self, amount: u64, now: i64) {
+        self.total_spent = self.total_spent.saturating_add(amount);
+        self.settlement_count += 1;
+        self.last_used = now;
+    }
+}
+
+/// Closed statement period of an escrow. Reconciles exactly:
+/// `closing_balance = opening_balance + deposits - settlements + other_net`.
+#[account]
+pub struct EscrowEpochSummary {
+    pub escrow: Pubkey,
+    pub index: u32,
+    pub started_at: i64,       // 0 for an escrow's first period
+    pub closed_at: i64,
+    pub opening_balance: u64,
+    pub deposits: u64,         // Agent, sponsor, authorized and CCTP deposits
+    pub settlements: u64,      // Gross settlements and tips, fees included
+    pub fees: u64,
+    pub other_net: i64,        // Every other balance movement, net
+    pub closing_balance: u64,
+    pub bump: u8,
+    pub sessions: u32,         // Sessions opened during the period
+}
+
+impl EscrowEpochSummary {
+    pub const SPACE: usize = 8 + 32 + 4 + 8 + 8 + 8 + 8 + 8 + 8 + 8 + 8 + 1 + 4;
+
+    /// Keccak hash of the summary's figures, as recorded on its invoice.
+    pub fn hash(&self) -> [u8; 32] {
+        keccak::hashv(&[
+            self.escrow.as_ref(),
+            &self.index.to_le_bytes(),
+            &self.started_at.to_le_bytes(),
+            &self.closed_at.to_le_bytes(),
+            &self.opening_balance.to_le_bytes(),
+            &self.deposits.to_le_bytes(),
+            &self.settlements.to_le_bytes(),
+            &self.fees.to_le_bytes(),
+            &self.other_net.to_le_bytes(),
+            &self.closing_balance.to_le_bytes(),
+            &self.sessions.to_le_bytes(),
+        ])
+        .to_bytes()
+    }
+}
+
+/// Agent-held, frozen invoice token for one statement period.
+#[account]
+pub struct Invoice {
+    pub summary: Pubkey,
+    pub escrow: Pubkey,
+    pub mint: Pubkey,
+    pub summary_hash: [u8; 32], // EscrowEpochSummary::hash at minting
+    pub minted_at: i64,
+    pub bump: u8,
+}
+
+impl Invoice {
+    pub const SPACE: usize = 8 + 32 + 32 + 32 + 32 + 8 + 1;
+}
+
+/// Agent-signed ceiling on what the router may settle from an escrow to one human.
+#[account]
+pub struct PaymentIntent {
+    pub escrow: Pubkey,
+    pub human: Pubkey,
+    pub intent_id: u64,
+    pub max_amount: u64,
+    pub remaining: u64, // Authorization left after the settlements drawn so far
+    pub expires_at: i64,
+    pub bump: u8,
+}
+
+impl PaymentIntent {
+    pub const SPACE: usize = 8 + 32 + 32 + 8 + 8 + 8 + 8 + 1;
+
+    pub fn spend(&mut self, amount: u64, now: i64) -> Result<()> {
+        require!(now < self.expires_at, ErrorCode::PaymentIntentExpired);
+        self.remaining = self.remaining.checked_sub(amount).ok_or(ErrorCode::PaymentIntentExceeded)?;
+        Ok(())
+    }
+}
+
+// Layout is committed for RPC memcmp filters: human at offset 8, agent at 40, escrow at 72
+#[account]
+pub struct SettlementReceipt {
+    pub human: Pubkey,
+    pub agent: Pubkey,
+    pub escrow: Pubkey,
+    pub nonce: u64,
+    pub verified_seconds: u64,
+    pub agreed_price_per_second: u64,
+    pub total_payout: u64,
+    pub quality_score: u8, // 1-100 from the verifier, 0 = unscored
+    pub settled_at: i64,
+    pub bump: u8,
+    pub flagged: bool, // verified_seconds was an outlier for its category; queued for review
+    pub response_hash: [u8; 32], // Hash of the human's deliverable (e.g. survey answers); zero = none
+    pub jurisdiction: [u8; 4],   // From the human's PayoutPrefs at settlement; zero = unset
+    pub tax_memo_hash: [u8; 32],
+    pub agent_rating: u8, // 1-5 stars from the human via rate_agent, 0 = unrated
+    pub quality_multiplier_bps: u16, // Engagement multiplier on agreed_price_per_second, 10_000 = 1x
+}
+
+#[account]
+pub struct AgentRating {
+    pub agent: Pubkey,
+    pub ratings: u64,
+    pub rating_sum: u64,     // Average = rating_sum / ratings
+    pub histogram: [u64; 5], // Ratings given per star count, 1 through 5
+    pub bump: u8,
+}
+
+impl AgentRating {
+    pub const SPACE: usize = 8 + 32 + 8 + 8 + 8 * 5 + 1;
+
+    pub fn record(&mut self, agent: Pubkey, rating: u8, bump: u8) -> Result<()> {
+        self.agent = agent;
+        self.bump = bump;
+        self.ratings = self.ratings.checked_add(1).ok_or(ErrorCode::MathOverflow)?;
+        self.rating_sum = self.rating_sum.checked_add(rating as u64).ok_or(ErrorCode::MathOverflow)?;
+        self.histogram[rating as usize - 1] += 1;
+        Ok(())
+    }
+}
+
+#[account]
+pub struct QualityStats {
+    pub subject: Pubkey, // Human wallet or agent key
+    pub settlements: u64,
+    pub scored_settlements: u64,
+    pub quality_score_sum: u64, // Average = quality_score_sum / scored_settlements
+    pub bump: u8,
+}
+
+impl QualityStats {
+    pub fn record(&mut self, subject: Pubkey, quality_score: Option<u8>, bump: u8) -> Result<()> {
+        self.subject = subject;
+        self.bump = bump;
+        self.settlements = self.settlements.checked_add(1).ok_or(ErrorCode::MathOverflow)?;
+        if let Some(score) = quality_score {
+            self.scored_settlements += 1;
+            self.quality_score_sum = self.quality_score_sum
+                .checked_add(score as u64)
+                .ok_or(ErrorCode::MathOverflow)?;
+        }
+        Ok(())
+    }
+}
+
+#[account]
+pub struct CreditLine {
+    pub escrow: Pubkey,
+    pub underwriter: Pubkey,
+    pub credit_limit: u64, // Equal to collateral held; interest-free
+    pub debt: u64,         // Drawn by settlements, repaid from the agent's next deposits
+    pub total_drawn: u64,
+    pub total_repaid: u64,
+    pub bump: u8,
+}
+
+impl CreditLine {
+    pub fn draw(&mut self, amount: u64) -> Result<()> {
+        let debt = self.debt.checked_add(amount).ok_or(ErrorCode::MathOverflow)?;
+        require!(debt <= self.credit_limit, ErrorCode::CreditLimitExceeded);
+        self.debt = debt;
+        self.total_drawn += amount;
+        Ok(())
+    }
+
+    pub fn repay(&mut self, available: u64) -> u64 {
+        let amount = available.min(self.debt);
+        self.debt -= amount;
+        self.total_repaid += amount;
+        amount
+    }
+}
+
+#[account]
+pub struct LstCollateral {
+    pub mint: Pubkey,
+    pub decimals: u8,
+    pub oracle: Pubkey,    // Posts prices alongside the admin
+    pub haircut_bps: u16,  // Discount on the posted price when lending against it
+    pub price: u64,        // Payment-mint base units per whole token
+    pub updated_at: i64,
+    pub bump: u8,
+}
+
+impl LstCollateral {
+    pub const SPACE: usize = 8 + 32 + 1 + 32 + 2 + 8 + 8 + 1;
+
+    /// `amount` base units at the posted price, if fresh enough to act on.
+    pub fn value(&self, amount: u64, now: i64) -> Result<u64> {
+        require!(
+            self.price > 0 && now <= self.updated_at.saturating_add(LST_PRICE_MAX_AGE),
+            ErrorCode::StaleLstPrice
+        );
+        let value = amount as u128 * self.price as u128 / 10u128.pow(self.decimals as u32);
+        u64::try_from(value).map_err(|_| ErrorCode::MathOverflow.into())
+    }
+
+    /// What `amount` base units can back in debt: their value less the haircut.
+    pub fn credit_value(&self, amount: u64, now: i64) -> Result<u64> {
+        let value = self.value(amount, now)? as u128;
+        Ok((value * (BPS_DENOMINATOR - self.haircut_bps as u64) as u128 / BPS_DENOMINATOR as u128) as u64)
+    }
+}
+
+#[account]
+pub struct LstPosition {
+    pub escrow: Pubkey,
+    pub mint: Pubkey,
+    pub amount: u64, // LST held in the position's vault
+    pub debt: u64,   // Fronted by the liquidity buffer, not yet repaid
+    pub bump: u8,
+}
+
+impl LstPosition {
+    pub const SPACE: usize = 8 + 32 + 32 + 8 + 8 + 1;
+}
+
+#[account]
+pub struct LiquidityBuffer {
+    pub vault: Pubkey,
+    pub outstanding: u64, // Fronted to LST positions, not yet repaid
+    pub bump: u8,
+    pub max_outstanding: u64,     // Exposure cap across all positions; 0 = uncapped
+    pub max_position_debt: u64,   // Exposure cap per position; 0 = uncapped
+    pub replenish_bps: u16,       // Share of new protocol fees moved in by the crank
+    pub replenished_through: u64, // fee_vault_state.total_collected at the last replenishment
+    pub total_funded: u64,
+    pub total_drawn: u64,
+    pub total_repaid: u64,
+}
+
+impl LiquidityBuffer {
+    pub const SPACE: usize = 8 + 32 + 8 + 1 + 8 + 8 + 2 + 8 + 8 + 8 + 8;
+
+    /// Records `amount` lent to a position whose debt becomes `position_debt`.
+    pub fn draw(&mut self, amount: u64, position_debt: u64) -> Result<()> {
+        let outstanding = self.outstanding.checked_add(amount).ok_or(ErrorCode::MathOverflow)?;
+        require!(
+            (self.max_outstanding == 0 || outstanding <= self.max_outstanding)
+                && (self.max_position_debt == 0 || position_debt <= self.max_position_debt),
+            ErrorCode::BufferExposureExceeded
+        );
+        self.outstanding = outstanding;
+        self.total_drawn = self.total_drawn.saturating_add(amount);
+        Ok(())
+    }
+
+    pub fn repay(&mut self, amount: u64) {
+        self.outstanding = self.outstanding.saturating_sub(amount);
+        self.total_repaid = self.total_repaid.saturating_add(amount);
+    }
+
+    /// Outstanding loans as a share of outstanding plus `available` (the vault balance).
+    pub fn utilization_bps(&self, available: u64) -> u16 {
+        let capital = self.outstanding as u128 + available as u128;
+        if capital == 0 {
+            return 0;
+        }
+        (self.outstanding as u128 * BPS_DENOMINATOR as u128 / capital) as u16
+    }
+}
+
+#[account]
+pub struct MintFeeVault {
+    pub mint: Pubkey,
+    pub vault: Pubkey, // Token account holding this mint's fees and holdbacks
+    pub protocol_balance: u64,
+    pub total_collected: u64,
+    pub bump: u8,
+}
+
+#[account]
+pub struct RevenuePool {
+    pub epoch_duration: i64,
+    pub current_epoch: u64,
+    pub epoch_started_at: i64,
+    pub epoch_start_collected: u64, // fee_vault_state.total_collected when the epoch opened
+    pub active_bps: u16,            // Sum of bps of positions live in the current epoch
+    pub pending_bps: u16,           // Minted this epoch; live from the next one
+    pub reserved: u64,              // Moved out of protocol_balance, owed to position holders
+    pub position_count: u64,
+    pub bump: u8,
+}
+
+#[account]
+pub struct RevenueShare {
+    pub nft_mint: Pubkey,
+    pub bps: u16,
+    pub start_epoch: u64,
+    pub end_epoch: u64, // Exclusive
+    pub next_claim_epoch: u64,
+    pub bump: u8,
+}
+
+#[account]
+pub struct FeeEpoch {
+    pub epoch: u64,
+    pub collected: u64,     // Fees collected in the epoch, set when it is rolled
+    pub active_bps: u16,    // Revenue-share bps live during the epoch
+    pub reserved: u64,      // Amount set aside for revenue-share holders
+    pub expiring_bps: u16,  // Bps of positions whose last epoch is the one before this
+    pub bump: u8,
+}
+
+impl FeeEpoch {
+    pub const SPACE: usize = 8 + 8 + 8 + 2 + 8 + 2 + 1;
+}
+
+#[account]
+pub struct RelayerStats {
+    pub relayer: Pubkey,
+    pub settlements: u64,         // Settlement transactions submitted
+    pub lamports_spent: u64,      // Cumulative gas paid on behalf of the market
+    pub lamports_reimbursed: u64,
+    pub amount_reimbursed: u64,   // Paid back in the fee vault mint
+    pub bump: u8,
+}
+
+impl RelayerStats {
+    pub fn record(&mut self, relayer: Pubkey, bump: u8) {
+        self.relayer = relayer;
+        self.settlements += 1;
+        self.lamports_spent += SETTLEMENT_TX_FEE_LAMPORTS;
+        self.bump = bump;
+    }
+}
+
+#[account]
+pub struct ForwardPosition {
+    pub escrow: Pubkey,
+    pub agent: Pubkey,
+    pub forward_id: u64,
+    pub cohort: Pubkey,        // Audience the seconds were bought from
+    pub price_per_second: u64, // Locked price; settlements must match it
+    pub seconds_total: u64,
+    pub seconds_used: u64,
+    pub expires_at: i64,
+    pub bump: u8,
+}
+
+impl ForwardPosition {
+    pub fn consume(&mut self, seconds: u64) -> Result<()> {
+        let used = self.seconds_used.checked_add(seconds).ok_or(ErrorCode::MathOverflow)?;
+        require!(used <= self.seconds_total, ErrorCode::ForwardExhausted);
+        self.seconds_used = used;
+        Ok(())
+    }
+}
+
+#[account]
+pub struct CohortPool {
+    pub cohort_id: [u8; 32],
+    pub vault: Pubkey,
+    pub member_count: u32,
+    pub current_epoch: u64,
+    pub undistributed: u64, // Funded but not yet assigned to an epoch
+    pub bump: u8,
+    pub application_deposit: u64, // Refundable deposit required to join; 0 = none
+}
+
+#[account]
+pub struct CohortMember {
+    pub pool: Pubkey,
+    pub human: Pubkey,
+    pub joined_at: i64,
+    pub bump: u8,
+}
+
+#[account]
+pub struct Faucet {
+    pub mint: Pubkey, // Test USDC; the faucet PDA is its mint authority
+    pub drip_amount: u64,
+    pub cooldown: i64, // Seconds between drips to the same wallet
+    pub bump: u8,
+}
+
+impl Faucet {
+    pub const SPACE: usize = 8 + 32 + 8 + 8 + 1;
+}
+
+#[account]
+pub struct FaucetClaim {
+    pub last_drip: i64,
+    pub bump: u8,
+}
+
+impl FaucetClaim {
+    pub const SPACE: usize = 8 + 8 + 1;
+}
+
+#[account]
+pub struct CohortDeposit {
+    pub pool: Pubkey,
+    pub human: Pubkey,
+    pub amount: u64,   // Held in the cohort vault; zeroed when slashed
+    pub flagged: bool, // Under dispute: withdrawals blocked, slashable
+    pub bump: u8,
+}
+
+impl CohortDeposit {
+    pub const SPACE: usize = 8 + 32 + 32 + 8 + 1 + 1;
+}
+
+#[account]
+pub struct CohortEpoch {
+    pub pool: Pubkey,
+    pub epoch: u64,
+    pub merkle_root: [u8; 32],
+    pub total: u64,
+    pub claimed: u64,
+    pub bump: u8,
+}
+
+#[account]
+pub struct CohortClaim {
+    pub amount: u64,
+    pub bump: u8,
+}
+
+#[account]
+pub struct PriceIndex {
+    pub category: [u8; 32],
+    pub ema_price_e6: u64, // EMA of agreed price_per_second, scaled by 1e6
+    pub samples: u64,
+    pub last_updated: i64,
+    pub bump: u8,
+}
+
+impl PriceIndex {
+    pub fn record(&mut self, price_per_second: u64, now: i64) {
+        let price = price_per_second as u128 * PRICE_EMA_SCALE;
+        let ema = if self.samples == 0 {
+            price
+        } else {
+            let prev = self.ema_price_e6 as u128;
+            (prev * (BPS_DENOMINATOR as u128 - PRICE_EMA_ALPHA_BPS) + price * PRICE_EMA_ALPHA_BPS)
+                / BPS_DENOMINATOR as u128
+        };
+        self.ema_price_e6 = ema.min(u64::MAX as u128) as u64;
+        self.samples += 1;
+        self.last_updated = now;
+    }
+}
+
+// Zero-copy: the sample window is rewritten on every settlement
+#[account(zero_copy)]
+pub struct SessionLengthStats {
+    pub category: [u8; 32],
+    pub samples: [u64; SESSION_STATS_WINDOW], // Ring buffer of recent verified_seconds
+    pub count: u64,                           // Samples ever recorded; next slot = count % window
+    pub median: u64,
+    pub p90: u64,
+    pub mean: u64,
+    pub std_dev: u64,
+    pub flag_z_score_e2: u64, // Flag threshold, z-score x100; 0 = never flag
+    pub bump: u8,
+    pub _padding: [u8; 7],
+}
+
+impl SessionLengthStats {
+    /// How far `seconds` sits above the window mean, in standard deviations x100. Sessions
+    /// shorter than the mean score 0.
+    pub fn z_score_e2(&self, seconds: u64) -> u64 {
+        if self.count < SESSION_STATS_MIN_SAMPLES || seconds <= self.mean {
+            return 0;
+        }
+        let excess = (seconds - self.mean) as u128 * 100;
+        match self.std_dev {
+            0 => u64::MAX,
+            std_dev => (excess / std_dev as u128).min(u64::MAX as u128) as u64,
+        }
+    }
+
+    pub fn is_outlier(&self, z_score_e2: u64) -> bool {
+        self.flag_z_score_e2 > 0 && z_score_e2 > self.flag_z_score_e2
+    }
+
+    /// Adds a sample and recomputes the window's percentiles and moments.
+    pub fn record(&mut self, seconds: u64) {
+        self.samples[(self.count % SESSION_STATS_WINDOW as u64) as usize] = seconds;
+        self.count += 1;
+
+        let len = self.count.min(SESSION_STATS_WINDOW as u64) as usize;
+        let mut window = self.samples;
+        let window = &mut window[..len];
+        window.sort_unstable();
+        self.median = window[len / 2];
+        self.p90 = window[len * 9 / 10];
+
+        let sum: u128 = window.iter().map(|&s| s as u128).sum();
+        let mean = sum / len as u128;
+        let variance = window.iter().map(|&s| (s as u128).abs_diff(mean).pow(2)).sum::<u128>() / len as u128;
+        self.mean = mean as u64;
+        self.std_dev = isqrt(variance);
+    }
+}
+
+#[account]
+pub struct SpotPrice {
+    pub category: [u8; 32],
+    pub oracle: Pubkey, // Posts prices alongside the admin
+    pub price_per_second: u64,
+    pub updated_at: i64,
+    pub bump: u8,
+}
+
+impl SpotPrice {
+    pub const SPACE: usize = 8 + 32 + 32 + 8 + 8 + 1;
+
+    /// Posted price, if fresh enough to open a session at.
+    pub fn current(&self, now: i64) -> Result<u64> {
+        require!(
+            self.price_per_second > 0 && now <= self.updated_at.saturating_add(SPOT_PRICE_MAX_AGE),
+            ErrorCode::StaleSpotPrice
+        );
+        Ok(self.price_per_second)
+    }
+}
+
+#[account]
+pub struct Availability {
+    pub human: Pubkey,
+    pub hours: [u32; 7],       // Per weekday (0 = Sunday), bit h = available during UTC hour h
+    pub rate_per_second: u64,  // Advertised rate; enforcement is via PayoutPrefs
+    pub bump: u8,
+}
+
+impl Availability {
+    pub fn is_available(&self, unix_timestamp: i64) -> bool {
+        let days = unix_timestamp.div_euclid(86_400);
+        // 1970-01-01 was a Thursday
+        let weekday = (days + 4).rem_euclid(7) as usize;
+        let hour = unix_timestamp.rem_euclid(86_400) / 3600;
+        self.hours[weekday] & (1 << hour) != 0
+    }
+}
+
+#[account]
+pub struct BlockedAgents {
+    pub human: Pubkey,
+    pub agents: Vec<Pubkey>, // At most MAX_BLOCKED_AGENTS
+    pub bump: u8,
+}
+
+impl BlockedAgents {
+    pub const SPACE: usize = 8 + 32 + 4 + MAX_BLOCKED_AGENTS * 32 + 1;
+}
+
+/// Marks an agent's deposit authorization nonce as spent.
+#[account]
+pub struct DepositAuthorization {
+    pub agent: Pubkey,
+    pub nonce: u64,
+    pub bump: u8,
+}
+
+/// Marks a funder's transfer authorization nonce as spent.
+#[account]
+pub struct TransferAuthorization {
+    pub from: Pubkey,
+    pub nonce: [u8; 32],
+    pub bump: u8,
+}
+
+/// Record of a CCTP burn credited to an escrow, one per (source domain, nonce).
+#[account]
+pub struct CctpDeposit {
+    pub escrow: Pubkey,
+    pub source_domain: u32,
+    pub nonce: u64,
+    pub amount: u64,
+    pub bump: u8,
+}
+
+/// Voucher for an escrow balance moved to another deployment of this program.
+#[account]
+pub struct EscrowExport {
+    pub agent: Pubkey,
+    pub export_id: u64,
+    pub mint: Pubkey,               // Escrow mint (default = primary)
+    pub amount: u64,
+    pub destination_program: Pubkey,
+    pub destination_vault: Pubkey,  // Already holds the exported tokens
+    pub exported_at: i64,
+    pub bump: u8,
+}
+
+/// Marks an export voucher as credited on this deployment.
+#[account]
+pub struct EscrowImport {
+    pub escrow_export: Pubkey,
+    pub amount: u64,
+    pub bump: u8,
+}
+
+#[event]
+pub struct EpochRewardsMinted {
+    pub epoch: u64,
+    pub amount: u64,
+    pub destination: Pubkey,
+    pub total_minted: u64,
+}
+
+#[event]
+pub struct RecoveryInitiated {
+    pub escrow: Pubkey,
+    pub agent: Pubkey,
+    pub balance: u64,
+    pub executable_at: i64,
+}
+
+#[event]
+pub struct RecoveryCancelled {
+    pub escrow: Pubkey,
+}
+
+#[event]
+pub struct RecoveryExecuted {
+    pub escrow: Pubkey,
+    pub agent: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct HumanTipped {
+    pub session: Pubkey,
+    pub human: Pubkey,
+    pub amount: u64,
+    pub fee: u64,
+}
+
+#[event]
+pub struct MatcherRegistered {
+    pub matcher_code: [u8; 32],
+    pub wallet: Pubkey,
+}
+
+#[event]
+pub struct MatcherPaid {
+    pub matcher_code: [u8; 32],
+    pub escrow: Pubkey,
+    pub nonce: u64,
+    pub matcher_share: u64,
+}
+
+#[event]
+pub struct BuilderRegistered {
+    pub builder_code: [u8; 32],
+    pub wallet: Pubkey,
+}
+
+#[event]
+pub struct CreditDrawn {
+    pub escrow: Pubkey,
+    pub amount: u64,
+    pub debt: u64,
+}
+
+#[event]
+pub struct CreditRepaid {
+    pub escrow: Pubkey,
+    pub amount: u64,
+    pub debt: u64,
+}
+
+#[event]
+pub struct ProtocolFeesClaimed {
+    pub amount: u64,
+    pub destination: Pubkey,
+    pub remaining: u64,
 }
 
+#[event]
+pub struct BuilderClaimSwapped {
+    pub builder_code: [u8; 32],
+    pub amount_in: u64,
+    pub output_mint: Pubkey,
+    pub amount_out: u64,
+}
 
-#[derive(Accounts)]
-pub struct DepositEscrow<'info> {
-    #[account(mut)]
-    pub agent: Signer<'info>,
-    #[account(mut)]
-    pub agent_token_account: Account<'info, TokenAccount>, 
-    #[account(
-        init_if_needed,
-        payer = agent,
-        space = 8 + 32 + 8 + 8 + 1,
-        seeds = [b"escrow", agent.key().as_ref()],
-        bump
-    )]
-    pub escrow_account: Account<'info, EscrowAccount>,
-    // Fee Vault reference for mint validation
-    #[account(seeds = [b"fee_vault_state"], bump)]
-    pub fee_vault_state: Account<'info, FeeVaultState>,
-    #[account(constraint = fee_vault.owner == fee_vault_state.key())]
-    pub fee_vault: Account<'info, TokenAccount>,
-    #[account(
-        mut,
-        constraint = vault.owner == escrow_account.key() @ ErrorCode::InvalidVault,
-        constraint = vault.mint == fee_vault.mint @ ErrorCode::InvalidMint
-    )]
-    pub vault: Account<'info, TokenAccount>,
-    pub token_program: Program<'info, Token>,
-    pub system_program: Program<'info, System>,
-    pub rent: Sysvar<'info, Rent>,
+#[event]
+pub struct BuilderClaimSwapFailed {
+    pub builder_code: [u8; 32],
+    pub amount: u64,
 }
 
-#[derive(Accounts)]
-pub struct WithdrawEscrow<'info> {
-    #[account(mut)]
-    pub agent: Signer<'info>,
-    #[account(mut)]
-    pub agent_token_account: Account<'info, TokenAccount>,
-    #[account(
-        mut,
-        seeds = [b"escrow", agent.key().as_ref()],
-        bump = escrow_account.bump
-    )]
-    pub escrow_account: Account<'info, EscrowAccount>,
-    #[account(
-        mut,
-        constraint = vault.owner == escrow_account.key() @ ErrorCode::InvalidVault
-    )]
-    pub vault: Account<'info, TokenAccount>,
-    pub token_program: Program<'info, Token>,
+#[event]
+pub struct RevenueShareMinted {
+    pub nft_mint: Pubkey,
+    pub recipient: Pubkey,
+    pub bps: u16,
+    pub start_epoch: u64,
+    pub end_epoch: u64,
 }
 
-#[derive(Accounts)]
-#[instruction(verified_seconds: u64, agreed_price_per_second: u64, nonce: u64, builder_code_opt: Option<[u8; 32]>)]
-pub struct CloseSettlement<'info> {
-    #[account(constraint = router.key() == market_config.authority)]
-    pub router: Signer<'info>,
-    #[account(
-        mut,
-        seeds = [b"escrow", escrow_account.agent.as_ref()],
-        bump = escrow_account.bump
-    )]
-    pub escrow_account: Account<'info, EscrowAccount>,
-    #[account(
-        mut,
-        constraint = vault.owner == escrow_account.key() @ ErrorCode::InvalidVault
-    )]
-    pub vault: Account<'info, TokenAccount>,
-    #[account(mut)]
-    pub user_wallet: Account<'info, TokenAccount>,
-    
-    // Fee Vault Accounts
-    #[account(
-        mut,
-        seeds = [b"fee_vault_state"],
-        bump = fee_vault_state.bump
-    )]
-    pub fee_vault_state: Account<'info, FeeVaultState>,
-    #[account(
-        mut,
-        constraint = fee_vault.owner == fee_vault_state.key()
-    )]
-    pub fee_vault: Account<'info, TokenAccount>,
-    
-    // Optional Builder Balance (only needed if builder_code provided)
-    #[account(
-        mut,
-        seeds = [b"builder", builder_code_opt.unwrap_or([0; 32]).as_ref()],
-        bump = builder_balance.bump
-    )]
-    pub builder_balance: Option<Account<'info, BuilderBalance>>,
+#[event]
+pub struct FeeEpochRolled {
+    pub epoch: u64,
+    pub collected: u64,
+    pub reserved: u64,
+}
 
-    #[account(
-        seeds = [b"market_config"],
-        bump
-    )]
-    pub market_config: Account<'info, MarketConfig>,
-    pub token_program: Program<'info, Token>,
+#[event]
+pub struct RevenueShareClaimed {
+    pub nft_mint: Pubkey,
+    pub holder: Pubkey,
+    pub amount: u64,
+    pub next_claim_epoch: u64,
 }
 
-#[derive(Accounts)]
-pub struct ClaimBuilderBalance<'info> {
-    #[account(mut)]
-    pub builder_wallet: Signer<'info>,
-    #[account(
-        mut,
-        constraint = builder_balance.wallet == builder_wallet.key() @ ErrorCode::Unauthorized,
-        seeds = [b"builder", builder_balance.builder_code.as_ref()],
-        bump = builder_balance.bump
-    )]
-    pub builder_balance: Account<'info, BuilderBalance>,
-    
-    #[account(
-        mut,
-        constraint = builder_token_account.owner == builder_wallet.key(),
-        constraint = builder_token_account.mint == fee_vault.mint
-    )]
-    pub builder_token_account: Account<'info, TokenAccount>,
+#[event]
+pub struct RelayerReimbursed {
+    pub relayer: Pubkey,
+    pub lamports: u64,
+    pub amount: u64,
+    pub outstanding_lamports: u64,
+}
 
-    #[account(
-        mut,
-        seeds = [b"fee_vault_state"],
-        bump = fee_vault_state.bump
-    )]
-    pub fee_vault_state: Account<'info, FeeVaultState>,
-    #[account(
-        mut,
-        constraint = fee_vault.owner == fee_vault_state.key()
-    )]
-    pub fee_vault: Account<'info, TokenAccount>,
-    
-    pub token_program: Program<'info, Token>,
+#[event]
+pub struct ForwardPurchased {
+    pub escrow: Pubkey,
+    pub forward_id: u64,
+    pub cohort: Pubkey,
+    pub seconds: u64,
+    pub price_per_second: u64,
+    pub expires_at: i64,
 }
 
-#[derive(Accounts)]
-pub struct ClaimProtocolFees<'info> {
-    #[account(mut)]
-    pub admin: Signer<'info>,
-    #[account(
-        mut,
-        constraint = fee_vault_state.authority == admin.key() @ ErrorCode::Unauthorized,
-        seeds = [b"fee_vault_state"],
-        bump = fee_vault_state.bump
-    )]
-    pub fee_vault_state: Account<'info, FeeVaultState>,
-    #[account(
-        mut,
-        constraint = fee_vault.owner == fee_vault_state.key()
-    )]
-    pub fee_vault: Account<'info, TokenAccount>,
-    #[account(mut)]
-    pub admin_wallet: Account<'info, TokenAccount>,
-    
-    pub token_program: Program<'info, Token>,
+#[event]
+pub struct ForwardRefunded {
+    pub escrow: Pubkey,
+    pub forward_id: u64,
+    pub unused_seconds: u64,
+    pub refund: u64,
 }
 
-#[account]
-pub struct MarketConfig {
+#[event]
+pub struct CohortFunded {
+    pub pool: Pubkey,
+    pub escrow: Pubkey,
+    pub amount: u64,
+    pub fee: u64,
+}
+
+#[event]
+pub struct EscrowExported {
+    pub agent: Pubkey,
+    pub export_id: u64,
+    pub amount: u64,
+    pub destination_program: Pubkey,
+}
+
+#[event]
+pub struct EscrowImported {
+    pub agent: Pubkey,
+    pub export_id: u64,
+    pub amount: u64,
+    pub source_program: Pubkey,
+}
+
+#[event]
+pub struct EscrowShardAssigned {
+    pub escrow: Pubkey,
+    pub shard_id: Option<u8>,
+}
+
+#[event]
+pub struct SessionExpired {
+    pub escrow: Pubkey,
+    pub session_id: u64,
+    pub released: u64,
+}
+
+#[event]
+pub struct SettlementEnqueued {
+    pub seq: u64,
+    pub escrow: Pubkey,
+    pub human: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct QueuedSettlementProcessed {
+    pub seq: u64,
+    pub escrow: Pubkey,
+    pub human: Pubkey,
+    pub settled: bool, // false = dropped (stale nonce or insufficient escrow)
+    pub fee_amount: u64,
+    pub user_payout: u64,
+    pub escrow_balance: u64, // Remaining after this item, for top-up automation
+    pub escrow_locked: u64,
+}
+
+#[event]
+pub struct SettlementClosed {
+    pub escrow: Pubkey,
+    pub human: Pubkey,
+    pub nonce: u64,
+    pub amount: u64,
+    pub fee_amount: u64,
+    pub holdback_amount: u64,
+    pub escrow_balance: u64, // Remaining after the settlement, for top-up automation
+    pub escrow_locked: u64,  // Part of escrow_balance the agent can't withdraw (EscrowAccount::locked)
+    pub quality_multiplier_bps: u16, // Engagement multiplier applied to the agreed price, 10_000 = 1x
+}
+
+#[event]
+pub struct FeeOverrideSet {
+    pub escrow: Pubkey,
+    pub fee_bps_override: Option<u16>,
+}
+
+#[event]
+pub struct FeeOverrideApplied {
+    pub escrow: Pubkey,
+    pub fee_bps: u16,
+    pub fee_amount: u64,
+}
+
+#[event]
+pub struct TaskFunded {
+    pub escrow: Pubkey,
+    pub task_id: u64,
+    pub amount: u64,
+    pub balance: u64,
+}
+
+#[event]
+pub struct SolvencyDiscrepancy {
+    pub account: Pubkey,
+    pub expected: u64,
+    pub actual: u64,
+}
+
+#[event]
+pub struct AuthorizationUsed {
+    pub authorizer: Pubkey,
+    pub nonce: [u8; 32],
+    pub escrow: Pubkey,
+    pub value: u64,
+}
+
+#[event]
+pub struct EscrowSnapshotTaken {
+    pub snapshot: Pubkey,
+    pub escrow: Pubkey,
+    pub challenge: [u8; 32],
+    pub balance: u64,
+    pub total_settled: u64,
+    pub account_age: i64,
+    pub expires_at: i64,
+}
+
+#[event]
+pub struct GroupSettlementClosed {
+    pub escrow: Pubkey,
+    pub session_id: u64,
+    pub nonce: u64,
+    pub participants: u8,
+    pub amount: u64,
+    pub fee_amount: u64,
+    pub holdback_amount: u64,
+    pub escrow_balance: u64,
+}
+
+#[event]
+pub struct AgentRated {
+    pub agent: Pubkey,
+    pub human: Pubkey,
+    pub receipt: Pubkey,
+    pub rating: u8,
+}
+
+#[event]
+pub struct KycTierSet {
+    pub escrow: Pubkey,
+    pub agent: Pubkey,
+    pub kyc_tier: u8,
+}
+
+#[event]
+pub struct EscrowClosed {
+    pub agent: Pubkey,
+    pub escrow: Pubkey,
+    pub dust: u64, // Uncredited vault tokens swept to the agent
+}
+
+#[event]
+pub struct InvoiceMinted {
+    pub escrow: Pubkey,
+    pub summary: Pubkey,
+    pub mint: Pubkey,
+    pub summary_hash: [u8; 32],
+}
+
+#[event]
+pub struct PayoutAnnotated {
+    pub human: Pubkey,
+    pub escrow: Pubkey, // Default for holdback releases
+    pub nonce: u64,     // Settlement nonce; 0 for holdback releases
+    pub amount: u64,
+    pub jurisdiction: [u8; 4],
+    pub memo_hash: [u8; 32],
+}
+
+#[event]
+pub struct DataConsentChanged {
+    pub human: Pubkey,
+    pub scope_hash: [u8; 32],
+    pub expiry: i64,
+    pub revoked: bool,
+}
+
+#[event]
+pub struct ConsentedSettlement {
+    pub consent: Pubkey,
+    pub human: Pubkey,
+    pub scope_hash: [u8; 32],
+    pub escrow: Pubkey,
+    pub nonce: u64,
+}
+
+#[event]
+pub struct LiquidityBufferFunded {
+    pub amount: u64,
+    pub replenishment: bool, // Fee share moved by the crank rather than treasury funding
+    pub outstanding: u64,
+}
+
+#[event]
+pub struct LstPricePosted {
+    pub mint: Pubkey,
+    pub price: u64,
+    pub updated_at: i64,
+}
+
+#[event]
+pub struct LstCreditDrawn {
+    pub escrow: Pubkey,
+    pub mint: Pubkey,
+    pub amount: u64,
+    pub debt: u64,
+}
+
+#[event]
+pub struct LstDebtRepaid {
+    pub escrow: Pubkey,
+    pub mint: Pubkey,
+    pub amount: u64,
+    pub lst_sold: u64, // 0 when repaid in the payment mint
+    pub debt: u64,
+}
+
+#[event]
+pub struct CircuitBreakerTripped {
+    pub velocity: u64, // Rolling-hour settled volume when it tripped
+    pub ceiling: u64,
+    pub tripped_at: i64,
+}
+
+#[event]
+pub struct SettlementsResumed {
     pub authority: Pubkey,
-    pub fee_basis_points: u16,
 }
 
-#[account]
-pub struct FeeVaultState {
+#[event]
+pub struct ObservedSettlement {
+    pub observer: Pubkey,
+    pub tag: [u8; 32],
+    pub escrow: Pubkey,
+    pub agent: Pubkey,
+    pub human: Pubkey,
+    pub nonce: u64,
+    pub verified_seconds: u64,
+    pub agreed_price_per_second: u64,
+    pub amount: u64,
+    pub fee_amount: u64,
+    pub holdback_amount: u64,
+    pub builder_code: Option<[u8; 32]>,
+    pub quality_score: Option<u8>,
+    pub settled_at: i64,
+}
+
+#[event]
+pub struct MarketConfigReinitialized {
     pub authority: Pubkey,
-    pub protocol_balance: u64,
-    pub total_collected: u64,
-    pub bump: u8,
+    pub payment_mint: Pubkey,
+    pub fee_basis_points: u16,
 }
 
-#[account]
-pub struct BuilderBalance {
-    pub builder_code: [u8; 32],
-    pub wallet: Pubkey,
-    pub balance: u64,
-    pub total_earned: u64,
-    pub bump: u8,
+#[event]
+pub struct MilestoneReleased {
+    pub task: Pubkey,
+    pub human: Pubkey,
+    pub index: u8,
+    pub amount: u64,
+    pub fee: u64,
 }
 
-#[account]
-pub struct EscrowAccount {
+#[event]
+pub struct BountyPaid {
+    pub bounty: Pubkey,
+    pub human: Pubkey,
+    pub completed_bps: u16,
+    pub amount: u64,
+    pub fee: u64,
+    pub completion_hash: [u8; 32],
+}
+
+#[event]
+pub struct EscrowFunded {
     pub agent: Pubkey,
-    pub balance: u64,
-    pub settlement_nonce: u64,  // Prevents replay attacks
-    pub bump: u8,
+    pub amount: u64,
+    pub new_balance: u64,
+    pub cumulative_deposited: u64,
+}
+
+#[event]
+pub struct BuilderReferral {
+    pub builder_code: [u8; 32],
+    pub builder_code_str: String,
+    pub escrow: Pubkey,
+    pub nonce: u64,
+    pub builder_share: u64,
+}
+
+#[event]
+pub struct FeeHolidayScheduled {
+    pub start: i64,
+    pub end: i64,
+}
+
+#[event]
+pub struct HoldbackInterestPaid {
+    pub human: Pubkey,
+    pub principal: u64,
+    pub interest: u64,
+}
+
+#[event]
+pub struct EscrowEpochClosed {
+    pub escrow: Pubkey,
+    pub index: u32,
+    pub summary: Pubkey,
+    pub closing_balance: u64,
+}
+
+#[event]
+pub struct PaymentIntentSpent {
+    pub payment_intent: Pubkey,
+    pub nonce: u64,
+    pub amount: u64,
+    pub remaining: u64,
+}
+
+#[event]
+pub struct CctpDepositCredited {
+    pub escrow: Pubkey,
+    pub source_domain: u32,
+    pub nonce: u64,
+    pub amount: u64,
+    pub depositor: [u8; 32],
+}
+
+#[event]
+pub struct SettlementFlagged {
+    pub escrow: Pubkey,
+    pub human: Pubkey,
+    pub nonce: u64,
+    pub category: [u8; 32],
+    pub verified_seconds: u64,
+    pub median_seconds: u64,
+    pub z_score_e2: u64,
+}
+
+#[event]
+pub struct SpotPricePosted {
+    pub category: [u8; 32],
+    pub price_per_second: u64,
+    pub updated_at: i64,
+}
+
+#[event]
+pub struct FastWithdrawal {
+    pub escrow: Pubkey,
+    pub amount: u64,
+    pub last_heartbeat: i64,
+}
+
+#[event]
+pub struct ApiKeySpend {
+    pub api_key: Pubkey,
+    pub key_hash: [u8; 32],
+    pub nonce: u64,
+    pub amount: u64,
+}
+
+#[event]
+pub struct OrgFeeTaken {
+    pub org: Pubkey,
+    pub human: Pubkey,
+    pub org_fee: u64,
+    pub member_payout: u64,
 }
 
 #[error_code]
@@ -563,4 +11337,218 @@ pub enum ErrorCode {
     InvalidBuilderCode,
     #[msg("Builder account must exist when builder code is provided")]
     BuilderAccountMissing,
+    #[msg("Session does not belong to this escrow and human")]
+    SessionMismatch,
+    #[msg("Verified seconds exceed the session hard cap")]
+    SessionCapExceeded,
+    #[msg("Verified seconds exceed the session's elapsed wall-clock time")]
+    SessionElapsedExceeded,
+    #[msg("Invalid emission schedule parameters")]
+    InvalidEmissionSchedule,
+    #[msg("Emission epoch has not ended yet")]
+    EmissionEpochNotEnded,
+    #[msg("Rewards destination does not match the emission schedule")]
+    InvalidEmissionsDestination,
+    #[msg("Escrow is tokenized - only the position holder can withdraw")]
+    EscrowTokenized,
+    #[msg("Invalid escrow position token")]
+    InvalidPosition,
+    #[msg("Invalid holdback parameters")]
+    InvalidHoldbackParams,
+    #[msg("Holdback account must be provided when holdback is enabled")]
+    HoldbackAccountMissing,
+    #[msg("Holdback is still within its review period")]
+    HoldbackLocked,
+    #[msg("Holdback is flagged for fraud review")]
+    HoldbackFlagged,
+    #[msg("Holdback must be flagged before it can be slashed")]
+    HoldbackNotFlagged,
+    #[msg("Attestation key is revoked or not yet active")]
+    AttestationKeyInactive,
+    #[msg("Missing or invalid attestation signature")]
+    InvalidAttestation,
+    #[msg("Settlements must be attested while the registry has a live key")]
+    SettlementAttestationRequired,
+    #[msg("Spot price feed is for a different category")]
+    CategoryMismatch,
+    #[msg("Invalid settlement batch")]
+    InvalidBatch,
+    #[msg("Task deadline must be in the future")]
+    InvalidDeadline,
+    #[msg("Task has no deadline or its grace period has not elapsed")]
+    TaskNotExpired,
+    #[msg("Task does not belong to this escrow")]
+    InvalidTask,
+    #[msg("Token account has the wrong owner or mint")]
+    InvalidTokenAccount,
+    #[msg("Unknown feature flag")]
+    InvalidFeatures,
+    #[msg("Recovery timelock has not elapsed")]
+    RecoveryLocked,
+    #[msg("Recovery does not match this escrow")]
+    InvalidRecovery,
+    #[msg("Not a payment router escrow account")]
+    InvalidEscrow,
+    #[msg("Payout mint does not match the human's preferred mint")]
+    PreferredMintMismatch,
+    #[msg("Holdback is below the human's auto-claim threshold")]
+    BelowAutoClaimThreshold,
+    #[msg("Price per second is below the human's price floor")]
+    BelowPriceFloor,
+    #[msg("Amount must be greater than zero")]
+    InvalidAmount,
+    #[msg("Quality score must be between 1 and 100")]
+    InvalidQualityScore,
+    #[msg("Builder code is already registered")]
+    BuilderAlreadyRegistered,
+    #[msg("Fee parameter out of range")]
+    InvalidFeeParams,
+    #[msg("Swap venue is not configured or not whitelisted")]
+    SwapUnavailable,
+    #[msg("Swap output is below the minimum amount")]
+    SlippageExceeded,
+    #[msg("Settlement would exceed the escrow's credit limit")]
+    CreditLimitExceeded,
+    #[msg("Revenue-share positions would exceed the fee stream cap")]
+    RevenueShareCapExceeded,
+    #[msg("Fee epoch has not finished")]
+    EpochNotFinished,
+    #[msg("Fee epoch accounts must be consecutive closed epochs within the position term")]
+    InvalidFeeEpoch,
+    #[msg("Forward position has expired")]
+    ForwardExpired,
+    #[msg("Forward position has not expired")]
+    ForwardNotExpired,
+    #[msg("Settlement price does not match the forward's locked price")]
+    ForwardPriceMismatch,
+    #[msg("Settlement exceeds the forward's remaining seconds")]
+    ForwardExhausted,
+    #[msg("Merkle proof does not match the epoch root")]
+    InvalidMerkleProof,
+    #[msg("Availability hour masks must fit in 24 bits")]
+    InvalidAvailability,
+    #[msg("Human is not available at this time")]
+    HumanUnavailable,
+    #[msg("Deposit authorization has expired")]
+    AuthorizationExpired,
+    #[msg("Escrow export voucher is not valid for this program")]
+    InvalidExport,
+    #[msg("Solvency check accounts are malformed")]
+    InvalidSolvencyAccounts,
+    #[msg("Vault balances are below recorded liabilities")]
+    Insolvent,
+    #[msg("Queued settlement is out of order or its accounts do not match")]
+    InvalidQueueItem,
+    #[msg("Session is still within its billable window")]
+    SessionNotExpired,
+    #[msg("Shard id is outside the configured shard count")]
+    InvalidShard,
+    #[msg("Escrow has open sessions")]
+    SessionsOpen,
+    #[msg("Payout split legs are invalid or their accounts do not match")]
+    InvalidPayoutSplit,
+    #[msg("Humans with a payout split or org can't be settled through the queue")]
+    QueuePayoutUnsupported,
+    #[msg("Humans with a payout split or org can't be settled in a group session")]
+    GroupPayoutUnsupported,
+    #[msg("Org fee exceeds the maximum")]
+    InvalidOrgFee,
+    #[msg("Org or org treasury does not match the human's membership")]
+    InvalidOrg,
+    #[msg("Human belongs to an org; its org and treasury accounts are required")]
+    OrgAccountMissing,
+    #[msg("API key has been revoked")]
+    ApiKeyRevoked,
+    #[msg("Heartbeat timeout and failover delay can't be negative")]
+    InvalidHeartbeatTimeout,
+    #[msg("Router heartbeat is current; fallbacks are not available")]
+    RouterLive,
+    #[msg("Feature is disabled for this deployment")]
+    FeatureDisabled,
+    #[msg("Spot price is unset or too old")]
+    StaleSpotPrice,
+    #[msg("Spot-priced sessions must settle at the snapshotted price")]
+    SpotPriceMismatch,
+    #[msg("Preceding instruction is not a matching CCTP receive_message")]
+    InvalidCctpMessage,
+    #[msg("Escrow requires settlements to reference a payment intent")]
+    PaymentIntentRequired,
+    #[msg("Payment intent has expired")]
+    PaymentIntentExpired,
+    #[msg("Settlement exceeds the payment intent's remaining authorization")]
+    PaymentIntentExceeded,
+    #[msg("Payment intent is for a different human")]
+    PaymentIntentMismatch,
+    #[msg("Escrow's statement period has not ended")]
+    StatementPeriodOpen,
+    #[msg("Task is gated; the human's NFT token account and metadata are required")]
+    NftGateRequired,
+    #[msg("Human does not hold an NFT from the task's collection")]
+    NftGateFailed,
+    #[msg("Task requires an attestation from a whitelisted issuer")]
+    AttestationRequired,
+    #[msg("Attestation issuer has been revoked")]
+    AttestationIssuerRevoked,
+    #[msg("Attestation does not match the task policy")]
+    AttestationMismatch,
+    #[msg("Bounty completion must increase and stay within 10000 bps")]
+    InvalidBountyProgress,
+    #[msg("Bounty is unpaid and its task has not expired")]
+    BountyActive,
+    #[msg("Milestones must number 1 to 8, each with a nonzero amount")]
+    InvalidMilestones,
+    #[msg("Milestone was already released or cancelled")]
+    MilestoneSettled,
+    #[msg("A response hash can only be recorded on a settlement receipt")]
+    ReceiptRequired,
+    #[msg("Cohort requires an application deposit before joining")]
+    CohortDepositRequired,
+    #[msg("Cohort deposit is flagged for dispute")]
+    CohortDepositFlagged,
+    #[msg("Cohort deposit must be flagged before it can be slashed")]
+    CohortDepositNotFlagged,
+    #[msg("Leave the cohort before withdrawing the deposit")]
+    CohortMemberActive,
+    #[msg("Faucet cooldown has not elapsed for this wallet")]
+    FaucetCooldown,
+    #[msg("Faucet is only available in devnet builds")]
+    FaucetDisabled,
+    #[msg("Market is live; the config can no longer be reinitialized")]
+    MarketLive,
+    #[msg("Price is finer than the payment mint's smallest unit")]
+    PriceNotRepresentable,
+    #[msg("Settlements are paused by the velocity circuit breaker")]
+    SettlementsPaused,
+    #[msg("LST price is missing or stale")]
+    StaleLstPrice,
+    #[msg("LST collateral does not cover the position's debt")]
+    LstUndercollateralized,
+    #[msg("Draw exceeds the liquidity buffer's exposure limits")]
+    BufferExposureExceeded,
+    #[msg("Data consent is revoked or expired")]
+    DataConsentInactive,
+    #[msg("Escrow still holds a balance")]
+    EscrowNotEmpty,
+    #[msg("Deposit would take the escrow above its KYC tier's cap")]
+    DepositCapExceeded,
+    #[msg("KYC tier is out of range")]
+    InvalidKycTier,
+    #[msg("Human has blocked this agent")]
+    AgentBlocked,
+    #[msg("Too many blocked agents")]
+    TooManyBlockedAgents,
+    #[msg("Rating must be between 1 and 5")]
+    InvalidRating,
+    #[msg("Settlement has already been rated")]
+    AlreadyRated,
+    #[msg("Matcher account does not match the session's matcher")]
+    MatcherMismatch,
+    #[msg("Group participants or their accounts are invalid")]
+    InvalidGroup,
+    #[msg("Quality multiplier is outside the session's bounds")]
+    InvalidQualityMultiplier,
+    #[msg("Escrow snapshot has not expired yet")]
+    SnapshotNotExpired,
+    #[msg("Transfer authorization is not valid yet")]
+    AuthorizationNotYetValid,
 }
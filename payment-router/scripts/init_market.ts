@@ -48,12 +48,14 @@ async function main() {
         PROGRAM_ID
     );
 
-    // 4. Initialize Market Config
+    // 4. Initialize Market Config (Devnet USDC is the canonical payment mint)
+    const USDC_MINT = new PublicKey("4zMMC9srt5Ri5X14GAgXhaHii3GnPAEERYPJgZJDncDU"); // Devnet USDC
     try {
         const tx = await program.methods.initializeMarketConfig(1500) // 15%
             .accounts({
                 admin: adminKeypair.publicKey,
                 config: marketConfigPDA,
+                paymentMint: USDC_MINT,
                 systemProgram: anchor.web3.SystemProgram.programId,
             })
             .rpc();
@@ -67,7 +69,6 @@ async function main() {
     }
 
     // 5. Initialize Fee Vault
-    const USDC_MINT = new PublicKey("4zMMC9srt5Ri5X14GAgXhaHii3GnPAEERYPJgZJDncDU"); // Devnet USDC
     try {
         const tx2 = await program.methods.initializeFeeVault()
             .accounts({
@@ -75,6 +76,7 @@ async function main() {
                 feeVaultState: feeVaultStatePDA,
                 feeVault: feeVaultPDA,
                 mint: USDC_MINT,
+                marketConfig: marketConfigPDA,
                 systemProgram: anchor.web3.SystemProgram.programId,
                 tokenProgram: new PublicKey("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA"),
                 rent: anchor.web3.SYSVAR_RENT_PUBKEY,
@@ -10,10 +10,12 @@ import { Program, BN } from "@coral-xyz/anchor";
 import { PublicKey, Keypair, SystemProgram, LAMPORTS_PER_SOL } from "@solana/web3.js";
 import {
     TOKEN_PROGRAM_ID,
+    ASSOCIATED_TOKEN_PROGRAM_ID,
     createMint,
     createAccount,
     mintTo,
     getAccount,
+    getAssociatedTokenAddressSync,
 } from "@solana/spl-token";
 import { expect } from "chai";
 
@@ -21,6 +23,13 @@ import { expect } from "chai";
 import {
     findMarketConfigPDA,
     findEscrowPDA,
+    findPayoutPrefsPDA,
+    findPayoutSplitPDA,
+    findOrgMemberPDA,
+    findAvailabilityPDA,
+    findBlockedAgentsPDA,
+    findSessionPDA,
+    findSessionStatsPDA,
     PAYMENT_ROUTER_PROGRAM_ID,
 } from "../client/src/index";
 
@@ -49,6 +58,56 @@ describe("payment_router", () => {
     let feeVaultStatePDA: PublicKey;
     let feeVaultPDA: PublicKey;
 
+    // Session the settlements below are billed against, opened without a stats category
+    const sessionId = new BN(1);
+    const uncategorized = Buffer.alloc(32);
+
+    // close_settlement accounts with every optional account omitted
+    const settlementAccounts = (escrowPDA: PublicKey, settlementVault: PublicKey) => ({
+        router: admin.publicKey,
+        escrowAccount: escrowPDA,
+        vault: settlementVault,
+        human: user.publicKey,
+        payoutPrefs: findPayoutPrefsPDA(user.publicKey)[0],
+        payoutSplit: findPayoutSplitPDA(user.publicKey)[0],
+        orgMember: findOrgMemberPDA(user.publicKey)[0],
+        session: findSessionPDA(escrowPDA, sessionId)[0],
+        userWallet: userTokenAccount,
+        mint: usdcMint,
+        feeVaultState: feeVaultStatePDA,
+        feeVault: feeVaultPDA,
+        builderBalance: null,
+        builderStats: null,
+        holdback: null, // holdback_bps = 0
+        attestationKey: null,
+        instructionsSysvar: null,
+        receipt: null,
+        humanStats: null,
+        agentStats: null,
+        creditLine: null,
+        creditCollateral: null,
+        mintFeeVault: null,
+        marketConfig: configPDA,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+        relayerStats: null,
+        forwardPosition: null,
+        priceIndex: null,
+        shard: null,
+        org: null,
+        orgTreasury: null,
+        apiKey: null,
+        sessionStats: findSessionStatsPDA(uncategorized)[0],
+        paymentIntent: null,
+        observer: null,
+        dataConsent: null,
+        matcherBalance: null,
+    });
+
+    // No builder, unscored, no SettlementExtras
+    const noExtras = { builderCodeStr: null, responseHash: null, qualityMultiplierBps: null };
+
     before(async () => {
         // Generate keypairs
         admin = Keypair.generate();
@@ -95,12 +154,8 @@ describe("payment_router", () => {
             agent.publicKey
         );
 
-        userTokenAccount = await createAccount(
-            provider.connection,
-            user,
-            usdcMint,
-            user.publicKey
-        );
+        // Human's payout ATA (created by close_settlement if missing)
+        userTokenAccount = getAssociatedTokenAddressSync(usdcMint, user.publicKey);
 
         // Vault for escrow (owned by Agent's escrow PDA, initialized during deposit)
         // Wait, the client usually creates the vault AT/Account. 
@@ -128,6 +183,7 @@ describe("payment_router", () => {
                 .accounts({
                     admin: admin.publicKey,
                     config: configPDA,
+                    paymentMint: usdcMint,
                     systemProgram: SystemProgram.programId,
                 })
                 .signers([admin])
@@ -135,6 +191,8 @@ describe("payment_router", () => {
 
             const config = await program.account.marketConfig.fetch(configPDA);
             expect(config.feeBasisPoints).to.equal(feeBasisPoints);
+            expect(config.paymentMint.toBase58()).to.equal(usdcMint.toBase58());
+            expect(config.paymentDecimals).to.equal(6);
 
             // 2. Initialize Fee Vault (NEW)
             await program.methods
@@ -144,6 +202,7 @@ describe("payment_router", () => {
                     feeVaultState: feeVaultStatePDA,
                     feeVault: feeVaultPDA,
                     mint: usdcMint,
+                    marketConfig: configPDA,
                     systemProgram: SystemProgram.programId,
                     tokenProgram: TOKEN_PROGRAM_ID,
                     rent: rentSysvar,
@@ -186,10 +245,16 @@ describe("payment_router", () => {
                     agent: agent.publicKey,
                     agentTokenAccount: agentTokenAccount,
                     escrowAccount: escrowPDA,
+                    marketConfig: configPDA,
+                    feeVaultState: feeVaultStatePDA,
+                    feeVault: feeVaultPDA,
                     vault: vault,
                     tokenProgram: TOKEN_PROGRAM_ID,
                     systemProgram: SystemProgram.programId,
                     rent: rentSysvar,
+                    creditLine: null, // Optional
+                    creditCollateral: null, // Optional
+                    mintFeeVault: null, // Optional
                 })
                 .signers([agent])
                 .rpc();
@@ -197,37 +262,107 @@ describe("payment_router", () => {
             const escrow = await program.account.escrowAccount.fetch(escrowPDA);
             expect(escrow.balance.toNumber()).to.equal(depositAmount.toNumber());
 
-            // Close Settlement
-            const verifiedSeconds = new BN(60);
+            // Open Session
             const pricePerSecond = new BN(1_000_000); // 1 USDC
-            const nonce = new BN(Date.now());
-
             await program.methods
-                .closeSettlement(verifiedSeconds, pricePerSecond, nonce, null) // No builder
+                .openSession(sessionId, pricePerSecond, [...uncategorized])
                 .accounts({
                     router: admin.publicKey,
                     escrowAccount: escrowPDA,
-                    vault: vault,
-                    userWallet: userTokenAccount,
-                    feeVaultState: feeVaultStatePDA,
-                    feeVault: feeVaultPDA,
-                    builderBalance: null, // Optional
+                    human: user.publicKey,
+                    payoutPrefs: findPayoutPrefsPDA(user.publicKey)[0],
+                    availability: findAvailabilityPDA(user.publicKey)[0],
+                    blockedAgents: findBlockedAgentsPDA(user.publicKey)[0],
+                    session: findSessionPDA(escrowPDA, sessionId)[0],
                     marketConfig: configPDA,
-                    tokenProgram: TOKEN_PROGRAM_ID,
+                    systemProgram: SystemProgram.programId,
+                    shard: null,
+                    spotPrice: null,
+                    taskGate: null,
+                    gateNftAccount: null,
+                    gateMetadata: null,
+                    taskPolicy: null,
+                    attestationIssuer: null,
+                    attestation: null,
+                    matcherBalance: null,
                 })
                 .signers([admin])
                 .rpc();
 
+            // Close Settlement (within the session's elapsed-time tolerance)
+            const verifiedSeconds = new BN(20);
+            const nonce = new BN(Date.now());
+
+            await program.methods
+                .closeSettlement(verifiedSeconds, pricePerSecond, nonce, null, null, noExtras)
+                .accounts(settlementAccounts(escrowPDA, vault))
+                .signers([admin])
+                .rpc();
+
             // Verify Payouts
-            // Total: 60 USDC
-            // Fee: 15% of 60 = 9 USDC
-            // User: 51 USDC
+            // Total: 20 USDC
+            // Fee: 15% of 20 = 3 USDC
+            // User: 17 USDC
 
             const userAccount = await getAccount(provider.connection, userTokenAccount);
-            expect(Number(userAccount.amount)).to.equal(51_000_000);
+            expect(Number(userAccount.amount)).to.equal(17_000_000);
 
             const feeAccount = await getAccount(provider.connection, feeVaultPDA);
-            expect(Number(feeAccount.amount)).to.equal(9_000_000);
+            expect(Number(feeAccount.amount)).to.equal(3_000_000);
         });
     });
+
+    describe("mint guards", () => {
+        // The fee vault was initialized with the 6-decimal USDC mock above; any other
+        // vault mint must be rejected before the escrow balance is touched.
+        const cases = [
+            { name: "foreign USDC-6 mint", decimals: 6 },
+            { name: "9-decimal mint", decimals: 9 },
+        ];
+
+        for (const { name, decimals } of cases) {
+            it(`rejects settlement from a ${name} vault`, async () => {
+                const [escrowPDA] = findEscrowPDA(agent.publicKey);
+                const otherMint = await createMint(
+                    provider.connection,
+                    admin,
+                    admin.publicKey,
+                    null,
+                    decimals
+                );
+                const otherVault = await createAccount(
+                    provider.connection,
+                    agent,
+                    otherMint,
+                    escrowPDA,
+                    Keypair.generate()
+                );
+                await mintTo(
+                    provider.connection,
+                    admin,
+                    otherMint,
+                    otherVault,
+                    admin,
+                    100 * 10 ** decimals
+                );
+
+                const before = await program.account.escrowAccount.fetch(escrowPDA);
+
+                try {
+                    await program.methods
+                        .closeSettlement(new BN(10), new BN(1_000_000), new BN(Date.now()), null, null, noExtras)
+                        .accounts(settlementAccounts(escrowPDA, otherVault))
+                        .signers([admin])
+                        .rpc();
+                    expect.fail("settlement with a mismatched vault mint should fail");
+                } catch (err: any) {
+                    expect(err.error?.errorCode?.code).to.equal("InvalidMint");
+                }
+
+                const after = await program.account.escrowAccount.fetch(escrowPDA);
+                expect(after.balance.toNumber()).to.equal(before.balance.toNumber());
+                expect(after.settlementNonce.toNumber()).to.equal(before.settlementNonce.toNumber());
+            });
+        }
+    });
 });